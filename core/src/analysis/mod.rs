@@ -0,0 +1,5 @@
+pub mod types;
+pub mod survival;
+
+pub use types::*;
+pub use survival::line_survival;
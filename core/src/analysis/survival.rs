@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+
+use super::types::{BlameSnapshot, LineSurvival, SnapshotSurvival};
+use crate::blame::BlameEntry;
+
+/// Where a line originated: commit, path in that commit and line number.
+type Origin<'a> = (&'a str, &'a str, u32);
+
+/// Committed lines of a blame as (final line, origin).
+fn origins(entries: &[BlameEntry]) -> Vec<(u32, Origin<'_>)> {
+    entries
+        .iter()
+        .filter(|e| !e.is_uncommitted)
+        .flat_map(|e| {
+            (0..e.num_lines).map(move |i| {
+                let origin = (e.sha.as_str(), e.filename.as_str(), e.orig_line.saturating_add(i));
+                (e.final_line.saturating_add(i), origin)
+            })
+        })
+        .collect()
+}
+
+/// Trace lines through `snapshots` of a file, ordered oldest first.
+///
+/// Lines are identified by the line they originate from, as in
+/// `diff_blame`, so a line survives as long as blame keeps tracing it to
+/// the same commit however much it moves. A line is introduced by the
+/// first snapshot it appears in. Uncommitted lines are left out.
+pub fn line_survival(snapshots: &[BlameSnapshot]) -> LineSurvival {
+    let lines: Vec<Vec<(u32, Origin)>> = snapshots.iter().map(|s| origins(&s.entries)).collect();
+
+    let mut introduced_by: HashMap<Origin, usize> = HashMap::new();
+    for (i, snapshot_lines) in lines.iter().enumerate() {
+        for &(_, origin) in snapshot_lines {
+            introduced_by.entry(origin).or_insert(i);
+        }
+    }
+    let latest: HashSet<Origin> = lines
+        .last()
+        .map(|last| last.iter().map(|&(_, origin)| origin).collect())
+        .unwrap_or_default();
+
+    let survival = snapshots
+        .iter()
+        .zip(&lines)
+        .enumerate()
+        .map(|(i, (snapshot, snapshot_lines))| {
+            let mut cohorts = vec![0; i + 1];
+            let mut surviving = 0;
+            for (_, origin) in snapshot_lines {
+                cohorts[introduced_by[origin]] += 1;
+                if latest.contains(origin) {
+                    surviving += 1;
+                }
+            }
+            let line_count = snapshot_lines.len() as u32;
+            SnapshotSurvival {
+                label: snapshot.label.clone(),
+                line_count,
+                introduced: cohorts[i],
+                surviving,
+                survival_rate: if line_count == 0 {
+                    1.0
+                } else {
+                    surviving as f64 / line_count as f64
+                },
+                cohorts,
+            }
+        })
+        .collect();
+
+    let mut line_introduced = Vec::new();
+    if let Some(snapshot) = snapshots.last() {
+        let covered = snapshot
+            .entries
+            .iter()
+            .map(|e| e.final_line.saturating_add(e.num_lines.saturating_sub(1)))
+            .max()
+            .unwrap_or(0);
+        line_introduced = vec![-1; covered as usize];
+        for (line, origin) in &lines[lines.len() - 1] {
+            if *line >= 1 {
+                line_introduced[*line as usize - 1] = introduced_by[origin] as i32;
+            }
+        }
+    }
+
+    LineSurvival {
+        snapshots: survival,
+        line_introduced,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entry;
+
+    fn snapshot(label: &str, entries: Vec<BlameEntry>) -> BlameSnapshot {
+        BlameSnapshot {
+            label: label.to_string(),
+            entries,
+        }
+    }
+
+    #[test]
+    fn test_line_survival() {
+        let mut uncommitted = entry(&"0".repeat(40), 1, 6, 1);
+        uncommitted.is_uncommitted = true;
+        let snapshots = vec![
+            // v1: four lines from commit a
+            snapshot("v1", vec![entry("a", 1, 1, 4)]),
+            // v2: a's first two lines deleted, b adds three after the rest
+            snapshot("v2", vec![entry("a", 3, 1, 2), entry("b", 1, 3, 3)]),
+            // v3: one of b's lines replaced by c, and an unsaved line
+            snapshot(
+                "v3",
+                vec![entry("a", 3, 1, 2), entry("b", 1, 3, 2), entry("c", 1, 5, 1), uncommitted],
+            ),
+        ];
+        let survival = line_survival(&snapshots);
+
+        let rows: Vec<(&str, u32, u32, u32, &[u32])> = survival
+            .snapshots
+            .iter()
+            .map(|s| {
+                (s.label.as_str(), s.line_count, s.introduced, s.surviving, s.cohorts.as_slice())
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("v1", 4, 4, 2, &[4][..]),
+                ("v2", 5, 3, 4, &[2, 3][..]),
+                ("v3", 5, 1, 5, &[2, 2, 1][..]),
+            ]
+        );
+        assert_eq!(survival.snapshots[0].survival_rate, 0.5);
+        assert_eq!(survival.line_introduced, vec![0, 0, 1, 1, 2, -1]);
+    }
+
+    #[test]
+    fn test_line_survival_empty() {
+        let survival = line_survival(&[]);
+        assert!(survival.snapshots.is_empty());
+        assert!(survival.line_introduced.is_empty());
+    }
+}
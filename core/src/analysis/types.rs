@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::blame::BlameEntry;
+
+/// A blame of a file at one point in its history, such as a release tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameSnapshot {
+    /// Name of the revision, e.g. `v1.2.0`.
+    pub label: String,
+    pub entries: Vec<BlameEntry>,
+}
+
+/// How the lines of one snapshot carry through to the latest one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotSurvival {
+    pub label: String,
+    /// Committed lines in this snapshot.
+    pub line_count: u32,
+    /// Lines first seen in this snapshot.
+    pub introduced: u32,
+    /// Lines of this snapshot still present in the latest one.
+    pub surviving: u32,
+    /// `surviving / line_count`, or 1.0 for an empty snapshot.
+    pub survival_rate: f64,
+    /// Lines of this snapshot by the snapshot that introduced them:
+    /// `cohorts[j]` counts the lines first seen in snapshot `j`.
+    pub cohorts: Vec<u32>,
+}
+
+/// Line survival across an ordered series of snapshots of a file, for a
+/// "code decay" chart.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineSurvival {
+    /// One per snapshot, oldest first.
+    pub snapshots: Vec<SnapshotSurvival>,
+    /// Index of the snapshot that introduced each line of the latest
+    /// snapshot, starting at line 1; -1 for uncommitted lines and lines the
+    /// blame does not cover.
+    pub line_introduced: Vec<i32>,
+}
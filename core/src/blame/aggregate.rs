@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use super::ownership::tally_ownership;
+use super::types::{BlameEntry, DirectoryOwnership, FileBlame};
+
+/// Roll per-file blames up into ownership per directory, for a "who owns
+/// this folder" treemap.
+///
+/// Each directory counts every file beneath it, so the root (with an empty
+/// path) covers all of `files`. Paths may use `/` or `\` and a leading
+/// `./`. Directories are returned in path order, parents first.
+pub fn aggregate_ownership(files: &[FileBlame]) -> Vec<DirectoryOwnership> {
+    let mut directories: BTreeMap<String, (u32, Vec<&BlameEntry>)> = BTreeMap::new();
+
+    for file in files {
+        let path = file.path.replace('\\', "/");
+        let parts: Vec<&str> = path
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+        // Every proper prefix of the path is a directory, the root included
+        for depth in 0..parts.len().max(1) {
+            let (file_count, entries) = directories.entry(parts[..depth].join("/")).or_default();
+            *file_count += 1;
+            entries.extend(&file.entries);
+        }
+    }
+
+    directories
+        .into_iter()
+        .map(|(path, (file_count, entries))| DirectoryOwnership {
+            path,
+            file_count,
+            ownership: tally_ownership(entries),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    fn file(path: &str, authors: &[(&str, u32)]) -> FileBlame {
+        let entries = authors
+            .iter()
+            .map(|&(email, num_lines)| BlameEntry {
+                num_lines,
+                author: Signature {
+                    name: email.to_string(),
+                    email: email.to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+        FileBlame {
+            path: path.to_string(),
+            entries,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_ownership_by_directory() {
+        let files = vec![
+            file("README.md", &[("ann@x", 2)]),
+            file("src/lib.rs", &[("ann@x", 1), ("ben@x", 3)]),
+            file("./src\\blame\\mod.rs", &[("ben@x", 4)]),
+        ];
+        let directories = aggregate_ownership(&files);
+
+        let summary: Vec<(&str, u32, u32)> = directories
+            .iter()
+            .map(|d| (d.path.as_str(), d.file_count, d.ownership.total_lines))
+            .collect();
+        assert_eq!(summary, vec![("", 3, 10), ("src", 2, 8), ("src/blame", 1, 4)]);
+
+        let src = &directories[1].ownership;
+        assert_eq!(src.owners[0].email, "ben@x");
+        assert_eq!(src.owners[0].lines, 7);
+        assert_eq!(src.owners[1].percent, 12.5);
+    }
+
+    #[test]
+    fn test_aggregate_ownership_empty() {
+        assert!(aggregate_ownership(&[]).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use super::types::BlameEntry;
+
+/// Default size budget: 32 MiB of raw blame output.
+pub const DEFAULT_BUDGET: usize = 32 * 1024 * 1024;
+
+struct CachedBlame {
+    entries: Vec<BlameEntry>,
+    /// Size charged against the budget: the length of the raw output.
+    size: usize,
+    last_used: u64,
+}
+
+/// Parsed blames keyed by (path, revision), evicting the least recently
+/// used once their raw output exceeds the size budget.
+pub struct BlameCache {
+    blames: HashMap<(String, String), CachedBlame>,
+    budget: usize,
+    total_size: usize,
+    clock: u64,
+}
+
+impl Default for BlameCache {
+    fn default() -> Self {
+        BlameCache::new(DEFAULT_BUDGET)
+    }
+}
+
+impl BlameCache {
+    pub fn new(budget: usize) -> Self {
+        BlameCache {
+            blames: HashMap::new(),
+            budget,
+            total_size: 0,
+            clock: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.blames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blames.is_empty()
+    }
+
+    /// Bytes of raw output currently charged against the budget.
+    pub fn total_size(&self) -> usize {
+        self.total_size
+    }
+
+    /// Change the budget, evicting as needed. Returns the number evicted.
+    pub fn set_budget(&mut self, budget: usize) -> usize {
+        self.budget = budget;
+        self.evict()
+    }
+
+    /// Cache `entries` parsed from `size` bytes of output, replacing any
+    /// blame for the same key. A blame larger than the whole budget is not
+    /// cached. Returns whether it was cached and the number evicted.
+    pub fn put(
+        &mut self,
+        path: &str,
+        revision: &str,
+        entries: Vec<BlameEntry>,
+        size: usize,
+    ) -> (bool, usize) {
+        self.remove(path, revision);
+        if size > self.budget {
+            return (false, 0);
+        }
+
+        self.clock += 1;
+        self.total_size += size;
+        self.blames.insert(
+            (path.to_string(), revision.to_string()),
+            CachedBlame {
+                entries,
+                size,
+                last_used: self.clock,
+            },
+        );
+        (true, self.evict())
+    }
+
+    /// Cached entries for the key, marking them recently used.
+    pub fn get(&mut self, path: &str, revision: &str) -> Option<&[BlameEntry]> {
+        self.clock += 1;
+        let cached = self.blames.get_mut(&(path.to_string(), revision.to_string()))?;
+        cached.last_used = self.clock;
+        Some(&cached.entries)
+    }
+
+    /// Drop cached blames: one revision of `path`, every revision of `path`
+    /// when `revision` is empty, or everything when both are empty.
+    /// Returns the number removed.
+    pub fn invalidate(&mut self, path: &str, revision: &str) -> usize {
+        let keys: Vec<(String, String)> = self
+            .blames
+            .keys()
+            .filter(|(p, r)| {
+                (path.is_empty() || p == path) && (revision.is_empty() || r == revision)
+            })
+            .cloned()
+            .collect();
+        for (p, r) in &keys {
+            self.remove(p, r);
+        }
+        keys.len()
+    }
+
+    fn remove(&mut self, path: &str, revision: &str) {
+        if let Some(old) = self.blames.remove(&(path.to_string(), revision.to_string())) {
+            self.total_size -= old.size;
+        }
+    }
+
+    /// Evict least recently used blames until within budget.
+    fn evict(&mut self) -> usize {
+        let mut evicted = 0;
+        while self.total_size > self.budget {
+            let oldest = self
+                .blames
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some((path, revision)) => {
+                    self.remove(&path, &revision);
+                    evicted += 1;
+                }
+                None => break,
+            }
+        }
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(sha: &str) -> Vec<BlameEntry> {
+        vec![BlameEntry {
+            sha: sha.to_string(),
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn test_cache_put_get_replace() {
+        let mut cache = BlameCache::new(100);
+        assert_eq!(cache.put("a.rs", "HEAD", entries("1"), 10), (true, 0));
+        assert_eq!(cache.put("a.rs", "HEAD", entries("2"), 20), (true, 0));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.total_size(), 20);
+        assert_eq!(cache.get("a.rs", "HEAD").unwrap()[0].sha, "2");
+        assert!(cache.get("a.rs", "v1").is_none());
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = BlameCache::new(100);
+        cache.put("a.rs", "r", entries("a"), 40);
+        cache.put("b.rs", "r", entries("b"), 40);
+        cache.get("a.rs", "r");
+        assert_eq!(cache.put("c.rs", "r", entries("c"), 40), (true, 1));
+        assert!(cache.get("b.rs", "r").is_none());
+        assert!(cache.get("a.rs", "r").is_some());
+
+        // Larger than the whole budget: not cached, nothing evicted
+        assert_eq!(cache.put("huge.rs", "r", entries("h"), 101), (false, 0));
+        assert_eq!(cache.len(), 2);
+
+        // `a.rs` was used more recently than `c.rs`
+        assert_eq!(cache.set_budget(40), 1);
+        assert!(cache.get("a.rs", "r").is_some());
+    }
+
+    #[test]
+    fn test_cache_invalidate() {
+        let mut cache = BlameCache::new(100);
+        cache.put("a.rs", "r1", entries("1"), 1);
+        cache.put("a.rs", "r2", entries("2"), 1);
+        cache.put("b.rs", "r1", entries("3"), 1);
+
+        assert_eq!(cache.invalidate("a.rs", "r1"), 1);
+        assert_eq!(cache.invalidate("a.rs", ""), 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.invalidate("", ""), 1);
+        assert!(cache.is_empty());
+        assert_eq!(cache.total_size(), 0);
+    }
+}
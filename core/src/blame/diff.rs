@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use super::types::{BlameDiff, BlameEntry, OwnershipChange};
+
+/// One blamed line: its final line number and where it originated.
+struct BlamedLine<'a> {
+    line: u32,
+    sha: &'a str,
+    origin: (&'a str, &'a str, u32),
+}
+
+fn expand(entries: &[BlameEntry]) -> Vec<BlamedLine<'_>> {
+    let mut lines: Vec<BlamedLine> = entries
+        .iter()
+        .flat_map(|e| {
+            (0..e.num_lines).map(move |i| BlamedLine {
+                line: e.final_line.saturating_add(i),
+                sha: e.sha.as_str(),
+                origin: (e.sha.as_str(), e.filename.as_str(), e.orig_line.saturating_add(i)),
+            })
+        })
+        .collect();
+    lines.sort_by_key(|l| l.line);
+    lines
+}
+
+/// Compare two blames of the same file and report the lines whose owning
+/// commit changed.
+///
+/// A line is unchanged when both blames trace it to the same line of the
+/// same commit, wherever it now sits. Between unchanged lines, new lines
+/// are paired in order with the old lines they replaced; surplus new
+/// lines are insertions and surplus old lines are removals.
+pub fn diff_blame(old: &[BlameEntry], new: &[BlameEntry]) -> BlameDiff {
+    let old_lines = expand(old);
+    let new_lines = expand(new);
+    let old_by_origin: HashMap<(&str, &str, u32), usize> = old_lines
+        .iter()
+        .enumerate()
+        .map(|(i, l)| (l.origin, i))
+        .collect();
+
+    let mut old_used = vec![false; old_lines.len()];
+    let matches: Vec<Option<usize>> = new_lines
+        .iter()
+        .map(|l| old_by_origin.get(&l.origin).copied())
+        .collect();
+    for &i in matches.iter().flatten() {
+        old_used[i] = true;
+    }
+
+    let mut diff = BlameDiff {
+        changed: Vec::new(),
+        removed_lines: Vec::new(),
+        unchanged_count: matches.iter().flatten().count() as u32,
+    };
+
+    // Walk runs of unmatched new lines between anchors
+    let mut anchor = 0; // index into old_lines just past the last match
+    let mut run_start = 0;
+    for i in 0..=new_lines.len() {
+        let next_anchor = match matches.get(i) {
+            Some(Some(old_index)) => *old_index,
+            Some(None) => continue,
+            None => old_lines.len(),
+        };
+
+        let replaced: Vec<usize> = (anchor..next_anchor.max(anchor))
+            .filter(|&j| !old_used[j])
+            .collect();
+        let mut replaced = replaced.into_iter();
+        for new_line in &new_lines[run_start..i] {
+            let old_line = replaced.next().map(|j| {
+                old_used[j] = true;
+                &old_lines[j]
+            });
+            diff.changed.push(OwnershipChange {
+                line: new_line.line,
+                old_line: old_line.map(|l| l.line),
+                old_sha: old_line.map(|l| l.sha.to_string()),
+                new_sha: new_line.sha.to_string(),
+            });
+        }
+
+        anchor = anchor.max(next_anchor + 1);
+        run_start = i + 1;
+    }
+
+    diff.removed_lines = old_lines
+        .iter()
+        .zip(&old_used)
+        .filter(|(_, &used)| !used)
+        .map(|(l, _)| l.line)
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entry;
+
+    #[test]
+    fn test_diff_blame_pairs_replaced_lines() {
+        // Old: lines 1-4 from A. New: line 2 rewritten by B, a line inserted
+        // after line 3 by B, and line 4 deleted.
+        let old = vec![entry("A", 1, 1, 4)];
+        let new = vec![
+            entry("A", 1, 1, 1),
+            entry("B", 2, 2, 1),
+            entry("A", 3, 3, 1),
+            entry("B", 4, 4, 1),
+        ];
+        let diff = diff_blame(&old, &new);
+
+        assert_eq!(diff.unchanged_count, 2);
+        assert_eq!(
+            diff.changed,
+            vec![
+                OwnershipChange {
+                    line: 2,
+                    old_line: Some(2),
+                    old_sha: Some("A".to_string()),
+                    new_sha: "B".to_string(),
+                },
+                OwnershipChange {
+                    line: 4,
+                    old_line: Some(4),
+                    old_sha: Some("A".to_string()),
+                    new_sha: "B".to_string(),
+                },
+            ]
+        );
+        assert!(diff.removed_lines.is_empty());
+    }
+
+    #[test]
+    fn test_diff_blame_insertions_and_removals() {
+        let old = vec![entry("A", 1, 1, 3)];
+        // Line 2 removed, two lines inserted at the top
+        let new = vec![entry("C", 1, 1, 2), entry("A", 1, 3, 1), entry("A", 3, 4, 1)];
+        let diff = diff_blame(&old, &new);
+
+        assert_eq!(diff.unchanged_count, 2);
+        let changed: Vec<(u32, Option<u32>)> =
+            diff.changed.iter().map(|c| (c.line, c.old_line)).collect();
+        assert_eq!(changed, vec![(1, None), (2, None)]);
+        assert_eq!(diff.removed_lines, vec![2]);
+    }
+}
@@ -0,0 +1,91 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::types::{BlameEntry, CommitGroup, GroupSort, LineRange};
+
+/// Gather entries into one group per commit, with its line ranges in line
+/// order, and sort the groups by `sort`. Ties are broken by SHA.
+/// Uncommitted lines are left out, as they have no commit.
+pub fn group_by_commit(entries: &[BlameEntry], sort: GroupSort) -> Vec<CommitGroup> {
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut groups: Vec<CommitGroup> = Vec::new();
+
+    for entry in entries.iter().filter(|e| !e.is_uncommitted) {
+        let i = *index.entry(entry.sha.as_str()).or_insert_with(|| {
+            groups.push(CommitGroup {
+                sha: entry.sha.clone(),
+                short_sha: entry.short_sha.clone(),
+                author: entry.author.clone(),
+                committer: entry.committer.clone(),
+                summary: entry.summary.clone(),
+                line_count: 0,
+                ranges: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[i].line_count += entry.num_lines;
+        groups[i].ranges.push(LineRange {
+            orig_line: entry.orig_line,
+            final_line: entry.final_line,
+            num_lines: entry.num_lines,
+        });
+    }
+
+    for group in &mut groups {
+        group.ranges.sort_by_key(|r| r.final_line);
+    }
+    groups.sort_by(|a, b| a.sha.cmp(&b.sha));
+    match sort {
+        GroupSort::Date => groups.sort_by_key(|g| Reverse(g.author.time)),
+        GroupSort::Lines => groups.sort_by_key(|g| Reverse(g.line_count)),
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    fn entry(sha: &str, time: u64, final_line: u32, num_lines: u32) -> BlameEntry {
+        BlameEntry {
+            sha: sha.to_string(),
+            final_line,
+            num_lines,
+            author: Signature {
+                time,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn entries() -> Vec<BlameEntry> {
+        vec![
+            entry("old", 100, 5, 1),
+            entry("new", 300, 1, 2),
+            entry("old", 100, 3, 2),
+            entry("mid", 200, 6, 1),
+        ]
+    }
+
+    #[test]
+    fn test_group_by_date() {
+        let groups = group_by_commit(&entries(), GroupSort::Date);
+        let shas: Vec<&str> = groups.iter().map(|g| g.sha.as_str()).collect();
+        assert_eq!(shas, vec!["new", "mid", "old"]);
+
+        let old = &groups[2];
+        assert_eq!(old.line_count, 3);
+        let starts: Vec<u32> = old.ranges.iter().map(|r| r.final_line).collect();
+        assert_eq!(starts, vec![3, 5]);
+    }
+
+    #[test]
+    fn test_group_by_lines() {
+        let groups = group_by_commit(&entries(), GroupSort::Lines);
+        let counts: Vec<(&str, u32)> =
+            groups.iter().map(|g| (g.sha.as_str(), g.line_count)).collect();
+        assert_eq!(counts, vec![("old", 3), ("new", 2), ("mid", 1)]);
+    }
+}
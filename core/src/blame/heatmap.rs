@@ -0,0 +1,210 @@
+use std::ops::Range;
+
+use super::types::{AgeRange, BlameEntry, BucketSpec, Heatmap, LineAges};
+use crate::error::{ErrorCode, ErrorInfo};
+
+/// Longest file mapped. Lines past it, which only a malformed header would
+/// claim, are left out instead of allocated for.
+const MAX_LINES: u32 = 1 << 24;
+
+/// Assign each blamed line to an age bucket, measuring the age of a line
+/// from its author time.
+///
+/// Lines dated after `now`, and uncommitted lines, count as age 0.
+pub fn compute_heatmap(entries: &[BlameEntry], spec: &BucketSpec) -> Result<Heatmap, ErrorInfo> {
+    if spec.boundaries.windows(2).any(|w| w[0] >= w[1]) {
+        let message = "Bucket boundaries must be increasing";
+        return Err(ErrorInfo::with_code(ErrorCode::BadArgument, message));
+    }
+
+    let now = spec.now.unwrap_or_else(|| committed_times(entries).max().unwrap_or(0));
+    let mut line_buckets = vec![-1; line_count(entries)];
+    for entry in entries {
+        let age = if entry.is_uncommitted {
+            0
+        } else {
+            now.saturating_sub(entry.author.time)
+        };
+        let bucket = spec.boundaries.partition_point(|&limit| age >= limit) as i32;
+        for line in lines(entry) {
+            line_buckets[line as usize - 1] = bucket;
+        }
+    }
+
+    Ok(Heatmap {
+        line_buckets,
+        boundaries: spec.boundaries.clone(),
+        now,
+    })
+}
+
+/// Score each blamed line's author time from 0.0 (newest) to 1.0 (oldest)
+/// within `range`, for mapping straight onto a color ramp.
+///
+/// When the range is a single instant every line scores 0.0, as do
+/// uncommitted lines.
+pub fn compute_line_ages(entries: &[BlameEntry], range: &AgeRange) -> LineAges {
+    let times = || committed_times(entries);
+    let oldest = range.oldest.unwrap_or_else(|| times().min().unwrap_or(0));
+    let newest = range.newest.unwrap_or_else(|| times().max().unwrap_or(0));
+    let span = newest.saturating_sub(oldest) as f64;
+
+    let mut ages = vec![-1.0; line_count(entries)];
+    for entry in entries {
+        let age = if span == 0.0 || entry.is_uncommitted {
+            0.0
+        } else {
+            (newest as f64 - entry.author.time as f64) / span
+        };
+        let age = age.clamp(0.0, 1.0) as f32;
+        for line in lines(entry) {
+            ages[line as usize - 1] = age;
+        }
+    }
+
+    LineAges {
+        ages,
+        oldest,
+        newest,
+    }
+}
+
+/// Author times of the committed entries.
+fn committed_times(entries: &[BlameEntry]) -> impl Iterator<Item = u64> + '_ {
+    entries.iter().filter(|e| !e.is_uncommitted).map(|e| e.author.time)
+}
+
+/// The 1-based line numbers `entry` covers, up to `MAX_LINES`.
+fn lines(entry: &BlameEntry) -> Range<u32> {
+    let end = entry.final_line.saturating_add(entry.num_lines);
+    entry.final_line.max(1)..end.min(MAX_LINES + 1)
+}
+
+/// Last line number covered by `entries`.
+fn line_count(entries: &[BlameEntry]) -> usize {
+    entries
+        .iter()
+        .map(lines)
+        .filter(|lines| !lines.is_empty())
+        .map(|lines| lines.end - 1)
+        .max()
+        .unwrap_or(0) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn entry(final_line: u32, num_lines: u32, time: u64) -> BlameEntry {
+        BlameEntry {
+            final_line,
+            num_lines,
+            author: Signature {
+                time,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_heatmap_default_buckets() {
+        let now = 1000 * DAY;
+        let entries = vec![
+            entry(1, 2, now - DAY),
+            entry(3, 1, now - 10 * DAY),
+            entry(5, 1, now - 100 * DAY),
+            entry(6, 1, now - 1000 * DAY),
+        ];
+        let spec = BucketSpec {
+            now: Some(now),
+            ..Default::default()
+        };
+        let heatmap = compute_heatmap(&entries, &spec).unwrap();
+        assert_eq!(heatmap.line_buckets, vec![0, 0, 1, -1, 2, 3]);
+        assert_eq!(heatmap.boundaries.len(), 3);
+    }
+
+    #[test]
+    fn test_heatmap_now_defaults_to_newest_line() {
+        let entries = vec![entry(1, 1, 50 * DAY), entry(2, 1, 10 * DAY)];
+        let spec = BucketSpec {
+            now: None,
+            boundaries: vec![DAY],
+        };
+        let heatmap = compute_heatmap(&entries, &spec).unwrap();
+        assert_eq!(heatmap.now, 50 * DAY);
+        assert_eq!(heatmap.line_buckets, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_line_ages_against_file_range() {
+        let entries = vec![entry(1, 1, 300), entry(2, 2, 100), entry(5, 1, 200)];
+        let ages = compute_line_ages(&entries, &AgeRange::default());
+        assert_eq!((ages.oldest, ages.newest), (100, 300));
+        assert_eq!(ages.ages, vec![0.0, 1.0, 1.0, -1.0, 0.5]);
+    }
+
+    #[test]
+    fn test_line_ages_supplied_range_clamps() {
+        let entries = vec![entry(1, 1, 50), entry(2, 1, 150), entry(3, 1, 400)];
+        let range = AgeRange {
+            oldest: Some(100),
+            newest: Some(300),
+        };
+        let ages = compute_line_ages(&entries, &range);
+        assert_eq!(ages.ages, vec![1.0, 0.75, 0.0]);
+
+        let single = compute_line_ages(&entries[..1], &AgeRange::default());
+        assert_eq!(single.ages, vec![0.0]);
+    }
+
+    #[test]
+    fn test_uncommitted_lines_are_newest() {
+        let mut uncommitted = entry(2, 1, 0);
+        uncommitted.is_uncommitted = true;
+        let entries = vec![entry(1, 1, 10 * DAY), uncommitted, entry(3, 1, 20 * DAY)];
+
+        let ages = compute_line_ages(&entries, &AgeRange::default());
+        assert_eq!((ages.oldest, ages.newest), (10 * DAY, 20 * DAY));
+        assert_eq!(ages.ages, vec![1.0, 0.0, 0.0]);
+
+        let spec = BucketSpec {
+            now: None,
+            boundaries: vec![DAY],
+        };
+        let heatmap = compute_heatmap(&entries, &spec).unwrap();
+        assert_eq!(heatmap.now, 20 * DAY);
+        assert_eq!(heatmap.line_buckets, vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn test_malformed_line_numbers() {
+        // `<sha> 1 4294967295 2` and `<sha> 1 3000000000 1` headers
+        let entries = vec![
+            entry(1, 2, 10 * DAY),
+            entry(u32::MAX, 2, 20 * DAY),
+            entry(3_000_000_000, 1, 20 * DAY),
+        ];
+        let spec = BucketSpec {
+            now: Some(20 * DAY),
+            boundaries: vec![DAY],
+        };
+        let heatmap = compute_heatmap(&entries, &spec).unwrap();
+        assert_eq!(heatmap.line_buckets, vec![1, 1]);
+        let ages = compute_line_ages(&entries, &AgeRange::default());
+        assert_eq!(ages.ages, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_heatmap_rejects_unordered_boundaries() {
+        let spec = BucketSpec {
+            now: None,
+            boundaries: vec![DAY, DAY],
+        };
+        assert!(compute_heatmap(&[], &spec).is_err());
+    }
+}
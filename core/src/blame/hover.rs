@@ -0,0 +1,166 @@
+use super::types::{BlameEntry, HoverOptions};
+use crate::graph::date::{format_date, relative_date};
+
+/// Hover layout used when no template is given.
+pub const DEFAULT_HOVER_TEMPLATE: &str =
+    "{avatar}**{author}**, {relativeDate} ({date})\n\n{summary}\n\n`{shortSha}`";
+
+/// Hover for uncommitted lines when no `uncommitted_template` is given.
+pub const DEFAULT_UNCOMMITTED_TEMPLATE: &str = "*Uncommitted changes*";
+
+/// Render a Markdown hover for a blame entry.
+///
+/// Placeholders: `{sha}`, `{shortSha}`, `{author}`, `{authorEmail}`,
+/// `{committer}`, `{date}` (author date as `YYYY-MM-DD` in the author's
+/// zone), `{relativeDate}`, `{summary}`, `{filename}` and `{avatar}` (an
+/// image followed by a space, or nothing without `avatar_url`). Names and
+/// summaries are escaped; unknown placeholders are left as they are.
+/// Uncommitted entries use `uncommitted_template` instead.
+pub fn render_hover(entry: &BlameEntry, options: &HoverOptions) -> String {
+    let date = format_date(entry.author.time, entry.author.tz_offset_min);
+    let relative = match options.now {
+        Some(now) => relative_date(entry.author.time, now),
+        None => date.clone(),
+    };
+    let avatar = match &options.avatar_url {
+        Some(url) => {
+            let url = url
+                .replace("{email}", &encode_uri_component(&entry.author.email))
+                .replace("{name}", &encode_uri_component(&entry.author.name));
+            format!("![{}]({}) ", escape_markdown(&entry.author.name), url)
+        }
+        None => String::new(),
+    };
+
+    let values: [(&str, String); 10] = [
+        ("sha", entry.sha.clone()),
+        ("shortSha", entry.short_sha.clone()),
+        ("author", escape_markdown(&entry.author.name)),
+        ("authorEmail", escape_markdown(&entry.author.email)),
+        ("committer", escape_markdown(&entry.committer.name)),
+        ("date", date),
+        ("relativeDate", relative),
+        ("summary", escape_markdown(&entry.summary)),
+        ("filename", escape_markdown(&entry.filename)),
+        ("avatar", avatar),
+    ];
+
+    // Substitute in one pass so values containing braces are not expanded
+    let template = if entry.is_uncommitted {
+        options.uncommitted_template.as_deref().unwrap_or(DEFAULT_UNCOMMITTED_TEMPLATE)
+    } else {
+        options.template.as_deref().unwrap_or(DEFAULT_HOVER_TEMPLATE)
+    };
+    let mut out = String::with_capacity(template.len() + 64);
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| values.iter().find(|(name, _)| *name == &after[..end]));
+        match value {
+            Some((name, value)) => {
+                out.push_str(value);
+                rest = &after[name.len() + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Backslash-escape characters that Markdown would interpret.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_{}[]()<>#+-.!|~".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Percent-encode everything but unreserved URI characters.
+fn encode_uri_component(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    fn entry() -> BlameEntry {
+        BlameEntry {
+            sha: "abcdef0123".to_string(),
+            short_sha: "abcdef0".to_string(),
+            author: Signature {
+                name: "Ann *Dev*".to_string(),
+                email: "ann+x@example.com".to_string(),
+                time: 1700000000,
+                tz_offset_min: 0,
+            },
+            summary: "Fix {x} in [parser]".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_hover() {
+        let options = HoverOptions {
+            now: Some(1700000000 + 2 * 86_400),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_hover(&entry(), &options),
+            "**Ann \\*Dev\\***, 2 days ago (2023-11-14)\n\nFix \\{x\\} in \\[parser\\]\n\n`abcdef0`"
+        );
+    }
+
+    #[test]
+    fn test_custom_template_and_avatar() {
+        let options = HoverOptions {
+            template: Some("{avatar}{shortSha} {relativeDate} {unknown}".to_string()),
+            now: None,
+            avatar_url: Some("https://avatars.test/?u={email}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_hover(&entry(), &options),
+            "![Ann \\*Dev\\*](https://avatars.test/?u=ann%2Bx%40example.com) abcdef0 2023-11-14 {unknown}"
+        );
+    }
+
+    #[test]
+    fn test_uncommitted_hover() {
+        let uncommitted = BlameEntry {
+            sha: "0".repeat(40),
+            filename: "src/a_b.rs".to_string(),
+            is_uncommitted: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_hover(&uncommitted, &HoverOptions::default()),
+            DEFAULT_UNCOMMITTED_TEMPLATE
+        );
+        let options = HoverOptions {
+            uncommitted_template: Some("Unsaved: {filename}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(render_hover(&uncommitted, &options), "Unsaved: src/a\\_b\\.rs");
+    }
+}
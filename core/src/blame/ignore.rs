@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use super::types::BlameEntry;
+use crate::graph::sha::is_hex_sha;
+
+/// Longest chain of ignored commits followed for one entry, as a guard
+/// against cycles in malformed input.
+const MAX_HOPS: usize = 64;
+
+/// Shortest abbreviated rev that is matched, git's default abbreviation
+/// length; shorter prefixes would ignore unrelated commits.
+const MIN_PREFIX: usize = 7;
+
+/// Parse `.git-blame-ignore-revs` contents, or a list of its lines: one
+/// SHA per line, with `#` comments and blank lines skipped. Entries that are
+/// not hex, or shorter than 7 characters, are dropped.
+pub fn parse_ignore_revs<S: AsRef<str>>(lines: &[S]) -> Vec<String> {
+    lines
+        .iter()
+        .flat_map(|item| item.as_ref().lines())
+        .map(|line| line.split('#').next().unwrap_or("").trim().to_lowercase())
+        .filter(|rev| rev.len() >= MIN_PREFIX && is_hex_sha(rev))
+        .collect()
+}
+
+/// Reattribute entries whose commit is in `ignore_revs` to the commit that
+/// last touched the file before it, following the `previous` chain through
+/// further ignored commits, like `git blame --ignore-rev`.
+///
+/// Revs may be abbreviated to no fewer than 7 characters; shorter ones are
+/// skipped. A reattributed entry takes the details of the previous commit
+/// from another entry for that commit, so this is only possible when the
+/// previous commit also appears in the blame. Its `orig_line` is cleared to
+/// 0, since where the lines sat in that commit is not known without
+/// diffing. Entries that cannot be reattributed keep their commit and are
+/// flagged `is_unblamable`. Either way `ignored_sha` records the ignored
+/// commit. Returns the number of unblamable entries.
+pub fn apply_ignore_revs(entries: &mut [BlameEntry], ignore_revs: &[String]) -> usize {
+    let ignore_revs: Vec<&str> = ignore_revs
+        .iter()
+        .map(String::as_str)
+        .filter(|rev| rev.len() >= MIN_PREFIX)
+        .collect();
+    if ignore_revs.is_empty() {
+        return 0;
+    }
+    let is_ignored = |sha: &str| ignore_revs.iter().any(|rev| sha.starts_with(rev));
+
+    // Details of each commit, taken from its first entry
+    let mut details: HashMap<String, BlameEntry> = HashMap::new();
+    for entry in entries.iter() {
+        details.entry(entry.sha.clone()).or_insert_with(|| BlameEntry {
+            lines: Vec::new(),
+            ..entry.clone()
+        });
+    }
+
+    let mut unblamable = 0;
+    for entry in entries.iter_mut() {
+        if !is_ignored(&entry.sha) {
+            continue;
+        }
+
+        let ignored_sha = entry.sha.clone();
+        let mut current = entry.clone();
+        let mut resolved = false;
+        for _ in 0..MAX_HOPS {
+            let previous = match current.previous_sha.as_deref().and_then(|p| details.get(p)) {
+                Some(previous) => previous,
+                None => break,
+            };
+            current.filename = current.previous_filename.clone().unwrap_or_default();
+            current.orig_line = 0;
+            current.sha = previous.sha.clone();
+            current.short_sha = previous.short_sha.clone();
+            current.author = previous.author.clone();
+            current.committer = previous.committer.clone();
+            current.summary = previous.summary.clone();
+            current.previous_sha = previous.previous_sha.clone();
+            current.previous_filename = previous.previous_filename.clone();
+            current.is_boundary = previous.is_boundary;
+            if !is_ignored(&current.sha) {
+                resolved = true;
+                break;
+            }
+        }
+
+        if resolved {
+            *entry = current;
+        } else {
+            entry.is_unblamable = true;
+            unblamable += 1;
+        }
+        entry.ignored_sha = Some(ignored_sha);
+    }
+    unblamable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    fn entry(sha: &str, final_line: u32, author: &str, previous: Option<&str>) -> BlameEntry {
+        BlameEntry {
+            sha: sha.to_string(),
+            orig_line: final_line,
+            final_line,
+            num_lines: 1,
+            author: Signature {
+                name: author.to_string(),
+                ..Default::default()
+            },
+            filename: "f.rs".to_string(),
+            previous_sha: previous.map(String::from),
+            previous_filename: previous.map(|_| "f.rs".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_ignore_revs() {
+        let revs =
+            parse_ignore_revs(&["# Formatting\nABCDEF12 # rustfmt\n\nnot-a-sha", "1234567", "123"]);
+        assert_eq!(revs, vec!["abcdef12", "1234567"]);
+    }
+
+    #[test]
+    fn test_ignored_commit_reattributed_through_chain() {
+        let mut entries = vec![
+            entry("aaaa1111", 1, "Alice", None),
+            entry("ffff0000", 2, "Formatter", Some("eeee0000")),
+            entry("eeee0000", 3, "Reindent", Some("aaaa1111")),
+        ];
+        let revs = vec!["ffff000".to_string(), "eeee0000".to_string()];
+        assert_eq!(apply_ignore_revs(&mut entries, &revs), 0);
+
+        assert_eq!(entries[1].sha, "aaaa1111");
+        assert_eq!(entries[1].author.name, "Alice");
+        assert_eq!(entries[1].final_line, 2);
+        assert_eq!(entries[1].orig_line, 0);
+        assert_eq!(entries[0].orig_line, 1);
+        assert_eq!(entries[1].ignored_sha.as_deref(), Some("ffff0000"));
+        assert_eq!(entries[2].sha, "aaaa1111");
+        assert!(!entries[2].is_unblamable);
+        assert_eq!(entries[0].ignored_sha, None);
+    }
+
+    #[test]
+    fn test_unresolvable_entries_are_flagged() {
+        let mut entries = vec![
+            entry("ffff0000", 1, "Formatter", Some("99990000")),
+            entry("ffff1111", 2, "Formatter", None),
+        ];
+        let revs = vec!["ffff".to_string()];
+        assert_eq!(apply_ignore_revs(&mut entries, &revs), 0);
+        assert!(entries.iter().all(|e| e.ignored_sha.is_none()));
+
+        let revs = vec!["ffff000".to_string(), "ffff111".to_string()];
+        assert_eq!(apply_ignore_revs(&mut entries, &revs), 2);
+        assert!(entries.iter().all(|e| e.is_unblamable));
+        assert_eq!(entries[0].sha, "ffff0000");
+        assert_eq!(entries[0].orig_line, 1);
+    }
+}
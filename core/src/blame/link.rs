@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use super::types::BlameEntry;
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+/// Annotate blame entries with the row, lane and color of their commit in
+/// `layout`, so the gutter can match the graph and jump to the commit.
+///
+/// Entries whose commit is not in the layout have any earlier annotation
+/// cleared. Returns the number of entries that were linked.
+pub fn link_to_layout(entries: &mut [BlameEntry], layout: &LayoutResult) -> usize {
+    let nodes: HashMap<&str, &LayoutNode> =
+        layout.nodes.iter().map(|n| (n.sha.as_str(), n)).collect();
+
+    let mut linked = 0;
+    for entry in entries {
+        let node = nodes.get(entry.sha.as_str());
+        entry.layout_row = node.map(|n| n.row);
+        entry.layout_lane = node.map(|n| n.lane);
+        entry.color_index = node.map(|n| n.color_index);
+        if node.is_some() {
+            linked += 1;
+        }
+    }
+    linked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_to_layout() {
+        let layout = LayoutResult {
+            nodes: vec![LayoutNode {
+                sha: "aaa".to_string(),
+                row: 3,
+                lane: 1,
+                color_index: 5,
+                ..Default::default()
+            }],
+            edges: Vec::new(),
+            total_count: 1,
+        };
+        let mut entries = vec![
+            BlameEntry {
+                sha: "aaa".to_string(),
+                ..Default::default()
+            },
+            BlameEntry {
+                sha: "bbb".to_string(),
+                layout_row: Some(9),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(link_to_layout(&mut entries, &layout), 1);
+        assert_eq!(entries[0].layout_row, Some(3));
+        assert_eq!(entries[0].layout_lane, Some(1));
+        assert_eq!(entries[0].color_index, Some(5));
+        assert_eq!(entries[1].layout_row, None);
+    }
+}
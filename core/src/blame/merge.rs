@@ -0,0 +1,57 @@
+use super::types::BlameEntry;
+
+/// Coalesce consecutive entries from the same commit and file into one
+/// range, ordered by line.
+///
+/// A merged entry keeps the `orig_line` of its first part, so it only
+/// describes the original position of its first line.
+pub fn merge_adjacent(entries: &[BlameEntry]) -> Vec<BlameEntry> {
+    let mut sorted: Vec<&BlameEntry> = entries.iter().collect();
+    sorted.sort_by_key(|e| e.final_line);
+
+    let mut merged: Vec<BlameEntry> = Vec::with_capacity(sorted.len());
+    for entry in sorted {
+        if let Some(last) = merged.last_mut() {
+            if last.sha == entry.sha
+                && last.filename == entry.filename
+                && last.final_line.saturating_add(last.num_lines) == entry.final_line
+            {
+                last.num_lines = last.num_lines.saturating_add(entry.num_lines);
+                last.lines.extend(entry.lines.iter().cloned());
+                continue;
+            }
+        }
+        merged.push(entry.clone());
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entry;
+
+    #[test]
+    fn test_merge_adjacent_same_commit() {
+        let entries = vec![
+            entry("A", 7, 3, 1),
+            entry("A", 1, 1, 2),
+            entry("B", 1, 4, 1),
+            entry("A", 9, 5, 2),
+        ];
+        let merged = merge_adjacent(&entries);
+
+        let ranges: Vec<(&str, u32, u32, u32)> = merged
+            .iter()
+            .map(|e| (e.sha.as_str(), e.orig_line, e.final_line, e.num_lines))
+            .collect();
+        assert_eq!(ranges, vec![("A", 1, 1, 3), ("B", 1, 4, 1), ("A", 9, 5, 2)]);
+    }
+
+    #[test]
+    fn test_merge_keeps_files_apart() {
+        let mut copied = entry("A", 1, 2, 1);
+        copied.filename = "other.rs".to_string();
+        assert_eq!(merge_adjacent(&[entry("A", 1, 1, 1), copied]).len(), 2);
+    }
+}
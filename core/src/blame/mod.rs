@@ -1,5 +1,33 @@
 pub mod types;
 pub mod parser;
+pub mod heatmap;
+pub mod ownership;
+pub mod link;
+pub mod range;
+pub mod diff;
+pub mod merge;
+pub mod ignore;
+pub mod renames;
+pub mod group;
+pub mod cache;
+pub mod summary;
+pub mod provenance;
+pub mod hover;
+pub mod aggregate;
 
 pub use types::*;
-pub use parser::parse_blame_output;
+pub use parser::{parse_blame_output, BlameParser};
+pub use heatmap::{compute_heatmap, compute_line_ages};
+pub use ownership::compute_ownership;
+pub use link::link_to_layout;
+pub use range::clip_to_lines;
+pub use diff::diff_blame;
+pub use merge::merge_adjacent;
+pub use ignore::{apply_ignore_revs, parse_ignore_revs};
+pub use renames::rename_history;
+pub use group::group_by_commit;
+pub use cache::BlameCache;
+pub use summary::summarize;
+pub use provenance::classify_provenance;
+pub use aggregate::aggregate_ownership;
+pub use hover::{render_hover, DEFAULT_HOVER_TEMPLATE, DEFAULT_UNCOMMITTED_TEMPLATE};
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use super::types::{AuthorOwnership, BlameEntry, Ownership};
+
+/// Count the lines attributed to each author, grouping by email without
+/// regard to case, and sort owners by line count, then by name.
+///
+/// An author's name is taken from their first entry; apply the mailmap to
+/// `entries` beforehand to merge identities. Uncommitted lines belong to
+/// nobody and are only counted.
+pub fn compute_ownership(entries: &[BlameEntry]) -> Ownership {
+    tally_ownership(entries)
+}
+
+/// `compute_ownership` over entries from any number of files.
+pub(crate) fn tally_ownership<'a>(entries: impl IntoIterator<Item = &'a BlameEntry>) -> Ownership {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut owners: Vec<AuthorOwnership> = Vec::new();
+    let mut total_lines = 0;
+    let mut uncommitted_lines = 0;
+
+    for entry in entries {
+        if entry.is_uncommitted {
+            uncommitted_lines += entry.num_lines;
+            continue;
+        }
+        total_lines += entry.num_lines;
+        let key = entry.author.email.to_lowercase();
+        let i = *index.entry(key).or_insert_with(|| {
+            owners.push(AuthorOwnership {
+                name: entry.author.name.clone(),
+                email: entry.author.email.clone(),
+                lines: 0,
+                percent: 0.0,
+            });
+            owners.len() - 1
+        });
+        owners[i].lines += entry.num_lines;
+    }
+
+    for owner in &mut owners {
+        owner.percent = if total_lines == 0 {
+            0.0
+        } else {
+            owner.lines as f64 * 100.0 / total_lines as f64
+        };
+    }
+    owners.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+
+    Ownership {
+        total_lines,
+        uncommitted_lines,
+        owners,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    fn entry(name: &str, email: &str, num_lines: u32) -> BlameEntry {
+        BlameEntry {
+            num_lines,
+            author: Signature {
+                name: name.to_string(),
+                email: email.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ownership_groups_and_sorts() {
+        let entries = vec![
+            entry("Bob", "bob@x", 1),
+            entry("Alice", "alice@x", 2),
+            entry("Bob", "BOB@x", 4),
+            entry("Carol", "carol@x", 1),
+        ];
+        let ownership = compute_ownership(&entries);
+        assert_eq!(ownership.total_lines, 8);
+
+        let owners: Vec<(&str, u32)> =
+            ownership.owners.iter().map(|o| (o.name.as_str(), o.lines)).collect();
+        assert_eq!(owners, vec![("Bob", 5), ("Alice", 2), ("Carol", 1)]);
+        assert_eq!(ownership.owners[0].percent, 62.5);
+        assert_eq!(ownership.owners[2].percent, 12.5);
+    }
+
+    #[test]
+    fn test_ownership_skips_uncommitted() {
+        let mut uncommitted = entry("", "", 3);
+        uncommitted.is_uncommitted = true;
+        let ownership = compute_ownership(&[entry("Ann", "ann@x", 1), uncommitted]);
+        assert_eq!((ownership.total_lines, ownership.uncommitted_lines), (1, 3));
+        assert_eq!(ownership.owners.len(), 1);
+        assert_eq!(ownership.owners[0].percent, 100.0);
+    }
+
+    #[test]
+    fn test_ownership_empty() {
+        let ownership = compute_ownership(&[]);
+        assert_eq!(ownership.total_lines, 0);
+        assert!(ownership.owners.is_empty());
+    }
+}
@@ -1,8 +1,24 @@
+use std::collections::HashMap;
+
 use super::types::BlameEntry;
+use crate::input::bom_len;
+use crate::signature::{parse_tz_offset, Signature};
+
+/// Commit details that git prints only the first time a commit appears.
+#[derive(Debug, Clone, Default)]
+struct CommitInfo {
+    author: Signature,
+    committer: Signature,
+    summary: String,
+    filename: String,
+    previous: Option<(String, String)>,
+    boundary: bool,
+}
 
-/// Parse `git blame --incremental` output into a Vec<BlameEntry>.
+/// Parse `git blame --incremental`, `--porcelain` or `--line-porcelain`
+/// output into a Vec<BlameEntry>.
 ///
-/// The incremental format looks like:
+/// Each group of lines starts with a header, followed by key-value lines:
 /// ```text
 /// <40-char sha> <orig_line> <final_line> <num_lines>
 /// author <name>
@@ -14,157 +30,240 @@ use super::types::BlameEntry;
 /// committer-time <epoch>
 /// committer-tz <tz>
 /// summary <text>
-/// previous <sha> <filename>
+/// boundary                         (if the commit is a range boundary)
+/// previous <sha> <filename>        (if the commit changed an existing file)
 /// filename <path>
-/// \t<line content>        (optional, only in porcelain, not incremental)
+/// \t<line content>        (porcelain formats only)
 /// ```
 ///
-/// A new blame chunk starts with a line matching the SHA pattern.
-/// Subsequent lines are key-value pairs until the next SHA line or EOF.
+/// The commit details are only printed the first time a commit appears
+/// (every time with `--line-porcelain`), so later groups reuse them. In the
+/// porcelain formats each further line of a group repeats the header
+/// without `<num_lines>`, and each line's content follows as a tab-prefixed
+/// line; contents are collected into `BlameEntry::lines`.
 pub fn parse_blame_output(raw: &[u8]) -> Vec<BlameEntry> {
-    let input = match std::str::from_utf8(raw) {
-        Ok(s) => s,
-        Err(_) => return Vec::new(),
-    };
-
-    let mut entries: Vec<BlameEntry> = Vec::new();
-
-    // Current entry being built
-    let mut current_sha = String::new();
-    let mut current_orig_line: u32 = 0;
-    let mut current_final_line: u32 = 0;
-    let mut current_num_lines: u32 = 0;
-    let mut author_name = String::new();
-    let mut author_email = String::new();
-    let mut author_date: u64 = 0;
-    let mut committer_name = String::new();
-    let mut committer_email = String::new();
-    let mut committer_date: u64 = 0;
-    let mut summary = String::new();
-    let mut filename = String::new();
-    let mut in_entry = false;
-
-    for line in input.lines() {
-        let line = line.trim_end();
+    let mut parser = BlameParser::new();
+    let mut entries = parser.push(raw);
+    entries.extend(parser.finish());
+    entries
+}
+
+/// Which of git's blame formats is being parsed, as far as can be told.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum BlameFormat {
+    #[default]
+    Unknown,
+    Incremental,
+    Porcelain,
+}
+
+/// Streaming parser for the formats accepted by `parse_blame_output`,
+/// fed with chunks of output as git produces them.
+///
+/// An `--incremental` entry is complete once its `filename` line is read
+/// and a porcelain entry once all of its line contents are read, so
+/// `push` returns each entry as soon as git has finished printing it.
+/// Chunks may split lines anywhere.
+#[derive(Debug, Default)]
+pub struct BlameParser {
+    commits: HashMap<String, CommitInfo>,
+    /// Entry whose lines are still being read.
+    current: Option<BlameEntry>,
+    format: BlameFormat,
+    /// The current entry has a filename but the format is not yet known,
+    /// so content lines may still follow.
+    awaiting_content: bool,
+    /// Bytes of an unterminated line from the previous chunk.
+    partial: Vec<u8>,
+    started: bool,
+    completed: Vec<BlameEntry>,
+}
+
+impl BlameParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next chunk of output; returns the entries it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<BlameEntry> {
+        let chunk = if !self.started && !chunk.is_empty() {
+            self.started = true;
+            &chunk[bom_len(chunk)..]
+        } else {
+            chunk
+        };
+
+        let mut rest = chunk;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            if self.partial.is_empty() {
+                self.push_line(&rest[..end]);
+            } else {
+                let mut line = std::mem::take(&mut self.partial);
+                line.extend_from_slice(&rest[..end]);
+                self.push_line(&line);
+            }
+            rest = &rest[end + 1..];
+        }
+        self.partial.extend_from_slice(rest);
+
+        std::mem::take(&mut self.completed)
+    }
 
+    /// Read an unterminated last line and complete the last entry.
+    pub fn finish(&mut self) -> Vec<BlameEntry> {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.push_line(&line);
+        }
+        self.complete_current();
+        std::mem::take(&mut self.completed)
+    }
+
+    fn push_line(&mut self, line: &[u8]) {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line = String::from_utf8_lossy(line);
+
+        // Line contents may have any text, including leading whitespace
+        if let Some(content) = line.strip_prefix('\t') {
+            if let Some(entry) = self.current.as_mut() {
+                self.format = BlameFormat::Porcelain;
+                self.awaiting_content = false;
+                entry.lines.push(content.to_string());
+                if entry.lines.len() as u32 >= entry.num_lines {
+                    self.complete_current();
+                }
+            }
+            return;
+        }
+
+        let line = line.trim_end();
         if line.is_empty() {
-            continue;
+            return;
         }
 
-        // Skip content lines (lines starting with a tab in porcelain mode)
-        if line.starts_with('\t') {
-            continue;
+        if self.awaiting_content {
+            // A filename not followed by content: this is --incremental
+            self.format = BlameFormat::Incremental;
+            self.complete_current();
         }
 
-        // Check if this line is a SHA header line.
-        // Format: <40-hex-chars> <orig_line> <final_line> <num_lines>
-        // or:     <40-hex-chars> <orig_line> <final_line>  (boundary commits in some modes)
         if is_sha_header(line) {
-            // If we were building an entry, finalize it
-            if in_entry && !current_sha.is_empty() && !filename.is_empty() {
-                entries.push(BlameEntry {
-                    sha: current_sha.clone(),
-                    short_sha: if current_sha.len() >= 7 {
-                        current_sha[..7].to_string()
-                    } else {
-                        current_sha.clone()
-                    },
-                    orig_line: current_orig_line,
-                    final_line: current_final_line,
-                    num_lines: current_num_lines,
-                    author_name: author_name.clone(),
-                    author_email: author_email.clone(),
-                    author_date,
-                    committer_name: committer_name.clone(),
-                    committer_email: committer_email.clone(),
-                    committer_date,
-                    summary: summary.clone(),
-                    filename: filename.clone(),
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let sha = parts[0];
+
+            // Porcelain repeats `<sha> <orig> <final>` for each further line
+            // of a group
+            let continues_group = parts.len() == 3
+                && self
+                    .current
+                    .as_ref()
+                    .is_some_and(|e| e.sha == sha && (e.lines.len() as u32) < e.num_lines);
+            if !continues_group {
+                self.complete_current();
+                self.current = Some(BlameEntry {
+                    sha: sha.to_string(),
+                    short_sha: sha.chars().take(7).collect(),
+                    orig_line: parts[1].parse().unwrap_or(0),
+                    final_line: parts[2].parse().unwrap_or(0),
+                    num_lines: parts.get(3).and_then(|s| s.parse().ok()).unwrap_or(1),
+                    ..Default::default()
                 });
             }
-
-            // Parse the header
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            current_sha = parts[0].to_string();
-            current_orig_line = parts.get(1).and_then(|s| s.parse().ok()).unwrap_or(0);
-            current_final_line = parts.get(2).and_then(|s| s.parse().ok()).unwrap_or(0);
-            current_num_lines = parts.get(3).and_then(|s| s.parse().ok()).unwrap_or(1);
-
-            // Reset fields for new entry
-            author_name.clear();
-            author_email.clear();
-            author_date = 0;
-            committer_name.clear();
-            committer_email.clear();
-            committer_date = 0;
-            summary.clear();
-            filename.clear();
-            in_entry = true;
-
-            continue;
+            return;
         }
 
-        if !in_entry {
-            continue;
-        }
+        let entry = match self.current.as_mut() {
+            Some(entry) => entry,
+            None => return,
+        };
+        let info = self.commits.entry(entry.sha.clone()).or_default();
 
         // Parse key-value pairs
         if let Some(val) = line.strip_prefix("author-mail ") {
             // Strip angle brackets: <email> -> email
-            author_email = val.trim_start_matches('<').trim_end_matches('>').to_string();
+            info.author.email = val.trim_start_matches('<').trim_end_matches('>').to_string();
         } else if let Some(val) = line.strip_prefix("author-time ") {
-            author_date = val.trim().parse().unwrap_or(0);
-        } else if line.starts_with("author-tz ") {
-            // Ignore timezone, we use epoch
+            info.author.time = val.trim().parse().unwrap_or(0);
+        } else if let Some(val) = line.strip_prefix("author-tz ") {
+            info.author.tz_offset_min = parse_tz_offset(val);
         } else if let Some(val) = line.strip_prefix("author ") {
-            author_name = val.to_string();
+            info.author.name = val.to_string();
         } else if let Some(val) = line.strip_prefix("committer-mail ") {
-            committer_email = val.trim_start_matches('<').trim_end_matches('>').to_string();
+            info.committer.email = val.trim_start_matches('<').trim_end_matches('>').to_string();
         } else if let Some(val) = line.strip_prefix("committer-time ") {
-            committer_date = val.trim().parse().unwrap_or(0);
-        } else if line.starts_with("committer-tz ") {
-            // Ignore timezone
+            info.committer.time = val.trim().parse().unwrap_or(0);
+        } else if let Some(val) = line.strip_prefix("committer-tz ") {
+            info.committer.tz_offset_min = parse_tz_offset(val);
         } else if let Some(val) = line.strip_prefix("committer ") {
-            committer_name = val.to_string();
+            info.committer.name = val.to_string();
         } else if let Some(val) = line.strip_prefix("summary ") {
-            summary = val.to_string();
+            info.summary = val.to_string();
         } else if let Some(val) = line.strip_prefix("filename ") {
-            filename = val.to_string();
-        } else if line.starts_with("previous ") || line.starts_with("boundary") {
-            // Ignore these metadata lines
+            // `previous` is only printed before `filename`, so one without
+            // the other means the file is new in this commit
+            if entry.previous_sha.is_none() {
+                info.previous = None;
+            }
+            info.filename = val.to_string();
+            entry.filename = val.to_string();
+            match self.format {
+                BlameFormat::Incremental => self.complete_current(),
+                BlameFormat::Unknown => self.awaiting_content = true,
+                BlameFormat::Porcelain => {}
+            }
+        } else if let Some(val) = line.strip_prefix("previous ") {
+            if let Some((sha, filename)) = val.split_once(' ') {
+                info.previous = Some((sha.to_string(), filename.to_string()));
+                entry.previous_sha = Some(sha.to_string());
+                entry.previous_filename = Some(filename.to_string());
+            }
+        } else if line == "boundary" {
+            info.boundary = true;
         }
     }
 
-    // Don't forget the last entry
-    if in_entry && !current_sha.is_empty() && !filename.is_empty() {
-        entries.push(BlameEntry {
-            sha: current_sha.clone(),
-            short_sha: if current_sha.len() >= 7 {
-                current_sha[..7].to_string()
-            } else {
-                current_sha.clone()
-            },
-            orig_line: current_orig_line,
-            final_line: current_final_line,
-            num_lines: current_num_lines,
-            author_name,
-            author_email,
-            author_date,
-            committer_name,
-            committer_email,
-            committer_date,
-            summary,
-            filename,
-        });
+    /// Fill the current entry with its commit's details and move it to the
+    /// completed list; entries that never named a file are dropped.
+    fn complete_current(&mut self) {
+        self.awaiting_content = false;
+        let mut entry = match self.current.take() {
+            Some(entry) => entry,
+            None => return,
+        };
+        entry.is_uncommitted = entry.sha.bytes().all(|b| b == b'0');
+        if entry.is_uncommitted {
+            // Not a real commit: keep no short SHA and none of the
+            // placeholder author ("Not Committed Yet") git makes up
+            entry.short_sha.clear();
+        }
+        if let Some(info) = self.commits.get(&entry.sha) {
+            if !entry.is_uncommitted {
+                entry.is_boundary = info.boundary;
+                entry.author = info.author.clone();
+                entry.committer = info.committer.clone();
+                entry.summary = info.summary.clone();
+            }
+            // Porcelain names the file only the first time a commit appears
+            if entry.filename.is_empty() {
+                entry.filename = info.filename.clone();
+                if let Some((sha, filename)) = &info.previous {
+                    entry.previous_sha = Some(sha.clone());
+                    entry.previous_filename = Some(filename.clone());
+                }
+            }
+        }
+        if !entry.filename.is_empty() {
+            self.completed.push(entry);
+        }
     }
-
-    entries
 }
 
 /// Check if a line looks like a blame SHA header.
 ///
-/// A SHA header line starts with 40 hex characters followed by at least two
-/// space-separated numbers (orig_line and final_line).
+/// A SHA header line starts with 40 hex characters followed by at least
+/// two space-separated numbers (orig_line and final_line). Headers with 64
+/// hex characters, from repositories using SHA-256 object names, are
+/// accepted too, so blame works there as well.
 fn is_sha_header(line: &str) -> bool {
     let parts: Vec<&str> = line.split_whitespace().collect();
     if parts.len() < 3 {
@@ -172,7 +271,7 @@ fn is_sha_header(line: &str) -> bool {
     }
 
     let sha_candidate = parts[0];
-    if sha_candidate.len() != 40 {
+    if sha_candidate.len() != 40 && sha_candidate.len() != 64 {
         return false;
     }
     if !sha_candidate.chars().all(|c| c.is_ascii_hexdigit()) {
@@ -202,7 +301,7 @@ mod tests {
 
     #[test]
     fn test_parse_blame_single_entry() {
-        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 3\nauthor Alice\nauthor-mail <alice@example.com>\nauthor-time 1700000000\nauthor-tz +0000\ncommitter Bob\ncommitter-mail <bob@example.com>\ncommitter-time 1700000100\ncommitter-tz +0000\nsummary Initial commit\nfilename src/main.rs\n";
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 3\nauthor Alice\nauthor-mail <alice@example.com>\nauthor-time 1700000000\nauthor-tz +0000\ncommitter Bob\ncommitter-mail <bob@example.com>\ncommitter-time 1700000100\ncommitter-tz -0800\nsummary Initial commit\nfilename src/main.rs\n";
         let entries = parse_blame_output(raw);
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].sha, "abcdef0123456789abcdef0123456789abcdef01");
@@ -210,13 +309,168 @@ mod tests {
         assert_eq!(entries[0].orig_line, 1);
         assert_eq!(entries[0].final_line, 1);
         assert_eq!(entries[0].num_lines, 3);
-        assert_eq!(entries[0].author_name, "Alice");
-        assert_eq!(entries[0].author_email, "alice@example.com");
-        assert_eq!(entries[0].committer_name, "Bob");
+        assert_eq!(entries[0].author.name, "Alice");
+        assert_eq!(entries[0].author.email, "alice@example.com");
+        assert_eq!(entries[0].committer.name, "Bob");
+        assert_eq!(entries[0].committer.time, 1700000100);
+        assert_eq!(entries[0].committer.tz_offset_min, -480);
         assert_eq!(entries[0].summary, "Initial commit");
         assert_eq!(entries[0].filename, "src/main.rs");
     }
 
+    #[test]
+    fn test_parse_blame_crlf_and_bom() {
+        let raw = b"\xef\xbb\xbfabcdef0123456789abcdef0123456789abcdef01 1 1 3\r\nauthor Alice\r\nauthor-mail <alice@example.com>\r\nauthor-tz +0100\r\nsummary Initial commit\r\nfilename src/main.rs\r\n";
+        let entries = parse_blame_output(raw);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].sha, "abcdef0123456789abcdef0123456789abcdef01");
+        assert_eq!(entries[0].author.email, "alice@example.com");
+        assert_eq!(entries[0].author.tz_offset_min, 60);
+        assert_eq!(entries[0].filename, "src/main.rs");
+    }
+
+    // Recorded from `git blame --porcelain f.txt`
+    const PORCELAIN: &str = "6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 1 1 1
+author A
+author-mail <a@x>
+author-time 1792149967
+author-tz +0000
+committer A
+committer-mail <a@x>
+committer-time 1792149967
+committer-tz +0000
+summary add f
+filename f.txt
+\ta
+251ef39599bf2ee2f5b761a4f06985a919ade1f5 2 2 2
+author B
+author-mail <b@x>
+author-time 1792150000
+author-tz +0100
+committer B
+committer-mail <b@x>
+committer-time 1792150000
+committer-tz +0100
+summary edit f
+previous 6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 f.txt
+filename f.txt
+\tB
+251ef39599bf2ee2f5b761a4f06985a919ade1f5 3 3
+\t    indented
+6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 2 4 1
+\tc
+";
+
+    #[test]
+    fn test_parse_blame_porcelain_lines() {
+        let entries = parse_blame_output(PORCELAIN.as_bytes());
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].lines, vec!["a"]);
+        assert_eq!(entries[1].num_lines, 2);
+        assert_eq!(entries[1].lines, vec!["B", "    indented"]);
+        assert_eq!(entries[1].author.tz_offset_min, 60);
+        assert_eq!(
+            entries[1].previous_sha.as_deref(),
+            Some("6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0")
+        );
+        assert_eq!(entries[1].previous_filename.as_deref(), Some("f.txt"));
+        assert_eq!(entries[0].previous_sha, None);
+
+        // Repeated commits carry no headers; details come from the first
+        // appearance
+        assert_eq!(entries[2].final_line, 4);
+        assert_eq!(entries[2].lines, vec!["c"]);
+        assert_eq!(entries[2].author.name, "A");
+        assert_eq!(entries[2].summary, "add f");
+        assert_eq!(entries[2].filename, "f.txt");
+    }
+
+    #[test]
+    fn test_parse_blame_previous_reused_for_repeated_commit() {
+        let raw = "251ef39599bf2ee2f5b761a4f06985a919ade1f5 1 1 1\nauthor B\n\
+previous 6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 old name.txt\nfilename f.txt\n\tx\n\
+6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 1 2 1\nauthor A\nfilename f.txt\n\ty\n\
+251ef39599bf2ee2f5b761a4f06985a919ade1f5 3 3 1\n\tz\n";
+        let entries = parse_blame_output(raw.as_bytes());
+        assert_eq!(entries[0].previous_filename.as_deref(), Some("old name.txt"));
+        assert_eq!(entries[1].previous_sha, None);
+        assert_eq!(entries[2].previous_sha, entries[0].previous_sha);
+        assert_eq!(entries[2].previous_filename.as_deref(), Some("old name.txt"));
+    }
+
+    #[test]
+    fn test_parse_blame_boundary_and_uncommitted() {
+        let raw = "6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 1 1 1\nauthor A\nboundary\nfilename f.txt\n\
+0000000000000000000000000000000000000000 2 2 1\nauthor Not Committed Yet\nfilename f.txt\n\
+6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 3 3 1\nfilename f.txt\n";
+        let entries = parse_blame_output(raw.as_bytes());
+        let flags: Vec<(bool, bool)> =
+            entries.iter().map(|e| (e.is_boundary, e.is_uncommitted)).collect();
+        assert_eq!(flags, vec![(true, false), (false, true), (true, false)]);
+
+        let uncommitted = &entries[1];
+        assert_eq!(uncommitted.short_sha, "");
+        assert_eq!(uncommitted.author.name, "");
+        assert_eq!(uncommitted.filename, "f.txt");
+    }
+
+    #[test]
+    fn test_parse_blame_line_porcelain() {
+        let header = "251ef39599bf2ee2f5b761a4f06985a919ade1f5";
+        let details = "author B\nauthor-mail <b@x>\nauthor-time 1792150000\nauthor-tz +0000\ncommitter B\ncommitter-mail <b@x>\ncommitter-time 1792150000\ncommitter-tz +0000\nsummary edit f\nfilename f.txt\n";
+        let raw = format!(
+            "{h} 1 1 2\n{d}\tfirst\n{h} 2 2\n{d}\tsecond\n",
+            h = header,
+            d = details
+        );
+        let entries = parse_blame_output(raw.as_bytes());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].lines, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_parse_blame_incremental_reuses_commit_details() {
+        let raw = "251ef39599bf2ee2f5b761a4f06985a919ade1f5 2 2 1\nauthor B\nsummary edit f\nfilename f.txt\n251ef39599bf2ee2f5b761a4f06985a919ade1f5 4 4 1\nfilename f.txt\n";
+        let entries = parse_blame_output(raw.as_bytes());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].author.name, "B");
+        assert!(entries[1].lines.is_empty());
+    }
+
+    #[test]
+    fn test_blame_parser_streams_entries() {
+        let mut parser = BlameParser::new();
+        let (first, second) = PORCELAIN.as_bytes().split_at(PORCELAIN.find("\tB").unwrap() + 1);
+
+        // The first group is complete once its content line is read
+        let done = parser.push(first);
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].lines, vec!["a"]);
+
+        let done = parser.push(second);
+        assert_eq!(done.len(), 2);
+        assert_eq!(done[0].lines, vec!["B", "    indented"]);
+        assert!(parser.finish().is_empty());
+    }
+
+    #[test]
+    fn test_blame_parser_incremental_chunks() {
+        let raw = "251ef39599bf2ee2f5b761a4f06985a919ade1f5 2 2 1\nauthor B\nfilename f.txt\n\
+6037c0fd65c2c3dc69513b4a7e3252fbbb0cfaa0 1 1 1\nauthor A\nfilename f.txt\n\
+251ef39599bf2ee2f5b761a4f06985a919ade1f5 4 4 1\nfilename f.txt";
+        let mut parser = BlameParser::new();
+        let mut seen = Vec::new();
+        for chunk in raw.as_bytes().chunks(7) {
+            seen.extend(parser.push(chunk).into_iter().map(|e| e.final_line));
+        }
+        // Once the format is known, entries complete at their filename line
+        assert_eq!(seen, vec![2, 1]);
+        let last = parser.finish();
+        assert_eq!(last[0].final_line, 4);
+        assert_eq!(last[0].author.name, "B");
+    }
+
     #[test]
     fn test_parse_blame_empty() {
         let entries = parse_blame_output(b"");
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+
+use super::types::{BlameEntry, Provenance};
+
+/// Classify where each entry's lines came from, for blames run with
+/// `-M`/`-C`.
+///
+/// `path` is the blamed file; when `None` it is taken from the uncommitted
+/// lines, or else the path with the most lines. Paths the file had before
+/// and after renames are found through the `previous` headers. Lines from
+/// any other path were copied or moved in from that file (`Copied`, with
+/// `source_path` set). Within the file, lines of a commit whose original
+/// order no longer matches their order in the file were moved (`Moved`);
+/// the longest run still in order counts as `Original`.
+pub fn classify_provenance(entries: &mut [BlameEntry], path: Option<&str>) {
+    let path = match path {
+        Some(path) => path.to_string(),
+        None => match infer_path(entries) {
+            Some(path) => path,
+            None => return,
+        },
+    };
+
+    // The file's own paths, following renames through `previous` both ways:
+    // an inferred path may be a name the file had before a rename
+    let mut lineage: HashSet<&str> = HashSet::from([path.as_str()]);
+    loop {
+        let before = lineage.len();
+        for entry in entries.iter() {
+            if let Some(previous) = &entry.previous_filename {
+                if lineage.contains(entry.filename.as_str()) {
+                    lineage.insert(previous);
+                } else if lineage.contains(previous.as_str()) {
+                    lineage.insert(&entry.filename);
+                }
+            }
+        }
+        if lineage.len() == before {
+            break;
+        }
+    }
+    let lineage: HashSet<String> = lineage.into_iter().map(String::from).collect();
+
+    let mut groups: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if lineage.contains(&entry.filename) {
+            groups
+                .entry((entry.sha.as_str(), entry.filename.as_str()))
+                .or_default()
+                .push(i);
+        }
+    }
+    let mut moved: HashSet<usize> = HashSet::new();
+    for indexes in groups.values_mut() {
+        indexes.sort_by_key(|&i| entries[i].final_line);
+        let orig_lines: Vec<u32> = indexes.iter().map(|&i| entries[i].orig_line).collect();
+        let in_order = longest_increasing(&orig_lines);
+        moved.extend(
+            indexes
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| !in_order.contains(k))
+                .map(|(_, &i)| i),
+        );
+    }
+
+    for (i, entry) in entries.iter_mut().enumerate() {
+        if !lineage.contains(&entry.filename) {
+            entry.provenance = Provenance::Copied;
+            entry.source_path = Some(entry.filename.clone());
+        } else if moved.contains(&i) {
+            entry.provenance = Provenance::Moved;
+            entry.source_path = None;
+        } else {
+            entry.provenance = Provenance::Original;
+            entry.source_path = None;
+        }
+    }
+}
+
+fn infer_path(entries: &[BlameEntry]) -> Option<String> {
+    if let Some(entry) = entries.iter().find(|e| e.is_uncommitted) {
+        return Some(entry.filename.clone());
+    }
+    let mut lines: HashMap<&str, u32> = HashMap::new();
+    for entry in entries {
+        *lines.entry(entry.filename.as_str()).or_default() += entry.num_lines;
+    }
+    lines
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(path, _)| path.to_string())
+}
+
+/// Positions of a longest strictly increasing subsequence of `values`.
+fn longest_increasing(values: &[u32]) -> HashSet<usize> {
+    // tails[k]: index of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev: Vec<Option<usize>> = vec![None; values.len()];
+    for (i, &value) in values.iter().enumerate() {
+        let k = tails.partition_point(|&t| values[t] < value);
+        prev[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut run = HashSet::new();
+    let mut current = tails.last().copied();
+    while let Some(i) = current {
+        run.insert(i);
+        current = prev[i];
+    }
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(sha: &str, filename: &str, orig_line: u32, final_line: u32) -> BlameEntry {
+        BlameEntry {
+            sha: sha.to_string(),
+            filename: filename.to_string(),
+            orig_line,
+            final_line,
+            num_lines: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_copied_from_other_file() {
+        let mut entries = vec![
+            entry("a", "main.rs", 1, 1),
+            entry("a", "main.rs", 2, 2),
+            entry("b", "utils.rs", 40, 3),
+        ];
+        classify_provenance(&mut entries, None);
+        assert_eq!(entries[0].provenance, Provenance::Original);
+        assert_eq!(entries[2].provenance, Provenance::Copied);
+        assert_eq!(entries[2].source_path.as_deref(), Some("utils.rs"));
+    }
+
+    #[test]
+    fn test_moved_within_file() {
+        // Line 9 of commit a now sits above lines 1-3
+        let mut entries = vec![
+            entry("a", "f.rs", 9, 1),
+            entry("a", "f.rs", 1, 2),
+            entry("a", "f.rs", 2, 3),
+            entry("a", "f.rs", 3, 4),
+        ];
+        classify_provenance(&mut entries, Some("f.rs"));
+        let kinds: Vec<Provenance> = entries.iter().map(|e| e.provenance).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Provenance::Moved,
+                Provenance::Original,
+                Provenance::Original,
+                Provenance::Original,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_renamed_paths_are_original() {
+        let mut renamed = entry("new", "new.rs", 1, 2);
+        renamed.previous_filename = Some("old.rs".to_string());
+        let mut entries = vec![entry("old", "old.rs", 1, 1), renamed];
+        classify_provenance(&mut entries, Some("new.rs"));
+        assert!(entries.iter().all(|e| e.provenance == Provenance::Original));
+    }
+
+    #[test]
+    fn test_renamed_file_with_inferred_path() {
+        // Most lines predate the rename, so the inferred path is the old name
+        let mut entries = vec![
+            entry("old", "old.rs", 1, 1),
+            entry("old", "old.rs", 2, 2),
+            entry("old", "old.rs", 3, 3),
+            entry("old", "old.rs", 4, 4),
+            entry("new", "new.rs", 9, 5),
+            entry("new", "new.rs", 5, 6),
+            entry("new", "new.rs", 6, 7),
+        ];
+        for entry in &mut entries[4..] {
+            entry.previous_filename = Some("old.rs".to_string());
+        }
+        classify_provenance(&mut entries, None);
+        let kinds: Vec<Provenance> = entries.iter().map(|e| e.provenance).collect();
+        assert_eq!(kinds[4], Provenance::Moved);
+        assert!(kinds.iter().enumerate().all(|(i, &k)| i == 4 || k == Provenance::Original));
+        assert!(entries.iter().all(|e| e.source_path.is_none()));
+    }
+}
@@ -0,0 +1,78 @@
+use super::types::BlameEntry;
+
+/// Restrict entries to the final lines `start..=end` (1-based), ordered by
+/// line. Entries that straddle either boundary are split, keeping
+/// `orig_line`, `final_line`, `num_lines` and any line contents in step.
+pub fn clip_to_lines(entries: &[BlameEntry], start: u32, end: u32) -> Vec<BlameEntry> {
+    let mut clipped: Vec<BlameEntry> = entries
+        .iter()
+        .filter_map(|entry| {
+            let last = entry.final_line.checked_add(entry.num_lines.checked_sub(1)?)?;
+            let lo = entry.final_line.max(start);
+            let hi = last.min(end);
+            if lo > hi {
+                return None;
+            }
+
+            let skipped = lo - entry.final_line;
+            let num_lines = hi - lo + 1;
+            let mut part = entry.clone();
+            part.orig_line = part.orig_line.saturating_add(skipped);
+            part.final_line = lo;
+            part.num_lines = num_lines;
+            part.lines = entry
+                .lines
+                .iter()
+                .skip(skipped as usize)
+                .take(num_lines as usize)
+                .cloned()
+                .collect();
+            Some(part)
+        })
+        .collect();
+    clipped.sort_by_key(|e| e.final_line);
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(orig_line: u32, final_line: u32, lines: &[&str]) -> BlameEntry {
+        BlameEntry {
+            orig_line,
+            final_line,
+            num_lines: lines.len() as u32,
+            lines: lines.iter().map(|l| l.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clip_splits_straddling_entries() {
+        let entries = vec![
+            entry(10, 5, &["e", "f", "g"]),
+            entry(1, 1, &["a", "b", "c", "d"]),
+            entry(20, 8, &["h"]),
+        ];
+        let clipped = clip_to_lines(&entries, 3, 6);
+
+        let ranges: Vec<(u32, u32, u32)> = clipped
+            .iter()
+            .map(|e| (e.orig_line, e.final_line, e.num_lines))
+            .collect();
+        assert_eq!(ranges, vec![(3, 3, 2), (10, 5, 2)]);
+        assert_eq!(clipped[0].lines, vec!["c", "d"]);
+        assert_eq!(clipped[1].lines, vec!["e", "f"]);
+    }
+
+    #[test]
+    fn test_clip_inside_one_entry() {
+        let clipped = clip_to_lines(&[entry(1, 1, &["a", "b", "c"])], 2, 2);
+        assert_eq!(clipped.len(), 1);
+        assert_eq!((clipped[0].orig_line, clipped[0].num_lines), (2, 1));
+        assert_eq!(clipped[0].lines, vec!["b"]);
+        assert!(clip_to_lines(&[entry(1, 1, &["a"])], 2, 9).is_empty());
+        assert!(clip_to_lines(&[entry(1, u32::MAX, &["a", "b"])], 1, u32::MAX).is_empty());
+    }
+}
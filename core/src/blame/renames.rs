@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, HashSet};
+
+use super::types::{BlameEntry, PathRename, RenameHistory};
+
+/// Collect the paths the blamed file had in each commit, and the commits
+/// that renamed it.
+///
+/// Each entry names the path in its commit (`filename`) and in the commit
+/// before (`previous`); an entry whose two paths differ marks a rename.
+/// Commits known only through `previous` are dated by the entry naming
+/// them, so paths are ordered newest first by committer time. Uncommitted
+/// entries are ignored.
+pub fn rename_history(entries: &[BlameEntry]) -> RenameHistory {
+    let mut path_at_commit: BTreeMap<String, String> = BTreeMap::new();
+    // (time, path) in the order seen, for ordering paths
+    let mut uses: Vec<(u64, &str)> = Vec::new();
+    let mut renames: Vec<(u64, PathRename)> = Vec::new();
+    let mut seen_renames: HashSet<&str> = HashSet::new();
+
+    for entry in entries.iter().filter(|e| !e.is_uncommitted) {
+        let time = entry.committer.time;
+        path_at_commit.insert(entry.sha.clone(), entry.filename.clone());
+        uses.push((time, &entry.filename));
+
+        let (Some(previous_sha), Some(previous_path)) =
+            (&entry.previous_sha, &entry.previous_filename)
+        else {
+            continue;
+        };
+        path_at_commit
+            .entry(previous_sha.clone())
+            .or_insert_with(|| previous_path.clone());
+        uses.push((time, previous_path));
+
+        if *previous_path != entry.filename && seen_renames.insert(&entry.sha) {
+            renames.push((
+                time,
+                PathRename {
+                    sha: entry.sha.clone(),
+                    from: previous_path.clone(),
+                    to: entry.filename.clone(),
+                },
+            ));
+        }
+    }
+
+    // Stable sorts keep a commit's own path ahead of its previous path
+    uses.sort_by_key(|&(time, _)| std::cmp::Reverse(time));
+    let mut paths: Vec<String> = Vec::new();
+    for (_, path) in uses {
+        if !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+    }
+    renames.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+
+    RenameHistory {
+        path_at_commit,
+        paths,
+        renames: renames.into_iter().map(|(_, rename)| rename).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    fn entry(sha: &str, time: u64, filename: &str, previous: Option<(&str, &str)>) -> BlameEntry {
+        BlameEntry {
+            sha: sha.to_string(),
+            committer: Signature {
+                time,
+                ..Default::default()
+            },
+            filename: filename.to_string(),
+            previous_sha: previous.map(|(sha, _)| sha.to_string()),
+            previous_filename: previous.map(|(_, path)| path.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rename_history() {
+        let entries = vec![
+            entry("c3", 300, "src/new.rs", Some(("c2", "src/new.rs"))),
+            entry("c2", 200, "src/new.rs", Some(("c1", "old.rs"))),
+            entry("c2", 200, "src/new.rs", Some(("c1", "old.rs"))),
+            entry("c0", 50, "older.rs", None),
+            entry("c1", 100, "old.rs", Some(("c0", "older.rs"))),
+        ];
+        let history = rename_history(&entries);
+
+        assert_eq!(history.paths, vec!["src/new.rs", "old.rs", "older.rs"]);
+        assert_eq!(history.path_at_commit["c1"], "old.rs");
+        assert_eq!(history.path_at_commit["c3"], "src/new.rs");
+        assert_eq!(
+            history.renames,
+            vec![
+                PathRename {
+                    sha: "c2".to_string(),
+                    from: "old.rs".to_string(),
+                    to: "src/new.rs".to_string(),
+                },
+                PathRename {
+                    sha: "c1".to_string(),
+                    from: "older.rs".to_string(),
+                    to: "old.rs".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use super::merge::merge_adjacent;
+use super::types::{BlameEntry, BlameSummary, CommitSpan};
+
+/// Summarize a blame for a compact header: date range, line count, number
+/// of commits and authors, and the longest run of lines from one commit.
+/// Ties for the longest run go to the earliest in the file. Uncommitted
+/// lines count towards `line_count` and `uncommitted_lines` only.
+pub fn summarize(entries: &[BlameEntry]) -> BlameSummary {
+    let committed = || entries.iter().filter(|e| !e.is_uncommitted);
+    let times = || committed().map(|e| e.author.time);
+    let commits: HashSet<&str> = committed().map(|e| e.sha.as_str()).collect();
+    let authors: HashSet<String> = committed().map(|e| e.author.email.to_lowercase()).collect();
+
+    let mut largest_span: Option<CommitSpan> = None;
+    for run in merge_adjacent(entries) {
+        if run.is_uncommitted {
+            continue;
+        }
+        if largest_span.as_ref().is_none_or(|s| run.num_lines > s.num_lines) {
+            largest_span = Some(CommitSpan {
+                sha: run.sha,
+                final_line: run.final_line,
+                num_lines: run.num_lines,
+            });
+        }
+    }
+
+    BlameSummary {
+        oldest_date: times().min().unwrap_or(0),
+        newest_date: times().max().unwrap_or(0),
+        line_count: entries.iter().map(|e| e.num_lines).sum(),
+        uncommitted_lines: entries
+            .iter()
+            .filter(|e| e.is_uncommitted)
+            .map(|e| e.num_lines)
+            .sum(),
+        commit_count: commits.len(),
+        author_count: authors.len(),
+        largest_span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    fn entry(sha: &str, email: &str, time: u64, final_line: u32, num_lines: u32) -> BlameEntry {
+        BlameEntry {
+            sha: sha.to_string(),
+            final_line,
+            num_lines,
+            author: Signature {
+                email: email.to_string(),
+                time,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let entries = vec![
+            entry("a", "ann@x", 100, 1, 2),
+            entry("b", "ANN@x", 300, 3, 3),
+            entry("a", "ann@x", 100, 6, 2),
+            entry("a", "ann@x", 100, 8, 2),
+            entry("c", "cy@x", 200, 10, 1),
+        ];
+        let summary = summarize(&entries);
+        assert_eq!((summary.oldest_date, summary.newest_date), (100, 300));
+        assert_eq!(summary.line_count, 10);
+        assert_eq!(summary.commit_count, 3);
+        assert_eq!(summary.author_count, 2);
+        assert_eq!(
+            summary.largest_span,
+            Some(CommitSpan {
+                sha: "a".to_string(),
+                final_line: 6,
+                num_lines: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_summarize_skips_uncommitted() {
+        let mut uncommitted = entry(&"0".repeat(40), "", 0, 2, 5);
+        uncommitted.is_uncommitted = true;
+        let summary = summarize(&[entry("a", "ann@x", 100, 1, 1), uncommitted]);
+        assert_eq!((summary.oldest_date, summary.newest_date), (100, 100));
+        assert_eq!((summary.line_count, summary.uncommitted_lines), (6, 5));
+        assert_eq!((summary.commit_count, summary.author_count), (1, 1));
+        assert_eq!(summary.largest_span.unwrap().sha, "a");
+    }
+
+    #[test]
+    fn test_summarize_empty() {
+        let summary = summarize(&[]);
+        assert_eq!(summary.commit_count, 0);
+        assert_eq!(summary.largest_span, None);
+    }
+}
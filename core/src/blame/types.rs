@@ -1,20 +1,351 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-/// A single blame entry from `git blame --incremental` output.
+use crate::signature::Signature;
+
+/// A single blame entry from `git blame --incremental` or porcelain output.
 /// Each entry attributes a range of lines to a specific commit.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BlameEntry {
     pub sha: String,
     pub short_sha: String,
+    /// Line the range started at in `sha`'s version of the file, or 0 when
+    /// that is unknown (see `apply_ignore_revs`).
     pub orig_line: u32,
     pub final_line: u32,
     pub num_lines: u32,
-    pub author_name: String,
-    pub author_email: String,
-    pub author_date: u64,
-    pub committer_name: String,
-    pub committer_email: String,
-    pub committer_date: u64,
+    /// Serialized flat as `author_name`, `author_email`, `author_date` and
+    /// `author_tz_offset_min`.
+    #[serde(flatten, with = "crate::signature::author_fields")]
+    pub author: Signature,
+    /// Serialized flat as `committer_name`, `committer_email`,
+    /// `committer_date` and `committer_tz_offset_min`.
+    #[serde(flatten, with = "crate::signature::blame_committer_fields")]
+    pub committer: Signature,
     pub summary: String,
     pub filename: String,
+    /// Commit and path of the file before the blamed commit changed it,
+    /// for stepping back to blame the previous revision; absent for lines
+    /// the commit added to a new file, and for boundary commits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_sha: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_filename: Option<String>,
+    /// The commit is a boundary of the blamed range (git's `boundary`
+    /// marker), e.g. the root commit or the limit of `--since`/`A..B`, so
+    /// its lines may be older than the commit itself.
+    #[serde(default)]
+    pub is_boundary: bool,
+    /// The lines are not committed yet (git reports them with an all-zero
+    /// SHA). Such entries have no short SHA, author, committer or summary,
+    /// and aggregations skip them.
+    #[serde(default)]
+    pub is_uncommitted: bool,
+    /// Ignored commit (`ignoreRevs`) the entry was originally attributed to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignored_sha: Option<String>,
+    /// The entry's commit is ignored but no earlier commit could be found
+    /// in the blame to attribute it to instead.
+    #[serde(default)]
+    pub is_unblamable: bool,
+    /// Whether `sha` introduced the lines or, for `git blame --reverse`, is
+    /// the last commit in which they still existed.
+    #[serde(default)]
+    pub mode: BlameMode,
+    /// Where the lines came from when blame ran with `-M`/`-C`; see
+    /// `classify_provenance`.
+    #[serde(default)]
+    pub provenance: Provenance,
+    /// For `Copied` lines, the other file they came from (also their
+    /// `filename`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_path: Option<String>,
+    /// Text of each line in the range, from `--porcelain` or
+    /// `--line-porcelain`; empty for `--incremental`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<String>,
+    /// Row, lane and color of the commit in a linked layout, set by
+    /// `link_to_layout` when the layout contains the commit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_row: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_lane: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_index: Option<u32>,
+}
+
+/// Seconds in a week, a 30-day month and a 365-day year.
+const WEEK: u64 = 7 * 24 * 60 * 60;
+const MONTH: u64 = 30 * 24 * 60 * 60;
+const YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// How to group blamed lines by age for `blame_heatmap`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketSpec {
+    /// Reference time in Unix epoch seconds. Defaults to the newest author
+    /// time in the blame.
+    #[serde(default)]
+    pub now: Option<u64>,
+    /// Increasing upper age limits in seconds. A line falls in the first
+    /// bucket whose limit exceeds its age, or in the extra last bucket.
+    #[serde(default = "default_boundaries")]
+    pub boundaries: Vec<u64>,
+}
+
+fn default_boundaries() -> Vec<u64> {
+    vec![WEEK, MONTH, YEAR]
+}
+
+impl Default for BucketSpec {
+    fn default() -> Self {
+        BucketSpec {
+            now: None,
+            boundaries: default_boundaries(),
+        }
+    }
+}
+
+/// Age bucket of every line in a blamed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Heatmap {
+    /// Bucket index per line, starting at line 1; 0 for uncommitted lines
+    /// and -1 for lines the blame does not cover.
+    pub line_buckets: Vec<i32>,
+    pub boundaries: Vec<u64>,
+    /// Reference time the ages were measured from.
+    pub now: u64,
+}
+
+/// Share of a file's blamed lines last touched by one author.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorOwnership {
+    pub name: String,
+    pub email: String,
+    pub lines: u32,
+    /// Percentage of the file's blamed lines, 0-100.
+    pub percent: f64,
+}
+
+/// Per-author line counts for a blamed file, most lines first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ownership {
+    /// Committed lines; the percentages are shares of these.
+    pub total_lines: u32,
+    #[serde(default)]
+    pub uncommitted_lines: u32,
+    pub owners: Vec<AuthorOwnership>,
+}
+
+/// One file's blame, as input to directory aggregation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBlame {
+    /// Path relative to the repository root.
+    pub path: String,
+    pub entries: Vec<BlameEntry>,
+}
+
+/// Per-author line counts for every file under a directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryOwnership {
+    /// Directory path without a trailing slash; empty for the root.
+    pub path: String,
+    pub file_count: u32,
+    #[serde(flatten)]
+    pub ownership: Ownership,
+}
+
+/// A line whose owning commit differs between two blames of a file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnershipChange {
+    /// Line number in the new blame.
+    pub line: u32,
+    /// Line it replaced in the old blame, if any; `None` for inserted lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_sha: Option<String>,
+    pub new_sha: String,
+}
+
+/// Line attribution changes between two blames of the same file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameDiff {
+    pub changed: Vec<OwnershipChange>,
+    /// Old line numbers that no longer exist and replaced no new line.
+    pub removed_lines: Vec<u32>,
+    /// Lines attributed to the same commit line in both blames.
+    pub unchanged_count: u32,
+}
+
+/// What the commit of a blame entry means.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BlameMode {
+    /// Plain blame: the commit that introduced the lines.
+    #[default]
+    Forward,
+    /// `git blame --reverse`: the last commit in which the lines existed;
+    /// they were removed or changed by one of its children.
+    Reverse,
+}
+
+/// Where the lines of a blame entry came from.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Provenance {
+    /// Written in this file, in place.
+    #[default]
+    Original,
+    /// Moved within this file from elsewhere in it (`-M`).
+    Moved,
+    /// Moved or copied from another file (`-C`).
+    Copied,
+}
+
+/// Post-processing applied by `parse_blame_with_options`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BlameOptions {
+    /// Coalesce consecutive entries from the same commit into one range.
+    pub merge_adjacent: bool,
+    /// Commits to look past, e.g. the lines of `.git-blame-ignore-revs`.
+    pub ignore_revs: Vec<String>,
+    /// Return one `CommitGroup` per commit instead of entries in file order.
+    pub group_by_commit: bool,
+    /// Order of commit groups.
+    pub sort_by: GroupSort,
+    /// The output is from `git blame --reverse`, which prints the same
+    /// format; entries are marked `BlameMode::Reverse`.
+    pub reverse: bool,
+    /// Path of the blamed file, for telling lines copied from other files
+    /// apart; inferred when absent.
+    pub path: Option<String>,
+}
+
+/// Order of the groups returned by `group_by_commit`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GroupSort {
+    /// Newest author date first.
+    #[default]
+    Date,
+    /// Most lines first.
+    Lines,
+}
+
+/// A run of lines attributed to one commit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineRange {
+    pub orig_line: u32,
+    pub final_line: u32,
+    pub num_lines: u32,
+}
+
+/// All the lines of a file attributed to one commit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitGroup {
+    pub sha: String,
+    pub short_sha: String,
+    pub author: Signature,
+    pub committer: Signature,
+    pub summary: String,
+    pub line_count: u32,
+    /// Ranges in line order.
+    pub ranges: Vec<LineRange>,
+}
+
+/// A commit in which the blamed file changed path.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PathRename {
+    pub sha: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Paths the blamed file has had, from the `filename` and `previous`
+/// headers of a blame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameHistory {
+    /// Path of the file in each commit the blame mentions.
+    pub path_at_commit: BTreeMap<String, String>,
+    /// Every historical path, most recently used first.
+    pub paths: Vec<String>,
+    /// Renames, newest first.
+    pub renames: Vec<PathRename>,
+}
+
+/// Date range that line ages are normalized against; either end defaults
+/// to the oldest or newest author time in the blame.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AgeRange {
+    pub oldest: Option<u64>,
+    pub newest: Option<u64>,
+}
+
+/// Normalized age of every line in a blamed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineAges {
+    /// Age per line, starting at line 1: 0.0 at `newest` to 1.0 at
+    /// `oldest`, clamped; 0.0 for uncommitted lines and -1.0 for lines the
+    /// blame does not cover.
+    pub ages: Vec<f32>,
+    pub oldest: u64,
+    pub newest: u64,
+}
+
+/// Longest run of consecutive lines attributed to one commit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSpan {
+    pub sha: String,
+    pub final_line: u32,
+    pub num_lines: u32,
+}
+
+/// Headline numbers for a blamed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameSummary {
+    /// Oldest and newest author times among the lines; 0 for an empty blame.
+    pub oldest_date: u64,
+    pub newest_date: u64,
+    /// All blamed lines, including `uncommitted_lines`; the other figures
+    /// only cover committed lines.
+    pub line_count: u32,
+    #[serde(default)]
+    pub uncommitted_lines: u32,
+    pub commit_count: usize,
+    /// Distinct author emails, ignoring case.
+    pub author_count: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub largest_span: Option<CommitSpan>,
+}
+
+/// How `render_hover` formats a blame hover.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HoverOptions {
+    /// Markdown with `{placeholder}`s; see `render_hover`. Defaults to
+    /// `DEFAULT_HOVER_TEMPLATE`.
+    pub template: Option<String>,
+    /// Current epoch time for `{relativeDate}`. Without it the relative
+    /// date falls back to the absolute date.
+    pub now: Option<u64>,
+    /// Avatar image URL with `{email}` and `{name}` placeholders, rendered
+    /// by `{avatar}`.
+    pub avatar_url: Option<String>,
+    /// Markdown for uncommitted lines, with the same placeholders; defaults
+    /// to `DEFAULT_UNCOMMITTED_TEMPLATE`.
+    pub uncommitted_template: Option<String>,
 }
@@ -0,0 +1,77 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Kind of failure reported by an export, for the caller to branch on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The layout or blame handle was never issued or has been freed.
+    InvalidHandle,
+    /// A regex or glob pattern does not compile.
+    BadRegex,
+    /// A filter query does not parse.
+    BadQuery,
+    /// A date or date expression does not parse.
+    BadDate,
+    /// A JSON argument, option, name or other input is malformed.
+    BadArgument,
+    /// Input that should be git output could not be parsed.
+    ParseFailed,
+    /// A named item, such as a saved filter, does not exist.
+    NotFound,
+    /// The handle does not support the operation, e.g. appending to a
+    /// filtered view.
+    InvalidState,
+    /// Shared state could not be locked.
+    LockFailed,
+    Serialization,
+    Internal,
+    /// The export panicked; the message has the panic's reason.
+    Panic,
+}
+
+/// The `error` of a failed export's result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorInfo {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Machine-readable context, such as the offending handle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ErrorInfo {
+    /// An error of the kind `code`. Each raising site picks the code, so it
+    /// does not depend on the wording of the message.
+    pub fn with_code(code: ErrorCode, message: impl Into<String>) -> ErrorInfo {
+        ErrorInfo {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+}
+
+impl fmt::Display for ErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_info_json() {
+        let error = ErrorInfo::with_code(ErrorCode::InvalidState, "Not a filtered view: 3");
+        assert_eq!(
+            serde_json::to_value(error).unwrap(),
+            serde_json::json!({
+                "code": "INVALID_STATE",
+                "message": "Not a filtered view: 3",
+            })
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use crate::graph::compute_layout;
+use crate::graph::types::{CommitNode, LayoutResult};
+
+/// Re-run lane and row assignment over the commits of a filtered view, so
+/// it draws without the gaps and idle lanes left by hidden commits.
+///
+/// The edges of `filtered`, `EdgeType::Elided` ones included, stand in for
+/// parent links, so nodes and edges are laid out as drawn. Nodes keep
+/// their order, commit data and flags, and edges their type; only rows,
+/// lanes and colors change.
+pub fn compact_layout(filtered: &mut LayoutResult) {
+    let mut parents: HashMap<&str, Vec<String>> = HashMap::new();
+    for edge in &filtered.edges {
+        let list = parents.entry(&edge.from_sha).or_default();
+        if !list.contains(&edge.to_sha) {
+            list.push(edge.to_sha.clone());
+        }
+    }
+    let commits: Vec<CommitNode> = filtered
+        .nodes
+        .iter()
+        .map(|node| CommitNode {
+            sha: node.sha.clone(),
+            parents: parents.remove(node.sha.as_str()).unwrap_or_default(),
+            ..Default::default()
+        })
+        .collect();
+
+    let relaid = compute_layout(&commits);
+    for (node, placed) in filtered.nodes.iter_mut().zip(&relaid.nodes) {
+        node.row = placed.row;
+        node.lane = placed.lane;
+        node.color_index = placed.color_index;
+    }
+    let placed_edges: HashMap<(&str, &str), _> = relaid
+        .edges
+        .iter()
+        .map(|e| ((e.from_sha.as_str(), e.to_sha.as_str()), e))
+        .collect();
+    for edge in &mut filtered.edges {
+        if let Some(placed) = placed_edges.get(&(edge.from_sha.as_str(), edge.to_sha.as_str())) {
+            edge.from_row = placed.from_row;
+            edge.from_lane = placed.from_lane;
+            edge.to_row = placed.to_row;
+            edge.to_lane = placed.to_lane;
+            edge.color_index = placed.color_index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{elide_hidden_commits, select_nodes};
+    use crate::graph::types::EdgeType;
+    use crate::test_support::commit;
+
+    #[test]
+    fn test_compact_layout() {
+        // A side branch `s` off `b`, merged at `m`
+        let layout = compute_layout(&[
+            commit("m", &["c", "s"]),
+            commit("s", &["b"]),
+            commit("c", &["b"]),
+            commit("b", &["a"]),
+            commit("a", &[]),
+        ]);
+        let mut filtered = select_nodes(&layout, |n| n.sha != "s" && n.sha != "c");
+        elide_hidden_commits(&layout, &mut filtered);
+        filtered.nodes[1].is_context = true;
+        compact_layout(&mut filtered);
+
+        let placed: Vec<(&str, i32, i32)> = filtered
+            .nodes
+            .iter()
+            .map(|n| (n.sha.as_str(), n.row, n.lane))
+            .collect();
+        assert_eq!(placed, vec![("m", 0, 0), ("b", 1, 0), ("a", 2, 0)]);
+        assert!(filtered.nodes[1].is_context);
+        assert_eq!(filtered.total_count, 3);
+
+        let elided = filtered
+            .edges
+            .iter()
+            .find(|e| e.edge_type == EdgeType::Elided)
+            .unwrap();
+        assert_eq!((elided.from_row, elided.to_row), (0, 1));
+        assert!(filtered.edges.iter().all(|e| e.from_lane == 0 && e.to_lane == 0));
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::{HashMap, HashSet};
+
+use super::select_nodes;
+use crate::graph::types::LayoutResult;
+
+/// Add the commits up to `depth` parent or child links away from each
+/// commit in `filtered`, like `grep -C`, so matches are shown where they
+/// sit in history. Added commits are flagged `is_context`.
+///
+/// Links are followed through `layout` only. Returns the number of
+/// commits added.
+pub fn add_context(layout: &LayoutResult, filtered: &mut LayoutResult, depth: u32) -> usize {
+    if depth == 0 {
+        return 0;
+    }
+
+    let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in &layout.nodes {
+        for parent in &node.parents {
+            neighbours.entry(&node.sha).or_default().push(parent);
+            neighbours.entry(parent).or_default().push(&node.sha);
+        }
+    }
+
+    let matches: HashSet<&str> = filtered.nodes.iter().map(|n| n.sha.as_str()).collect();
+    let mut kept = matches.clone();
+    let mut frontier: Vec<&str> = matches.iter().copied().collect();
+    for _ in 0..depth {
+        frontier = frontier
+            .iter()
+            .flat_map(|sha| neighbours.get(sha).into_iter().flatten().copied())
+            .filter(|sha| kept.insert(sha))
+            .collect();
+        if frontier.is_empty() {
+            break;
+        }
+    }
+
+    let mut result = select_nodes(layout, |node| kept.contains(node.sha.as_str()));
+    for node in &mut result.nodes {
+        node.is_context = !matches.contains(node.sha.as_str());
+    }
+    let added = result.nodes.len() - matches.len();
+    *filtered = result;
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::test_support::commit;
+
+    #[test]
+    fn test_context_in_both_directions() {
+        let layout = compute_layout(&[
+            commit("e", &["d"]),
+            commit("d", &["c", "x"]),
+            commit("x", &["b"]),
+            commit("c", &["b"]),
+            commit("b", &["a"]),
+            commit("a", &[]),
+        ]);
+        let mut filtered = select_nodes(&layout, |n| n.sha == "c");
+
+        assert_eq!(add_context(&layout, &mut filtered, 1), 2);
+        let kept: Vec<(&str, bool)> = filtered
+            .nodes
+            .iter()
+            .map(|n| (n.sha.as_str(), n.is_context))
+            .collect();
+        assert_eq!(kept, vec![("d", true), ("c", false), ("b", true)]);
+        assert_eq!(filtered.edges.len(), 2);
+
+        let mut filtered = select_nodes(&layout, |n| n.sha == "c");
+        add_context(&layout, &mut filtered, 2);
+        let kept: Vec<&str> = filtered.nodes.iter().map(|n| n.sha.as_str()).collect();
+        assert_eq!(kept, vec!["e", "d", "x", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_zero_context_is_a_no_op() {
+        let layout = compute_layout(&[commit("b", &["a"]), commit("a", &[])]);
+        let mut filtered = select_nodes(&layout, |n| n.sha == "b");
+        assert_eq!(add_context(&layout, &mut filtered, 0), 0);
+        assert_eq!(filtered.total_count, 1);
+        assert!(!filtered.nodes[0].is_context);
+    }
+}
@@ -1,5 +1,13 @@
+use crate::error::{ErrorCode, ErrorInfo};
+use crate::graph::date::{months_before, parse_iso8601, start_of_day};
 use crate::graph::types::LayoutResult;
 
+/// Whether `date` is within `after..=before`, where 0 leaves that side
+/// open.
+pub(crate) fn in_date_range(date: u64, after: u64, before: u64) -> bool {
+    (after == 0 || date >= after) && (before == 0 || date <= before)
+}
+
 /// Filter commits in a LayoutResult by date range.
 ///
 /// `after` and `before` are unix epoch timestamps (seconds).
@@ -13,12 +21,7 @@ pub fn filter_commits_by_date(
     let matching_shas: std::collections::HashSet<String> = layout
         .nodes
         .iter()
-        .filter(|node| {
-            let date = node.author_date;
-            let after_ok = after == 0 || date >= after;
-            let before_ok = before == 0 || date <= before;
-            after_ok && before_ok
-        })
+        .filter(|node| in_date_range(node.author_date, after, before))
         .map(|node| node.sha.clone())
         .collect();
 
@@ -45,6 +48,62 @@ pub fn filter_commits_by_date(
     }
 }
 
+/// Resolve a date bound written the way people write it into epoch
+/// seconds.
+///
+/// Accepts `now`, `today` and `yesterday` (at the start of the day),
+/// `N <unit>s ago`, `a <unit> ago` and `last <unit>`, with units from
+/// seconds to years, as well as everything `parse_absolute_date` takes.
+/// Relative expressions count back from `now`; days start at midnight in
+/// `offset_min`, the user's UTC offset in minutes, and months and years
+/// are calendar months and years.
+pub fn parse_date_expr(text: &str, now: u64, offset_min: i32) -> Result<u64, ErrorInfo> {
+    let invalid = |message: String| ErrorInfo::with_code(ErrorCode::BadDate, message);
+    let text = text.trim();
+    if let Some(time) = parse_absolute_date(text, offset_min) {
+        return Ok(time);
+    }
+
+    let lower = text.to_ascii_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    let (count, unit) = match words.as_slice() {
+        ["now"] => return Ok(now),
+        ["today"] => return Ok(start_of_day(now, offset_min)),
+        ["yesterday"] => return Ok(start_of_day(now.saturating_sub(86_400), offset_min)),
+        ["a" | "an" | "one", unit, "ago"] | ["last", unit] => (1, *unit),
+        [count, unit, "ago"] => match count.parse::<u64>() {
+            Ok(count) => (count, *unit),
+            Err(_) => return Err(invalid(format!("Invalid date: '{}'", text))),
+        },
+        _ => return Err(invalid(format!("Invalid date: '{}'", text))),
+    };
+
+    let seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+        "second" | "sec" => 1,
+        "minute" | "min" => 60,
+        "hour" => 3600,
+        "day" => 86_400,
+        "week" => 7 * 86_400,
+        "month" => return Ok(months_before(now, count, offset_min)),
+        "year" => return Ok(months_before(now, count.saturating_mul(12), offset_min)),
+        _ => return Err(invalid(format!("Invalid date: unknown unit '{}'", unit))),
+    };
+    Ok(now.saturating_sub(count.saturating_mul(seconds)))
+}
+
+/// Epoch seconds, a `YYYY-MM-DD` date at midnight in `offset_min` or an
+/// ISO-8601 timestamp.
+pub(crate) fn parse_absolute_date(text: &str, offset_min: i32) -> Option<u64> {
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+        return text.parse().ok();
+    }
+    if text.len() == 10 {
+        let time = parse_iso8601(&format!("{}T00:00:00Z", text))?.0;
+        return Some((time as i64 - offset_min as i64 * 60).max(0) as u64);
+    }
+    parse_iso8601(text).map(|(time, _)| time)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,6 +124,7 @@ mod tests {
                     refs: vec![],
                     parents: vec!["bbb222".to_string()],
                     node_type: NodeType::Normal,
+                    ..Default::default()
                 },
                 LayoutNode {
                     sha: "bbb222".to_string(),
@@ -78,6 +138,7 @@ mod tests {
                     refs: vec![],
                     parents: vec!["ccc333".to_string()],
                     node_type: NodeType::Normal,
+                    ..Default::default()
                 },
                 LayoutNode {
                     sha: "ccc333".to_string(),
@@ -91,6 +152,7 @@ mod tests {
                     refs: vec![],
                     parents: vec![],
                     node_type: NodeType::Normal,
+                    ..Default::default()
                 },
             ],
             edges: vec![
@@ -103,6 +165,7 @@ mod tests {
                     to_row: 1,
                     edge_type: EdgeType::Normal,
                     color_index: 0,
+                    truncated_direction: None,
                 },
                 Edge {
                     from_sha: "bbb222".to_string(),
@@ -113,6 +176,7 @@ mod tests {
                     to_row: 2,
                     edge_type: EdgeType::Normal,
                     color_index: 0,
+                    truncated_direction: None,
                 },
             ],
             total_count: 3,
@@ -153,6 +217,37 @@ mod tests {
         assert_eq!(result.total_count, 3);
     }
 
+    #[test]
+    fn test_parse_date_expr() {
+        // 2023-11-14T22:13:20Z
+        let now = 1700000000;
+        let parse = |text: &str| parse_date_expr(text, now, 0);
+        assert_eq!(parse("now"), Ok(now));
+        assert_eq!(parse("today"), Ok(1699920000));
+        assert_eq!(parse(" Yesterday "), Ok(1699833600));
+        assert_eq!(parse("2 weeks ago"), Ok(now - 14 * 86_400));
+        assert_eq!(parse("3 Hours ago"), Ok(now - 3 * 3600));
+        assert_eq!(parse("1 day ago"), Ok(now - 86_400));
+        assert_eq!(parse("a month ago"), Ok(now - 31 * 86_400));
+        assert_eq!(parse("last year"), Ok(now - 365 * 86_400));
+        assert_eq!(parse("100000 years ago"), Ok(0));
+        assert_eq!(parse("2024-03-01"), Ok(1709251200));
+        assert_eq!(parse("2023-11-14T23:13:20+01:00"), Ok(now));
+        assert_eq!(parse("1690000000"), Ok(1690000000));
+
+        for bad in ["", "soon", "2 fortnights ago", "many days ago", "2024-13-01"] {
+            assert_eq!(parse(bad).unwrap_err().code, ErrorCode::BadDate, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_date_expr_in_local_time() {
+        // 00:13:20 on Nov 15 at UTC+2
+        let now = 1700000000;
+        assert_eq!(parse_date_expr("today", now, 120), Ok(now - 800));
+        assert_eq!(parse_date_expr("2024-03-01", now, 60), Ok(1709251200 - 3600));
+    }
+
     #[test]
     fn test_filter_no_match() {
         let layout = make_test_layout();
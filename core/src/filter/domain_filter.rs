@@ -0,0 +1,112 @@
+use super::select_nodes;
+use crate::error::{ErrorCode, ErrorInfo};
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+/// The domain of an email address: what follows the last `@`.
+pub fn email_domain(email: &str) -> Option<&str> {
+    email.rsplit_once('@').map(|(_, domain)| domain.trim_end_matches('>'))
+}
+
+/// Whether `email` is at `domain` or one of its subdomains, ignoring case,
+/// so `mycompany.com` covers `eng.mycompany.com` but not
+/// `notmycompany.com`. A leading `@` on `domain` is ignored.
+pub fn in_domain(email: &str, domain: &str) -> bool {
+    let domain = domain.trim().trim_start_matches('@');
+    let Some(actual) = email_domain(email) else {
+        return false;
+    };
+    if domain.is_empty() || actual.len() < domain.len() {
+        return false;
+    }
+    let (prefix, suffix) = actual.split_at(actual.len() - domain.len());
+    suffix.eq_ignore_ascii_case(domain) && (prefix.is_empty() || prefix.ends_with('.'))
+}
+
+/// The emails of `node` that `field` looks at: "author", "committer", or
+/// "email" for either.
+pub(crate) fn field_emails<'a>(node: &'a LayoutNode, field: &str) -> Option<Vec<&'a str>> {
+    match field {
+        "author" => Some(vec![&node.author_email]),
+        "committer" => Some(vec![&node.committer_email]),
+        "email" => Some(vec![&node.author_email, &node.committer_email]),
+        _ => None,
+    }
+}
+
+/// Filter commits in a LayoutResult by email domain, e.g. to audit
+/// external contributions.
+///
+/// `field` is "author", "committer" or "email" (either). Keeps commits
+/// whose email is in any of `domains` or their subdomains, or with
+/// `exclude` the commits whose email is in none of them. Returns a new
+/// LayoutResult containing only matching nodes and their edges.
+pub fn filter_commits_by_domain(
+    layout: &LayoutResult,
+    field: &str,
+    domains: &[String],
+    exclude: bool,
+) -> Result<LayoutResult, ErrorInfo> {
+    if field_emails(&LayoutNode::default(), field).is_none() {
+        let message = format!("Unknown email field: {}", field);
+        return Err(ErrorInfo::with_code(ErrorCode::BadArgument, message));
+    }
+    Ok(select_nodes(layout, |node| {
+        let emails = field_emails(node, field).unwrap_or_default();
+        let inside = emails.iter().any(|e| domains.iter().any(|d| in_domain(e, d)));
+        inside != exclude
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(sha: &str, author_email: &str, committer_email: &str) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            author_email: author_email.to_string(),
+            committer_email: committer_email.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_in_domain() {
+        assert!(in_domain("alice@mycompany.com", "mycompany.com"));
+        assert!(in_domain("alice@Eng.MyCompany.com", "@mycompany.com"));
+        assert!(!in_domain("mallory@notmycompany.com", "mycompany.com"));
+        assert!(!in_domain("alice@mycompany.com.evil", "mycompany.com"));
+        assert!(!in_domain("no-email", "mycompany.com"));
+        assert!(!in_domain("alice@mycompany.com", ""));
+        assert_eq!(email_domain("a@b@c.org"), Some("c.org"));
+    }
+
+    #[test]
+    fn test_filter_by_domain() {
+        let layout = LayoutResult {
+            nodes: vec![
+                node("inside", "alice@mycompany.com", "alice@mycompany.com"),
+                node("applied", "bob@gmail.com", "carol@eng.mycompany.com"),
+                node("outside", "dave@example.org", "dave@example.org"),
+            ],
+            edges: vec![],
+            total_count: 3,
+        };
+        let domains = vec!["mycompany.com".to_string()];
+        let shas = |result: LayoutResult| -> Vec<String> {
+            result.nodes.into_iter().map(|n| n.sha).collect()
+        };
+
+        let kept = filter_commits_by_domain(&layout, "email", &domains, false).unwrap();
+        assert_eq!(shas(kept), vec!["inside", "applied"]);
+
+        // Authored outside the company, whoever committed it
+        let external = filter_commits_by_domain(&layout, "author", &domains, true).unwrap();
+        assert_eq!(shas(external), vec!["applied", "outside"]);
+
+        let committed = filter_commits_by_domain(&layout, "committer", &domains, false).unwrap();
+        assert_eq!(shas(committed), vec!["inside", "applied"]);
+
+        assert!(filter_commits_by_domain(&layout, "sha", &domains, false).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::types::{Edge, EdgeType, LayoutNode, LayoutResult};
+
+/// Reconnect a filtered view: for every commit in `filtered` whose parents
+/// were filtered out of `layout`, add an `EdgeType::Elided` edge to each
+/// nearest ancestor that is still in `filtered`.
+///
+/// Ancestors are followed through `layout` only, so a path leaving the
+/// loaded commits ends there. Returns the number of edges added.
+pub fn elide_hidden_commits(layout: &LayoutResult, filtered: &mut LayoutResult) -> usize {
+    let nodes: HashMap<&str, &LayoutNode> =
+        layout.nodes.iter().map(|n| (n.sha.as_str(), n)).collect();
+    let kept: HashSet<&str> = filtered.nodes.iter().map(|n| n.sha.as_str()).collect();
+
+    let mut elided = Vec::new();
+    for node in &filtered.nodes {
+        let mut targets: Vec<&str> = Vec::new();
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut stack: Vec<&str> = node
+            .parents
+            .iter()
+            .rev()
+            .map(String::as_str)
+            .filter(|p| !kept.contains(p))
+            .collect();
+        while let Some(sha) = stack.pop() {
+            if !visited.insert(sha) {
+                continue;
+            }
+            let Some(hidden) = nodes.get(sha) else {
+                continue;
+            };
+            for parent in hidden.parents.iter().rev() {
+                if kept.contains(parent.as_str()) {
+                    let direct = node.parents.contains(parent);
+                    if !direct && !targets.contains(&parent.as_str()) {
+                        targets.push(parent);
+                    }
+                } else {
+                    stack.push(parent);
+                }
+            }
+        }
+
+        for target in targets {
+            let to = nodes[target];
+            elided.push(Edge {
+                from_sha: node.sha.clone(),
+                to_sha: to.sha.clone(),
+                from_lane: node.lane,
+                to_lane: to.lane,
+                from_row: node.row,
+                to_row: to.row,
+                edge_type: EdgeType::Elided,
+                color_index: node.color_index,
+                truncated_direction: None,
+            });
+        }
+    }
+
+    let added = elided.len();
+    filtered.edges.extend(elided);
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::select_nodes;
+
+    fn node(sha: &str, row: i32, parents: &[&str]) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            row,
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_elides_hidden_paths() {
+        // e -> d -> c (merge of b and x) -> a, with x -> a
+        let layout = LayoutResult {
+            nodes: vec![
+                node("e", 0, &["d"]),
+                node("d", 1, &["c"]),
+                node("c", 2, &["b", "x"]),
+                node("x", 3, &["a"]),
+                node("b", 4, &["a"]),
+                node("a", 5, &["outside"]),
+            ],
+            edges: vec![],
+            total_count: 6,
+        };
+        let mut filtered = select_nodes(&layout, |n| ["e", "b", "a"].contains(&n.sha.as_str()));
+        let added = elide_hidden_commits(&layout, &mut filtered);
+
+        let edges: Vec<(&str, &str, i32)> = filtered
+            .edges
+            .iter()
+            .map(|e| (e.from_sha.as_str(), e.to_sha.as_str(), e.to_row))
+            .collect();
+        // e reaches b through d and c, and a through x; b's parent a is kept
+        assert_eq!(edges, vec![("e", "b", 4), ("e", "a", 5)]);
+        assert_eq!(added, 2);
+        assert!(filtered.edges.iter().all(|e| e.edge_type == EdgeType::Elided));
+    }
+
+    #[test]
+    fn test_direct_edges_are_not_duplicated() {
+        let layout = LayoutResult {
+            nodes: vec![node("b", 0, &["a", "h"]), node("h", 1, &["a"]), node("a", 2, &[])],
+            edges: vec![],
+            total_count: 3,
+        };
+        let mut filtered = select_nodes(&layout, |n| n.sha != "h");
+        assert_eq!(elide_hidden_commits(&layout, &mut filtered), 0);
+    }
+}
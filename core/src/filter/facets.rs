@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::graph::date::format_date;
+use crate::graph::types::{LayoutResult, RefType};
+
+/// How many matching commits share one facet value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Aggregate counts over a filter's matches, for "narrow by" chips.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Facets {
+    /// By author name, most matches first.
+    pub authors: Vec<FacetCount>,
+    /// By author date as "YYYY", newest first.
+    pub years: Vec<FacetCount>,
+    /// By author date as "YYYY-MM", newest first.
+    pub months: Vec<FacetCount>,
+    /// By local or remote branch the match is reachable from, most matches
+    /// first.
+    pub refs: Vec<FacetCount>,
+}
+
+/// Filter results together with their facets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacetView {
+    #[serde(flatten)]
+    pub layout: LayoutResult,
+    pub facets: Facets,
+}
+
+/// Count the matches in `filtered` by author, year, month and branch.
+///
+/// Dates are taken in the author's own time zone. Context commits are not
+/// matches and are left out. Branch counts follow parents through the whole
+/// of `layout`, so a branch counts matches on history the filter hid.
+pub fn compute_facets(layout: &LayoutResult, filtered: &LayoutResult) -> Facets {
+    let matches: Vec<_> = filtered.nodes.iter().filter(|n| !n.is_context).collect();
+
+    let mut authors: HashMap<&str, usize> = HashMap::new();
+    let mut years: BTreeMap<String, usize> = BTreeMap::new();
+    let mut months: BTreeMap<String, usize> = BTreeMap::new();
+    for node in &matches {
+        *authors.entry(node.author_name.as_str()).or_default() += 1;
+        let date = format_date(node.author_date, node.author_tz_offset_min);
+        *years.entry(date[..4].to_string()).or_default() += 1;
+        *months.entry(date[..7].to_string()).or_default() += 1;
+    }
+
+    let matched: HashSet<&str> = matches.iter().map(|n| n.sha.as_str()).collect();
+    let refs = branch_counts(layout, &matched);
+
+    Facets {
+        authors: by_count(authors.into_iter().map(|(name, count)| (name.to_string(), count))),
+        years: newest_first(years),
+        months: newest_first(months),
+        refs: by_count(refs.into_iter().filter(|&(_, count)| count > 0)),
+    }
+}
+
+/// Count the `matched` commits reachable from each branch in `layout`.
+///
+/// One pass from the tips down: every commit is visited after all of its
+/// children, and hands the set of branches reaching it (as a bitset over
+/// the branch refs) on to its parents. This keeps the cost at commits ×
+/// branches / 64 rather than a walk of the history per branch, and does
+/// not rely on the rows being in topological order.
+fn branch_counts(layout: &LayoutResult, matched: &HashSet<&str>) -> Vec<(String, usize)> {
+    let nodes = &layout.nodes;
+    let rows: HashMap<&str, usize> =
+        nodes.iter().enumerate().map(|(row, n)| (n.sha.as_str(), row)).collect();
+
+    let mut names = Vec::new();
+    let mut reaching: Vec<Vec<u64>> = vec![Vec::new(); nodes.len()];
+    for (row, node) in nodes.iter().enumerate() {
+        for r in &node.refs {
+            if matches!(r.ref_type, RefType::Branch | RefType::RemoteBranch) {
+                set_bit(&mut reaching[row], names.len());
+                names.push(r.name.clone());
+            }
+        }
+    }
+    if names.is_empty() {
+        return Vec::new();
+    }
+
+    let mut children = vec![0usize; nodes.len()];
+    for node in nodes {
+        for parent in &node.parents {
+            if let Some(&row) = rows.get(parent.as_str()) {
+                children[row] += 1;
+            }
+        }
+    }
+
+    let mut counts = vec![0usize; names.len()];
+    let mut ready: Vec<usize> = (0..nodes.len()).filter(|&row| children[row] == 0).collect();
+    while let Some(row) = ready.pop() {
+        let branches = std::mem::take(&mut reaching[row]);
+        if matched.contains(nodes[row].sha.as_str()) {
+            for (i, word) in branches.iter().enumerate() {
+                for bit in 0..64 {
+                    if word & (1 << bit) != 0 {
+                        counts[i * 64 + bit] += 1;
+                    }
+                }
+            }
+        }
+        for parent in &nodes[row].parents {
+            let Some(&parent_row) = rows.get(parent.as_str()) else {
+                continue;
+            };
+            let into = &mut reaching[parent_row];
+            if into.len() < branches.len() {
+                into.resize(branches.len(), 0);
+            }
+            for (into, word) in into.iter_mut().zip(&branches) {
+                *into |= word;
+            }
+            children[parent_row] -= 1;
+            if children[parent_row] == 0 {
+                ready.push(parent_row);
+            }
+        }
+    }
+
+    names.into_iter().zip(counts).collect()
+}
+
+fn set_bit(bits: &mut Vec<u64>, bit: usize) {
+    if bits.len() <= bit / 64 {
+        bits.resize(bit / 64 + 1, 0);
+    }
+    bits[bit / 64] |= 1 << (bit % 64);
+}
+
+fn by_count(counts: impl Iterator<Item = (String, usize)>) -> Vec<FacetCount> {
+    let mut counts: Vec<FacetCount> = counts
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
+
+fn newest_first(counts: BTreeMap<String, usize>) -> Vec<FacetCount> {
+    counts
+        .into_iter()
+        .rev()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::select_nodes;
+    use crate::graph::compute_layout;
+    use crate::graph::types::{CommitNode, RefInfo};
+    use crate::signature::Signature;
+
+    fn commit(sha: &str, parents: &[&str], author: &str, date: u64, branch: &str) -> CommitNode {
+        CommitNode {
+            sha: sha.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            author: Signature {
+                name: author.to_string(),
+                time: date,
+                ..Default::default()
+            },
+            refs: if branch.is_empty() {
+                Vec::new()
+            } else {
+                vec![RefInfo {
+                    name: branch.to_string(),
+                    ref_type: RefType::Branch,
+                    is_head: false,
+                }]
+            },
+            ..Default::default()
+        }
+    }
+
+    fn count(value: &str, count: usize) -> FacetCount {
+        FacetCount {
+            value: value.to_string(),
+            count,
+        }
+    }
+
+    #[test]
+    fn test_compute_facets() {
+        // 2024-03-01, 2024-02-01, 2023-12-01 and 2023-11-01 UTC
+        let layout = compute_layout(&[
+            commit("d", &["b"], "bob", 1_709_251_200, "feature"),
+            commit("c", &["b"], "alice", 1_706_745_600, "main"),
+            commit("b", &["a"], "alice", 1_701_388_800, ""),
+            commit("a", &[], "carol", 1_698_796_800, ""),
+        ]);
+        let mut filtered = select_nodes(&layout, |n| n.sha != "a");
+        filtered.nodes.iter_mut().find(|n| n.sha == "b").unwrap().is_context = true;
+
+        let facets = compute_facets(&layout, &filtered);
+        assert_eq!(facets.authors, vec![count("alice", 1), count("bob", 1)]);
+        assert_eq!(facets.years, vec![count("2024", 2)]);
+        assert_eq!(facets.months, vec![count("2024-03", 1), count("2024-02", 1)]);
+        assert_eq!(facets.refs, vec![count("feature", 1), count("main", 1)]);
+
+        let filtered = select_nodes(&layout, |n| n.author_name != "bob");
+        let facets = compute_facets(&layout, &filtered);
+        assert_eq!(facets.authors, vec![count("alice", 2), count("carol", 1)]);
+        assert_eq!(facets.years, vec![count("2024", 1), count("2023", 2)]);
+        assert_eq!(facets.refs, vec![count("main", 3), count("feature", 2)]);
+    }
+
+    #[test]
+    fn test_branch_counts_past_one_word() {
+        // 70 branches, each one commit off a shared root, plus a merge of
+        // the first two; the rows are not in topological order
+        let mut commits = vec![commit("root", &[], "alice", 0, "")];
+        let tips: Vec<String> = (0..70).map(|i| format!("tip{}", i)).collect();
+        for (i, tip) in tips.iter().enumerate() {
+            commits.push(commit(tip, &["root"], "alice", 0, &format!("b{:02}", i)));
+        }
+        commits.push(commit("merge", &["tip0", "tip1"], "alice", 0, "merged"));
+        let layout = compute_layout(&commits);
+
+        let facets = compute_facets(&layout, &layout);
+        assert_eq!(facets.refs.len(), 71);
+        assert_eq!(facets.refs[0], count("merged", 4));
+        assert_eq!(facets.refs[1], count("b00", 2));
+        assert!(facets.refs[1..].iter().all(|f| f.count == 2));
+
+        let filtered = select_nodes(&layout, |n| n.sha == "tip69");
+        assert_eq!(compute_facets(&layout, &filtered).refs, vec![count("b69", 1)]);
+    }
+}
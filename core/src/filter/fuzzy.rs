@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+
+use super::regex_filter::MatchFlags;
+use crate::error::{ErrorCode, ErrorInfo};
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+/// Match at the start of a word.
+const BONUS_BOUNDARY: i32 = 8;
+/// Match at a lowercase-to-uppercase or letter-to-digit transition.
+const BONUS_CAMEL: i32 = 7;
+/// Match right after another match.
+const BONUS_CONSECUTIVE: i32 = 4;
+/// The first pattern character's bonus counts double.
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// A commit matched by `fuzzy_filter_commits`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuzzyMatch {
+    pub node: LayoutNode,
+    /// Higher is better.
+    pub score: i32,
+    /// UTF-16 offsets of the matched characters in the field, as
+    /// JavaScript indexes strings, for highlighting.
+    pub positions: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    NonWord,
+    Lower,
+    Upper,
+    Number,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_lowercase() {
+        CharClass::Lower
+    } else if c.is_uppercase() {
+        CharClass::Upper
+    } else if c.is_numeric() {
+        CharClass::Number
+    } else if c.is_alphabetic() {
+        CharClass::Lower
+    } else {
+        CharClass::NonWord
+    }
+}
+
+fn bonus(prev: CharClass, class: CharClass) -> i32 {
+    match (prev, class) {
+        (_, CharClass::NonWord) => 0,
+        (CharClass::NonWord, _) => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL,
+        (CharClass::Lower | CharClass::Upper, CharClass::Number) => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// Score `text` against `pattern` as an fzf-style subsequence match.
+///
+/// Finds the leftmost occurrence of the pattern's characters in order,
+/// shrinks it to the shortest window ending at the same place, and scores
+/// that window: every matched character scores, more so at word starts,
+/// camelCase humps and in runs, and gaps cost. Returns the score and the
+/// char indices of the matched characters, or `None` if `text` does not
+/// contain the pattern as a subsequence. An empty pattern matches with
+/// score 0.
+pub fn fuzzy_match(text: &str, pattern: &str, ignore_case: bool) -> Option<(i32, Vec<usize>)> {
+    let fold = |c: char| {
+        if ignore_case {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    };
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().map(fold).collect();
+    if pattern.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    // Leftmost end of the pattern
+    let mut matched = 0;
+    let end = text.iter().position(|&c| {
+        if fold(c) == pattern[matched] {
+            matched += 1;
+        }
+        matched == pattern.len()
+    })?;
+
+    // Latest start for that end
+    let mut remaining = pattern.len();
+    let start = (0..=end).rev().find(|&i| {
+        if fold(text[i]) == pattern[remaining - 1] {
+            remaining -= 1;
+        }
+        remaining == 0
+    })?;
+
+    let mut score = 0;
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut prev_class = match start {
+        0 => CharClass::NonWord,
+        _ => char_class(text[start - 1]),
+    };
+    let (mut in_gap, mut consecutive, mut first_bonus) = (false, 0, 0);
+    for (i, &c) in text.iter().enumerate().take(end + 1).skip(start) {
+        let class = char_class(c);
+        if positions.len() < pattern.len() && fold(c) == pattern[positions.len()] {
+            let mut char_bonus = bonus(prev_class, class);
+            if consecutive == 0 {
+                first_bonus = char_bonus;
+            } else {
+                // A run keeps the bonus it started with
+                if char_bonus >= BONUS_BOUNDARY && char_bonus > first_bonus {
+                    first_bonus = char_bonus;
+                }
+                char_bonus = char_bonus.max(first_bonus).max(BONUS_CONSECUTIVE);
+            }
+            if positions.is_empty() {
+                char_bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+            }
+            score += SCORE_MATCH + char_bonus;
+            positions.push(i);
+            in_gap = false;
+            consecutive += 1;
+        } else {
+            score += if in_gap { SCORE_GAP_EXTENSION } else { SCORE_GAP_START };
+            in_gap = true;
+            consecutive = 0;
+            first_bonus = 0;
+        }
+        prev_class = class;
+    }
+    Some((score, positions))
+}
+
+/// Fuzzy-match commits on a field, best matches first.
+///
+/// Supported fields: "subject" (or "message"), "author", "committer",
+/// "sha", "source". `flags` selects case handling; `negate` and `syntax`
+/// do not apply, as non-matches have no score. Ties keep log order.
+/// Returns an error for unknown fields.
+pub fn fuzzy_filter_commits(
+    layout: &LayoutResult,
+    field: &str,
+    pattern: &str,
+    flags: &MatchFlags,
+) -> Result<Vec<FuzzyMatch>, ErrorInfo> {
+    let text: fn(&LayoutNode) -> &str = match field {
+        "subject" | "message" => |n| &n.subject,
+        "author" => |n| &n.author_name,
+        "committer" => |n| &n.committer_name,
+        "sha" | "hash" => |n| &n.sha,
+        "source" => |n| &n.source_ref,
+        _ => {
+            let message = format!("Unknown fuzzy filter field: {}", field);
+            return Err(ErrorInfo::with_code(ErrorCode::BadArgument, message));
+        }
+    };
+    let ignore_case = flags.ignores_case(pattern);
+
+    let mut matches: Vec<FuzzyMatch> = layout
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let text = text(node);
+            let (score, positions) = fuzzy_match(text, pattern, ignore_case)?;
+            Some(FuzzyMatch {
+                node: node.clone(),
+                score,
+                positions: utf16_offsets(text, &positions),
+            })
+        })
+        .collect();
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    Ok(matches)
+}
+
+/// Convert char indices into `text` to UTF-16 offsets.
+fn utf16_offsets(text: &str, indices: &[usize]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(indices.len());
+    let mut wanted = indices.iter().peekable();
+    let mut offset = 0;
+    for (i, c) in text.chars().enumerate() {
+        if wanted.next_if_eq(&&i).is_some() {
+            offsets.push(offset);
+        }
+        offset += c.len_utf16();
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::CaseMode;
+
+    fn make_test_layout() -> LayoutResult {
+        let subjects = ["Add blame heatmap", "Fix parser bug", "fix: bad heap"];
+        LayoutResult {
+            nodes: subjects
+                .iter()
+                .enumerate()
+                .map(|(i, subject)| LayoutNode {
+                    sha: format!("{:03}", i),
+                    subject: subject.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            edges: vec![],
+            total_count: subjects.len(),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match_positions_and_bonuses() {
+        let (score, positions) = fuzzy_match("Fix parser bug", "fpb", true).unwrap();
+        assert_eq!(positions, vec![0, 4, 11]);
+        // Word starts beat letters inside words
+        let (inner, _) = fuzzy_match("xfxpxb", "fpb", true).unwrap();
+        assert!(score > inner);
+
+        // The window shrinks to the shortest one ending at the first match
+        let (_, positions) = fuzzy_match("a_a_b", "ab", false).unwrap();
+        assert_eq!(positions, vec![2, 4]);
+
+        assert_eq!(fuzzy_match("abc", "acb", true), None);
+        assert_eq!(fuzzy_match("ABC", "abc", false), None);
+        assert_eq!(fuzzy_match("abc", "", false), Some((0, vec![])));
+    }
+
+    #[test]
+    fn test_fuzzy_filter_orders_by_score() {
+        let layout = make_test_layout();
+        let smart = MatchFlags {
+            case: CaseMode::Smart,
+            ..Default::default()
+        };
+        let matches = fuzzy_filter_commits(&layout, "subject", "heap", &smart).unwrap();
+        let subjects: Vec<&str> = matches.iter().map(|m| m.node.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["fix: bad heap", "Add blame heatmap"]);
+        assert_eq!(matches[0].positions, vec![9, 10, 11, 12]);
+
+        let matches = fuzzy_filter_commits(&layout, "subject", "Fix", &smart).unwrap();
+        assert_eq!(matches.len(), 1);
+
+        assert!(fuzzy_filter_commits(&layout, "body", "x", &smart).is_err());
+    }
+
+    #[test]
+    fn test_positions_are_utf16_offsets() {
+        assert_eq!(utf16_offsets("😀ab", &[1, 2]), vec![2, 3]);
+    }
+}
@@ -0,0 +1,113 @@
+use regex::Regex;
+
+use crate::error::{ErrorCode, ErrorInfo};
+
+/// Translate a glob into an unanchored regex.
+///
+/// `?` matches one character and `*` any run of them; `[...]` is a
+/// character class, negated by a leading `!` or `^`. With `paths`, `*` and
+/// `?` stop at `/` while `**` crosses directories (`**/` also matching no
+/// directory at all), as in `.gitignore`.
+pub fn glob_to_regex(glob: &str, paths: bool) -> String {
+    let (star, single) = if paths { ("[^/]*", "[^/]") } else { (".*", ".") };
+    let chars: Vec<char> = glob.chars().collect();
+    let mut out = String::with_capacity(glob.len() * 2);
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if paths && chars.get(i + 1) == Some(&'*') => {
+                if chars.get(i + 2) == Some(&'/') {
+                    out.push_str("(?:.*/)?");
+                    i += 3;
+                } else {
+                    out.push_str(".*");
+                    i += 2;
+                }
+                continue;
+            }
+            '*' => out.push_str(star),
+            '?' => out.push_str(single),
+            '[' => {
+                // A `]` first in the class is literal
+                let first = i + 1 + usize::from(chars.get(i + 1) == Some(&']'));
+                let len = chars.get(first..).and_then(|rest| rest.iter().position(|&c| c == ']'));
+                match len {
+                    Some(len) => {
+                        let close = first + len;
+                        let class: String = chars[i + 1..close].iter().collect();
+                        let class = match class.strip_prefix('!') {
+                            Some(rest) => format!("^{}", rest),
+                            None => class,
+                        };
+                        out.push('[');
+                        out.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                        out.push(']');
+                        i = close + 1;
+                        continue;
+                    }
+                    None => out.push_str("\\["),
+                }
+            }
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Compile a path pattern into a regex matching repository paths.
+///
+/// A pattern matches a path it names in full or any path below it, so
+/// `src/graph`, `src/graph/` and `src/*` all match `src/graph/layout.rs`.
+/// A leading `./` or `/` is ignored.
+pub fn compile_path_pattern(pattern: &str) -> Result<Regex, ErrorInfo> {
+    let pattern = pattern.trim();
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    if pattern.is_empty() {
+        return Err(ErrorInfo::with_code(ErrorCode::BadArgument, "Empty path pattern"));
+    }
+    Regex::new(&format!("^{}(?:/|$)", glob_to_regex(pattern, true))).map_err(|e| {
+        ErrorInfo::with_code(ErrorCode::BadRegex, format!("Invalid path pattern: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_matches(pattern: &str, path: &str) -> bool {
+        compile_path_pattern(pattern).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn test_path_patterns() {
+        assert!(path_matches("src/graph", "src/graph/layout.rs"));
+        assert!(path_matches("./src/graph/", "src/graph/layout.rs"));
+        assert!(path_matches("src/graph/layout.rs", "src/graph/layout.rs"));
+        assert!(!path_matches("src/gr", "src/graph/layout.rs"));
+
+        assert!(path_matches("src/*.rs", "src/lib.rs"));
+        assert!(!path_matches("*.rs", "src/lib.rs"));
+        assert!(path_matches("src/*", "src/graph/layout.rs"));
+        assert!(path_matches("**/*.rs", "src/graph/layout.rs"));
+        assert!(path_matches("**/*.rs", "lib.rs"));
+        assert!(path_matches("src/**/layout.rs", "src/graph/layout.rs"));
+        assert!(path_matches("src/lib.r?", "src/lib.rs"));
+        assert!(path_matches("src/[gb]*/mod.rs", "src/blame/mod.rs"));
+        assert!(!path_matches("src/[!gb]*/mod.rs", "src/blame/mod.rs"));
+        assert!(path_matches("a+b(1)", "a+b(1)/x"));
+
+        assert_eq!(compile_path_pattern("  / ").unwrap_err().code, ErrorCode::BadArgument);
+        assert_eq!(compile_path_pattern("[z-a]").unwrap_err().code, ErrorCode::BadRegex);
+    }
+
+    #[test]
+    fn test_text_globs() {
+        let re = Regex::new(&format!("^{}$", glob_to_regex("*fix*", false))).unwrap();
+        assert!(re.is_match("hotfix/a"));
+        assert!(!re.is_match("feature"));
+        assert_eq!(glob_to_regex("[]a]", false), "[]a]");
+        assert_eq!(glob_to_regex("a[", false), "a\\[");
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+use super::regex_filter::{compile_pattern, field_texts, MatchFlags, ANY_FIELDS};
+use crate::error::ErrorInfo;
+use crate::graph::types::LayoutResult;
+
+/// A commit matched by `highlight_commits`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightMatch {
+    pub sha: String,
+    pub row: i32,
+    /// The field that matched: for "any", the first of subject, author,
+    /// email and sha that did; otherwise the requested field.
+    pub field: String,
+    /// `[start, end)` UTF-16 offsets of the matches in `field`'s main
+    /// text (the subject for "message"); empty when only another text, such
+    /// as the body, matched, or when the flags negate the match.
+    pub ranges: Vec<[usize; 2]>,
+}
+
+/// Find the commits `filter_commits_by_field` would keep, without building
+/// a filtered layout, so the UI can dim the other rows in place.
+///
+/// Matches are in row order, each naming the field that matched so the
+/// row can highlight it.
+pub fn highlight_commits(
+    layout: &LayoutResult,
+    field: &str,
+    pattern: &str,
+    flags: &MatchFlags,
+) -> Result<Vec<HighlightMatch>, ErrorInfo> {
+    let re = compile_pattern(pattern, flags)?;
+    let candidates: &[&str] = if field == "any" { &ANY_FIELDS } else { &[field] };
+
+    let matches = layout
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let matched = candidates
+                .iter()
+                .map(|&f| (f, field_texts(node, f)))
+                .find(|(_, texts)| texts.iter().any(|t| re.is_match(t)));
+            let (field, ranges) = match (matched, flags.negate) {
+                (Some((field, texts)), false) => {
+                    let main = texts.first().copied().unwrap_or_default();
+                    let ranges = re
+                        .find_iter(main)
+                        .filter(|m| !m.is_empty())
+                        .map(|m| [utf16_len(&main[..m.start()]), utf16_len(&main[..m.end()])])
+                        .collect();
+                    (field, ranges)
+                }
+                (None, true) => (field, Vec::new()),
+                _ => return None,
+            };
+            Some(HighlightMatch {
+                sha: node.sha.clone(),
+                row: node.row,
+                field: field.to_string(),
+                ranges,
+            })
+        })
+        .collect();
+    Ok(matches)
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::LayoutNode;
+
+    fn node(sha: &str, row: i32, subject: &str, body: &str) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            row,
+            subject: subject.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_highlight_ranges() {
+        let layout = LayoutResult {
+            nodes: vec![
+                node("a", 0, "fix: Fix the fix", ""),
+                node("b", 1, "Refactor", ""),
+                node("c", 2, "Übersicht fix", "fix in body"),
+                node("d", 3, "Docs", "a fix"),
+            ],
+            edges: vec![],
+            total_count: 4,
+        };
+        let flags = MatchFlags {
+            case: crate::filter::CaseMode::Smart,
+            ..Default::default()
+        };
+        let matches = highlight_commits(&layout, "message", "fix", &flags).unwrap();
+
+        let summary: Vec<(&str, i32, &[[usize; 2]])> =
+            matches.iter().map(|m| (m.sha.as_str(), m.row, m.ranges.as_slice())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a", 0, &[[0, 3], [5, 8], [13, 16]][..]),
+                ("c", 2, &[[10, 13]][..]),
+                ("d", 3, &[][..]),
+            ]
+        );
+        assert!(highlight_commits(&layout, "message", "(", &flags).is_err());
+        assert!(highlight_commits(&layout, "nope", "fix", &flags).unwrap().is_empty());
+
+        let negated = MatchFlags {
+            negate: true,
+            ..flags
+        };
+        let matches = highlight_commits(&layout, "message", "fix", &negated).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].sha.as_str(), matches[0].ranges.len()), ("b", 0));
+        assert_eq!(matches[0].field, "message");
+    }
+
+    #[test]
+    fn test_highlight_any_field() {
+        let mut by_alice = node("c0ffee", 0, "Refactor", "");
+        by_alice.author_name = "Alice".to_string();
+        let mut mailed = node("beef", 1, "Docs", "");
+        mailed.author_email = "alice@example.com".to_string();
+        let layout = LayoutResult {
+            nodes: vec![
+                node("a11ce", 2, "Thank alice", ""),
+                by_alice,
+                mailed,
+                node("f00d", 3, "Other", ""),
+            ],
+            edges: vec![],
+            total_count: 4,
+        };
+        let flags = MatchFlags {
+            case: crate::filter::CaseMode::Insensitive,
+            ..Default::default()
+        };
+
+        let matches = highlight_commits(&layout, "any", "alice", &flags).unwrap();
+        let summary: Vec<(&str, &str, &[[usize; 2]])> = matches
+            .iter()
+            .map(|m| (m.sha.as_str(), m.field.as_str(), m.ranges.as_slice()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a11ce", "subject", &[[6, 11]][..]),
+                ("c0ffee", "author", &[[0, 5]][..]),
+                ("beef", "email", &[[0, 5]][..]),
+            ]
+        );
+
+        let matches = highlight_commits(&layout, "any", "^a11", &flags).unwrap();
+        assert_eq!((matches[0].field.as_str(), matches[0].ranges.as_slice()), ("sha", &[[0, 3]][..]));
+    }
+}
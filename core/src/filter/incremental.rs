@@ -0,0 +1,93 @@
+use std::collections::{HashMap, HashSet};
+
+use super::query::Query;
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+/// The matches of `query` among the commits of `layout` named in `new_shas`,
+/// typically the page just appended, so an active filter can be extended
+/// without re-running it over the whole layout.
+///
+/// Returns the new matching nodes, plus the edges between two matching
+/// commits that involve at least one of them, including edges reaching
+/// back to matches on earlier pages.
+pub fn filter_new_commits(
+    layout: &LayoutResult,
+    query: &Query,
+    new_shas: &HashSet<&str>,
+) -> LayoutResult {
+    let by_sha: HashMap<&str, &LayoutNode> =
+        layout.nodes.iter().map(|n| (n.sha.as_str(), n)).collect();
+    let is_new = |sha: &str| new_shas.contains(sha);
+    let matches = |sha: &str| by_sha.get(sha).is_some_and(|n| query.matches(n));
+
+    let nodes: Vec<LayoutNode> = layout
+        .nodes
+        .iter()
+        .filter(|n| is_new(&n.sha) && query.matches(n))
+        .cloned()
+        .collect();
+    let matched: HashSet<&str> = nodes.iter().map(|n| n.sha.as_str()).collect();
+
+    let edges = layout
+        .edges
+        .iter()
+        .filter(|e| {
+            let (from, to) = (e.from_sha.as_str(), e.to_sha.as_str());
+            let from_matches = matched.contains(from) || (!is_new(from) && matches(from));
+            let to_matches = matched.contains(to) || (!is_new(to) && matches(to));
+            (matched.contains(from) || matched.contains(to)) && from_matches && to_matches
+        })
+        .cloned()
+        .collect();
+
+    LayoutResult {
+        total_count: nodes.len(),
+        nodes,
+        edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_query;
+    use crate::graph::compute_layout;
+    use crate::graph::types::CommitNode;
+
+    fn commit(sha: &str, subject: &str, parents: &[&str]) -> CommitNode {
+        CommitNode {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_only_new_matches_and_their_edges() {
+        let layout = compute_layout(&[
+            commit("d", "fix d", &["c"]),
+            commit("c", "fix c", &["b"]),
+            // Appended page
+            commit("b", "fix b", &["a"]),
+            commit("a", "docs", &["z"]),
+            commit("z", "fix z", &[]),
+        ]);
+        let query = parse_query("fix").unwrap();
+        let new_shas: HashSet<&str> = ["b", "a", "z"].into_iter().collect();
+
+        let result = filter_new_commits(&layout, &query, &new_shas);
+        let shas: Vec<&str> = result.nodes.iter().map(|n| n.sha.as_str()).collect();
+        assert_eq!(shas, vec!["b", "z"]);
+        assert_eq!(result.total_count, 2);
+
+        // c -> b reaches back to the earlier page; b -> a and a -> z do not
+        // connect two matches
+        let edges: Vec<(&str, &str)> = result
+            .edges
+            .iter()
+            .map(|e| (e.from_sha.as_str(), e.to_sha.as_str()))
+            .collect();
+        assert_eq!(edges, vec![("c", "b")]);
+    }
+}
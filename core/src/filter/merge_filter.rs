@@ -0,0 +1,80 @@
+use crate::graph::types::LayoutResult;
+
+/// Filter commits in a LayoutResult by whether they are merges, like git's
+/// `--merges` (`merges_only = true`) and `--no-merges` (`false`).
+///
+/// Uses the precomputed `is_merge` flag of each LayoutNode.
+pub fn filter_commits_by_merge(layout: &LayoutResult, merges_only: bool) -> LayoutResult {
+    let matching_shas: std::collections::HashSet<&str> = layout
+        .nodes
+        .iter()
+        .filter(|node| node.is_merge == merges_only)
+        .map(|node| node.sha.as_str())
+        .collect();
+
+    let filtered_nodes: Vec<_> = layout
+        .nodes
+        .iter()
+        .filter(|n| matching_shas.contains(n.sha.as_str()))
+        .cloned()
+        .collect();
+
+    let filtered_edges: Vec<_> = layout
+        .edges
+        .iter()
+        .filter(|e| {
+            matching_shas.contains(e.from_sha.as_str()) && matching_shas.contains(e.to_sha.as_str())
+        })
+        .cloned()
+        .collect();
+
+    let total_count = filtered_nodes.len();
+
+    LayoutResult {
+        nodes: filtered_nodes,
+        edges: filtered_edges,
+        total_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::test_support::commit;
+
+    fn make_test_layout() -> LayoutResult {
+        compute_layout(&[
+            commit("merge", &["main", "topic"]),
+            commit("topic", &["main"]),
+            commit("main", &["root"]),
+            commit("root", &[]),
+        ])
+    }
+
+    #[test]
+    fn test_layout_flags() {
+        let layout = make_test_layout();
+        assert!(layout.nodes[0].is_merge);
+        assert_eq!(layout.nodes[0].parent_count, 2);
+        assert!(!layout.nodes[1].is_merge);
+        assert_eq!(layout.nodes[3].parent_count, 0);
+    }
+
+    #[test]
+    fn test_no_merges() {
+        let result = filter_commits_by_merge(&make_test_layout(), false);
+        assert_eq!(result.total_count, 3);
+        assert!(result.nodes.iter().all(|n| !n.is_merge));
+        // Edges between the remaining commits are kept
+        assert!(result.edges.iter().any(|e| e.from_sha == "topic" && e.to_sha == "main"));
+    }
+
+    #[test]
+    fn test_merges_only() {
+        let result = filter_commits_by_merge(&make_test_layout(), true);
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.nodes[0].sha, "merge");
+        assert!(result.edges.is_empty());
+    }
+}
@@ -1,5 +1,75 @@
 pub mod regex_filter;
 pub mod date_filter;
+pub mod merge_filter;
+pub mod query;
+pub mod fuzzy;
+pub mod elide;
+pub mod context;
+pub mod options;
+pub mod highlight;
+pub mod glob;
+pub mod path_filter;
+pub mod sha_filter;
+pub mod size_filter;
+pub mod saved;
+pub mod incremental;
+pub mod regex_cache;
+pub mod release;
+pub mod domain_filter;
+pub mod sort;
+pub mod facets;
+pub mod trailer_filter;
+pub mod signature_filter;
+pub mod compact;
 
-pub use regex_filter::filter_commits_by_field;
-pub use date_filter::filter_commits_by_date;
+pub use regex_filter::{
+    filter_commits_by_field, filter_commits_by_field_in_range, CaseMode, MatchFlags, PatternSyntax,
+    FILTER_FIELDS,
+};
+pub use date_filter::{filter_commits_by_date, parse_date_expr};
+pub use merge_filter::filter_commits_by_merge;
+pub use path_filter::filter_commits_by_path;
+pub use sha_filter::filter_commits_by_shas;
+pub use size_filter::{filter_commits_by_size, SizeFilter, SizeMetric};
+pub use highlight::{highlight_commits, HighlightMatch};
+pub use elide::elide_hidden_commits;
+pub use context::add_context;
+pub use options::FilterOptions;
+pub use fuzzy::{fuzzy_filter_commits, fuzzy_match, FuzzyMatch};
+pub use query::{filter_commits_by_query, parse_query, Query, QUERY_FIELDS};
+pub use saved::{SavedFilter, SavedFilters};
+pub use incremental::filter_new_commits;
+pub use regex_cache::RegexCache;
+pub use domain_filter::{email_domain, filter_commits_by_domain, in_domain};
+pub use sort::{sort_fuzzy_matches, sort_nodes, SortOrder};
+pub use release::{filter_releases, Release, ReleaseView, DEFAULT_RELEASE_PATTERN};
+pub use facets::{compute_facets, FacetCount, FacetView, Facets};
+pub use trailer_filter::filter_commits_by_trailer;
+pub use signature_filter::{filter_commits_by_signature, SignatureFilter};
+pub use compact::compact_layout;
+
+use std::collections::HashSet;
+
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+/// Keep the nodes of `layout` that satisfy `keep`, and the edges between
+/// them.
+pub(crate) fn select_nodes(
+    layout: &LayoutResult,
+    keep: impl Fn(&LayoutNode) -> bool,
+) -> LayoutResult {
+    let nodes: Vec<LayoutNode> = layout.nodes.iter().filter(|n| keep(n)).cloned().collect();
+    let shas: HashSet<&str> = nodes.iter().map(|n| n.sha.as_str()).collect();
+    let edges = layout
+        .edges
+        .iter()
+        .filter(|e| shas.contains(e.from_sha.as_str()) && shas.contains(e.to_sha.as_str()))
+        .cloned()
+        .collect();
+
+    LayoutResult {
+        total_count: nodes.len(),
+        nodes,
+        edges,
+    }
+}
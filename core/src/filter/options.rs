@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use super::compact::compact_layout;
+use super::context::add_context;
+use super::elide::elide_hidden_commits;
+use super::sort::{sort_nodes, SortOrder};
+use crate::graph::types::LayoutResult;
+
+/// How a filtered view is built from the commits a filter matched.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FilterOptions {
+    /// Connect matches across filtered-out commits with `EdgeType::Elided`
+    /// edges, so the view stays drawable.
+    pub elide_hidden: bool,
+    /// Also show commits up to this many parent or child links from each
+    /// match, like `grep -C`.
+    pub context: u32,
+    /// Re-run row and lane assignment over the filtered commits, closing
+    /// the gaps left by hidden ones, instead of keeping their coordinates
+    /// in the full graph so the view lines up with it.
+    pub compact: bool,
+    /// Order of the returned nodes, for a flat results list; graph row
+    /// order by default.
+    pub sort: SortOrder,
+}
+
+impl FilterOptions {
+    /// Post-process `filtered`, the matches of a filter over `layout`.
+    pub fn apply(&self, layout: &LayoutResult, filtered: &mut LayoutResult) {
+        add_context(layout, filtered, self.context);
+        if self.elide_hidden {
+            elide_hidden_commits(layout, filtered);
+        }
+        if self.compact {
+            compact_layout(filtered);
+        }
+        if self.sort != SortOrder::Row {
+            sort_nodes(&mut filtered.nodes, self.sort);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use regex::Regex;
+
+use super::glob::compile_path_pattern;
+use super::select_nodes;
+use crate::error::ErrorInfo;
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+/// Filter commits in a LayoutResult to those touching a path, like
+/// `git log -- <path>` within the loaded history.
+///
+/// `pattern` is a path or glob, matching the files it names and everything
+/// below them (see `compile_path_pattern`). Only commits parsed with their
+/// changed files (`--name-status` or `--numstat`) can match; renames and
+/// copies listed by `--name-status` match on either path. Returns a new
+/// LayoutResult containing only matching nodes and their edges.
+pub fn filter_commits_by_path(
+    layout: &LayoutResult,
+    pattern: &str,
+) -> Result<LayoutResult, ErrorInfo> {
+    let re = compile_path_pattern(pattern)?;
+    Ok(select_nodes(layout, |node| touches_path(node, &re)))
+}
+
+/// Whether any file `node` changed matches `re`.
+pub(crate) fn touches_path(node: &LayoutNode, re: &Regex) -> bool {
+    node.changes
+        .iter()
+        .any(|c| re.is_match(&c.path) || c.old_path.as_deref().is_some_and(|p| re.is_match(p)))
+        || node.files.iter().any(|f| re.is_match(&f.path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::{ChangeType, FileChange, FileStat};
+
+    fn node(sha: &str, changes: &[(&str, Option<&str>)]) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            changes: changes
+                .iter()
+                .map(|&(path, old_path)| FileChange {
+                    change_type: ChangeType::Modified,
+                    path: path.to_string(),
+                    old_path: old_path.map(str::to_string),
+                    similarity: None,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_by_path() {
+        let layout = LayoutResult {
+            nodes: vec![
+                node("a", &[("src/graph/layout.rs", None), ("README.md", None)]),
+                node("b", &[("docs/graph.md", Some("src/graph/notes.md"))]),
+                node("c", &[("src/blame/mod.rs", None)]),
+                node("d", &[]),
+            ],
+            edges: vec![],
+            total_count: 4,
+        };
+        let shas = |pattern: &str| -> Vec<String> {
+            let result = filter_commits_by_path(&layout, pattern).unwrap();
+            result.nodes.into_iter().map(|n| n.sha).collect()
+        };
+        assert_eq!(shas("src/graph/"), vec!["a", "b"]);
+        assert_eq!(shas("src/*/mod.rs"), vec!["c"]);
+        assert_eq!(shas("**/*.md"), vec!["a", "b"]);
+        assert_eq!(shas("src"), vec!["a", "b", "c"]);
+        assert!(filter_commits_by_path(&layout, "").is_err());
+    }
+
+    #[test]
+    fn test_filter_by_path_with_numstat_only() {
+        let stat = |path: &str| FileStat {
+            path: path.to_string(),
+            insertions: 1,
+            ..Default::default()
+        };
+        let layout = LayoutResult {
+            nodes: vec![
+                LayoutNode {
+                    sha: "a".to_string(),
+                    files: vec![stat("src/graph/layout.rs")],
+                    ..Default::default()
+                },
+                LayoutNode {
+                    sha: "b".to_string(),
+                    files: vec![stat("README.md")],
+                    ..Default::default()
+                },
+            ],
+            edges: vec![],
+            total_count: 2,
+        };
+        let result = filter_commits_by_path(&layout, "src/graph/").unwrap();
+        let shas: Vec<&str> = result.nodes.iter().map(|n| n.sha.as_str()).collect();
+        assert_eq!(shas, vec!["a"]);
+    }
+}
@@ -0,0 +1,692 @@
+use regex::Regex;
+
+use super::date_filter::parse_absolute_date;
+use super::domain_filter::{email_domain, in_domain};
+use super::glob::compile_path_pattern;
+use super::path_filter::touches_path;
+use super::regex_cache::cached_regex;
+use super::select_nodes;
+use super::signature_filter::SignatureFilter;
+use super::size_filter::{commit_size, parse_size_range, SizeMetric};
+use crate::error::{ErrorCode, ErrorInfo};
+use crate::graph::types::{LayoutNode, LayoutResult};
+use crate::trailers::parse_trailers;
+
+/// A parsed filter query, e.g.
+/// `author:alice AND (message:/panic/i OR sha:abc123) AND after:2024-01-01`.
+#[derive(Debug, Clone)]
+pub enum Query {
+    /// Matches when every subquery does; an empty list matches everything.
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+/// A single `field:value` condition.
+#[derive(Debug, Clone)]
+pub enum Term {
+    Text { field: TextField, pattern: Pattern },
+    /// Author date at or after this epoch time.
+    After(u64),
+    /// Author date at or before this epoch time.
+    Before(u64),
+    /// Whether the commit is a merge, from `is:merge` or `is:nonmerge`.
+    Merge(bool),
+    /// Changes a file whose path matches.
+    Path(Regex),
+    /// Size of the commit within an inclusive range.
+    Size { metric: SizeMetric, min: u64, max: u64 },
+    /// Author or committer email in this domain or a subdomain.
+    Domain(String),
+    /// Has a message trailer with this key, optionally with a value the
+    /// pattern matches.
+    Trailer { key: String, value: Option<Pattern> },
+    /// A message trailer, as a `Key: value` line, matches the regex.
+    TrailerLine(Regex),
+    /// Signature verification status.
+    Signature(SignatureFilter),
+}
+
+/// The node text a `Term::Text` looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    /// Subject and body.
+    Message,
+    Subject,
+    Body,
+    Author,
+    Committer,
+    /// Author or committer email.
+    Email,
+    /// Domain of the author or committer email.
+    Domain,
+    Sha,
+    /// The `%S` ref the commit was reached from.
+    Source,
+    /// Any ref decorating the commit.
+    Ref,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Case-insensitive substring, or prefix for SHAs; stored lowercased.
+    Text(String),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    /// Pattern and flags of a `/pattern/flags` literal.
+    Regex(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Option<String>, Value),
+}
+
+/// Parse a filter query.
+///
+/// Terms are `field:value`, where the value is a word, a `"quoted string"`
+/// or a `/regex/` with optional `i`, `m`, `s` and `x` flags. Fields are
+/// `message` (the default for bare values), `subject`, `body`, `author`,
+/// `committer`, `email` (author or committer), `domain` (of either email,
+/// covering subdomains), `sha` (or `hash`), `source`, `ref`, `trailer`
+/// (`trailer:Signed-off-by` for commits with that trailer,
+/// `trailer:co-authored-by=alice` for one whose value contains a word, or
+/// a regex matched against `Key: value` lines), `is` (`is:merge` or
+/// `is:nonmerge`, like `--merges` and `--no-merges`), `signature`
+/// (`verified`, `unverified`, `bad`, `signed` or `unsigned`), `path` (a
+/// path or glob as for `filter_commits_by_path`, or a regex),
+/// `insertions`, `deletions`, `lines` and `files`, which take a count `N`,
+/// `>N`, `>=N`, `<N`, `<=N` or `N..M` (e.g. `lines:>500`), and
+/// `after`/`before`, which take a `YYYY-MM-DD` date (UTC), an ISO-8601
+/// timestamp or epoch seconds. Plain values match case-insensitively as
+/// substrings, or as prefixes of SHAs.
+///
+/// Terms combine with `AND`, `OR`, `NOT` (or a `!` prefix) and
+/// parentheses; `NOT` binds tightest and `OR` loosest, and adjacent terms
+/// are ANDed. An empty query matches every commit.
+pub fn parse_query(query: &str) -> Result<Query, ErrorInfo> {
+    let tokens = tokenize(query)?;
+    if tokens.is_empty() {
+        return Ok(Query::And(Vec::new()));
+    }
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        depth: 0,
+    };
+    let parsed = parser.parse_or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(parsed),
+        Some(Token::Close) => Err(invalid_query("unmatched ')'")),
+        Some(_) => Err(invalid_query("unexpected token")),
+    }
+}
+
+/// Filter commits in a LayoutResult by a query (see `parse_query`).
+/// Returns a new LayoutResult containing only matching nodes and their edges.
+pub fn filter_commits_by_query(
+    layout: &LayoutResult,
+    query: &str,
+) -> Result<LayoutResult, ErrorInfo> {
+    let query = parse_query(query)?;
+    Ok(select_nodes(layout, |node| query.matches(node)))
+}
+
+impl Query {
+    /// Whether `node` satisfies the query.
+    pub fn matches(&self, node: &LayoutNode) -> bool {
+        match self {
+            Query::And(queries) => queries.iter().all(|q| q.matches(node)),
+            Query::Or(queries) => queries.iter().any(|q| q.matches(node)),
+            Query::Not(query) => !query.matches(node),
+            Query::Term(term) => term.matches(node),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, node: &LayoutNode) -> bool {
+        match self {
+            Term::After(time) => node.author_date >= *time,
+            Term::Before(time) => node.author_date <= *time,
+            Term::Merge(merge) => node.is_merge == *merge,
+            Term::Path(re) => touches_path(node, re),
+            Term::Size { metric, min, max } => (*min..=*max).contains(&commit_size(node, *metric)),
+            Term::Domain(domain) => {
+                in_domain(&node.author_email, domain) || in_domain(&node.committer_email, domain)
+            }
+            Term::Trailer { key, value } => parse_trailers(&node.body).iter().any(|t| {
+                t.has_key(key) && value.as_ref().is_none_or(|v| v.matches(&t.value, false))
+            }),
+            Term::Signature(filter) => filter.matches(node.signature_status),
+            Term::TrailerLine(re) => parse_trailers(&node.body)
+                .iter()
+                .any(|t| re.is_match(&format!("{}: {}", t.key, t.value))),
+            Term::Text { field, pattern } => {
+                let hit = |text: &str| pattern.matches(text, *field == TextField::Sha);
+                match field {
+                    TextField::Message => hit(&node.subject) || hit(&node.body),
+                    TextField::Subject => hit(&node.subject),
+                    TextField::Body => hit(&node.body),
+                    TextField::Author => hit(&node.author_name),
+                    TextField::Committer => hit(&node.committer_name),
+                    TextField::Email => hit(&node.author_email) || hit(&node.committer_email),
+                    TextField::Domain => [&node.author_email, &node.committer_email]
+                        .iter()
+                        .filter_map(|email| email_domain(email))
+                        .any(hit),
+                    TextField::Sha => hit(&node.sha),
+                    TextField::Source => hit(&node.source_ref),
+                    TextField::Ref => node.refs.iter().any(|r| hit(&r.name)),
+                }
+            }
+        }
+    }
+}
+
+impl Pattern {
+    fn matches(&self, text: &str, prefix: bool) -> bool {
+        match self {
+            Pattern::Text(needle) if prefix => text.to_lowercase().starts_with(needle.as_str()),
+            Pattern::Text(needle) => text.to_lowercase().contains(needle.as_str()),
+            Pattern::Regex(re) => re.is_match(text),
+        }
+    }
+}
+
+/// Deepest nesting of parentheses and `NOT`s accepted. The parser recurses
+/// once per level, and the WASM stack is small enough that a few thousand
+/// levels would overflow it and trap the module.
+const MAX_DEPTH: usize = 256;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Parentheses and `NOT`s open at the current position.
+    depth: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.pos) == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ErrorInfo> {
+        let mut queries = vec![self.parse_and()?];
+        while self.eat(&Token::Or) {
+            queries.push(self.parse_and()?);
+        }
+        Ok(flatten(queries, Query::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ErrorInfo> {
+        let mut queries = vec![self.parse_not()?];
+        loop {
+            let implicit = matches!(
+                self.tokens.get(self.pos),
+                Some(Token::Open | Token::Not | Token::Term(..))
+            );
+            if self.eat(&Token::And) || implicit {
+                queries.push(self.parse_not()?);
+            } else {
+                break;
+            }
+        }
+        Ok(flatten(queries, Query::And))
+    }
+
+    /// Run `parse` one nesting level deeper.
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Query, ErrorInfo>,
+    ) -> Result<Query, ErrorInfo> {
+        if self.depth == MAX_DEPTH {
+            return Err(invalid_query("query nested too deeply"));
+        }
+        self.depth += 1;
+        let query = parse(self);
+        self.depth -= 1;
+        query
+    }
+
+    fn parse_not(&mut self) -> Result<Query, ErrorInfo> {
+        if self.eat(&Token::Not) {
+            return Ok(Query::Not(Box::new(self.nested(Self::parse_not)?)));
+        }
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Open) => {
+                let query = self.nested(Self::parse_or)?;
+                if !self.eat(&Token::Close) {
+                    return Err(invalid_query("missing ')'"));
+                }
+                Ok(query)
+            }
+            Some(Token::Term(field, value)) => build_term(field.as_deref(), value),
+            Some(Token::Close) => Err(invalid_query("unexpected ')'")),
+            Some(_) => Err(invalid_query("AND/OR needs a term on both sides")),
+            None => Err(invalid_query("unexpected end of query")),
+        }
+    }
+}
+
+/// A single query as is, several combined with `combine`.
+fn flatten(mut queries: Vec<Query>, combine: fn(Vec<Query>) -> Query) -> Query {
+    if queries.len() == 1 {
+        queries.remove(0)
+    } else {
+        combine(queries)
+    }
+}
+
+/// The fields a query term can name, as in `field:value`.
+pub const QUERY_FIELDS: &[&str] = &[
+    "message",
+    "subject",
+    "body",
+    "author",
+    "committer",
+    "email",
+    "domain",
+    "sha",
+    "hash",
+    "source",
+    "ref",
+    "trailer",
+    "is",
+    "signature",
+    "path",
+    "insertions",
+    "deletions",
+    "lines",
+    "files",
+    "after",
+    "before",
+];
+
+fn build_term(field: Option<&str>, value: Value) -> Result<Query, ErrorInfo> {
+    let field = field.unwrap_or("message").to_ascii_lowercase();
+    let text_field = match field.as_str() {
+        "after" | "before" => {
+            let Value::Text(text) = &value else {
+                return Err(invalid_query(&format!("{} takes a date", field)));
+            };
+            let time = parse_absolute_date(text, 0)
+                .ok_or_else(|| {
+                    let message = format!("Invalid query: invalid date '{}'", text);
+                    ErrorInfo::with_code(ErrorCode::BadDate, message)
+                })?;
+            let term = if field == "after" { Term::After(time) } else { Term::Before(time) };
+            return Ok(Query::Term(term));
+        }
+        "is" => {
+            let merge = match &value {
+                Value::Text(text) if text.eq_ignore_ascii_case("merge") => true,
+                Value::Text(text) if text.eq_ignore_ascii_case("nonmerge") => false,
+                _ => return Err(invalid_query("is takes merge or nonmerge")),
+            };
+            return Ok(Query::Term(Term::Merge(merge)));
+        }
+        "path" => {
+            let re = match value {
+                Value::Text(text) => compile_path_pattern(&text)?,
+                Value::Regex(pattern, flags) => build_regex(&pattern, &flags)?,
+            };
+            return Ok(Query::Term(Term::Path(re)));
+        }
+        "signature" => {
+            let filter = match &value {
+                Value::Text(text) => SignatureFilter::from_name(text),
+                Value::Regex(..) => None,
+            };
+            let filter = filter.ok_or_else(|| {
+                invalid_query("signature takes verified, unverified, bad, signed or unsigned")
+            })?;
+            return Ok(Query::Term(Term::Signature(filter)));
+        }
+        "insertions" | "deletions" | "lines" | "files" => {
+            let metric = match field.as_str() {
+                "insertions" => SizeMetric::Insertions,
+                "deletions" => SizeMetric::Deletions,
+                "lines" => SizeMetric::Lines,
+                _ => SizeMetric::Files,
+            };
+            let range = match &value {
+                Value::Text(text) => parse_size_range(text),
+                Value::Regex(..) => None,
+            };
+            let (min, max) =
+                range.ok_or_else(|| invalid_query(&format!("{} takes a count", field)))?;
+            return Ok(Query::Term(Term::Size { metric, min, max }));
+        }
+        "message" => TextField::Message,
+        "subject" => TextField::Subject,
+        "body" => TextField::Body,
+        "author" => TextField::Author,
+        "committer" => TextField::Committer,
+        "email" => TextField::Email,
+        "domain" => match value {
+            Value::Text(domain) => return Ok(Query::Term(Term::Domain(domain))),
+            Value::Regex(..) => TextField::Domain,
+        },
+        "trailer" => {
+            let term = match value {
+                Value::Text(text) => match text.split_once('=') {
+                    Some((key, value)) => Term::Trailer {
+                        key: key.to_string(),
+                        value: Some(Pattern::Text(value.to_lowercase())),
+                    },
+                    None => Term::Trailer {
+                        key: text,
+                        value: None,
+                    },
+                },
+                Value::Regex(pattern, flags) => Term::TrailerLine(build_regex(&pattern, &flags)?),
+            };
+            return Ok(Query::Term(term));
+        }
+        "sha" | "hash" => TextField::Sha,
+        "source" => TextField::Source,
+        "ref" => TextField::Ref,
+        _ => return Err(invalid_query(&format!("unknown field '{}'", field))),
+    };
+
+    let pattern = match value {
+        Value::Text(text) => Pattern::Text(text.to_lowercase()),
+        Value::Regex(pattern, flags) => Pattern::Regex(build_regex(&pattern, &flags)?),
+    };
+    Ok(Query::Term(Term::Text {
+        field: text_field,
+        pattern,
+    }))
+}
+
+fn build_regex(pattern: &str, flags: &str) -> Result<Regex, ErrorInfo> {
+    if let Some(flag) = flags.chars().find(|c| !"imsx".contains(*c)) {
+        let message = format!("Invalid query: unknown regex flag '{}'", flag);
+        return Err(ErrorInfo::with_code(ErrorCode::BadRegex, message));
+    }
+    cached_regex(pattern, flags)
+}
+
+/// The error for a query that does not parse.
+fn invalid_query(reason: &str) -> ErrorInfo {
+    ErrorInfo::with_code(ErrorCode::BadQuery, format!("Invalid query: {}", reason))
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, ErrorInfo> {
+    let mut tokens = Vec::new();
+    let mut rest = query.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let (token, after) = match c {
+            '(' => (Token::Open, &rest[1..]),
+            '!' => (Token::Not, &rest[1..]),
+            ')' => (Token::Close, &rest[1..]),
+            _ => lex_term(rest)?,
+        };
+        tokens.push(token);
+        rest = after.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn lex_term(input: &str) -> Result<(Token, &str), ErrorInfo> {
+    let word_end = input
+        .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .unwrap_or(input.len());
+    let keyword = match &input[..word_end] {
+        "AND" => Some(Token::And),
+        "OR" => Some(Token::Or),
+        "NOT" => Some(Token::Not),
+        _ => None,
+    };
+    if let Some(keyword) = keyword {
+        return Ok((keyword, &input[word_end..]));
+    }
+
+    let name_len = input.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(input.len());
+    let (field, value) = if name_len > 0 && input[name_len..].starts_with(':') {
+        (Some(input[..name_len].to_string()), &input[name_len + 1..])
+    } else {
+        (None, input)
+    };
+    let (value, rest) = lex_value(value)?;
+    Ok((Token::Term(field, value), rest))
+}
+
+fn lex_value(input: &str) -> Result<(Value, &str), ErrorInfo> {
+    if let Some(body) = input.strip_prefix('/') {
+        let mut escaped = false;
+        for (i, c) in body.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '/' {
+                let after = &body[i + 1..];
+                let flags_len = after
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(after.len());
+                let pattern = body[..i].replace("\\/", "/");
+                return Ok((
+                    Value::Regex(pattern, after[..flags_len].to_string()),
+                    &after[flags_len..],
+                ));
+            }
+        }
+        return Err(invalid_query("unterminated regex"));
+    }
+
+    if let Some(body) = input.strip_prefix('"') {
+        let mut text = String::new();
+        let mut escaped = false;
+        for (i, c) in body.char_indices() {
+            if escaped {
+                text.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                return Ok((Value::Text(text), &body[i + 1..]));
+            } else {
+                text.push(c);
+            }
+        }
+        return Err(invalid_query("unterminated quote"));
+    }
+
+    let end = input
+        .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .unwrap_or(input.len());
+    if end == 0 {
+        return Err(invalid_query("missing value"));
+    }
+    Ok((Value::Text(input[..end].to_string()), &input[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::*;
+
+    fn node(sha: &str, subject: &str, author: &str, date: u64) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            short_sha: sha.chars().take(7).collect(),
+            subject: subject.to_string(),
+            author_name: author.to_string(),
+            author_date: date,
+            ..Default::default()
+        }
+    }
+
+    fn make_test_layout() -> LayoutResult {
+        let mut fix = node("aaa111", "Fix PANIC in parser", "Alice", 1_710_000_000);
+        fix.changes = vec![FileChange {
+            path: "src/parser.rs".to_string(),
+            ..Default::default()
+        }];
+        fix.files = vec![FileStat {
+            path: "src/parser.rs".to_string(),
+            insertions: 480,
+            deletions: 40,
+            binary: false,
+        }];
+        let mut bot = node("bbb222", "Handle panic", "Bob", 1_710_000_000);
+        bot.author_email = "bob[bot]@users.noreply.github.com".to_string();
+        bot.changes = vec![FileChange {
+            path: "README.md".to_string(),
+            ..Default::default()
+        }];
+        let mut tagged = node("ccc333", "Release", "Carol", 1_600_000_000);
+        tagged.is_merge = true;
+        tagged.signature_status = SignatureStatus::Good;
+        tagged.committer_name = "Carol".to_string();
+        tagged.body = "Signed-off-by: Carol <c@e.com>\nCo-authored-by: Dave <d@e.com>".to_string();
+        tagged.refs = vec![RefInfo {
+            name: "v1.0".to_string(),
+            ref_type: RefType::Tag,
+            is_head: false,
+        }];
+        LayoutResult {
+            nodes: vec![
+                fix,
+                node("abc123", "Refactor layout", "Alice", 1_700_000_000),
+                bot,
+                tagged,
+            ],
+            edges: vec![Edge {
+                from_sha: "aaa111".to_string(),
+                to_sha: "abc123".to_string(),
+                from_lane: 0,
+                to_lane: 0,
+                from_row: 0,
+                to_row: 1,
+                edge_type: EdgeType::Normal,
+                color_index: 0,
+                truncated_direction: None,
+            }],
+            total_count: 4,
+        }
+    }
+
+    fn shas(query: &str) -> Vec<String> {
+        let result = filter_commits_by_query(&make_test_layout(), query).unwrap();
+        result.nodes.into_iter().map(|n| n.sha).collect()
+    }
+
+    #[test]
+    fn test_compound_query() {
+        let query = "author:alice AND (message:/panic/i OR sha:abc123) AND after:2023-01-01";
+        assert_eq!(shas(query), vec!["aaa111", "abc123"]);
+
+        let result = filter_commits_by_query(&make_test_layout(), "author:alice").unwrap();
+        assert_eq!(result.total_count, 2);
+        assert_eq!(result.edges.len(), 1);
+    }
+
+    #[test]
+    fn test_precedence_and_implicit_and() {
+        // NOT binds tighter than AND, which binds tighter than OR
+        assert_eq!(shas("author:bob OR author:alice NOT layout"), vec!["aaa111", "bbb222"]);
+        assert_eq!(shas("panic author:bob"), vec!["bbb222"]);
+        assert_eq!(shas("NOT (panic OR ref:v1)"), vec!["abc123"]);
+        assert_eq!(shas("!author:alice !ref:v1"), vec!["bbb222"]);
+        assert_eq!(shas("!!sha:abc"), vec!["abc123"]);
+    }
+
+    #[test]
+    fn test_value_forms() {
+        assert_eq!(shas("message:/^Fix/"), vec!["aaa111"]);
+        assert_eq!(shas("message:/^fix/"), Vec::<String>::new());
+        assert_eq!(shas("subject:\"handle panic\""), vec!["bbb222"]);
+        assert_eq!(shas("sha:AB"), vec!["abc123"]);
+        assert_eq!(shas("before:1650000000"), vec!["ccc333"]);
+        assert_eq!(shas("ref:V1"), vec!["ccc333"]);
+        assert_eq!(shas("is:merge"), vec!["ccc333"]);
+        assert_eq!(shas("path:src/ OR path:/\\.md$/"), vec!["aaa111", "bbb222"]);
+        assert_eq!(shas("is:nonmerge author:bob"), vec!["bbb222"]);
+        assert_eq!(shas("email:/\\[bot\\]/ OR committer:carol"), vec!["bbb222", "ccc333"]);
+        assert_eq!(shas("domain:users.noreply.github.com"), vec!["bbb222"]);
+        assert_eq!(shas("domain:github.com"), vec!["bbb222"]);
+        assert_eq!(shas("domain:hub.com"), Vec::<String>::new());
+        assert_eq!(shas("domain:/^users\\./"), vec!["bbb222"]);
+        assert_eq!(shas("trailer:signed-off-by"), vec!["ccc333"]);
+        assert_eq!(shas("!trailer:Signed-off-by"), vec!["aaa111", "abc123", "bbb222"]);
+        assert_eq!(shas("trailer:Co-authored-by=dave"), vec!["ccc333"]);
+        assert_eq!(shas("trailer:\"Co-authored-by=Dave <d@\""), vec!["ccc333"]);
+        assert_eq!(shas("trailer:Co-authored-by=carol"), Vec::<String>::new());
+        assert_eq!(shas("trailer:/^co-authored-by: .*@e\\.com/i"), vec!["ccc333"]);
+        assert_eq!(shas("signature:verified"), vec!["ccc333"]);
+        assert_eq!(shas("signature:Unsigned author:alice"), vec!["aaa111", "abc123"]);
+        assert_eq!(shas("lines:>500"), vec!["aaa111"]);
+        assert_eq!(shas("insertions:<=480 files:1"), vec!["aaa111", "bbb222"]);
+        assert_eq!(shas("files:0 deletions:0..10"), vec!["abc123", "ccc333"]);
+        // Quoted keywords are plain values
+        assert_eq!(shas("\"NOT\""), Vec::<String>::new());
+        assert_eq!(shas("  "), vec!["aaa111", "abc123", "bbb222", "ccc333"]);
+    }
+
+    #[test]
+    fn test_query_errors() {
+        for query in [
+            "author:",
+            "(panic",
+            "panic)",
+            "AND panic",
+            "panic OR",
+            "foo:bar",
+            "after:yesterday",
+            "is:tag",
+            "signature:trusted",
+            "lines:many",
+            "files:/1/",
+            "message:/x",
+            "message:/x/q",
+            "message:/(/",
+            "subject:\"open",
+        ] {
+            assert!(parse_query(query).is_err(), "{}", query);
+        }
+
+        let code = |query: &str| parse_query(query).unwrap_err().code;
+        assert_eq!(code("(panic"), ErrorCode::BadQuery);
+        assert_eq!(code("after:yesterday"), ErrorCode::BadDate);
+        assert_eq!(code("message:/x/q"), ErrorCode::BadRegex);
+        assert_eq!(code("message:/(/"), ErrorCode::BadRegex);
+
+        let deep = format!("{}panic{}", "(".repeat(200_000), ")".repeat(200_000));
+        let error = parse_query(&deep).unwrap_err();
+        assert_eq!(error.code, ErrorCode::BadQuery);
+        assert!(error.message.contains("nested too deeply"));
+        assert!(parse_query(&"!".repeat(200_000)).is_err());
+        let nested = format!("{}panic{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(parse_query(&nested).is_ok());
+        assert_eq!(code("path:src/[z-a]"), ErrorCode::BadRegex);
+    }
+
+    #[test]
+    fn test_query_fields_are_known() {
+        for field in QUERY_FIELDS {
+            // Some fields reject the value, but none is unknown
+            if let Err(e) = parse_query(&format!("{}:1", field)) {
+                assert!(!e.message.contains("unknown field"), "{}", e);
+            }
+        }
+    }
+}
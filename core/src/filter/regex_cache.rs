@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::error::{ErrorCode, ErrorInfo};
+
+/// Default number of compiled patterns kept.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+struct CachedRegex {
+    regex: Regex,
+    last_used: u64,
+}
+
+/// Compiled regexes keyed by (pattern, flags), evicting the least recently
+/// used beyond the capacity. Filters run on every keystroke with the same
+/// few patterns, so this saves recompiling them on each call.
+pub struct RegexCache {
+    regexes: HashMap<(String, String), CachedRegex>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl Default for RegexCache {
+    fn default() -> Self {
+        RegexCache::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl RegexCache {
+    pub fn new(capacity: usize) -> Self {
+        RegexCache {
+            regexes: HashMap::new(),
+            capacity,
+            clock: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.regexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regexes.is_empty()
+    }
+
+    /// The regex for `pattern` compiled with `flags` (any of `i`, `m`, `s`
+    /// and `x`), compiling and caching it if needed. Invalid patterns are
+    /// not cached.
+    pub fn get_or_compile(&mut self, pattern: &str, flags: &str) -> Result<Regex, ErrorInfo> {
+        self.clock += 1;
+        let key = (pattern.to_string(), flags.to_string());
+        if let Some(cached) = self.regexes.get_mut(&key) {
+            cached.last_used = self.clock;
+            return Ok(cached.regex.clone());
+        }
+
+        let regex = compile(pattern, flags)?;
+        self.regexes.insert(
+            key,
+            CachedRegex {
+                regex: regex.clone(),
+                last_used: self.clock,
+            },
+        );
+        self.evict();
+        Ok(regex)
+    }
+
+    /// Evict least recently used regexes until within capacity.
+    fn evict(&mut self) {
+        while self.regexes.len() > self.capacity {
+            let oldest = self
+                .regexes
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => {
+                    self.regexes.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+/// `RegexCache::get_or_compile` on the cache shared by all filters.
+pub(crate) fn cached_regex(pattern: &str, flags: &str) -> Result<Regex, ErrorInfo> {
+    static CACHE: OnceLock<Mutex<RegexCache>> = OnceLock::new();
+    match CACHE.get_or_init(|| Mutex::new(RegexCache::default())).lock() {
+        Ok(mut cache) => cache.get_or_compile(pattern, flags),
+        Err(_) => compile(pattern, flags),
+    }
+}
+
+fn compile(pattern: &str, flags: &str) -> Result<Regex, ErrorInfo> {
+    let mut builder = RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            'm' => builder.multi_line(true),
+            's' => builder.dot_matches_new_line(true),
+            'x' => builder.ignore_whitespace(true),
+            _ => {
+                let message = format!("Unknown regex flag '{}'", flag);
+                return Err(ErrorInfo::with_code(ErrorCode::BadRegex, message));
+            }
+        };
+    }
+    builder.build().map_err(|e| {
+        ErrorInfo::with_code(ErrorCode::BadRegex, format!("Invalid regex pattern: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_reuses_and_keys_by_flags() {
+        let mut cache = RegexCache::new(4);
+        let re = cache.get_or_compile("^fix", "i").unwrap();
+        assert!(re.is_match("FIX typo"));
+        cache.get_or_compile("^fix", "i").unwrap();
+        assert_eq!(cache.len(), 1);
+
+        let re = cache.get_or_compile("^fix", "").unwrap();
+        assert!(!re.is_match("FIX typo"));
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache.get_or_compile("(", "").is_err());
+        assert!(cache.get_or_compile("x", "q").is_err());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = RegexCache::new(2);
+        cache.get_or_compile("a", "").unwrap();
+        cache.get_or_compile("b", "").unwrap();
+        cache.get_or_compile("a", "").unwrap();
+        cache.get_or_compile("c", "").unwrap();
+
+        assert_eq!(cache.len(), 2);
+        let keys: Vec<&str> = {
+            let mut keys: Vec<&str> = cache.regexes.keys().map(|(p, _)| p.as_str()).collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys, vec!["a", "c"]);
+    }
+}
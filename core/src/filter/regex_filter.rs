@@ -1,54 +1,171 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-use crate::graph::types::LayoutResult;
+use super::date_filter::in_date_range;
+use super::domain_filter::email_domain;
+use super::glob::glob_to_regex;
+use super::regex_cache::cached_regex;
+use super::select_nodes;
+use crate::error::ErrorInfo;
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+/// How letter case is compared when matching a pattern.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaseMode {
+    #[default]
+    Sensitive,
+    Insensitive,
+    /// Insensitive unless the pattern contains an uppercase letter, as in
+    /// editor search boxes.
+    Smart,
+}
+
+/// How a filter pattern is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PatternSyntax {
+    #[default]
+    Regex,
+    /// A glob matched against the whole text: `*` matches any run of
+    /// characters, `?` one character and `[...]` a class, so `*fix*` finds
+    /// "hotfix" without tripping over regex syntax.
+    Glob,
+}
+
+/// Options for `filter_commits_by_field`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MatchFlags {
+    pub case: CaseMode,
+    /// Keep the commits that do not match instead, e.g. to hide bots.
+    pub negate: bool,
+    pub syntax: PatternSyntax,
+}
+
+impl MatchFlags {
+    /// Whether `pattern` should match without regard to case. Letters
+    /// escaped with a backslash, like `\S`, do not count for smart case.
+    pub fn ignores_case(&self, pattern: &str) -> bool {
+        match self.case {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => {
+                let mut escaped = false;
+                !pattern.chars().any(|c| {
+                    let literal = !escaped;
+                    escaped = literal && c == '\\';
+                    literal && c.is_uppercase()
+                })
+            }
+        }
+    }
+}
 
 /// Filter commits in a LayoutResult by a regex pattern on a specified field.
 ///
-/// Supported fields: "message" (subject), "author", "committer", "sha".
+/// Supported fields: "message" (subject and body), "subject", "body",
+/// "author", "author_email", "committer", "committer_email", "email"
+/// (either email), "author_domain", "committer_domain", "domain" (either
+/// email's domain), "sha", "source" (the `%S` ref), "ref" (any
+/// branch, tag or other ref decorating the commit, by its displayed name,
+/// e.g. `release/1.2` or `origin/main`), and "any", which matches any of
+/// the subject, author, email or SHA like a single search box. `flags` selects
+/// case-insensitive or smart-case matching (an inline `(?i)` still works),
+/// can invert the filter and can take the pattern as a glob instead.
 /// Returns a new LayoutResult containing only matching nodes and their edges.
 pub fn filter_commits_by_field(
     layout: &LayoutResult,
     field: &str,
     pattern: &str,
-) -> Result<LayoutResult, String> {
-    let re = Regex::new(pattern).map_err(|e| format!("Invalid regex pattern: {}", e))?;
-
-    let matching_shas: std::collections::HashSet<String> = layout
-        .nodes
-        .iter()
-        .filter(|node| {
-            let value = match field {
-                "message" | "subject" => &node.subject,
-                "author" => &node.author_name,
-                "sha" | "hash" => &node.sha,
-                _ => return false,
-            };
-            re.is_match(value)
-        })
-        .map(|node| node.sha.clone())
-        .collect();
-
-    let filtered_nodes: Vec<_> = layout
-        .nodes
-        .iter()
-        .filter(|n| matching_shas.contains(&n.sha))
-        .cloned()
-        .collect();
-
-    let filtered_edges: Vec<_> = layout
-        .edges
-        .iter()
-        .filter(|e| matching_shas.contains(&e.from_sha) && matching_shas.contains(&e.to_sha))
-        .cloned()
-        .collect();
-
-    let total_count = filtered_nodes.len();
-
-    Ok(LayoutResult {
-        nodes: filtered_nodes,
-        edges: filtered_edges,
-        total_count,
-    })
+    flags: &MatchFlags,
+) -> Result<LayoutResult, ErrorInfo> {
+    let re = compile_pattern(pattern, flags)?;
+    Ok(select_nodes(layout, |node| {
+        field_texts(node, field).iter().any(|t| re.is_match(t)) != flags.negate
+    }))
+}
+
+/// `filter_commits_by_field` within an author date range, in one pass.
+///
+/// `after` and `before` are as for `filter_commits_by_date`, 0 leaving that
+/// side open. `flags.negate` inverts only the pattern, so the result is
+/// always within the range, e.g. the commits not by a bot in March.
+pub fn filter_commits_by_field_in_range(
+    layout: &LayoutResult,
+    field: &str,
+    pattern: &str,
+    flags: &MatchFlags,
+    after: u64,
+    before: u64,
+) -> Result<LayoutResult, ErrorInfo> {
+    let re = compile_pattern(pattern, flags)?;
+    Ok(select_nodes(layout, |node| {
+        in_date_range(node.author_date, after, before)
+            && field_texts(node, field).iter().any(|t| re.is_match(t)) != flags.negate
+    }))
+}
+
+/// The fields "any" searches, in the order a match is attributed to them.
+pub(crate) const ANY_FIELDS: [&str; 4] = ["subject", "author", "email", "sha"];
+
+/// Compile a filter pattern with `flags`, reusing the cached regex when
+/// the same pattern was compiled recently.
+pub(crate) fn compile_pattern(pattern: &str, flags: &MatchFlags) -> Result<Regex, ErrorInfo> {
+    let case = if flags.ignores_case(pattern) { "i" } else { "" };
+    match flags.syntax {
+        PatternSyntax::Regex => cached_regex(pattern, case),
+        PatternSyntax::Glob => {
+            let re = format!("^{}$", glob_to_regex(pattern, false));
+            cached_regex(&re, &format!("{}s", case))
+        }
+    }
+}
+
+/// The fields `field_texts` knows, for `filter_commits` and the other
+/// single-field filters.
+pub const FILTER_FIELDS: &[&str] = &[
+    "message",
+    "subject",
+    "body",
+    "author",
+    "author_email",
+    "committer",
+    "committer_email",
+    "email",
+    "author_domain",
+    "committer_domain",
+    "domain",
+    "sha",
+    "hash",
+    "source",
+    "ref",
+    "refs",
+    "any",
+];
+
+/// The texts of `node` that `field` matches against, the one shown in the
+/// graph row first. Unknown fields have none.
+pub(crate) fn field_texts<'a>(node: &'a LayoutNode, field: &str) -> Vec<&'a str> {
+    match field {
+        "message" => vec![&node.subject, &node.body],
+        "subject" => vec![&node.subject],
+        "body" => vec![&node.body],
+        "author" => vec![&node.author_name],
+        "author_email" => vec![&node.author_email],
+        "committer" => vec![&node.committer_name],
+        "committer_email" => vec![&node.committer_email],
+        "email" => vec![&node.author_email, &node.committer_email],
+        "author_domain" => email_domain(&node.author_email).into_iter().collect(),
+        "committer_domain" => email_domain(&node.committer_email).into_iter().collect(),
+        "domain" => field_texts(node, "author_domain")
+            .into_iter()
+            .chain(field_texts(node, "committer_domain"))
+            .collect(),
+        "sha" | "hash" => vec![&node.sha],
+        "source" => vec![&node.source_ref],
+        "ref" | "refs" => node.refs.iter().map(|r| r.name.as_str()).collect(),
+        "any" => ANY_FIELDS.iter().flat_map(|f| field_texts(node, f)).collect(),
+        _ => Vec::new(),
+    }
 }
 
 #[cfg(test)]
@@ -71,6 +188,8 @@ mod tests {
                     refs: vec![],
                     parents: vec!["bbb222".to_string()],
                     node_type: NodeType::Normal,
+                    source_ref: "refs/heads/fix".to_string(),
+                    ..Default::default()
                 },
                 LayoutNode {
                     sha: "bbb222".to_string(),
@@ -84,6 +203,7 @@ mod tests {
                     refs: vec![],
                     parents: vec![],
                     node_type: NodeType::Normal,
+                    ..Default::default()
                 },
             ],
             edges: vec![Edge {
@@ -95,6 +215,7 @@ mod tests {
                 to_row: 1,
                 edge_type: EdgeType::Normal,
                 color_index: 0,
+                truncated_direction: None,
             }],
             total_count: 2,
         }
@@ -103,7 +224,8 @@ mod tests {
     #[test]
     fn test_filter_by_author() {
         let layout = make_test_layout();
-        let result = filter_commits_by_field(&layout, "author", "Alice").unwrap();
+        let result =
+            filter_commits_by_field(&layout, "author", "Alice", &MatchFlags::default()).unwrap();
         assert_eq!(result.total_count, 1);
         assert_eq!(result.nodes[0].author_name, "Alice");
     }
@@ -111,30 +233,262 @@ mod tests {
     #[test]
     fn test_filter_by_message() {
         let layout = make_test_layout();
-        let result = filter_commits_by_field(&layout, "message", "(?i)bug").unwrap();
+        let result =
+            filter_commits_by_field(&layout, "message", "(?i)bug", &MatchFlags::default()).unwrap();
         assert_eq!(result.total_count, 1);
         assert_eq!(result.nodes[0].sha, "aaa111");
     }
 
+    #[test]
+    fn test_filter_message_includes_body() {
+        let mut layout = make_test_layout();
+        layout.nodes[1].body = "Closes a long-standing bug report.".to_string();
+
+        let result =
+            filter_commits_by_field(&layout, "message", "bug", &MatchFlags::default()).unwrap();
+        assert_eq!(result.total_count, 2);
+
+        let result =
+            filter_commits_by_field(&layout, "subject", "bug", &MatchFlags::default()).unwrap();
+        assert_eq!(result.total_count, 1);
+
+        let result =
+            filter_commits_by_field(&layout, "body", "bug", &MatchFlags::default()).unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.nodes[0].sha, "bbb222");
+    }
+
     #[test]
     fn test_filter_by_sha() {
         let layout = make_test_layout();
-        let result = filter_commits_by_field(&layout, "sha", "bbb").unwrap();
+        let result =
+            filter_commits_by_field(&layout, "sha", "bbb", &MatchFlags::default()).unwrap();
         assert_eq!(result.total_count, 1);
         assert_eq!(result.nodes[0].sha, "bbb222");
     }
 
+    #[test]
+    fn test_filter_by_source() {
+        let layout = make_test_layout();
+        let result = filter_commits_by_field(
+            &layout,
+            "source",
+            "^refs/heads/fix$",
+            &MatchFlags::default(),
+        )
+        .unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.nodes[0].sha, "aaa111");
+    }
+
+    #[test]
+    fn test_filter_by_committer_and_email() {
+        let mut layout = make_test_layout();
+        layout.nodes[0].author_email = "alice@example.com".to_string();
+        layout.nodes[0].committer_name = "GitHub".to_string();
+        layout.nodes[0].committer_email = "noreply@github.com".to_string();
+        layout.nodes[1].author_email = "dependabot[bot]@users.noreply.github.com".to_string();
+        layout.nodes[1].committer_name = "Bob".to_string();
+        layout.nodes[1].committer_email = "bob@example.com".to_string();
+
+        let flags = MatchFlags::default();
+        let shas = |field: &str, pattern: &str| -> Vec<String> {
+            let result = filter_commits_by_field(&layout, field, pattern, &flags).unwrap();
+            result.nodes.into_iter().map(|n| n.sha).collect()
+        };
+        assert_eq!(shas("committer", "^GitHub$"), vec!["aaa111"]);
+        assert_eq!(shas("author_email", r"\[bot\]@"), vec!["bbb222"]);
+        assert_eq!(shas("committer_email", "example"), vec!["bbb222"]);
+        assert_eq!(shas("email", "noreply"), vec!["aaa111", "bbb222"]);
+    }
+
+    #[test]
+    fn test_filter_by_ref() {
+        let mut layout = make_test_layout();
+        layout.nodes[1].refs = vec![
+            RefInfo {
+                name: "main".to_string(),
+                ref_type: RefType::Branch,
+                is_head: false,
+            },
+            RefInfo {
+                name: "release/1.2".to_string(),
+                ref_type: RefType::Branch,
+                is_head: false,
+            },
+        ];
+
+        let flags = MatchFlags::default();
+        let result = filter_commits_by_field(&layout, "ref", "^release/.*", &flags).unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.nodes[0].sha, "bbb222");
+
+        // The source ref is a separate field
+        let result = filter_commits_by_field(&layout, "ref", "fix", &flags).unwrap();
+        assert_eq!(result.total_count, 0);
+    }
+
     #[test]
     fn test_filter_no_match() {
         let layout = make_test_layout();
-        let result = filter_commits_by_field(&layout, "author", "Charlie").unwrap();
+        let result =
+            filter_commits_by_field(&layout, "author", "Charlie", &MatchFlags::default()).unwrap();
         assert_eq!(result.total_count, 0);
     }
 
+    #[test]
+    fn test_filter_case_modes() {
+        let layout = make_test_layout();
+        let insensitive = MatchFlags {
+            case: CaseMode::Insensitive,
+            ..Default::default()
+        };
+        let smart = MatchFlags {
+            case: CaseMode::Smart,
+            ..Default::default()
+        };
+
+        let result = filter_commits_by_field(&layout, "author", "alice", &insensitive).unwrap();
+        assert_eq!(result.total_count, 1);
+        let result = filter_commits_by_field(&layout, "author", "alice", &smart).unwrap();
+        assert_eq!(result.total_count, 1);
+        let result = filter_commits_by_field(&layout, "author", "ALICE", &smart).unwrap();
+        assert_eq!(result.total_count, 0);
+        // Escapes such as \S are not uppercase letters
+        let result = filter_commits_by_field(&layout, "author", "^b\\S+$", &smart).unwrap();
+        assert_eq!(result.total_count, 1);
+    }
+
+    #[test]
+    fn test_filter_negated() {
+        let layout = make_test_layout();
+        let flags = MatchFlags {
+            negate: true,
+            ..Default::default()
+        };
+        let result = filter_commits_by_field(&layout, "author", "^Alice$", &flags).unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.nodes[0].author_name, "Bob");
+
+        // Unknown fields match nothing, so negating them keeps everything
+        let result = filter_commits_by_field(&layout, "nope", "x", &flags).unwrap();
+        assert_eq!(result.total_count, 2);
+    }
+
+    #[test]
+    fn test_filter_field_in_range() {
+        let layout = make_test_layout();
+        let flags = MatchFlags::default();
+        let subjects = "^(Fix|Add)";
+        let result =
+            filter_commits_by_field_in_range(&layout, "subject", subjects, &flags, 1699999500, 0)
+                .unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.nodes[0].sha, "aaa111");
+
+        let negated = MatchFlags {
+            negate: true,
+            ..Default::default()
+        };
+        let result =
+            filter_commits_by_field_in_range(&layout, "author", "Alice", &negated, 0, 1699999500)
+                .unwrap();
+        assert_eq!(result.nodes.len(), 1);
+        assert_eq!(result.nodes[0].author_name, "Bob");
+        let result =
+            filter_commits_by_field_in_range(&layout, "author", "Bob", &negated, 0, 1699999500)
+                .unwrap();
+        assert!(result.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_filter_any_field() {
+        let layout = make_test_layout();
+        let flags = MatchFlags::default();
+        let by_subject = filter_commits_by_field(&layout, "any", "graph", &flags).unwrap();
+        assert_eq!(by_subject.nodes[0].sha, "bbb222");
+        let by_author = filter_commits_by_field(&layout, "any", "^Alice$", &flags).unwrap();
+        assert_eq!(by_author.nodes[0].sha, "aaa111");
+        let by_sha = filter_commits_by_field(&layout, "any", "^bbb", &flags).unwrap();
+        assert_eq!(by_sha.nodes[0].sha, "bbb222");
+        // The source ref is not one of the searched fields
+        let result = filter_commits_by_field(&layout, "any", "heads/fix", &flags).unwrap();
+        assert_eq!(result.total_count, 0);
+    }
+
+    #[test]
+    fn test_filter_glob_syntax() {
+        let layout = make_test_layout();
+        let glob = MatchFlags {
+            syntax: PatternSyntax::Glob,
+            ..Default::default()
+        };
+        let result = filter_commits_by_field(&layout, "subject", "*bug*", &glob).unwrap();
+        assert_eq!(result.nodes[0].sha, "aaa111");
+        // Globs match the whole text and escape regex syntax
+        let result = filter_commits_by_field(&layout, "subject", "bug", &glob).unwrap();
+        assert_eq!(result.total_count, 0);
+        let result = filter_commits_by_field(&layout, "author", "B?b", &glob).unwrap();
+        assert_eq!(result.nodes[0].sha, "bbb222");
+        let result = filter_commits_by_field(&layout, "subject", "Add (new)*", &glob).unwrap();
+        assert_eq!(result.total_count, 0);
+
+        let smart = MatchFlags {
+            case: CaseMode::Smart,
+            ..glob
+        };
+        let result = filter_commits_by_field(&layout, "subject", "fix *", &smart).unwrap();
+        assert_eq!(result.nodes[0].sha, "aaa111");
+
+        // The same pattern is a regex error
+        let regex = MatchFlags::default();
+        assert!(filter_commits_by_field(&layout, "subject", "*fix*", &regex).is_err());
+    }
+
+    #[test]
+    fn test_filter_email_domains() {
+        let mut layout = make_test_layout();
+        layout.nodes[0].author_email = "alice@mycompany.com".to_string();
+        layout.nodes[1].author_email = "bob@gmail.com".to_string();
+        layout.nodes[1].committer_email = "carol@mycompany.com".to_string();
+        let flags = MatchFlags::default();
+
+        let result = filter_commits_by_field(&layout, "author_domain", "^mycompany", &flags);
+        assert_eq!(result.unwrap().nodes[0].sha, "aaa111");
+        let result = filter_commits_by_field(&layout, "domain", "^mycompany\\.com$", &flags);
+        assert_eq!(result.unwrap().total_count, 2);
+        let result = filter_commits_by_field(&layout, "committer_domain", "gmail", &flags);
+        assert_eq!(result.unwrap().total_count, 0);
+    }
+
     #[test]
     fn test_filter_invalid_regex() {
         let layout = make_test_layout();
-        let result = filter_commits_by_field(&layout, "author", "[invalid");
+        let result = filter_commits_by_field(&layout, "author", "[invalid", &MatchFlags::default());
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_filter_fields_have_texts() {
+        let node = LayoutNode {
+            sha: "aaa111".to_string(),
+            subject: "Subject".to_string(),
+            body: "Body".to_string(),
+            author_name: "Alice".to_string(),
+            author_email: "alice@a.com".to_string(),
+            committer_name: "Carol".to_string(),
+            committer_email: "carol@c.com".to_string(),
+            source_ref: "refs/heads/main".to_string(),
+            refs: vec![RefInfo {
+                name: "main".to_string(),
+                ref_type: RefType::Branch,
+                is_head: false,
+            }],
+            ..Default::default()
+        };
+        for field in FILTER_FIELDS {
+            assert!(!field_texts(&node, field).is_empty(), "{}", field);
+        }
+        assert!(field_texts(&node, "title").is_empty());
+    }
 }
@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::elide::elide_hidden_commits;
+use super::regex_cache::cached_regex;
+use super::select_nodes;
+use crate::error::ErrorInfo;
+use crate::graph::types::{LayoutNode, LayoutResult, RefType};
+
+/// Tags that look like versions: `v1.2`, `1.2.3`, `v2.0.0-rc.1`.
+pub const DEFAULT_RELEASE_PATTERN: &str = r"^v?\d+\.\d+(\.\d+)?([-+.].*)?$";
+
+/// A commit carrying at least one release tag.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Release {
+    pub sha: String,
+    pub row: i32,
+    /// Its tags matching the release pattern.
+    pub tags: Vec<String>,
+    /// Commits in this release since the previous (older) one, like
+    /// `git rev-list --count <previous>..<release>`, counted within the
+    /// loaded commits. For the oldest release, every loaded ancestor.
+    pub commit_count: usize,
+}
+
+/// The release commits of a layout, as a drawable view plus per-release
+/// details.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseView {
+    /// Release commits, connected by `EdgeType::Elided` edges across the
+    /// commits between them.
+    #[serde(flatten)]
+    pub layout: LayoutResult,
+    /// In row order, newest first.
+    pub releases: Vec<Release>,
+}
+
+/// Keep only the commits tagged as releases, for a "releases only" toggle.
+///
+/// `pattern` is a regex matched against tag names; an empty pattern uses
+/// `DEFAULT_RELEASE_PATTERN`.
+pub fn filter_releases(layout: &LayoutResult, pattern: &str) -> Result<ReleaseView, ErrorInfo> {
+    let pattern = if pattern.trim().is_empty() {
+        DEFAULT_RELEASE_PATTERN
+    } else {
+        pattern
+    };
+    let re = cached_regex(pattern, "")?;
+
+    let tagged: HashMap<&str, Vec<String>> = layout
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let tags = release_tags(node, &re);
+            (!tags.is_empty()).then_some((node.sha.as_str(), tags))
+        })
+        .collect();
+
+    let mut view = select_nodes(layout, |node| tagged.contains_key(node.sha.as_str()));
+    elide_hidden_commits(layout, &mut view);
+
+    let parents: HashMap<&str, &[String]> = layout
+        .nodes
+        .iter()
+        .map(|n| (n.sha.as_str(), n.parents.as_slice()))
+        .collect();
+    // Walk from the oldest release up, so each count excludes the history
+    // of the releases before it, which stays marked as reached
+    let mut releases = Vec::with_capacity(view.nodes.len());
+    let mut reachable: HashSet<&str> = HashSet::new();
+    for node in view.nodes.iter().rev() {
+        let mut stack = vec![node.sha.as_str()];
+        let mut commit_count = 0;
+        while let Some(sha) = stack.pop() {
+            if !reachable.insert(sha) {
+                continue;
+            }
+            // Parents outside the loaded history are not counted
+            if let Some(parents) = parents.get(sha) {
+                commit_count += 1;
+                stack.extend(parents.iter().map(String::as_str));
+            }
+        }
+
+        releases.push(Release {
+            sha: node.sha.clone(),
+            row: node.row,
+            tags: tagged[node.sha.as_str()].clone(),
+            commit_count,
+        });
+    }
+    releases.reverse();
+
+    Ok(ReleaseView {
+        layout: view,
+        releases,
+    })
+}
+
+fn release_tags(node: &LayoutNode, re: &Regex) -> Vec<String> {
+    node.refs
+        .iter()
+        .filter(|r| r.ref_type == RefType::Tag && re.is_match(&r.name))
+        .map(|r| r.name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::graph::types::{CommitNode, EdgeType, RefInfo};
+
+    fn commit(sha: &str, parents: &[&str], tags: &[&str]) -> CommitNode {
+        CommitNode {
+            sha: sha.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            refs: tags
+                .iter()
+                .map(|t| RefInfo {
+                    name: t.to_string(),
+                    ref_type: RefType::Tag,
+                    is_head: false,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn make_test_layout() -> LayoutResult {
+        compute_layout(&[
+            commit("m", &["c", "f"], &["v2.0.0"]),
+            commit("f", &["b"], &[]),
+            commit("c", &["b"], &["nightly"]),
+            commit("b", &["a"], &["v1.1", "latest"]),
+            commit("a", &["r"], &[]),
+            commit("r", &[], &["v1.0.0-rc.1"]),
+        ])
+    }
+
+    #[test]
+    fn test_releases_and_counts() {
+        let view = filter_releases(&make_test_layout(), "").unwrap();
+        let summary: Vec<(&str, Vec<String>, usize)> = view
+            .releases
+            .iter()
+            .map(|r| (r.sha.as_str(), r.tags.clone(), r.commit_count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("m", vec!["v2.0.0".to_string()], 3),
+                ("b", vec!["v1.1".to_string()], 2),
+                ("r", vec!["v1.0.0-rc.1".to_string()], 1),
+            ]
+        );
+
+        // Releases are chained across the commits between them
+        assert_eq!(view.layout.total_count, 3);
+        let edges: Vec<(&str, &str, EdgeType)> = view
+            .layout
+            .edges
+            .iter()
+            .map(|e| (e.from_sha.as_str(), e.to_sha.as_str(), e.edge_type.clone()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![("m", "b", EdgeType::Elided), ("b", "r", EdgeType::Elided)]
+        );
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let view = filter_releases(&make_test_layout(), "^nightly$").unwrap();
+        assert_eq!(view.releases.len(), 1);
+        assert_eq!(view.releases[0].sha, "c");
+        assert_eq!(view.releases[0].commit_count, 4);
+
+        assert!(filter_releases(&make_test_layout(), "(").is_err());
+    }
+
+    #[test]
+    fn test_counts_skip_unloaded_parents() {
+        // "a" is the boundary of a shallow load: its parent "z" is missing
+        let layout = compute_layout(&[
+            commit("c", &["b"], &["v2.0"]),
+            commit("b", &["a"], &[]),
+            commit("a", &["z"], &["v1.0"]),
+        ]);
+        let view = filter_releases(&layout, "").unwrap();
+        let counts: Vec<(&str, usize)> = view
+            .releases
+            .iter()
+            .map(|r| (r.sha.as_str(), r.commit_count))
+            .collect();
+        assert_eq!(counts, vec![("c", 2), ("a", 1)]);
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::query::{parse_query, Query};
+use crate::error::{ErrorCode, ErrorInfo};
+
+/// A named query kept for reuse across layouts.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedFilter {
+    pub name: String,
+    /// The query as the user wrote it.
+    pub query: String,
+    /// Parsed once when saved, so applying the filter does not re-parse.
+    #[serde(skip)]
+    pub parsed: Query,
+}
+
+/// Saved filters by name, listed in name order.
+#[derive(Debug, Clone, Default)]
+pub struct SavedFilters {
+    filters: BTreeMap<String, SavedFilter>,
+}
+
+impl SavedFilters {
+    /// Save `query` as `name`, replacing any filter of that name. Fails,
+    /// keeping the previous filter, if the name is blank or the query does
+    /// not parse.
+    pub fn save(&mut self, name: &str, query: &str) -> Result<&SavedFilter, ErrorInfo> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ErrorInfo::with_code(ErrorCode::BadArgument, "Filter name is empty"));
+        }
+        let parsed = parse_query(query)?;
+        let filter = SavedFilter {
+            name: name.to_string(),
+            query: query.to_string(),
+            parsed,
+        };
+        self.filters.insert(name.to_string(), filter);
+        Ok(&self.filters[name])
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SavedFilter> {
+        self.filters.get(name.trim())
+    }
+
+    /// Remove a filter, returning whether it existed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.filters.remove(name.trim()).is_some()
+    }
+
+    pub fn list(&self) -> Vec<&SavedFilter> {
+        self.filters.values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::LayoutNode;
+
+    #[test]
+    fn test_save_replace_and_remove() {
+        let mut filters = SavedFilters::default();
+        filters.save("mine", "author:alice").unwrap();
+        filters.save(" bots ", "email:/\\[bot\\]/").unwrap();
+        filters.save("mine", "author:alice is:nonmerge").unwrap();
+
+        let names: Vec<&str> = filters.list().iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["bots", "mine"]);
+        assert_eq!(filters.get("mine").unwrap().query, "author:alice is:nonmerge");
+
+        assert!(filters.remove("bots"));
+        assert!(!filters.remove("bots"));
+        assert!(filters.get("bots").is_none());
+    }
+
+    #[test]
+    fn test_invalid_save_keeps_previous() {
+        let mut filters = SavedFilters::default();
+        filters.save("mine", "author:alice").unwrap();
+        assert!(filters.save("mine", "(author:bob").is_err());
+        assert!(filters.save("  ", "author:bob").is_err());
+        assert_eq!(filters.get("mine").unwrap().query, "author:alice");
+    }
+
+    #[test]
+    fn test_saved_query_matches() {
+        let mut filters = SavedFilters::default();
+        let saved = filters.save("alice", "author:alice").unwrap();
+        let node = LayoutNode {
+            author_name: "Alice".to_string(),
+            ..Default::default()
+        };
+        assert!(saved.parsed.matches(&node));
+
+        let json = serde_json::to_value(saved).unwrap();
+        assert_eq!(json, serde_json::json!({ "name": "alice", "query": "author:alice" }));
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+use super::select_nodes;
+use crate::graph::types::LayoutResult;
+
+/// Filter commits in a LayoutResult to a caller-provided set of SHAs, such
+/// as the commits of a pull request fetched from the forge.
+///
+/// SHAs may be abbreviated and are compared case-insensitively; SHAs that
+/// name no commit in the layout are ignored.
+pub fn filter_commits_by_shas(layout: &LayoutResult, shas: &[String]) -> LayoutResult {
+    let wanted: HashSet<String> = shas
+        .iter()
+        .map(|s| s.trim().to_ascii_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    select_nodes(layout, |node| {
+        let sha = node.sha.to_ascii_lowercase();
+        (1..=sha.len()).any(|len| wanted.contains(&sha[..len]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::test_support::commit;
+
+    fn make_test_layout() -> LayoutResult {
+        compute_layout(&[
+            commit("cc33", &["bb22"]),
+            commit("bb22", &["aa11"]),
+            commit("aa11", &["0000"]),
+            commit("0000", &[]),
+        ])
+    }
+
+    fn shas(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_filter_by_shas_keeps_edges_within_set() {
+        let result = filter_commits_by_shas(&make_test_layout(), &shas(&["cc33", "bb22", "0000"]));
+        let kept: Vec<&str> = result.nodes.iter().map(|n| n.sha.as_str()).collect();
+        assert_eq!(kept, vec!["cc33", "bb22", "0000"]);
+        assert_eq!(result.total_count, 3);
+        assert_eq!(result.edges.len(), 1);
+        assert_eq!(result.edges[0].from_sha, "cc33");
+        assert_eq!(result.edges[0].to_sha, "bb22");
+    }
+
+    #[test]
+    fn test_filter_by_shas_prefix_and_case() {
+        let result = filter_commits_by_shas(
+            &make_test_layout(),
+            &shas(&["AA", "aa0", " cc3 ", "ffff", ""]),
+        );
+        let kept: Vec<&str> = result.nodes.iter().map(|n| n.sha.as_str()).collect();
+        assert_eq!(kept, vec!["cc33", "aa11"]);
+    }
+
+    #[test]
+    fn test_filter_by_shas_empty_set() {
+        let result = filter_commits_by_shas(&make_test_layout(), &[]);
+        assert!(result.nodes.is_empty());
+        assert!(result.edges.is_empty());
+    }
+}
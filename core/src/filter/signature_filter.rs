@@ -0,0 +1,107 @@
+use super::select_nodes;
+use crate::graph::types::{LayoutResult, SignatureStatus};
+
+/// Which commits `filter_commits_by_signature` keeps, by the verification
+/// status of their signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFilter {
+    /// A good signature from a trusted key.
+    Verified,
+    /// Anything else, unsigned commits included.
+    Unverified,
+    /// A bad signature or a revoked key.
+    Bad,
+    /// Signed, whatever the status.
+    Signed,
+    Unsigned,
+}
+
+impl SignatureFilter {
+    /// Parse a filter name without regard to case, e.g. `verified`.
+    pub fn from_name(name: &str) -> Option<SignatureFilter> {
+        let filter = match name.trim().to_ascii_lowercase().as_str() {
+            "verified" => SignatureFilter::Verified,
+            "unverified" => SignatureFilter::Unverified,
+            "bad" => SignatureFilter::Bad,
+            "signed" => SignatureFilter::Signed,
+            "unsigned" => SignatureFilter::Unsigned,
+            _ => return None,
+        };
+        Some(filter)
+    }
+
+    pub fn matches(self, status: SignatureStatus) -> bool {
+        match self {
+            SignatureFilter::Verified => status == SignatureStatus::Good,
+            SignatureFilter::Unverified => status != SignatureStatus::Good,
+            SignatureFilter::Bad => status == SignatureStatus::Bad,
+            SignatureFilter::Signed => status != SignatureStatus::None,
+            SignatureFilter::Unsigned => status == SignatureStatus::None,
+        }
+    }
+}
+
+/// Filter commits in a LayoutResult by signature verification status, e.g.
+/// to isolate unsigned commits in an audit.
+///
+/// Uses the `%G?` status of each LayoutNode, so the log must have been
+/// read with a format that includes it. Returns a new LayoutResult
+/// containing only matching nodes and their edges.
+pub fn filter_commits_by_signature(layout: &LayoutResult, filter: SignatureFilter) -> LayoutResult {
+    select_nodes(layout, |node| filter.matches(node.signature_status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::LayoutNode;
+
+    fn node(sha: &str, row: i32, status: SignatureStatus) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            row,
+            signature_status: status,
+            ..Default::default()
+        }
+    }
+
+    fn shas(layout: &LayoutResult, filter: SignatureFilter) -> Vec<String> {
+        filter_commits_by_signature(layout, filter)
+            .nodes
+            .into_iter()
+            .map(|n| n.sha)
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_by_signature() {
+        let layout = LayoutResult {
+            nodes: vec![
+                node("good", 0, SignatureStatus::Good),
+                node("bad", 1, SignatureStatus::Bad),
+                node("expired", 2, SignatureStatus::Expired),
+                node("none", 3, SignatureStatus::None),
+            ],
+            edges: vec![],
+            total_count: 4,
+        };
+
+        assert_eq!(shas(&layout, SignatureFilter::Verified), vec!["good"]);
+        assert_eq!(
+            shas(&layout, SignatureFilter::Unverified),
+            vec!["bad", "expired", "none"]
+        );
+        assert_eq!(shas(&layout, SignatureFilter::Bad), vec!["bad"]);
+        assert_eq!(
+            shas(&layout, SignatureFilter::Signed),
+            vec!["good", "bad", "expired"]
+        );
+        assert_eq!(shas(&layout, SignatureFilter::Unsigned), vec!["none"]);
+
+        assert_eq!(
+            SignatureFilter::from_name(" Unsigned"),
+            Some(SignatureFilter::Unsigned)
+        );
+        assert_eq!(SignatureFilter::from_name("trusted"), None);
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use super::select_nodes;
+use crate::graph::types::{LayoutNode, LayoutResult};
+
+/// What a size threshold counts for a commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeMetric {
+    Insertions,
+    Deletions,
+    /// Insertions plus deletions.
+    Lines,
+    /// Files changed.
+    Files,
+}
+
+/// Inclusive thresholds on the size of a commit; unset bounds are open.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SizeFilter {
+    pub min_insertions: Option<u64>,
+    pub max_insertions: Option<u64>,
+    pub min_deletions: Option<u64>,
+    pub max_deletions: Option<u64>,
+    pub min_lines: Option<u64>,
+    pub max_lines: Option<u64>,
+    pub min_files: Option<u64>,
+    pub max_files: Option<u64>,
+}
+
+impl SizeFilter {
+    /// Whether `node` is within every bound.
+    pub fn matches(&self, node: &LayoutNode) -> bool {
+        let bounds = [
+            (SizeMetric::Insertions, self.min_insertions, self.max_insertions),
+            (SizeMetric::Deletions, self.min_deletions, self.max_deletions),
+            (SizeMetric::Lines, self.min_lines, self.max_lines),
+            (SizeMetric::Files, self.min_files, self.max_files),
+        ];
+        bounds.iter().all(|&(metric, min, max)| {
+            let size = commit_size(node, metric);
+            min.is_none_or(|min| size >= min) && max.is_none_or(|max| size <= max)
+        })
+    }
+}
+
+/// Filter commits in a LayoutResult by how much they change, e.g. commits
+/// touching more than 500 lines.
+///
+/// Line counts need the `--numstat` data of the log; binary files count as
+/// changed files with no lines. Returns a new LayoutResult containing only
+/// matching nodes and their edges.
+pub fn filter_commits_by_size(layout: &LayoutResult, filter: &SizeFilter) -> LayoutResult {
+    select_nodes(layout, |node| filter.matches(node))
+}
+
+/// `metric` for `node`. Files are counted from `--numstat`, or from
+/// `--name-status` when the log has no line counts.
+pub(crate) fn commit_size(node: &LayoutNode, metric: SizeMetric) -> u64 {
+    let insertions = || node.files.iter().map(|f| f.insertions as u64).sum::<u64>();
+    let deletions = || node.files.iter().map(|f| f.deletions as u64).sum::<u64>();
+    match metric {
+        SizeMetric::Insertions => insertions(),
+        SizeMetric::Deletions => deletions(),
+        SizeMetric::Lines => insertions() + deletions(),
+        SizeMetric::Files => node.files.len().max(node.changes.len()) as u64,
+    }
+}
+
+/// Parse a size comparison into inclusive bounds: `N`, `>N`, `>=N`, `<N`,
+/// `<=N` or a range `N..M`.
+pub(crate) fn parse_size_range(text: &str) -> Option<(u64, u64)> {
+    let number = |s: &str| s.parse::<u64>().ok();
+    if let Some(n) = text.strip_prefix(">=") {
+        Some((number(n)?, u64::MAX))
+    } else if let Some(n) = text.strip_prefix("<=") {
+        Some((0, number(n)?))
+    } else if let Some(n) = text.strip_prefix('>') {
+        Some((number(n)?.checked_add(1)?, u64::MAX))
+    } else if let Some(n) = text.strip_prefix('<') {
+        // `<0` is an empty range
+        Some(number(n)?.checked_sub(1).map_or((1, 0), |max| (0, max)))
+    } else if let Some((min, max)) = text.split_once("..") {
+        Some((number(min)?, number(max)?))
+    } else {
+        number(text).map(|n| (n, n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::{FileChange, FileStat};
+
+    fn node(sha: &str, stats: &[(u32, u32)]) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            files: stats
+                .iter()
+                .enumerate()
+                .map(|(i, &(insertions, deletions))| FileStat {
+                    path: format!("file{}", i),
+                    insertions,
+                    deletions,
+                    binary: false,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn make_test_layout() -> LayoutResult {
+        LayoutResult {
+            nodes: vec![
+                node("big", &[(400, 150), (10, 0)]),
+                node("small", &[(3, 1)]),
+                node("empty", &[]),
+            ],
+            edges: Vec::new(),
+            total_count: 3,
+        }
+    }
+
+    fn kept(filter: &SizeFilter) -> Vec<String> {
+        filter_commits_by_size(&make_test_layout(), filter)
+            .nodes
+            .into_iter()
+            .map(|n| n.sha)
+            .collect()
+    }
+
+    #[test]
+    fn test_commit_size() {
+        let big = &make_test_layout().nodes[0];
+        assert_eq!(commit_size(big, SizeMetric::Insertions), 410);
+        assert_eq!(commit_size(big, SizeMetric::Deletions), 150);
+        assert_eq!(commit_size(big, SizeMetric::Lines), 560);
+        assert_eq!(commit_size(big, SizeMetric::Files), 2);
+    }
+
+    #[test]
+    fn test_files_fall_back_to_name_status() {
+        let node = LayoutNode {
+            changes: vec![FileChange {
+                path: "a".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(commit_size(&node, SizeMetric::Files), 1);
+        assert_eq!(commit_size(&node, SizeMetric::Lines), 0);
+    }
+
+    #[test]
+    fn test_filter_by_size_thresholds() {
+        let filter = SizeFilter {
+            min_lines: Some(501),
+            ..Default::default()
+        };
+        assert_eq!(kept(&filter), vec!["big"]);
+
+        let filter = SizeFilter {
+            min_files: Some(1),
+            max_deletions: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(kept(&filter), vec!["small"]);
+
+        assert_eq!(kept(&SizeFilter::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_size_range() {
+        assert_eq!(parse_size_range("500"), Some((500, 500)));
+        assert_eq!(parse_size_range(">500"), Some((501, u64::MAX)));
+        assert_eq!(parse_size_range(">=500"), Some((500, u64::MAX)));
+        assert_eq!(parse_size_range("<10"), Some((0, 9)));
+        assert_eq!(parse_size_range("<=10"), Some((0, 10)));
+        assert_eq!(parse_size_range("<0"), Some((1, 0)));
+        assert_eq!(parse_size_range("5..10"), Some((5, 10)));
+        assert_eq!(parse_size_range("lots"), None);
+        assert_eq!(parse_size_range(">-1"), None);
+    }
+}
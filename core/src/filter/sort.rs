@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+use super::fuzzy::FuzzyMatch;
+use crate::graph::types::LayoutNode;
+
+/// Order of a flat list of filter results, independent of the layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    /// Graph row order.
+    #[default]
+    Row,
+    /// Newest author date first.
+    DateDesc,
+    /// Oldest author date first.
+    DateAsc,
+    /// Best fuzzy score first; row order for filters without a score.
+    Relevance,
+}
+
+/// Sort filtered nodes; ties keep row order. Nodes keep their rows and
+/// lanes, so a sorted view is for listing rather than drawing.
+pub fn sort_nodes(nodes: &mut [LayoutNode], order: SortOrder) {
+    match order {
+        SortOrder::Row | SortOrder::Relevance => nodes.sort_by_key(|n| n.row),
+        SortOrder::DateDesc => nodes.sort_by_key(|n| (std::cmp::Reverse(n.author_date), n.row)),
+        SortOrder::DateAsc => nodes.sort_by_key(|n| (n.author_date, n.row)),
+    }
+}
+
+/// Sort fuzzy matches; ties keep row order.
+pub fn sort_fuzzy_matches(matches: &mut [FuzzyMatch], order: SortOrder) {
+    match order {
+        SortOrder::Relevance => matches.sort_by_key(|m| (std::cmp::Reverse(m.score), m.node.row)),
+        SortOrder::Row => matches.sort_by_key(|m| m.node.row),
+        SortOrder::DateDesc => {
+            matches.sort_by_key(|m| (std::cmp::Reverse(m.node.author_date), m.node.row))
+        }
+        SortOrder::DateAsc => matches.sort_by_key(|m| (m.node.author_date, m.node.row)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(sha: &str, row: i32, date: u64) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            row,
+            author_date: date,
+            ..Default::default()
+        }
+    }
+
+    fn shas(nodes: &[LayoutNode]) -> Vec<&str> {
+        nodes.iter().map(|n| n.sha.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_nodes() {
+        // Author dates need not follow rows, e.g. after a rebase
+        let mut nodes = vec![node("a", 0, 200), node("b", 1, 300), node("c", 2, 200)];
+
+        sort_nodes(&mut nodes, SortOrder::DateDesc);
+        assert_eq!(shas(&nodes), vec!["b", "a", "c"]);
+        sort_nodes(&mut nodes, SortOrder::DateAsc);
+        assert_eq!(shas(&nodes), vec!["a", "c", "b"]);
+        sort_nodes(&mut nodes, SortOrder::Row);
+        assert_eq!(shas(&nodes), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_sort_fuzzy_matches() {
+        let scored = |sha: &str, row: i32, date: u64, score: i32| FuzzyMatch {
+            node: node(sha, row, date),
+            score,
+            positions: Vec::new(),
+        };
+        let mut matches = vec![
+            scored("a", 0, 100, 10),
+            scored("b", 1, 300, 40),
+            scored("c", 2, 200, 40),
+        ];
+        let order = |matches: &[FuzzyMatch]| -> Vec<String> {
+            matches.iter().map(|m| m.node.sha.clone()).collect()
+        };
+
+        sort_fuzzy_matches(&mut matches, SortOrder::Relevance);
+        assert_eq!(order(&matches), vec!["b", "c", "a"]);
+        sort_fuzzy_matches(&mut matches, SortOrder::DateAsc);
+        assert_eq!(order(&matches), vec!["a", "c", "b"]);
+        sort_fuzzy_matches(&mut matches, SortOrder::Row);
+        assert_eq!(order(&matches), vec!["a", "b", "c"]);
+    }
+}
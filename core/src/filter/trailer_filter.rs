@@ -0,0 +1,90 @@
+use super::regex_filter::{compile_pattern, MatchFlags};
+use super::select_nodes;
+use crate::error::{ErrorCode, ErrorInfo};
+use crate::graph::types::LayoutResult;
+use crate::trailers::parse_trailers;
+
+/// Filter commits in a LayoutResult by a trailer of their message, e.g.
+/// `Co-authored-by` naming someone.
+///
+/// `key` is compared without regard to case; `pattern` is matched against
+/// the values of the trailers with that key, as for
+/// `filter_commits_by_field`, and an empty pattern matches any value, so
+/// the filter keeps commits that have the trailer at all. With
+/// `flags.negate` it keeps the commits without one, such as those missing
+/// `Signed-off-by`. Returns a new LayoutResult containing only matching
+/// nodes and their edges.
+pub fn filter_commits_by_trailer(
+    layout: &LayoutResult,
+    key: &str,
+    pattern: &str,
+    flags: &MatchFlags,
+) -> Result<LayoutResult, ErrorInfo> {
+    if key.trim().is_empty() {
+        return Err(ErrorInfo::with_code(ErrorCode::BadArgument, "Empty trailer key"));
+    }
+    let re = if pattern.is_empty() {
+        None
+    } else {
+        Some(compile_pattern(pattern, flags)?)
+    };
+    Ok(select_nodes(layout, |node| {
+        let found = parse_trailers(&node.body)
+            .iter()
+            .any(|t| t.has_key(key) && re.as_ref().is_none_or(|re| re.is_match(&t.value)));
+        found != flags.negate
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::LayoutNode;
+
+    fn node(sha: &str, row: i32, body: &str) -> LayoutNode {
+        LayoutNode {
+            sha: sha.to_string(),
+            row,
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn make_test_layout() -> LayoutResult {
+        LayoutResult {
+            nodes: vec![
+                node("aaa", 0, "Signed-off-by: Alice <a@e.com>\nCo-authored-by: Bob <b@e.com>"),
+                node("bbb", 1, "Reword.\n\nsigned-off-by: Bob <b@e.com>"),
+                node("ccc", 2, "Mentions Signed-off-by: in prose."),
+            ],
+            edges: vec![],
+            total_count: 3,
+        }
+    }
+
+    fn shas(result: &LayoutResult) -> Vec<&str> {
+        result.nodes.iter().map(|n| n.sha.as_str()).collect()
+    }
+
+    #[test]
+    fn test_filter_by_trailer() {
+        let layout = make_test_layout();
+        let flags = MatchFlags::default();
+
+        let signed = filter_commits_by_trailer(&layout, "Signed-off-by", "", &flags).unwrap();
+        assert_eq!(shas(&signed), vec!["aaa", "bbb"]);
+
+        let bob = filter_commits_by_trailer(&layout, "co-authored-by", "Bob", &flags).unwrap();
+        assert_eq!(shas(&bob), vec!["aaa"]);
+
+        let missing = MatchFlags {
+            negate: true,
+            ..Default::default()
+        };
+        let unsigned = filter_commits_by_trailer(&layout, "Signed-off-by", "", &missing).unwrap();
+        assert_eq!(shas(&unsigned), vec!["ccc"]);
+
+        assert!(filter_commits_by_trailer(&layout, " ", "", &flags).is_err());
+        assert!(filter_commits_by_trailer(&layout, "Fixes", "(", &flags).is_err());
+    }
+}
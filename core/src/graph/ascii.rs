@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::encoding::decode_record;
+use super::format::{LogField, LogFormat};
+use super::parser::{link_children, parse_log_with_format, parse_record};
+use super::types::{CommitNode, Side};
+use crate::input::normalize_input;
+
+/// Parse `git log --graph --format=…` output, where every line starts with
+/// ASCII graph columns such as `| * \ /`.
+///
+/// The graph prefix is stripped and the remaining records are parsed with
+/// `format` as usual. When the format has no `%P`, parent links are
+/// recovered by replaying the drawn lines from each commit marker down to
+/// the commit they end at; parents outside the input cannot be recovered.
+///
+/// Limitations: leading indentation of multi-line bodies is lost, and a body
+/// line made only of graph characters is read as graph art.
+pub fn parse_graph_log(raw: &[u8], format: &LogFormat) -> Vec<CommitNode> {
+    let raw = normalize_input(raw);
+    let mut stripped: Vec<u8> = Vec::with_capacity(raw.len());
+    let mut graph = GraphReplay::default();
+    // Raw record text for each commit row, to learn its SHA
+    let mut row_records: Vec<Vec<u8>> = Vec::new();
+    // Side shown by each commit row's marker
+    let mut row_sides: Vec<Option<Side>> = Vec::new();
+    let mut in_record = false;
+
+    for line in raw.split(|&b| b == b'\n') {
+        let (prefix_len, is_commit) = split_prefix(line, !in_record);
+        let (prefix, content) = line.split_at(prefix_len);
+
+        if is_commit {
+            graph.replay_row(prefix, Some(row_records.len()));
+            row_records.push(Vec::new());
+            row_sides.push(marker_side(prefix));
+            in_record = true;
+        } else {
+            graph.replay_row(prefix, None);
+        }
+
+        if in_record {
+            if let Some(record) = row_records.last_mut() {
+                record.extend_from_slice(content);
+                record.push(b'\n');
+            }
+        }
+        if in_record || !content.is_empty() {
+            stripped.extend_from_slice(content);
+            stripped.push(b'\n');
+        }
+        if content.contains(&0x1e) {
+            in_record = false;
+        }
+    }
+
+    let mut commits = parse_log_with_format(&stripped, format);
+    let row_shas: Vec<Option<String>> = row_records
+        .iter()
+        .map(|record| {
+            let end = record.iter().position(|&b| b == 0x1e).unwrap_or(record.len());
+            parse_record(&decode_record(&record[..end], format), format)
+                .ok()
+                .map(|node| node.sha)
+        })
+        .collect();
+
+    let sides: HashMap<&str, Side> = row_shas
+        .iter()
+        .zip(&row_sides)
+        .filter_map(|(sha, side)| Some((sha.as_deref()?, (*side)?)))
+        .collect();
+    for commit in &mut commits {
+        if commit.side.is_none() {
+            commit.side = sides.get(commit.sha.as_str()).copied();
+        }
+    }
+
+    if format.fields.contains(&LogField::Parents) {
+        return commits;
+    }
+
+    let mut parents_by_sha: HashMap<&str, Vec<String>> = HashMap::new();
+    for (row, slots) in graph.parents.iter().enumerate() {
+        let sha = match row_shas.get(row).and_then(|s| s.as_deref()) {
+            Some(sha) => sha,
+            None => continue,
+        };
+        let parents = slots
+            .iter()
+            .filter_map(|slot| slot.and_then(|p| row_shas.get(p).cloned().flatten()))
+            .collect();
+        parents_by_sha.insert(sha, parents);
+    }
+
+    for commit in &mut commits {
+        if let Some(parents) = parents_by_sha.remove(commit.sha.as_str()) {
+            commit.parents = parents;
+        }
+    }
+    link_children(&mut commits);
+    commits
+}
+
+/// Length of the graph prefix of `line`, and whether it holds a commit
+/// marker (`*`, `o`, `<` or `>`).
+///
+/// Commit rows are only recognized outside a record; inside one, only line
+/// characters are stripped so that bodies like `* item` survive. An octopus
+/// merge marker continues with dashes and a dot, e.g. `*---.`.
+fn split_prefix(line: &[u8], allow_commit: bool) -> (usize, bool) {
+    let mut has_marker = false;
+    let mut i = 0;
+    while i < line.len() {
+        let b = line[i];
+        let is_line_char = matches!(b, b'|' | b'/' | b'\\' | b'_' | b' ');
+        let is_marker = allow_commit && !has_marker && matches!(b, b'*' | b'o' | b'<' | b'>');
+        let is_octopus = has_marker && matches!(b, b'-' | b'.') && matches!(line[i - 1], b'*' | b'o' | b'<' | b'>' | b'-');
+        if !(is_line_char || is_marker || is_octopus) {
+            break;
+        }
+        has_marker |= is_marker;
+        i += 1;
+    }
+    (i, has_marker)
+}
+
+/// Side drawn by a commit row's marker: `--left-right` draws `<` and `>`
+/// and `--boundary` draws `o` in place of `*`.
+fn marker_side(prefix: &[u8]) -> Option<Side> {
+    match prefix.iter().find(|b| matches!(b, b'*' | b'o' | b'<' | b'>'))? {
+        b'<' => Some(Side::Left),
+        b'>' => Some(Side::Right),
+        b'o' => Some(Side::Boundary),
+        _ => None,
+    }
+}
+
+/// A drawn element of one graph row, with the column positions it connects
+/// on the row above (`top`, or `alt` for diagonals drawn one character per
+/// row) and on the row below (`bottoms`).
+#[derive(Debug)]
+struct Element {
+    top: i32,
+    alt: i32,
+    bottoms: Vec<i32>,
+    is_commit: bool,
+}
+
+/// A line leaving a commit towards one of its parents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PendingEdge {
+    /// Commit row the line starts at.
+    child: usize,
+    /// Parent index, assigned on the row where the line splits off.
+    slot: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Lane {
+    pos: i32,
+    /// Position on the row above, used to order lanes that meet.
+    from: i32,
+    edges: Vec<PendingEdge>,
+}
+
+/// Replays graph rows top to bottom, tracking which pending parent edges
+/// each drawn line carries.
+#[derive(Debug, Default)]
+struct GraphReplay {
+    lanes: Vec<Lane>,
+    /// Per commit row, the commit row of each parent by slot.
+    parents: Vec<Vec<Option<usize>>>,
+    next_slot: HashMap<usize, usize>,
+}
+
+impl GraphReplay {
+    fn replay_row(&mut self, prefix: &[u8], commit: Option<usize>) {
+        if let Some(row) = commit {
+            if self.parents.len() <= row {
+                self.parents.resize(row + 1, Vec::new());
+            }
+        }
+
+        let elements = tokenize_row(prefix);
+        if elements.is_empty() {
+            // Unprefixed continuation lines carry no graph information
+            return;
+        }
+
+        // Lanes meeting each top position, left to right
+        let mut groups: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+        for (i, lane) in self.lanes.iter().enumerate() {
+            groups.entry(lane.pos).or_default().push(i);
+        }
+        let mut consumers: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+        for (i, element) in elements.iter().enumerate() {
+            if let Some(pos) = [element.top, element.alt]
+                .into_iter()
+                .find(|p| groups.contains_key(p))
+            {
+                consumers.entry(pos).or_default().push(i);
+            }
+        }
+
+        let mut incoming: Vec<Vec<PendingEdge>> = vec![Vec::new(); elements.len()];
+        for (pos, lane_ids) in &groups {
+            let cons = match consumers.get(pos) {
+                Some(c) => c,
+                None => continue,
+            };
+            // Lanes and elements meet in order; extra lanes merge into the
+            // last element, extra elements split off the last lane.
+            for i in 0..lane_ids.len().max(cons.len()) {
+                let lane = &self.lanes[lane_ids[i.min(lane_ids.len() - 1)]];
+                incoming[cons[i.min(cons.len() - 1)]].extend(lane.edges.iter().cloned());
+            }
+        }
+
+        let mut lanes = Vec::new();
+        for (element, mut edges) in elements.iter().zip(incoming) {
+            if element.is_commit {
+                let row = commit.unwrap_or_default();
+                for edge in edges {
+                    let slot = edge.slot.unwrap_or_else(|| self.take_slot(edge.child));
+                    let slots = &mut self.parents[edge.child];
+                    if slots.len() <= slot {
+                        slots.resize(slot + 1, None);
+                    }
+                    slots[slot] = Some(row);
+                }
+                for &bottom in &element.bottoms {
+                    lanes.push(Lane {
+                        pos: bottom,
+                        from: element.top,
+                        edges: vec![PendingEdge {
+                            child: row,
+                            slot: None,
+                        }],
+                    });
+                }
+            } else {
+                // A line split off a commit takes the commit's next parent slot
+                dedup_edges(&mut edges);
+                for edge in &mut edges {
+                    if edge.slot.is_none() {
+                        edge.slot = Some(self.take_slot(edge.child));
+                    }
+                }
+                if !edges.is_empty() {
+                    lanes.push(Lane {
+                        pos: element.bottoms[0],
+                        from: element.top,
+                        edges,
+                    });
+                }
+            }
+        }
+
+        // Where lanes meet, one arriving from further right sits left of a
+        // straight one, matching how git draws `_` crossings
+        lanes.sort_by_key(|lane| (lane.pos, -lane.from));
+        self.lanes = lanes;
+    }
+
+    fn take_slot(&mut self, child: usize) -> usize {
+        let next = self.next_slot.entry(child).or_insert(0);
+        *next += 1;
+        *next - 1
+    }
+}
+
+fn dedup_edges(edges: &mut Vec<PendingEdge>) {
+    let mut seen: Vec<PendingEdge> = Vec::with_capacity(edges.len());
+    edges.retain(|edge| {
+        if seen.contains(edge) {
+            false
+        } else {
+            seen.push(edge.clone());
+            true
+        }
+    });
+}
+
+/// Split a graph prefix into drawn elements.
+fn tokenize_row(prefix: &[u8]) -> Vec<Element> {
+    let mut elements = Vec::new();
+    let mut i = 0;
+    while i < prefix.len() {
+        let p = i as i32;
+        match prefix[i] {
+            b'|' => elements.push(Element {
+                top: p,
+                alt: p,
+                bottoms: vec![p],
+                is_commit: false,
+            }),
+            b'\\' => elements.push(Element {
+                top: p - 1,
+                alt: p,
+                bottoms: vec![p + 1],
+                is_commit: false,
+            }),
+            b'/' => {
+                // A `/` may continue left along a run of `_`, bridging
+                // over `|` columns: `| |_|_|/`
+                let mut leftmost = None;
+                let mut j = i;
+                while j > 0 && matches!(prefix[j - 1], b'_' | b'|') {
+                    j -= 1;
+                    if prefix[j] == b'_' {
+                        leftmost = Some(j as i32);
+                    }
+                }
+                elements.push(Element {
+                    top: p + 1,
+                    alt: p,
+                    bottoms: vec![leftmost.unwrap_or(p) - 1],
+                    is_commit: false,
+                });
+            }
+            b'*' | b'o' | b'<' | b'>' => {
+                // Octopus merges draw `*-.`, `*---.`, …, with one line per
+                // column up to the dot
+                let mut end = i;
+                while end + 1 < prefix.len() && matches!(prefix[end + 1], b'-' | b'.') {
+                    end += 1;
+                }
+                let bottoms = if end > i && prefix[end] == b'.' {
+                    (i..=end).step_by(2).map(|b| b as i32).collect()
+                } else {
+                    vec![p]
+                };
+                elements.push(Element {
+                    top: p,
+                    alt: p,
+                    bottoms,
+                    is_commit: true,
+                });
+                i = end;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a `--graph` log from (prefix, sha, subject) rows; an empty sha
+    /// marks a graph-only row.
+    fn graph_log(rows: &[(&str, &str, &str)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (prefix, sha, subject) in rows {
+            out.extend_from_slice(prefix.as_bytes());
+            if !sha.is_empty() {
+                out.extend_from_slice(format!("{}\x00{}\x1e", sha, subject).as_bytes());
+            }
+            out.push(b'\n');
+        }
+        out
+    }
+
+    fn parents_of<'a>(commits: &'a [CommitNode], sha: &str) -> Vec<&'a str> {
+        commits
+            .iter()
+            .find(|c| c.sha == sha)
+            .unwrap()
+            .parents
+            .iter()
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn sha_subject_format() -> LogFormat {
+        LogFormat::from_placeholders(&["%H", "%s"]).unwrap()
+    }
+
+    #[test]
+    fn test_merges_and_branch_points() {
+        // Recorded from `git log --graph` on a repo with three merged branches
+        let raw = graph_log(&[
+            ("* ", "m2", "m2"),
+            ("*   ", "mb3", "Merge b3"),
+            ("|\\  ", "", ""),
+            ("| * ", "b3a", "b3a"),
+            ("* |   ", "mb2", "Merge b2"),
+            ("|\\ \\  ", "", ""),
+            ("| * | ", "b2a", "b2a"),
+            ("| |/  ", "", ""),
+            ("* |   ", "mb1", "Merge b1"),
+            ("|\\ \\  ", "", ""),
+            ("| * | ", "b1a", "b1a"),
+            ("| |/  ", "", ""),
+            ("* / ", "m1", "m1"),
+            ("|/  ", "", ""),
+            ("* ", "base", "base"),
+        ]);
+        let commits = parse_graph_log(&raw, &sha_subject_format());
+        assert_eq!(commits.len(), 9);
+
+        assert_eq!(parents_of(&commits, "m2"), vec!["mb3"]);
+        assert_eq!(parents_of(&commits, "mb3"), vec!["mb2", "b3a"]);
+        assert_eq!(parents_of(&commits, "mb2"), vec!["mb1", "b2a"]);
+        assert_eq!(parents_of(&commits, "mb1"), vec!["m1", "b1a"]);
+        for sha in ["b3a", "b2a", "b1a", "m1"] {
+            assert_eq!(parents_of(&commits, sha), vec!["base"], "parents of {}", sha);
+        }
+        assert!(parents_of(&commits, "base").is_empty());
+        assert_eq!(commits[0].subject, "m2");
+    }
+
+    #[test]
+    fn test_octopus_and_underscore_crossing() {
+        // `git log --graph --date-order` output with a 4-parent octopus and a
+        // line bridging two columns with `_`
+        let raw = graph_log(&[
+            ("* ", "m3", "m3"),
+            ("*   ", "mp", "Merge p"),
+            ("|\\  ", "", ""),
+            ("* | ", "m2", "m2"),
+            ("| * ", "p2", "p2"),
+            ("| |       ", "", ""),
+            ("|  \\      ", "", ""),
+            ("|   \\     ", "", ""),
+            ("|    \\    ", "", ""),
+            ("*---. \\   ", "oct", "Merge q, r and s"),
+            ("|\\ \\ \\ \\  ", "", ""),
+            ("| | | | * ", "p1", "p1"),
+            ("* | | | | ", "m1", "m1"),
+            ("| |_|_|/  ", "", ""),
+            ("|/| | |   ", "", ""),
+            ("| * | | ", "q1", "q1"),
+            ("|/ / /  ", "", ""),
+            ("| * | ", "r2", "r2"),
+            ("| | * ", "s1", "s1"),
+            ("| |/  ", "", ""),
+            ("|/|   ", "", ""),
+            ("| * ", "r1", "r1"),
+            ("|/  ", "", ""),
+            ("* ", "base", "base"),
+        ]);
+        let commits = parse_graph_log(&raw, &sha_subject_format());
+        assert_eq!(commits.len(), 12);
+
+        assert_eq!(parents_of(&commits, "mp"), vec!["m2", "p2"]);
+        assert_eq!(parents_of(&commits, "m2"), vec!["oct"]);
+        assert_eq!(parents_of(&commits, "p2"), vec!["p1"]);
+        assert_eq!(parents_of(&commits, "oct"), vec!["m1", "q1", "r2", "s1"]);
+        assert_eq!(parents_of(&commits, "r2"), vec!["r1"]);
+        for sha in ["p1", "m1", "q1", "s1", "r1"] {
+            assert_eq!(parents_of(&commits, sha), vec!["base"], "parents of {}", sha);
+        }
+
+        let base = commits.iter().find(|c| c.sha == "base").unwrap();
+        assert_eq!(base.children.len(), 5);
+    }
+
+    #[test]
+    fn test_explicit_parents_and_multiline_bodies_are_kept() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%s", "%b"]).unwrap();
+        // With NUL-separated formats git leaves body lines unprefixed
+        let raw = b"* aaa\x00bbb\x00Tip\x00First line\n* bullet\n\x1e\n* bbb\x00\x00Root\x00\x1e\n";
+        let commits = parse_graph_log(raw, &format);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].parents, vec!["bbb"]);
+        assert_eq!(commits[0].body, "First line\n* bullet");
+        assert_eq!(commits[1].children, vec!["aaa"]);
+    }
+
+    #[test]
+    fn test_left_right_markers_set_side() {
+        let raw = graph_log(&[
+            ("> ", "aaa", "Right"),
+            ("| < ", "bbb", "Left"),
+            ("|/  ", "", ""),
+            ("o ", "ccc", "Boundary"),
+        ]);
+        let commits = parse_graph_log(&raw, &sha_subject_format());
+        assert_eq!(commits[0].side, Some(Side::Right));
+        assert_eq!(commits[1].side, Some(Side::Left));
+        assert_eq!(commits[2].side, Some(Side::Boundary));
+        assert_eq!(parents_of(&commits, "bbb"), vec!["ccc"]);
+    }
+
+    #[test]
+    fn test_split_prefix() {
+        assert_eq!(split_prefix(b"| * | abc", true), (6, true));
+        assert_eq!(split_prefix(b"*---. \\   abc", true), (10, true));
+        assert_eq!(split_prefix(b"| |/  ", true), (6, false));
+        assert_eq!(split_prefix(b"| | * item", false), (4, false));
+        assert_eq!(split_prefix(b"-\t-\timage.png", true), (0, false));
+    }
+}
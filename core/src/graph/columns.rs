@@ -0,0 +1,114 @@
+use super::types::LayoutResult;
+
+/// Strings stored back to back as UTF-8, for handing to JS as one buffer.
+///
+/// String `i` is `bytes[offsets[i]..offsets[i + 1]]`, so JS can decode
+/// only the ones it draws.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringTable {
+    pub bytes: Vec<u8>,
+    /// One more than the number of strings, starting at 0.
+    pub offsets: Vec<u32>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        StringTable {
+            bytes: Vec::new(),
+            offsets: vec![0],
+        }
+    }
+}
+
+impl StringTable {
+    pub fn push(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+        self.offsets.push(self.bytes.len() as u32);
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<&str> {
+        let start = *self.offsets.get(i)? as usize;
+        let end = *self.offsets.get(i + 1)? as usize;
+        std::str::from_utf8(&self.bytes[start..end]).ok()
+    }
+}
+
+/// The drawing data of a layout's nodes as parallel columns, entry `i` of
+/// each describing node `i`, so a canvas or WebGL renderer can read it
+/// straight from WASM memory without parsing JSON.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeColumns {
+    pub rows: Vec<u32>,
+    pub lanes: Vec<u32>,
+    pub colors: Vec<u32>,
+    /// Author dates in epoch seconds, as JS numbers.
+    pub author_dates: Vec<f64>,
+    pub shas: StringTable,
+    pub subjects: StringTable,
+}
+
+impl NodeColumns {
+    pub fn from_layout(layout: &LayoutResult) -> NodeColumns {
+        let mut columns = NodeColumns::default();
+        for node in &layout.nodes {
+            columns.rows.push(node.row.max(0) as u32);
+            columns.lanes.push(node.lane.max(0) as u32);
+            columns.colors.push(node.color_index);
+            columns.author_dates.push(node.author_date as f64);
+            columns.shas.push(&node.sha);
+            columns.subjects.push(&node.subject);
+        }
+        columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::graph::types::CommitNode;
+
+    fn commit(sha: &str, parents: &[&str], subject: &str) -> CommitNode {
+        CommitNode {
+            sha: sha.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            subject: subject.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_node_columns() {
+        let layout = compute_layout(&[
+            commit("m", &["a", "b"], "Merge"),
+            commit("b", &["a"], "Côté"),
+            commit("a", &[], ""),
+        ]);
+        let columns = NodeColumns::from_layout(&layout);
+
+        assert_eq!(columns.len(), 3);
+        assert_eq!(columns.rows, vec![0, 1, 2]);
+        assert_eq!(columns.lanes, layout.nodes.iter().map(|n| n.lane as u32).collect::<Vec<_>>());
+        assert_eq!(columns.shas.get(1), Some("b"));
+        assert_eq!(columns.subjects.get(1), Some("Côté"));
+        assert_eq!(columns.subjects.get(2), Some(""));
+        assert_eq!(columns.subjects.get(3), None);
+        assert_eq!(columns.subjects.offsets, vec![0, 5, 11, 11]);
+    }
+}
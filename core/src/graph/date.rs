@@ -0,0 +1,260 @@
+/// Parse an ISO-8601 timestamp as produced by `%aI`/`%cI` into
+/// `(unix epoch seconds, UTC offset in minutes)`.
+///
+/// Accepts the strict form `2023-11-14T22:13:20+01:00` (or `Z`) as well as
+/// the looser `%ai` form `2023-11-14 22:13:20 +0100`. Fractional seconds are
+/// ignored. Dates before 1970 clamp to 0.
+pub fn parse_iso8601(s: &str) -> Option<(u64, i32)> {
+    let s = s.trim();
+    if s.len() < 19 || !s.is_char_boundary(19) {
+        return None;
+    }
+    let (datetime, zone) = s.split_at(19);
+
+    let b = datetime.as_bytes();
+    let separators_ok = b[4] == b'-'
+        && b[7] == b'-'
+        && (b[10] == b'T' || b[10] == b' ')
+        && b[13] == b':'
+        && b[16] == b':';
+    if !separators_ok {
+        return None;
+    }
+
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let part = &datetime[range];
+        if part.bytes().all(|c| c.is_ascii_digit()) {
+            part.parse().ok()
+        } else {
+            None
+        }
+    };
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // Skip fractional seconds
+    let zone = zone.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset_min = parse_zone(zone.trim())?;
+
+    let epoch = to_epoch(year, month, day, (hour, minute, second), offset_min);
+    Some((epoch, offset_min))
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Parse git's default date format (`Tue Nov 14 23:13:20 2023 +0100`) or
+/// RFC 2822 (`Tue, 14 Nov 2023 23:13:20 +0100`) into
+/// `(unix epoch seconds, UTC offset in minutes)`.
+///
+/// The weekday is ignored. Formats without a zone, like `--date=local`,
+/// are rejected since the instant would be ambiguous.
+pub fn parse_human_date(s: &str) -> Option<(u64, i32)> {
+    let (mut year, mut month, mut day, mut time, mut zone) = (None, None, None, None, None);
+
+    for token in s.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()) {
+        let lower = token.to_ascii_lowercase();
+        if let Some(m) = MONTHS.iter().position(|m| lower.starts_with(m)) {
+            month = Some(m as i64 + 1);
+        } else if token.starts_with(['+', '-']) {
+            zone = Some(parse_zone(token)?);
+        } else if token.contains(':') {
+            let mut parts = token.split(':').map(|p| p.parse::<i64>().ok());
+            let (h, m, s) = (parts.next()??, parts.next()??, parts.next().unwrap_or(Some(0))?);
+            time = Some((h, m, s));
+        } else if token.bytes().all(|b| b.is_ascii_digit()) {
+            match token.len() {
+                1 | 2 => day = Some(token.parse().ok()?),
+                4 => year = Some(token.parse().ok()?),
+                _ => return None,
+            }
+        }
+    }
+
+    let (year, month, day, time, zone) = (year?, month?, day?, time?, zone?);
+    let (hour, minute, second) = time;
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some((to_epoch(year, month, day, time, zone), zone))
+}
+
+/// Seconds since the epoch for a local date and time at `offset_min` from
+/// UTC, clamped to 0.
+fn to_epoch(year: i64, month: i64, day: i64, time: (i64, i64, i64), offset_min: i32) -> u64 {
+    let (hour, minute, second) = time;
+    let local = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    let epoch = local - offset_min as i64 * 60;
+    epoch.max(0) as u64
+}
+
+/// Parse `Z`, `+hh:mm`, `+hhmm` or `+hh` into signed minutes.
+pub(crate) fn parse_zone(zone: &str) -> Option<i32> {
+    if zone.is_empty() || zone == "Z" || zone == "z" {
+        return Some(0);
+    }
+    let sign = match zone.as_bytes()[0] {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits: String = zone[1..].chars().filter(|&c| c != ':').collect();
+    if !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (digits[..2].parse::<i32>().ok()?, digits[2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian (year, month, day) for days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = (if z >= 0 { z } else { z - 146_096 }) / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Start of the day containing `time`, in the given UTC offset.
+pub(crate) fn start_of_day(time: u64, offset_min: i32) -> u64 {
+    let local = time as i64 + offset_min as i64 * 60;
+    (local - local.rem_euclid(86_400) - offset_min as i64 * 60).max(0) as u64
+}
+
+/// `time` moved back by calendar months in the given UTC offset, keeping
+/// the time of day. Days past the end of the target month clamp to its
+/// last day, so March 31 minus one month is the end of February.
+pub(crate) fn months_before(time: u64, months: u64, offset_min: i32) -> u64 {
+    let local = time as i64 + offset_min as i64 * 60;
+    let seconds = local.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+
+    let index = year * 12 + (month - 1) - months.min(i64::MAX as u64 / 2) as i64;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) + 1);
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    let month_len = days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1);
+
+    let days = days_from_civil(year, month, day.min(month_len));
+    (days * 86_400 + seconds - offset_min as i64 * 60).max(0) as u64
+}
+
+/// `YYYY-MM-DD` for an epoch time in the given UTC offset.
+pub(crate) fn format_date(time: u64, offset_min: i32) -> String {
+    let local = time as i64 + offset_min as i64 * 60;
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// How long before `now` `time` was, in words: `just now`, `5 minutes ago`,
+/// `1 day ago`, ..., `3 years ago`. Times after `now` are `just now`.
+pub(crate) fn relative_date(time: u64, now: u64) -> String {
+    const UNITS: [(u64, &str); 6] = [
+        (365 * 86_400, "year"),
+        (30 * 86_400, "month"),
+        (7 * 86_400, "week"),
+        (86_400, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+    let elapsed = now.saturating_sub(time);
+    for (seconds, unit) in UNITS {
+        let count = elapsed / seconds;
+        if count >= 1 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{} {}{} ago", count, unit, plural);
+        }
+    }
+    "just now".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_strict_iso() {
+        assert_eq!(parse_iso8601("2023-11-14T22:13:20Z"), Some((1700000000, 0)));
+        assert_eq!(parse_iso8601("2023-11-14T23:43:20+01:30"), Some((1700000000, 90)));
+        assert_eq!(parse_iso8601("2023-11-14T14:13:20-08:00"), Some((1700000000, -480)));
+        assert_eq!(parse_iso8601("1970-01-01T00:00:00+00:00"), Some((0, 0)));
+        assert_eq!(parse_iso8601("2024-02-29T12:00:00.250Z"), Some((1709208000, 0)));
+    }
+
+    #[test]
+    fn test_parse_loose_iso() {
+        assert_eq!(parse_iso8601("2023-11-14 23:13:20 +0100"), Some((1700000000, 60)));
+    }
+
+    #[test]
+    fn test_calendar_arithmetic() {
+        // 2024-03-31T12:00:00Z
+        let time = 1711886400;
+        assert_eq!(format_date(months_before(time, 1, 0), 0), "2024-02-29");
+        assert_eq!(format_date(months_before(time, 13, 0), 0), "2023-02-28");
+        assert_eq!(months_before(time, 12, 0) % 86_400, 12 * 3600);
+        assert_eq!(months_before(time, 100_000, 0), 0);
+
+        assert_eq!(start_of_day(time, 0), 1711843200);
+        // 01:30 at +02:00 is still the previous UTC day
+        assert_eq!(format_date(start_of_day(1711846800 + 1800, 120), 120), "2024-03-31");
+        assert_eq!(start_of_day(1711846800 + 1800, 120), 1711843200 - 7200);
+    }
+
+    #[test]
+    fn test_parse_human_dates() {
+        assert_eq!(parse_human_date("Tue Nov 14 23:13:20 2023 +0100"), Some((1700000000, 60)));
+        assert_eq!(parse_human_date("Tue, 14 Nov 2023 14:13:20 -0800"), Some((1700000000, -480)));
+        assert_eq!(parse_human_date("Tue Nov 14 22:13:20 2023"), None);
+        assert_eq!(parse_human_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_format_dates() {
+        assert_eq!(format_date(1700000000, 0), "2023-11-14");
+        assert_eq!(format_date(1700000000, 120), "2023-11-15");
+        assert_eq!(format_date(0, -60), "1969-12-31");
+        assert_eq!(format_date(1709208000, 0), "2024-02-29");
+
+        assert_eq!(relative_date(100, 130), "just now");
+        assert_eq!(relative_date(0, 60), "1 minute ago");
+        assert_eq!(relative_date(0, 3 * 86_400), "3 days ago");
+        assert_eq!(relative_date(0, 800 * 86_400), "2 years ago");
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert_eq!(parse_iso8601("1700000000"), None);
+        assert_eq!(parse_iso8601("2023-13-01T00:00:00Z"), None);
+        assert_eq!(parse_iso8601("2023-11-14T22:13:20 CET"), None);
+        assert_eq!(parse_iso8601(""), None);
+    }
+}
@@ -0,0 +1,123 @@
+use std::borrow::Cow;
+
+use super::format::{LogField, LogFormat};
+
+/// Windows-1252 characters for bytes 0x80..=0x9F; `None` marks the five
+/// unassigned bytes, which fall back to the matching C1 control.
+const CP1252_HIGH: [Option<char>; 32] = [
+    Some('€'), None, Some('‚'), Some('ƒ'), Some('„'), Some('…'), Some('†'), Some('‡'),
+    Some('ˆ'), Some('‰'), Some('Š'), Some('‹'), Some('Œ'), None, Some('Ž'), None,
+    None, Some('‘'), Some('’'), Some('“'), Some('”'), Some('•'), Some('–'), Some('—'),
+    Some('˜'), Some('™'), Some('š'), Some('›'), Some('œ'), None, Some('ž'), Some('Ÿ'),
+];
+
+/// Legacy single-byte encodings that can be transcoded to UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Legacy {
+    Latin1,
+    Latin9,
+    Windows1252,
+}
+
+impl Legacy {
+    /// Match an `encoding` header value such as `ISO-8859-1` or `cp1252`.
+    /// Unknown labels are treated as Latin-1, the most common legacy case.
+    fn from_label(label: &str) -> Legacy {
+        let label = label.trim().to_ascii_lowercase().replace(['-', '_'], "");
+        match label.as_str() {
+            "iso885915" | "latin9" => Legacy::Latin9,
+            "windows1252" | "cp1252" => Legacy::Windows1252,
+            _ => Legacy::Latin1,
+        }
+    }
+
+    fn decode_byte(self, b: u8) -> char {
+        match (self, b) {
+            (Legacy::Windows1252, 0x80..=0x9f) => {
+                CP1252_HIGH[(b - 0x80) as usize].unwrap_or(b as char)
+            }
+            (Legacy::Latin9, 0xa4) => '€',
+            (Legacy::Latin9, 0xa6) => 'Š',
+            (Legacy::Latin9, 0xa8) => 'š',
+            (Legacy::Latin9, 0xb4) => 'Ž',
+            (Legacy::Latin9, 0xb8) => 'ž',
+            (Legacy::Latin9, 0xbc) => 'Œ',
+            (Legacy::Latin9, 0xbd) => 'œ',
+            (Legacy::Latin9, 0xbe) => 'Ÿ',
+            // Latin-1 maps each byte to the code point of the same value
+            _ => b as char,
+        }
+    }
+}
+
+/// Decode one record (and any file lines before it) to text.
+///
+/// git re-encodes commits that declare an `encoding` header, but commits
+/// written in a legacy encoding without one are emitted as raw bytes. Valid
+/// UTF-8 is used as is; anything else is transcoded using the record's `%e`
+/// field when the format has one, or Latin-1 otherwise.
+pub(crate) fn decode_record<'a>(bytes: &'a [u8], format: &LogFormat) -> Cow<'a, str> {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+
+    let label = format
+        .fields
+        .iter()
+        .position(|f| *f == LogField::Encoding)
+        .and_then(|i| bytes.split(|&b| b == 0).nth(i))
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    decode_with_label(bytes, &label)
+}
+
+/// Map an offset in `decode_record(bytes, ..)` back to the offset of the
+/// same character in `bytes`. Transcoded text has one character per byte.
+pub(crate) fn source_offset(bytes: &[u8], decoded: &str, offset: usize) -> usize {
+    if std::str::from_utf8(bytes).is_ok() {
+        offset
+    } else {
+        decoded[..offset].chars().count()
+    }
+}
+
+/// Decode bytes to text, transcoding from the encoding named by `label`
+/// (as in a commit's `encoding` header) unless they are valid UTF-8.
+pub(crate) fn decode_with_label<'a>(bytes: &'a [u8], label: &str) -> Cow<'a, str> {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+    let encoding = Legacy::from_label(label);
+    Cow::Owned(bytes.iter().map(|&b| encoding.decode_byte(b)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_is_borrowed() {
+        let format = LogFormat::default();
+        let decoded = decode_record("René".as_bytes(), &format);
+        assert!(matches!(decoded, Cow::Borrowed("René")));
+    }
+
+    #[test]
+    fn test_latin1_fallback() {
+        let format = LogFormat::default();
+        assert_eq!(decode_record(b"Ren\xe9 M\xfcller", &format), "René Müller");
+    }
+
+    #[test]
+    fn test_encoding_field_selects_codepage() {
+        let format = LogFormat::from_placeholders(&["%H", "%e", "%s"]).unwrap();
+        assert_eq!(
+            decode_record(b"aaa\x00windows-1252\x00\x93Quoted\x94 \x80", &format),
+            "aaa\x00windows-1252\x00\u{201c}Quoted\u{201d} €"
+        );
+        assert_eq!(
+            decode_record(b"aaa\x00ISO-8859-15\x00\xa4 \xbd", &format),
+            "aaa\x00ISO-8859-15\x00€ œ"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use crate::error::{ErrorCode, ErrorInfo};
+
+/// A single placeholder in a `git log --format` string.
+///
+/// Each record is a NUL-separated list of fields; a `LogFormat` says which
+/// placeholder produced each field so the parser can map it into `CommitNode`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogField {
+    /// `%H` - full commit hash
+    Hash,
+    /// `%h` - abbreviated commit hash
+    ShortHash,
+    /// `%P` (or abbreviated `%p`) - parent hashes (space-separated)
+    Parents,
+    /// `%an` - author name
+    AuthorName,
+    /// `%ae` - author email
+    AuthorEmail,
+    /// `%at` - author date (unix epoch)
+    AuthorDate,
+    /// `%aI` or `%ai` - author date (ISO-8601), keeping the timezone offset
+    AuthorDateIso,
+    /// `%ad` - author date as formatted by `--date`; `raw`, `iso`,
+    /// `iso-strict`, `rfc` and the default format are understood, and a bare
+    /// offset (`--date=format:%z`) only sets the timezone
+    AuthorDateFormatted,
+    /// `%cn` - committer name
+    CommitterName,
+    /// `%ce` - committer email
+    CommitterEmail,
+    /// `%ct` - committer date (unix epoch)
+    CommitDate,
+    /// `%cI` or `%ci` - committer date (ISO-8601), keeping the timezone offset
+    CommitDateIso,
+    /// `%cd` - committer date as formatted by `--date`, like `%ad`
+    CommitDateFormatted,
+    /// `%s` - subject
+    Subject,
+    /// `%b` - body; may span multiple lines but must not contain NUL
+    Body,
+    /// `%d` - ref decoration
+    Decorate,
+    /// `%G?` - signature verification code
+    SignatureStatus,
+    /// `%GS` - signer name
+    Signer,
+    /// `%GK` - signing key
+    SigningKey,
+    /// `%N` - commit notes; may span multiple lines
+    Notes,
+    /// `%gd` - reflog selector, e.g. `HEAD@{3}` (only with `git log -g`)
+    ReflogSelector,
+    /// `%gs` - reflog subject, e.g. `checkout: moving from main to dev`
+    ReflogSubject,
+    /// `%e` - encoding declared by the commit, empty for UTF-8
+    Encoding,
+    /// `%(describe)` or `%(describe:<options>)` - nearest tag, e.g.
+    /// `v2.3.1-14-gabc1234`
+    Describe,
+    /// `%S` - ref the commit was reached from (only with `git log --source`)
+    Source,
+    /// `%m` - left/right/boundary mark (`<`, `>`, `-`) with `--left-right`
+    Mark,
+    /// Any other placeholder. The field is consumed but not interpreted.
+    Other(String),
+}
+
+impl LogField {
+    /// Parse a placeholder such as `%H` or `%an`.
+    pub fn from_placeholder(placeholder: &str) -> Result<LogField, ErrorInfo> {
+        let field = match placeholder {
+            "%H" => LogField::Hash,
+            "%h" => LogField::ShortHash,
+            "%P" | "%p" => LogField::Parents,
+            "%an" => LogField::AuthorName,
+            "%ae" => LogField::AuthorEmail,
+            "%at" => LogField::AuthorDate,
+            "%aI" | "%ai" => LogField::AuthorDateIso,
+            "%ad" => LogField::AuthorDateFormatted,
+            "%cn" => LogField::CommitterName,
+            "%ce" => LogField::CommitterEmail,
+            "%ct" => LogField::CommitDate,
+            "%cI" | "%ci" => LogField::CommitDateIso,
+            "%cd" => LogField::CommitDateFormatted,
+            "%s" => LogField::Subject,
+            "%b" => LogField::Body,
+            "%d" => LogField::Decorate,
+            "%G?" => LogField::SignatureStatus,
+            "%GS" => LogField::Signer,
+            "%GK" => LogField::SigningKey,
+            "%N" => LogField::Notes,
+            "%gd" => LogField::ReflogSelector,
+            "%gs" => LogField::ReflogSubject,
+            "%e" => LogField::Encoding,
+            "%S" => LogField::Source,
+            "%m" => LogField::Mark,
+            p if p == "%(describe)" || (p.starts_with("%(describe:") && p.ends_with(')')) => {
+                LogField::Describe
+            }
+            other if other.len() > 1 && other.starts_with('%') => {
+                LogField::Other(other.to_string())
+            }
+            other => {
+                let message = format!("Invalid log format placeholder: {:?}", other);
+                return Err(ErrorInfo::with_code(ErrorCode::BadArgument, message));
+            }
+        };
+        Ok(field)
+    }
+
+    /// Whether a record may omit this field when it is the last one.
+    ///
+    /// git drops trailing empty fields in some shells, and `%d` and `%N` are
+    /// empty for most commits.
+    fn is_optional(&self) -> bool {
+        matches!(
+            self,
+            LogField::Decorate | LogField::Body | LogField::Notes | LogField::Other(_)
+        )
+    }
+}
+
+/// Ordered description of the fields in each git log record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFormat {
+    pub fields: Vec<LogField>,
+}
+
+impl Default for LogFormat {
+    /// The format produced by
+    /// `%H%x00%h%x00%P%x00%an%x00%ae%x00%at%x00%cn%x00%ce%x00%ct%x00%s%x00%d%x1e`.
+    fn default() -> Self {
+        LogFormat {
+            fields: vec![
+                LogField::Hash,
+                LogField::ShortHash,
+                LogField::Parents,
+                LogField::AuthorName,
+                LogField::AuthorEmail,
+                LogField::AuthorDate,
+                LogField::CommitterName,
+                LogField::CommitterEmail,
+                LogField::CommitDate,
+                LogField::Subject,
+                LogField::Decorate,
+            ],
+        }
+    }
+}
+
+impl LogFormat {
+    /// Build a format from an ordered list of placeholders, e.g.
+    /// `["%H", "%P", "%an", "%at", "%s"]`.
+    ///
+    /// The format must include `%H`, since commits are keyed by full hash.
+    pub fn from_placeholders<S: AsRef<str>>(placeholders: &[S]) -> Result<LogFormat, ErrorInfo> {
+        let fields = placeholders
+            .iter()
+            .map(|p| LogField::from_placeholder(p.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !fields.contains(&LogField::Hash) {
+            let message = "Log format must include %H";
+            return Err(ErrorInfo::with_code(ErrorCode::BadArgument, message));
+        }
+
+        Ok(LogFormat { fields })
+    }
+
+    /// Minimum number of fields a record needs to be parsed.
+    ///
+    /// Trailing optional fields may be missing; everything before them is
+    /// required.
+    pub fn required_fields(&self) -> usize {
+        let optional_tail = self
+            .fields
+            .iter()
+            .rev()
+            .take_while(|f| f.is_optional())
+            .count();
+        self.fields.len() - optional_tail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_format_requires_ten_fields() {
+        let format = LogFormat::default();
+        assert_eq!(format.fields.len(), 11);
+        assert_eq!(format.required_fields(), 10);
+    }
+
+    #[test]
+    fn test_from_placeholders() {
+        let format = LogFormat::from_placeholders(&["%H", "%s", "%N", "%cr"]).unwrap();
+        assert_eq!(
+            format.fields,
+            vec![
+                LogField::Hash,
+                LogField::Subject,
+                LogField::Notes,
+                LogField::Other("%cr".to_string())
+            ]
+        );
+        assert_eq!(format.required_fields(), 2);
+    }
+
+    #[test]
+    fn test_from_placeholders_rejects_bad_input() {
+        assert!(LogFormat::from_placeholders(&["%h", "%s"]).is_err());
+        assert!(LogFormat::from_placeholders(&["%H", "subject"]).is_err());
+        assert!(LogFormat::from_placeholders(&["%H", "%"]).is_err());
+    }
+
+    #[test]
+    fn test_describe_placeholders() {
+        let format =
+            LogFormat::from_placeholders(&["%H", "%(describe)", "%(describe:tags=true)"]).unwrap();
+        assert_eq!(format.fields[1], LogField::Describe);
+        assert_eq!(format.fields[2], LogField::Describe);
+    }
+}
@@ -1,14 +1,18 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 
 use super::types::*;
 
+/// Colors in the palette when `LayoutOptions::palette_size` is unset.
+const DEFAULT_PALETTE_SIZE: u32 = 12;
+
 /// Simple hash function for branch names to produce a color index.
-fn hash_branch_name(name: &str) -> u32 {
+fn hash_branch_name(name: &str, palette: u32) -> u32 {
     let mut hash: u32 = 5381;
     for byte in name.bytes() {
         hash = hash.wrapping_mul(33).wrapping_add(byte as u32);
     }
-    hash % 12
+    hash % palette
 }
 
 /// Determine the NodeType for a commit based on its refs.
@@ -26,17 +30,19 @@ fn determine_node_type(node: &CommitNode) -> NodeType {
 
 /// Determine the color index for a commit.
 ///
-/// If the commit has a branch ref, use hash(branch_name) % 12.
-/// Otherwise, inherit the color from the first parent's lane.
+/// If the commit has a branch ref, use hash(branch_name) % palette.
+/// Otherwise, inherit the color from the first parent's lane, or failing
+/// that hash the `%S` source ref so it matches that branch's color.
 fn determine_color_index(
     node: &CommitNode,
     lane_colors: &HashMap<i32, u32>,
     parent_lane: Option<i32>,
+    palette: u32,
 ) -> u32 {
     // Check for a branch ref on this commit
     for r in &node.refs {
         if r.ref_type == RefType::Branch || r.ref_type == RefType::RemoteBranch {
-            return hash_branch_name(&r.name);
+            return hash_branch_name(&r.name, palette);
         }
     }
 
@@ -47,20 +53,240 @@ fn determine_color_index(
         }
     }
 
+    // Commits listed via `--source` carry the branch they were reached from
+    if !node.source_ref.is_empty() {
+        let branch = node
+            .source_ref
+            .strip_prefix("refs/heads/")
+            .or_else(|| node.source_ref.strip_prefix("refs/remotes/"))
+            .unwrap_or(&node.source_ref);
+        return hash_branch_name(branch, palette);
+    }
+
     // Fallback: hash the sha
-    hash_branch_name(&node.sha)
+    hash_branch_name(&node.sha, palette)
+}
+
+/// A run of commits that share one lane, from the row where the lane is
+/// first reserved to the row where it is released.
+struct LaneChain<'a> {
+    members: Vec<&'a str>,
+    start_row: usize,
+    /// Last row the lane is held; `usize::MAX` when the chain continues past
+    /// the loaded window.
+    end_row: usize,
+    /// Number of loaded commits drawn on this lane.
+    weight: usize,
+}
+
+/// Pre-assign a lane to every commit and every referenced parent.
+///
+/// A parent is claimed by the first commit (in row order) that lists it.
+/// If that is a first-parent claim the parent continues the child's lane;
+/// otherwise a merge opens a new lane for it. Following these claims splits
+/// the window into lane chains. Chains are then placed heaviest first, each
+/// into the lowest lane that is free over its whole row span, so branches
+/// with many upcoming commits stay in inner lanes and short-lived topic
+/// branches are pushed outwards.
+fn assign_lanes(commits: &[CommitNode]) -> HashMap<&str, i32> {
+    let mut sha_to_row: HashMap<&str, usize> = HashMap::with_capacity(commits.len());
+    for (row, commit) in commits.iter().enumerate() {
+        sha_to_row.entry(commit.sha.as_str()).or_insert(row);
+    }
+
+    // parent SHA -> (claiming row, claimed as first parent)
+    let mut claimed_by: HashMap<&str, (usize, bool)> = HashMap::new();
+    for (row, commit) in commits.iter().enumerate() {
+        for (i, parent) in commit.parents.iter().enumerate() {
+            claimed_by.entry(parent.as_str()).or_insert((row, i == 0));
+        }
+    }
+
+    let mut chain_of: HashMap<&str, usize> = HashMap::new();
+    let mut chains: Vec<LaneChain> = Vec::new();
+
+    for (row, commit) in commits.iter().enumerate() {
+        if chain_of.contains_key(commit.sha.as_str()) {
+            continue;
+        }
+
+        // A merge parent's lane is reserved from the merge row onwards
+        let start_row = match claimed_by.get(commit.sha.as_str()) {
+            Some(&(claim_row, false)) => claim_row.min(row),
+            _ => row,
+        };
+
+        let id = chains.len();
+        let mut chain = LaneChain {
+            members: Vec::new(),
+            start_row,
+            end_row: row,
+            weight: 0,
+        };
+
+        let mut current = commit;
+        let mut current_row = row;
+        loop {
+            chain_of.insert(current.sha.as_str(), id);
+            chain.members.push(current.sha.as_str());
+            chain.weight += 1;
+            chain.end_row = chain.end_row.max(current_row);
+
+            let first_parent = match current.parents.first() {
+                Some(p) => p.as_str(),
+                None => break,
+            };
+            if claimed_by.get(first_parent) != Some(&(current_row, true))
+                || chain_of.contains_key(first_parent)
+            {
+                break;
+            }
+            match sha_to_row.get(first_parent) {
+                Some(&parent_row) => {
+                    current = &commits[parent_row];
+                    current_row = parent_row;
+                }
+                None => {
+                    // Parent is outside the window; the lane stays open
+                    chain_of.insert(first_parent, id);
+                    chain.members.push(first_parent);
+                    chain.end_row = usize::MAX;
+                    break;
+                }
+            }
+        }
+
+        chains.push(chain);
+    }
+
+    // Merge parents outside the window hold their lane to the end
+    for (row, commit) in commits.iter().enumerate() {
+        for parent in commit.parents.iter().skip(1) {
+            let parent = parent.as_str();
+            if chain_of.contains_key(parent) || claimed_by.get(parent) != Some(&(row, false)) {
+                continue;
+            }
+            chain_of.insert(parent, chains.len());
+            chains.push(LaneChain {
+                members: vec![parent],
+                start_row: row,
+                end_row: usize::MAX,
+                weight: 0,
+            });
+        }
+    }
+
+    let mut order: Vec<usize> = (0..chains.len()).collect();
+    order.sort_by(|&a, &b| {
+        chains[b]
+            .weight
+            .cmp(&chains[a].weight)
+            .then(chains[a].start_row.cmp(&chains[b].start_row))
+    });
+
+    // Occupied row spans per lane: start -> end, non-overlapping
+    let mut occupied: Vec<BTreeMap<usize, usize>> = Vec::new();
+    let mut sha_lane: HashMap<&str, i32> = HashMap::with_capacity(chain_of.len());
+
+    for id in order {
+        let chain = &chains[id];
+        let is_free = |spans: &BTreeMap<usize, usize>| {
+            match spans.range(..=chain.end_row).next_back() {
+                Some((_, &end)) => end < chain.start_row,
+                None => true,
+            }
+        };
+        let lane = match occupied.iter().position(is_free) {
+            Some(lane) => lane,
+            None => {
+                occupied.push(BTreeMap::new());
+                occupied.len() - 1
+            }
+        };
+        occupied[lane].insert(chain.start_row, chain.end_row);
+        for &sha in &chain.members {
+            sha_lane.insert(sha, lane as i32);
+        }
+    }
+
+    sha_lane
+}
+
+/// Reorder commits newest commit date first, holding each parent back until
+/// all of its children are placed, as `git log --date-order` does. Ties keep
+/// the given order; commits without a commit date use the author date.
+fn date_order(commits: &[CommitNode]) -> Vec<CommitNode> {
+    let mut index: HashMap<&str, usize> = HashMap::with_capacity(commits.len());
+    for (i, commit) in commits.iter().enumerate() {
+        index.entry(commit.sha.as_str()).or_insert(i);
+    }
+    let mut pending_children = vec![0usize; commits.len()];
+    for commit in commits {
+        for parent in &commit.parents {
+            if let Some(&p) = index.get(parent.as_str()) {
+                pending_children[p] += 1;
+            }
+        }
+    }
+
+    let date = |i: usize| {
+        let c = &commits[i];
+        if c.committer.time > 0 {
+            c.committer.time
+        } else {
+            c.author.time
+        }
+    };
+    let mut ready: BinaryHeap<(u64, Reverse<usize>)> = (0..commits.len())
+        .filter(|&i| pending_children[i] == 0)
+        .map(|i| (date(i), Reverse(i)))
+        .collect();
+
+    let mut ordered = Vec::with_capacity(commits.len());
+    while let Some((_, Reverse(i))) = ready.pop() {
+        for parent in &commits[i].parents {
+            if let Some(&p) = index.get(parent.as_str()) {
+                pending_children[p] -= 1;
+                if pending_children[p] == 0 {
+                    ready.push((date(p), Reverse(p)));
+                }
+            }
+        }
+        ordered.push(commits[i].clone());
+    }
+    // Commits in a cycle, which git never produces, keep their place at
+    // the end rather than being lost
+    if ordered.len() < commits.len() {
+        let placed: HashSet<&str> = ordered.iter().map(|c| c.sha.as_str()).collect();
+        let rest: Vec<CommitNode> = commits
+            .iter()
+            .filter(|c| !placed.contains(c.sha.as_str()))
+            .cloned()
+            .collect();
+        ordered.extend(rest);
+    }
+    ordered
 }
 
 /// Compute the DAG layout for a list of commits in topological order.
 ///
 /// The algorithm uses a "straight branches" approach:
-/// 1. Process commits in the order given (topological from git).
-/// 2. For each commit, if it continues an existing lane (a parent occupied that lane),
-///    reuse it. Otherwise, allocate the first available lane.
-/// 3. When a merge happens (commit has multiple parents), free the non-primary
-///    parent lanes after the merge row.
+/// 1. Pre-assign lanes with `assign_lanes`, which looks ahead over the whole
+///    window so long-lived branches get inner lanes.
+/// 2. Process commits in the order given (topological from git); each commit
+///    is drawn on its pre-assigned lane.
+/// 3. A first parent claimed by this commit continues on the same lane; merge
+///    parents claimed here open a new lane colored after the parent.
 /// 4. Generate Edge structs connecting each parent-child pair.
 pub fn compute_layout(commits: &[CommitNode]) -> LayoutResult {
+    compute_layout_with_options(commits, &LayoutOptions::default())
+}
+
+/// Compute the DAG layout, as `compute_layout`, with explicit options.
+pub fn compute_layout_with_options(
+    commits: &[CommitNode],
+    options: &LayoutOptions,
+) -> LayoutResult {
     if commits.is_empty() {
         return LayoutResult {
             nodes: Vec::new(),
@@ -70,13 +296,45 @@ pub fn compute_layout(commits: &[CommitNode]) -> LayoutResult {
     }
 
     let total_count = commits.len();
+    let palette = options.palette_size.unwrap_or(DEFAULT_PALETTE_SIZE).max(1);
+
+    let ordered: Vec<CommitNode>;
+    let commits = match options.order {
+        CommitOrder::Given => commits,
+        CommitOrder::Date => {
+            ordered = date_order(commits);
+            &ordered[..]
+        }
+    };
+
+    // Lay out against swapped or cut parents, but report git's order on the
+    // nodes
+    let original = commits;
+    let rewired: Vec<CommitNode>;
+    let commits = if options.swap_merge_parents || options.first_parent {
+        rewired = commits
+            .iter()
+            .map(|c| {
+                let mut c = c.clone();
+                if options.swap_merge_parents && c.parents.len() > 1 {
+                    c.parents.swap(0, 1);
+                }
+                if options.first_parent {
+                    c.parents.truncate(1);
+                }
+                c
+            })
+            .collect();
+        &rewired[..]
+    } else {
+        commits
+    };
 
-    // Track which lane each SHA currently occupies (SHA -> lane)
-    let mut sha_lane: HashMap<&str, i32> = HashMap::new();
+    let sha_lane = assign_lanes(commits);
+    let lane_for = |sha: &str| sha_lane.get(sha).copied().unwrap_or(0);
 
-    // Track which lanes are currently in use (lane -> bool)
-    // We use a vec to track active lanes; index = lane number
-    let mut active_lanes: Vec<bool> = Vec::new();
+    // Parents already reached by an earlier commit keep their existing lane
+    let mut seen_parents: HashSet<&str> = HashSet::new();
 
     // Track color for each lane
     let mut lane_colors: HashMap<i32, u32> = HashMap::new();
@@ -85,148 +343,94 @@ pub fn compute_layout(commits: &[CommitNode]) -> LayoutResult {
     let mut layout_nodes: Vec<LayoutNode> = Vec::with_capacity(total_count);
     let mut edges: Vec<Edge> = Vec::new();
 
-    /// Find the first available (inactive) lane, or extend the vec.
-    fn allocate_lane(active_lanes: &mut Vec<bool>) -> i32 {
-        for (i, active) in active_lanes.iter().enumerate() {
-            if !active {
-                active_lanes[i] = true;
-                return i as i32;
-            }
-        }
-        // All lanes are active, add a new one
-        active_lanes.push(true);
-        (active_lanes.len() - 1) as i32
-    }
-
-    fn free_lane(active_lanes: &mut Vec<bool>, lane: i32) {
-        if lane >= 0 && (lane as usize) < active_lanes.len() {
-            active_lanes[lane as usize] = false;
-        }
-    }
-
     // Process commits in topological order (row = index in the list)
     for (row, commit) in commits.iter().enumerate() {
         let row_i32 = row as i32;
+        let lane = lane_for(&commit.sha);
 
-        // Determine lane for this commit:
-        // 1. If this commit's first child already assigned us a lane (via parent reservation),
-        //    use it.
-        // 2. Otherwise, allocate a new lane.
-        let lane = if let Some(&reserved_lane) = sha_lane.get(commit.sha.as_str()) {
-            // We already have a lane reserved from a child commit
-            reserved_lane
-        } else {
-            // No reservation; allocate a new lane
-            let new_lane = allocate_lane(&mut active_lanes);
-            sha_lane.insert(&commit.sha, new_lane);
-            new_lane
-        };
-
-        let color_index = determine_color_index(commit, &lane_colors, Some(lane));
+        let color_index = determine_color_index(commit, &lane_colors, Some(lane), palette);
         lane_colors.insert(lane, color_index);
 
         let node_type = determine_node_type(commit);
 
+        // Verified tint only recolors the node itself; the lane keeps its color
+        let node_color_index = match options.verified_color_index {
+            Some(tint) if commit.signature_status == SignatureStatus::Good => tint,
+            _ => color_index,
+        };
+
         layout_nodes.push(LayoutNode {
             sha: commit.sha.clone(),
             short_sha: commit.short_sha.clone(),
             lane,
             row: row_i32,
-            color_index,
+            color_index: node_color_index,
             subject: commit.subject.clone(),
-            author_name: commit.author_name.clone(),
-            author_date: commit.author_date,
+            body: commit.body.clone(),
+            author_name: commit.author.name.clone(),
+            author_email: commit.author.email.clone(),
+            committer_name: commit.committer.name.clone(),
+            committer_email: commit.committer.email.clone(),
+            author_date: commit.author.time,
+            author_tz_offset_min: commit.author.tz_offset_min,
+            committer_tz_offset_min: commit.committer.tz_offset_min,
             refs: commit.refs.clone(),
-            parents: commit.parents.clone(),
+            parents: original[row].parents.clone(),
+            parent_count: original[row].parents.len().min(u8::MAX as usize) as u8,
+            is_merge: original[row].parents.len() > 1,
             node_type,
+            signature_status: commit.signature_status,
+            signer: commit.signer.clone(),
+            signing_key: commit.signing_key.clone(),
+            changes: commit.changes.clone(),
+            files: commit.files.clone(),
+            notes: commit.notes.clone(),
+            has_note: !commit.notes.is_empty(),
+            reflog_selector: commit.reflog_selector.clone(),
+            reflog_subject: commit.reflog_subject.clone(),
+            reflog_index: commit.reflog_index,
+            issue_refs: commit.issue_refs.clone(),
+            nearest_tag: commit.nearest_tag.clone(),
+            distance: commit.distance,
+            source_ref: commit.source_ref.clone(),
+            side: commit.side,
+            is_context: false,
         });
 
-        // Process parents: reserve lanes for them
-        if commit.parents.is_empty() {
-            // Root commit: free the lane after this row (branch ends here going back in time)
-            free_lane(&mut active_lanes, lane);
-            sha_lane.remove(commit.sha.as_str());
-        } else {
-            // First parent continues on the same lane
-            let first_parent = &commit.parents[0];
-
-            // Check if the first parent already has a lane assigned (from another child)
-            if sha_lane.contains_key(first_parent.as_str()) {
-                // First parent already has a lane from another path.
-                // Free our current lane since it merges into the parent's lane.
-                let parent_lane = sha_lane[first_parent.as_str()];
-
-                // Generate edge from this commit to its first parent
-                // (parent's row/lane will be filled in when we reach it)
-                // For now we record what we know
-                edges.push(Edge {
-                    from_sha: commit.sha.clone(),
-                    to_sha: first_parent.clone(),
-                    from_lane: lane,
-                    to_lane: parent_lane,
-                    from_row: row_i32,
-                    to_row: -1, // will be filled in later
-                    edge_type: EdgeType::Normal,
-                    color_index: lane_colors.get(&parent_lane).copied().unwrap_or(color_index),
-                });
-
-                // Free this commit's lane since the parent is already tracked elsewhere
-                free_lane(&mut active_lanes, lane);
-                sha_lane.remove(commit.sha.as_str());
-            } else {
-                // First parent inherits this commit's lane
-                sha_lane.remove(commit.sha.as_str());
-                sha_lane.insert(first_parent, lane);
-
-                edges.push(Edge {
-                    from_sha: commit.sha.clone(),
-                    to_sha: first_parent.clone(),
-                    from_lane: lane,
-                    to_lane: lane,
-                    from_row: row_i32,
-                    to_row: -1,
-                    edge_type: EdgeType::Normal,
-                    color_index,
-                });
-            }
+        for (i, parent) in commit.parents.iter().enumerate() {
+            let parent_lane = lane_for(parent);
+            let first_claim = seen_parents.insert(parent.as_str());
 
-            // Additional parents (merge parents) get new lanes
-            for merge_parent in commit.parents.iter().skip(1) {
-                if sha_lane.contains_key(merge_parent.as_str()) {
-                    // Parent already has a lane from another path
-                    let parent_lane = sha_lane[merge_parent.as_str()];
-                    edges.push(Edge {
-                        from_sha: commit.sha.clone(),
-                        to_sha: merge_parent.clone(),
-                        from_lane: lane,
-                        to_lane: parent_lane,
-                        from_row: row_i32,
-                        to_row: -1,
-                        edge_type: EdgeType::Merge,
-                        color_index: lane_colors
-                            .get(&parent_lane)
-                            .copied()
-                            .unwrap_or(color_index),
-                    });
+            let (edge_type, edge_color) = if i == 0 {
+                let color = if first_claim {
+                    // First parent inherits this commit's lane
+                    color_index
                 } else {
-                    // Allocate a new lane for this merge parent
-                    let merge_lane = allocate_lane(&mut active_lanes);
-                    let merge_color = hash_branch_name(merge_parent);
-                    lane_colors.insert(merge_lane, merge_color);
-                    sha_lane.insert(merge_parent, merge_lane);
-
-                    edges.push(Edge {
-                        from_sha: commit.sha.clone(),
-                        to_sha: merge_parent.clone(),
-                        from_lane: lane,
-                        to_lane: merge_lane,
-                        from_row: row_i32,
-                        to_row: -1,
-                        edge_type: EdgeType::Merge,
-                        color_index: merge_color,
-                    });
-                }
-            }
+                    lane_colors.get(&parent_lane).copied().unwrap_or(color_index)
+                };
+                (EdgeType::Normal, color)
+            } else if first_claim {
+                // A new lane opens for this merge parent
+                let merge_color = hash_branch_name(parent, palette);
+                lane_colors.insert(parent_lane, merge_color);
+                (EdgeType::Merge, merge_color)
+            } else {
+                let color = lane_colors.get(&parent_lane).copied().unwrap_or(color_index);
+                (EdgeType::Merge, color)
+            };
+
+            // Parent's row is filled in by the second pass
+            edges.push(Edge {
+                from_sha: commit.sha.clone(),
+                to_sha: parent.clone(),
+                from_lane: lane,
+                to_lane: parent_lane,
+                from_row: row_i32,
+                to_row: -1,
+                edge_type,
+                color_index: edge_color,
+                truncated_direction: None,
+            });
         }
     }
 
@@ -244,6 +448,58 @@ pub fn compute_layout(commits: &[CommitNode]) -> LayoutResult {
         // Also update to_lane from the layout node at that row
         if edge.to_row >= 0 && (edge.to_row as usize) < layout_nodes.len() {
             edge.to_lane = layout_nodes[edge.to_row as usize].lane;
+        } else {
+            // Parent is outside the loaded page. Parents always come after
+            // their children in git's output, so the stub points downwards.
+            edge.edge_type = EdgeType::Truncated;
+            edge.truncated_direction = Some(EdgeDirection::Down);
+        }
+    }
+
+    if let Some(max_lanes) = options.max_lanes {
+        let last = max_lanes.max(1) as i32 - 1;
+        for node in &mut layout_nodes {
+            node.lane = node.lane.min(last);
+        }
+        for edge in &mut edges {
+            edge.from_lane = edge.from_lane.min(last);
+            edge.to_lane = edge.to_lane.min(last);
+        }
+    }
+
+    if options.merge_side == MergeSide::Left {
+        let max_lane = layout_nodes
+            .iter()
+            .map(|n| n.lane)
+            .chain(edges.iter().map(|e| e.to_lane))
+            .max()
+            .unwrap_or(0);
+        for node in &mut layout_nodes {
+            node.lane = max_lane - node.lane;
+        }
+        for edge in &mut edges {
+            edge.from_lane = max_lane - edge.from_lane;
+            edge.to_lane = max_lane - edge.to_lane;
+        }
+    }
+
+    if options.color_strategy == ColorStrategy::Lane {
+        let lane_color = |lane: i32| lane.max(0) as u32 % palette;
+        for (node, commit) in layout_nodes.iter_mut().zip(commits) {
+            let tinted = options.verified_color_index.is_some()
+                && commit.signature_status == SignatureStatus::Good;
+            if !tinted {
+                node.color_index = lane_color(node.lane);
+            }
+        }
+        for edge in &mut edges {
+            // A merge edge is drawn in the lane of the branch it brings in
+            let lane = if edge.edge_type == EdgeType::Merge {
+                edge.to_lane
+            } else {
+                edge.from_lane
+            };
+            edge.color_index = lane_color(lane);
         }
     }
 
@@ -257,7 +513,8 @@ pub fn compute_layout(commits: &[CommitNode]) -> LayoutResult {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::graph::parser::parse_log;
+    use crate::graph::format::LogFormat;
+    use crate::graph::parser::{parse_log, parse_log_with_format};
 
     #[test]
     fn test_compute_layout_empty() {
@@ -306,4 +563,240 @@ mod tests {
         // Actually: M->A (normal), M->B (merge), A->C (normal), B->C (normal) = 4 edges
         assert!(result.edges.len() >= 3);
     }
+
+    #[test]
+    fn test_compute_layout_long_lived_branch_gets_inner_lane() {
+        // A short topic branch X -> Y appears first and merges into B, the
+        // long-lived mainline A -> B -> C -> D.
+        let raw = concat!(
+            "xxx\x00xx\x00yyy\x00Bob\x00b@e.com\x001700005000\x00Bob\x00b@e.com\x001700005000\x00Topic tip\x00\x1e",
+            "aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700004000\x00Alice\x00a@e.com\x001700004000\x00Main tip\x00\x1e",
+            "yyy\x00yy\x00bbb\x00Bob\x00b@e.com\x001700003000\x00Bob\x00b@e.com\x001700003000\x00Topic base\x00\x1e",
+            "bbb\x00bb\x00ccc\x00Alice\x00a@e.com\x001700002000\x00Alice\x00a@e.com\x001700002000\x00Main 3\x00\x1e",
+            "ccc\x00cc\x00ddd\x00Alice\x00a@e.com\x001700001000\x00Alice\x00a@e.com\x001700001000\x00Main 2\x00\x1e",
+            "ddd\x00dd\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Main 1\x00\x1e"
+        );
+        let commits = parse_log(raw.as_bytes());
+        let result = compute_layout(&commits);
+
+        let lane_of = |sha: &str| result.nodes.iter().find(|n| n.sha == sha).unwrap().lane;
+        for sha in ["aaa", "bbb", "ccc", "ddd"] {
+            assert_eq!(lane_of(sha), 0, "mainline commit {} left lane 0", sha);
+        }
+        assert_eq!(lane_of("xxx"), 1);
+        assert_eq!(lane_of("yyy"), 1);
+
+        // Y's edge joins the mainline lane at B
+        let join = result.edges.iter().find(|e| e.from_sha == "yyy").unwrap();
+        assert_eq!(join.to_lane, 0);
+        assert_eq!(join.to_row, 3);
+    }
+
+    fn merge_fixture() -> Vec<CommitNode> {
+        // M merges B into A; both sides share the root C
+        let raw = concat!(
+            "mmm\x00mm\x00aaa bbb\x00Alice\x00a@e.com\x001700003000\x00Alice\x00a@e.com\x001700003000\x00Merge\x00\x1e",
+            "aaa\x00aa\x00ccc\x00Alice\x00a@e.com\x001700002000\x00Alice\x00a@e.com\x001700002000\x00On main\x00\x1e",
+            "bbb\x00bb\x00ccc\x00Bob\x00b@e.com\x001700001000\x00Bob\x00b@e.com\x001700001000\x00On branch\x00\x1e",
+            "ccc\x00cc\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Root\x00\x1e"
+        );
+        parse_log(raw.as_bytes())
+    }
+
+    #[test]
+    fn test_compute_layout_date_order() {
+        // The branch commit is newer than the mainline commit, and the
+        // merge is listed after its parents' children as git would
+        let mut commits = merge_fixture();
+        commits[2].committer.time = 1_700_002_500;
+        let options = LayoutOptions {
+            order: CommitOrder::Date,
+            ..Default::default()
+        };
+        let result = compute_layout_with_options(&commits, &options);
+        let shas: Vec<&str> = result.nodes.iter().map(|n| n.sha.as_str()).collect();
+        assert_eq!(shas, vec!["mmm", "bbb", "aaa", "ccc"]);
+        assert_eq!(result.nodes[0].parents, vec!["aaa", "bbb"]);
+        let to_root = result.edges.iter().find(|e| e.from_sha == "aaa").unwrap();
+        assert_eq!((to_root.from_row, to_root.to_row), (2, 3));
+
+        // A parent newer than its child still comes after it
+        commits[3].committer.time = 1_800_000_000;
+        let result = compute_layout_with_options(&commits, &options);
+        assert_eq!(result.nodes[3].sha, "ccc");
+    }
+
+    #[test]
+    fn test_compute_layout_first_parent() {
+        // `git log --first-parent` lists the merge but not the branch
+        let mut commits = merge_fixture();
+        commits.remove(2);
+        let options = LayoutOptions {
+            first_parent: true,
+            ..Default::default()
+        };
+        let result = compute_layout_with_options(&commits, &options);
+        assert!(result.nodes.iter().all(|n| n.lane == 0));
+        assert_eq!(result.edges.len(), 2);
+        assert!(result.edges.iter().all(|e| e.edge_type == EdgeType::Normal));
+        assert!(result.nodes[0].is_merge);
+        assert_eq!(result.nodes[0].parents, vec!["aaa", "bbb"]);
+
+        let full = compute_layout(&commits);
+        assert!(full.edges.iter().any(|e| e.edge_type == EdgeType::Truncated));
+    }
+
+    #[test]
+    fn test_compute_layout_lane_colors_and_limits() {
+        let commits = merge_fixture();
+        let options = LayoutOptions {
+            color_strategy: ColorStrategy::Lane,
+            palette_size: Some(4),
+            ..Default::default()
+        };
+        let result = compute_layout_with_options(&commits, &options);
+        for node in &result.nodes {
+            assert_eq!(node.color_index, node.lane as u32 % 4);
+        }
+        let merge = result.edges.iter().find(|e| e.edge_type == EdgeType::Merge).unwrap();
+        assert_eq!(merge.color_index, 1);
+
+        let options = LayoutOptions {
+            palette_size: Some(3),
+            ..Default::default()
+        };
+        let result = compute_layout_with_options(&commits, &options);
+        assert!(result.nodes.iter().all(|n| n.color_index < 3));
+        assert!(result.edges.iter().all(|e| e.color_index < 3));
+
+        let options = LayoutOptions {
+            max_lanes: Some(1),
+            ..Default::default()
+        };
+        let result = compute_layout_with_options(&commits, &options);
+        assert!(result.nodes.iter().all(|n| n.lane == 0));
+        assert!(result.edges.iter().all(|e| e.from_lane == 0 && e.to_lane == 0));
+    }
+
+    #[test]
+    fn test_compute_layout_merge_side_left_mirrors_lanes() {
+        let commits = merge_fixture();
+        let right = compute_layout(&commits);
+        assert_eq!(right.nodes[0].lane, 0);
+        assert_eq!(right.nodes[2].lane, 1);
+
+        let options = LayoutOptions {
+            merge_side: MergeSide::Left,
+            ..Default::default()
+        };
+        let left = compute_layout_with_options(&commits, &options);
+        assert_eq!(left.nodes[0].lane, 1);
+        assert_eq!(left.nodes[1].lane, 1);
+        assert_eq!(left.nodes[2].lane, 0);
+        let merge_edge = left.edges.iter().find(|e| e.edge_type == EdgeType::Merge).unwrap();
+        assert_eq!(merge_edge.from_lane, 1);
+        assert_eq!(merge_edge.to_lane, 0);
+    }
+
+    #[test]
+    fn test_compute_layout_swap_merge_parents() {
+        let commits = merge_fixture();
+        let options = LayoutOptions {
+            swap_merge_parents: true,
+            ..Default::default()
+        };
+        let result = compute_layout_with_options(&commits, &options);
+
+        // The merged branch B now continues the merge commit's lane
+        assert_eq!(result.nodes[0].lane, result.nodes[2].lane);
+        assert_ne!(result.nodes[0].lane, result.nodes[1].lane);
+        // Reported parents keep git's order
+        assert_eq!(result.nodes[0].parents, vec!["aaa", "bbb"]);
+
+        let to_a = result.edges.iter().find(|e| e.from_sha == "mmm" && e.to_sha == "aaa").unwrap();
+        assert_eq!(to_a.edge_type, EdgeType::Merge);
+    }
+
+    #[test]
+    fn test_compute_layout_truncated_parent() {
+        // B's parent zzz is not part of the loaded page
+        let raw = b"aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Second\x00\x1ebbb\x00bb\x00zzz\x00Alice\x00a@e.com\x001699999000\x00Alice\x00a@e.com\x001699999000\x00First\x00\x1e";
+        let commits = parse_log(raw);
+        let result = compute_layout(&commits);
+
+        assert_eq!(result.edges.len(), 2);
+        assert_eq!(result.edges[0].edge_type, EdgeType::Normal);
+        assert_eq!(result.edges[0].truncated_direction, None);
+
+        let stub = &result.edges[1];
+        assert_eq!(stub.to_sha, "zzz");
+        assert_eq!(stub.to_row, -1);
+        assert_eq!(stub.edge_type, EdgeType::Truncated);
+        assert_eq!(stub.truncated_direction, Some(EdgeDirection::Down));
+    }
+
+    #[test]
+    fn test_compute_layout_verified_tint() {
+        let raw = b"aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Signed\x00\x1ebbb\x00bb\x00\x00Alice\x00a@e.com\x001699999000\x00Alice\x00a@e.com\x001699999000\x00Unsigned\x00\x1e";
+        let mut commits = parse_log(raw);
+        commits[0].signature_status = SignatureStatus::Good;
+
+        let plain = compute_layout(&commits);
+        assert_eq!(plain.nodes[0].signature_status, SignatureStatus::Good);
+        assert_eq!(plain.nodes[1].signature_status, SignatureStatus::None);
+
+        let options = LayoutOptions {
+            verified_color_index: Some(12),
+            ..Default::default()
+        };
+        let tinted = compute_layout_with_options(&commits, &options);
+        assert_eq!(tinted.nodes[0].color_index, 12);
+        assert_eq!(tinted.nodes[1].color_index, plain.nodes[1].color_index);
+        // The edge keeps the lane color
+        assert_eq!(tinted.edges[0].color_index, plain.edges[0].color_index);
+    }
+
+    #[test]
+    fn test_compute_layout_has_note() {
+        let raw = b"aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Noted\x00\x1ebbb\x00bb\x00\x00Alice\x00a@e.com\x001699999000\x00Alice\x00a@e.com\x001699999000\x00Plain\x00\x1e";
+        let mut commits = parse_log(raw);
+        commits[0].notes = "Reviewed-by: Bob".to_string();
+
+        let result = compute_layout(&commits);
+        assert!(result.nodes[0].has_note);
+        assert_eq!(result.nodes[0].notes, "Reviewed-by: Bob");
+        assert!(!result.nodes[1].has_note);
+    }
+
+    #[test]
+    fn test_source_ref_colors_like_its_branch() {
+        let branch = CommitNode {
+            sha: "aaa".to_string(),
+            refs: vec![RefInfo {
+                name: "feature".to_string(),
+                ref_type: RefType::Branch,
+                is_head: false,
+            }],
+            ..Default::default()
+        };
+        let sourced = CommitNode {
+            sha: "bbb".to_string(),
+            source_ref: "refs/heads/feature".to_string(),
+            ..Default::default()
+        };
+        let empty = HashMap::new();
+        assert_eq!(
+            determine_color_index(&sourced, &empty, None, DEFAULT_PALETTE_SIZE),
+            determine_color_index(&branch, &empty, None, DEFAULT_PALETTE_SIZE)
+        );
+    }
+
+    #[test]
+    fn test_compute_layout_keeps_tz_offsets() {
+        let format = LogFormat::from_placeholders(&["%H", "%aI", "%cI", "%s"]).unwrap();
+        let raw = b"aaa\x002023-11-14T23:43:20+01:30\x002023-11-14T14:15:00-08:00\x00Tz\x1e";
+        let result = compute_layout(&parse_log_with_format(raw, &format));
+        assert_eq!(result.nodes[0].author_tz_offset_min, 90);
+        assert_eq!(result.nodes[0].committer_tz_offset_min, -480);
+    }
 }
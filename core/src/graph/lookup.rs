@@ -0,0 +1,89 @@
+use serde::Serialize;
+
+use super::types::{Edge, LayoutNode, LayoutResult};
+use crate::error::{ErrorCode, ErrorInfo};
+
+/// One node of a layout with the edges to its parents and children, for
+/// detail panes and hovers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDetail {
+    pub node: LayoutNode,
+    pub edges: Vec<Edge>,
+}
+
+/// Look up a node by full SHA or by a prefix of it, without regard to
+/// case.
+///
+/// Errors when no node matches or a prefix matches several.
+pub fn find_node<'a>(layout: &'a LayoutResult, sha: &str) -> Result<&'a LayoutNode, ErrorInfo> {
+    let sha = sha.trim().to_ascii_lowercase();
+    if sha.is_empty() {
+        return Err(ErrorInfo::with_code(ErrorCode::BadArgument, "Empty SHA"));
+    }
+    if let Some(node) = layout.nodes.iter().find(|n| n.sha == sha) {
+        return Ok(node);
+    }
+
+    let mut matches = layout.nodes.iter().filter(|n| n.sha.starts_with(&sha));
+    match (matches.next(), matches.next()) {
+        (Some(node), None) => Ok(node),
+        (Some(_), Some(_)) => Err(ErrorInfo::with_code(
+            ErrorCode::BadArgument,
+            format!("Ambiguous SHA prefix: '{}'", sha),
+        )),
+        (None, _) => Err(ErrorInfo::with_code(
+            ErrorCode::NotFound,
+            format!("Unknown commit: '{}'", sha),
+        )),
+    }
+}
+
+/// `find_node` together with the node's incident edges.
+pub fn node_detail(layout: &LayoutResult, sha: &str) -> Result<NodeDetail, ErrorInfo> {
+    let node = find_node(layout, sha)?;
+    let edges = layout
+        .edges
+        .iter()
+        .filter(|e| e.from_sha == node.sha || e.to_sha == node.sha)
+        .cloned()
+        .collect();
+    Ok(NodeDetail {
+        node: node.clone(),
+        edges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::test_support::commit;
+
+    #[test]
+    fn test_node_detail() {
+        let layout = compute_layout(&[
+            commit("abc1", &["abd2", "ff00"]),
+            commit("ff00", &["abd2"]),
+            commit("abd2", &[]),
+        ]);
+
+        let detail = node_detail(&layout, "FF00").unwrap();
+        assert_eq!(detail.node.row, 1);
+        let edges: Vec<(&str, &str)> = detail
+            .edges
+            .iter()
+            .map(|e| (e.from_sha.as_str(), e.to_sha.as_str()))
+            .collect();
+        assert_eq!(edges, vec![("abc1", "ff00"), ("ff00", "abd2")]);
+
+        assert_eq!(find_node(&layout, "abd").unwrap().sha, "abd2");
+        let ambiguous = find_node(&layout, "ab").unwrap_err();
+        assert_eq!(ambiguous.message, "Ambiguous SHA prefix: 'ab'");
+        assert_eq!(ambiguous.code, ErrorCode::BadArgument);
+        let unknown = find_node(&layout, "123").unwrap_err();
+        assert_eq!(unknown.message, "Unknown commit: '123'");
+        assert_eq!(unknown.code, ErrorCode::NotFound);
+        assert!(find_node(&layout, " ").is_err());
+    }
+}
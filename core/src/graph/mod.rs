@@ -1,7 +1,28 @@
 pub mod types;
+pub mod ascii;
+pub mod date;
+pub mod encoding;
+pub mod format;
 pub mod parser;
+pub mod revlist;
+pub mod sha;
+pub mod stream;
 pub mod layout;
+pub mod columns;
+pub mod window;
+pub mod lookup;
 
 pub use types::*;
-pub use parser::parse_log;
-pub use layout::compute_layout;
+pub use ascii::parse_graph_log;
+pub use format::{LogField, LogFormat};
+pub use parser::{
+    parse_log, parse_log_partial, parse_log_partial_with_format, parse_log_with_diagnostics,
+    parse_log_with_format,
+};
+pub use revlist::parse_rev_list;
+pub use sha::normalize_parents;
+pub use stream::LogParser;
+pub use layout::{compute_layout, compute_layout_with_options};
+pub use columns::{NodeColumns, StringTable};
+pub use window::layout_window;
+pub use lookup::{find_node, node_detail, NodeDetail};
@@ -1,4 +1,14 @@
-use super::types::{CommitNode, RefInfo, RefType};
+use std::collections::HashMap;
+
+use super::date::{parse_human_date, parse_iso8601, parse_zone};
+use super::encoding::{decode_record, source_offset};
+use super::format::{LogField, LogFormat};
+use super::sha::normalize_parents;
+use super::types::{
+    ChangeType, CommitNode, FileChange, FileStat, ParseDiagnostic, ParseResult, RefInfo, RefType,
+    Side, SignatureStatus,
+};
+use crate::input::{bom_len, normalize_input, raw_offset};
 
 /// Parse the decorate string from git log `%d` into a Vec<RefInfo>.
 ///
@@ -132,6 +142,260 @@ fn parse_refs(decorate: &str) -> Vec<RefInfo> {
     refs
 }
 
+/// Split the per-file lines git prints after a record (`--numstat` or
+/// `--name-status`) from the start of the next record.
+///
+/// With a format ending in `%x1e`, git writes a commit's file lines after its
+/// separator, so they lead the following piece. File lines contain a tab and
+/// never a NUL, which distinguishes them from record fields.
+pub(crate) fn split_file_lines(piece: &str) -> (Vec<&str>, &str) {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in piece.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if !content.is_empty() {
+            if content.contains('\x00') || !content.contains('\t') {
+                break;
+            }
+            lines.push(content);
+        }
+        offset += line.len();
+    }
+    (lines, &piece[offset..])
+}
+
+/// Attach file lines that followed a record to its commit.
+///
+/// `--numstat` lines start with a count or `-`; `--name-status` lines start
+/// with a status letter.
+pub(crate) fn attach_file_lines(node: &mut CommitNode, lines: &[&str]) {
+    for line in lines {
+        let starts_with_letter = line.starts_with(|c: char| c.is_ascii_alphabetic());
+        if starts_with_letter {
+            node.changes.extend(parse_name_status_line(line));
+        } else {
+            node.files.extend(parse_numstat_line(line));
+        }
+    }
+}
+
+/// Parse a `--name-status` line: `<status>\t<path>`, or
+/// `<R|C><score>\t<old path>\t<new path>` for renames and copies.
+fn parse_name_status_line(line: &str) -> Option<FileChange> {
+    let mut parts = line.splitn(3, '\t');
+    let status = parts.next()?;
+    let first_path = parts.next()?;
+    if first_path.is_empty() {
+        return None;
+    }
+
+    let mut status_chars = status.chars();
+    let change_type = ChangeType::from_code(status_chars.next()?);
+    let similarity = status_chars.as_str().parse().ok();
+
+    match (change_type, parts.next()) {
+        (ChangeType::Renamed | ChangeType::Copied, Some(new_path)) if !new_path.is_empty() => {
+            Some(FileChange {
+                change_type,
+                path: new_path.to_string(),
+                old_path: Some(first_path.to_string()),
+                similarity,
+            })
+        }
+        _ => Some(FileChange {
+            change_type,
+            path: first_path.to_string(),
+            old_path: None,
+            similarity: None,
+        }),
+    }
+}
+
+/// Parse a `--numstat` line: `<insertions>\t<deletions>\t<path>`.
+fn parse_numstat_line(line: &str) -> Option<FileStat> {
+    let mut parts = line.splitn(3, '\t');
+    let insertions = parts.next()?;
+    let deletions = parts.next()?;
+    let path = parts.next()?;
+    if path.is_empty() {
+        return None;
+    }
+
+    if insertions == "-" && deletions == "-" {
+        return Some(FileStat {
+            path: path.to_string(),
+            binary: true,
+            ..Default::default()
+        });
+    }
+
+    Some(FileStat {
+        path: path.to_string(),
+        insertions: insertions.parse().ok()?,
+        deletions: deletions.parse().ok()?,
+        binary: false,
+    })
+}
+
+/// Parse a date field as unix epoch seconds, falling back to ISO-8601 so
+/// logs produced with `%aI`/`%cI` by wrapper tooling still get dates.
+///
+/// Returns `(epoch, UTC offset in minutes)`; the offset is 0 for epochs.
+fn parse_date(value: &str) -> (u64, i32) {
+    let value = value.trim();
+    if let Ok(epoch) = value.parse() {
+        return (epoch, 0);
+    }
+    // `--date=raw`: `<epoch> <+hhmm>`
+    if let Some((epoch, zone)) = value.split_once(' ') {
+        if let (Ok(epoch), Some(offset)) = (epoch.parse(), parse_offset(zone)) {
+            return (epoch, offset);
+        }
+    }
+    parse_iso8601(value)
+        .or_else(|| parse_human_date(value))
+        .unwrap_or((0, 0))
+}
+
+/// Parse a bare `+hhmm`/`-hh:mm` offset, as from `--date=format:%z`.
+fn parse_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    if value.starts_with(['+', '-']) {
+        parse_zone(value)
+    } else {
+        None
+    }
+}
+
+/// Split `git describe` output into (tag, distance).
+///
+/// `v2.3.1-14-gabc1234` -> (`v2.3.1`, 14); an exact match such as `v2.3.1`
+/// has distance 0. Tags may themselves contain dashes. Empty output (no
+/// reachable tag) yields None.
+fn parse_describe(value: &str) -> Option<(String, u32)> {
+    let value = value.trim().trim_end_matches("-dirty");
+    if value.is_empty() {
+        return None;
+    }
+
+    let mut parts = value.rsplitn(3, '-');
+    let hash = parts.next()?;
+    let is_abbrev = hash.len() > 1
+        && hash.starts_with('g')
+        && hash[1..].bytes().all(|b| b.is_ascii_hexdigit());
+    if let (true, Some(distance), Some(tag)) = (is_abbrev, parts.next(), parts.next()) {
+        if let Ok(distance) = distance.parse() {
+            return Some((tag.to_string(), distance));
+        }
+    }
+    Some((value.to_string(), 0))
+}
+
+/// Extract the position from a reflog selector such as `HEAD@{3}`.
+///
+/// Date selectors (`HEAD@{2024-01-01 ...}`) have no position.
+fn parse_reflog_index(selector: &str) -> Option<u32> {
+    let start = selector.rfind("@{")? + 2;
+    let inner = selector[start..].strip_suffix('}')?;
+    inner.parse().ok()
+}
+
+/// Parse a single `\x1e`-terminated record (without the separator).
+///
+/// Returns the reason as an error for blank records and for records that
+/// are missing required fields or the commit hash.
+pub(crate) fn parse_record(record: &str, format: &LogFormat) -> Result<CommitNode, String> {
+    let record = record.trim();
+    if record.is_empty() {
+        return Err("Empty record".to_string());
+    }
+
+    // Split by NUL \x00
+    let fields: Vec<&str> = record.split('\x00').collect();
+    if fields.len() < format.required_fields() {
+        return Err(format!(
+            "Expected at least {} fields, found {}",
+            format.required_fields(),
+            fields.len()
+        ));
+    }
+
+    let mut node = CommitNode {
+        lane: -1,
+        row: -1,
+        ..Default::default()
+    };
+
+    for (field, value) in format.fields.iter().zip(fields.iter().copied()) {
+        match field {
+            LogField::Hash => {
+                // `%m%H` prefixes the hash with the left/right mark
+                let value = value.trim();
+                let hash = value.trim_start_matches(['<', '>', '-']);
+                if let Some(side) = Side::from_mark(&value[..value.len() - hash.len()]) {
+                    node.side = Some(side);
+                }
+                node.sha = hash.trim_start().to_string();
+            }
+            LogField::ShortHash => node.short_sha = value.trim().to_string(),
+            LogField::Parents => {
+                node.parents = value
+                    .split_whitespace()
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+                    .collect();
+            }
+            LogField::AuthorName => node.author.name = value.to_string(),
+            LogField::AuthorEmail => node.author.email = value.to_string(),
+            LogField::AuthorDate | LogField::AuthorDateIso => {
+                (node.author.time, node.author.tz_offset_min) = parse_date(value);
+            }
+            LogField::CommitterName => node.committer.name = value.to_string(),
+            LogField::CommitterEmail => node.committer.email = value.to_string(),
+            LogField::AuthorDateFormatted => match parse_offset(value) {
+                Some(offset) => node.author.tz_offset_min = offset,
+                None => (node.author.time, node.author.tz_offset_min) = parse_date(value),
+            },
+            LogField::CommitDateFormatted => match parse_offset(value) {
+                Some(offset) => node.committer.tz_offset_min = offset,
+                None => (node.committer.time, node.committer.tz_offset_min) = parse_date(value),
+            },
+            LogField::CommitDate | LogField::CommitDateIso => {
+                (node.committer.time, node.committer.tz_offset_min) = parse_date(value);
+            }
+            LogField::Subject => node.subject = value.to_string(),
+            // git ends %b with a newline; keep inner blank lines intact
+            LogField::Body => node.body = value.trim_end().to_string(),
+            LogField::Decorate => node.refs = parse_refs(value),
+            LogField::SignatureStatus => node.signature_status = SignatureStatus::from_code(value),
+            LogField::Signer => node.signer = value.trim().to_string(),
+            LogField::SigningKey => node.signing_key = value.trim().to_string(),
+            LogField::Notes => node.notes = value.trim_end().to_string(),
+            LogField::ReflogSelector => {
+                node.reflog_selector = value.trim().to_string();
+                node.reflog_index = parse_reflog_index(&node.reflog_selector);
+            }
+            LogField::ReflogSubject => node.reflog_subject = value.to_string(),
+            LogField::Encoding => node.encoding = value.trim().to_string(),
+            LogField::Describe => {
+                if let Some((tag, distance)) = parse_describe(value) {
+                    node.nearest_tag = Some(tag);
+                    node.distance = distance;
+                }
+            }
+            LogField::Source => node.source_ref = value.trim().to_string(),
+            LogField::Mark => node.side = Side::from_mark(value),
+            LogField::Other(_) => {}
+        }
+    }
+
+    if node.sha.is_empty() {
+        return Err("Missing commit hash".to_string());
+    }
+
+    Ok(node)
+}
+
 /// Parse the raw git log output into a Vec<CommitNode>.
 ///
 /// Expected format uses NUL (\x00) delimited fields and record separator (\x1e)
@@ -151,75 +415,118 @@ fn parse_refs(decorate: &str) -> Vec<RefInfo> {
 ///   9: %s  - subject
 ///  10: %d  - ref decoration
 pub fn parse_log(raw: &[u8]) -> Vec<CommitNode> {
-    let input = match std::str::from_utf8(raw) {
-        Ok(s) => s,
-        Err(_) => return Vec::new(),
-    };
+    parse_log_with_format(raw, &LogFormat::default())
+}
 
-    let mut commits = Vec::new();
+/// Parse raw git log output whose records follow `format`.
+///
+/// Records are still NUL-delimited fields separated by `\x1e`, but the field
+/// order and set is taken from the format descriptor instead of being fixed.
+pub fn parse_log_with_format(raw: &[u8], format: &LogFormat) -> Vec<CommitNode> {
+    parse_log_with_diagnostics(raw, format).commits
+}
 
-    // Split by record separator \x1e
-    for record in input.split('\x1e') {
-        let record = record.trim();
-        if record.is_empty() {
-            continue;
-        }
+/// Parse the complete records at the start of `raw`, for callers that feed
+/// a buffer in pieces.
+///
+/// Returns the commits and the number of bytes consumed, i.e. the offset
+/// just past the last record separator. The caller should keep
+/// `raw[bytes_consumed..]` and resubmit it in front of the next chunk.
+/// Children links only cover commits within the consumed bytes.
+///
+/// File lines (`--numstat`) after the last separator are left unconsumed, so
+/// the final record of each call is returned without them; use `LogParser`
+/// when parsing file lists incrementally.
+pub fn parse_log_partial(raw: &[u8]) -> (Vec<CommitNode>, usize) {
+    parse_log_partial_with_format(raw, &LogFormat::default())
+}
 
-        // Split by NUL \x00
-        let fields: Vec<&str> = record.split('\x00').collect();
-        if fields.len() < 10 {
-            // Not enough fields, skip malformed record
-            continue;
+/// `parse_log_partial` for records that follow `format`.
+pub fn parse_log_partial_with_format(raw: &[u8], format: &LogFormat) -> (Vec<CommitNode>, usize) {
+    let consumed = match raw.iter().rposition(|&b| b == 0x1e) {
+        Some(pos) => pos + 1,
+        None => return (Vec::new(), 0),
+    };
+    (parse_log_with_format(&raw[..consumed], format), consumed)
+}
+
+/// Like `parse_log_with_format`, but also reports every record that was
+/// skipped and why.
+pub fn parse_log_with_diagnostics(raw: &[u8], format: &LogFormat) -> ParseResult {
+    let mut commits: Vec<CommitNode> = Vec::new();
+    let mut diagnostics: Vec<ParseDiagnostic> = Vec::new();
+    // Index of the commit that file lines in the next piece belong to
+    let mut attach_to: Option<usize> = None;
+    let mut record_index = 0;
+    let bom = bom_len(raw);
+    let mut piece_offset = bom;
+    // First occurrence of each SHA, for deduplicating concatenated logs
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut duplicate_count = 0;
+
+    for raw_piece in raw[bom..].split(|&b| b == 0x1e) {
+        let piece_start = piece_offset;
+        piece_offset += raw_piece.len() + 1;
+
+        let normalized = normalize_input(raw_piece);
+        let piece = decode_record(&normalized, format);
+        let (file_lines, record) = split_file_lines(&piece);
+        if let Some(idx) = attach_to {
+            attach_file_lines(&mut commits[idx], &file_lines);
         }
 
-        let sha = fields[0].trim().to_string();
-        if sha.is_empty() {
+        if record.trim().is_empty() {
             continue;
         }
-
-        let short_sha = fields[1].trim().to_string();
-
-        let parents: Vec<String> = fields[2]
-            .split_whitespace()
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-            .collect();
-
-        let author_name = fields[3].to_string();
-        let author_email = fields[4].to_string();
-        let author_date: u64 = fields[5].trim().parse().unwrap_or(0);
-
-        let committer_name = fields[6].to_string();
-        let committer_email = fields[7].to_string();
-        let commit_date: u64 = fields[8].trim().parse().unwrap_or(0);
-
-        let subject = fields[9].to_string();
-
-        let decorate = if fields.len() > 10 { fields[10] } else { "" };
-        let refs = parse_refs(decorate);
-
-        let node = CommitNode {
-            sha,
-            short_sha,
-            parents,
-            children: Vec::new(),
-            author_name,
-            author_email,
-            author_date,
-            committer_name,
-            committer_email,
-            commit_date,
-            subject,
-            refs,
-            lane: -1,
-            row: -1,
+        attach_to = match parse_record(record, format) {
+            // Reflog walks list the same commit once per entry; keep those
+            Ok(node) if !node.reflog_selector.is_empty() => {
+                commits.push(node);
+                Some(commits.len() - 1)
+            }
+            Ok(node) => match seen.get(&node.sha) {
+                Some(&idx) => {
+                    merge_refs(&mut commits[idx].refs, node.refs);
+                    duplicate_count += 1;
+                    // The first occurrence already has this commit's files
+                    None
+                }
+                None => {
+                    seen.insert(node.sha.clone(), commits.len());
+                    commits.push(node);
+                    Some(commits.len() - 1)
+                }
+            },
+            Err(reason) => {
+                let leading = record.len() - record.trim_start().len();
+                let decoded_offset = piece.len() - record.len() + leading;
+                let offset = source_offset(&normalized, &piece, decoded_offset);
+                diagnostics.push(ParseDiagnostic {
+                    record_index,
+                    byte_offset: piece_start + raw_offset(raw_piece, offset),
+                    reason,
+                });
+                None
+            }
         };
+        record_index += 1;
+    }
 
-        commits.push(node);
+    let unresolved_parents = normalize_parents(&mut commits);
+    link_children(&mut commits);
+
+    ParseResult {
+        commits,
+        diagnostics,
+        duplicate_count,
+        unresolved_parents,
     }
+}
 
-    // Build children index: for each commit, add it as a child of its parents
-    let sha_to_idx: std::collections::HashMap<String, usize> = commits
+/// Rebuild the children index: each commit is added as a child of its
+/// parents that are present in `commits`.
+pub(crate) fn link_children(commits: &mut [CommitNode]) {
+    let sha_to_idx: HashMap<String, usize> = commits
         .iter()
         .enumerate()
         .map(|(i, c)| (c.sha.clone(), i))
@@ -227,7 +534,7 @@ pub fn parse_log(raw: &[u8]) -> Vec<CommitNode> {
 
     // Collect parent-child relationships first to avoid borrow issues
     let mut child_additions: Vec<(usize, String)> = Vec::new();
-    for commit in &commits {
+    for commit in commits.iter() {
         for parent_sha in &commit.parents {
             if let Some(&parent_idx) = sha_to_idx.get(parent_sha) {
                 child_additions.push((parent_idx, commit.sha.clone()));
@@ -235,11 +542,22 @@ pub fn parse_log(raw: &[u8]) -> Vec<CommitNode> {
         }
     }
 
+    for commit in commits.iter_mut() {
+        commit.children.clear();
+    }
     for (parent_idx, child_sha) in child_additions {
         commits[parent_idx].children.push(child_sha);
     }
+}
 
-    commits
+/// Add decorations from a duplicate record that the kept record lacks.
+fn merge_refs(refs: &mut Vec<RefInfo>, extra: Vec<RefInfo>) {
+    for r in extra {
+        match refs.iter_mut().find(|existing| existing.name == r.name) {
+            Some(existing) => existing.is_head |= r.is_head,
+            None => refs.push(r),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -278,7 +596,383 @@ mod tests {
         assert_eq!(commits[0].parents.len(), 2);
         assert_eq!(commits[0].parents[0], "def456");
         assert_eq!(commits[0].parents[1], "ghi789");
-        assert_eq!(commits[0].author_name, "Alice");
+        assert_eq!(commits[0].author.name, "Alice");
+    }
+
+    #[test]
+    fn test_parse_log_with_custom_format() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%an", "%at", "%G?", "%s"]).unwrap();
+        let raw = b"abc123\x00def456\x00Alice\x001700000000\x00G\x00Reordered fields\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].sha, "abc123");
+        assert_eq!(commits[0].parents, vec!["def456"]);
+        assert_eq!(commits[0].author.name, "Alice");
+        assert_eq!(commits[0].author.time, 1700000000);
+        assert_eq!(commits[0].subject, "Reordered fields");
+        // Omitted fields stay empty
+        assert!(commits[0].short_sha.is_empty());
+        assert!(commits[0].refs.is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_multiline_body() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%s", "%b", "%d"]).unwrap();
+        let raw = b"abc123\x00def456\x00Add parser\x00First paragraph\nwraps here.\n\nSigned-off-by: Alice\n\x00 (HEAD -> main)\x1e\ndef456\x00\x00Initial\x00\x00\x1e\n";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].subject, "Add parser");
+        assert_eq!(
+            commits[0].body,
+            "First paragraph\nwraps here.\n\nSigned-off-by: Alice"
+        );
+        assert_eq!(commits[0].refs.len(), 2);
+        assert_eq!(commits[1].subject, "Initial");
+        assert!(commits[1].body.is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_signature_fields() {
+        let format = LogFormat::from_placeholders(&["%H", "%s", "%G?", "%GS", "%GK"]).unwrap();
+        let raw = b"aaa\x00Signed\x00G\x00Alice <alice@example.com>\x00ABCDEF0123456789\x1ebbb\x00Expired\x00Y\x00Bob\x00FEDCBA\x1eccc\x00Unsigned\x00N\x00\x00\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].signature_status, SignatureStatus::Good);
+        assert_eq!(commits[0].signer, "Alice <alice@example.com>");
+        assert_eq!(commits[0].signing_key, "ABCDEF0123456789");
+        assert_eq!(commits[1].signature_status, SignatureStatus::Expired);
+        assert_eq!(commits[2].signature_status, SignatureStatus::None);
+        assert!(commits[2].signer.is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_notes() {
+        let format = LogFormat::from_placeholders(&["%H", "%s", "%N"]).unwrap();
+        let raw = b"aaa\x00Reviewed\x00Reviewed-by: Bob\nTested-by: CI\n\n\x1ebbb\x00No note\x00\x1eccc\x00Short\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].notes, "Reviewed-by: Bob\nTested-by: CI");
+        assert!(commits[1].notes.is_empty());
+        assert!(commits[2].notes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_reflog_fields() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%gd", "%gs", "%s"]).unwrap();
+        let raw = b"bbb\x00aaa\x00HEAD@{0}\x00commit (amend): Fix typo\x00Fix typo\x1eccc\x00aaa\x00HEAD@{1}\x00commit: Fix tpyo\x00Fix tpyo\x1ebbb\x00aaa\x00HEAD@{2024-01-01 10:00:00}\x00checkout: moving from dev to main\x00Fix typo\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].reflog_selector, "HEAD@{0}");
+        assert_eq!(commits[0].reflog_index, Some(0));
+        assert_eq!(commits[0].reflog_subject, "commit (amend): Fix typo");
+        assert_eq!(commits[1].reflog_index, Some(1));
+        assert_eq!(commits[2].reflog_index, None);
+        assert_eq!(commits[2].reflog_subject, "checkout: moving from dev to main");
+    }
+
+    #[test]
+    fn test_parse_log_transcodes_legacy_records() {
+        let format = LogFormat::from_placeholders(&["%H", "%an", "%e", "%s"]).unwrap();
+        let raw = b"aaa\x00Ren\xe9\x00ISO-8859-1\x00Caf\xe9 cr\xe8me\x1ebbb\x00Zo\xc3\xab\x00\x00UTF-8 subject\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].author.name, "René");
+        assert_eq!(commits[0].subject, "Café crème");
+        assert_eq!(commits[0].encoding, "ISO-8859-1");
+        assert_eq!(commits[1].author.name, "Zoë");
+        assert!(commits[1].encoding.is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_with_diagnostics() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%s"]).unwrap();
+        let raw = b"aaa\x00bbb\x00Good\x1e\nbbb\x1e\x00ccc\x00No hash\x1eccc\x00\x00Root\x1e";
+        let result = parse_log_with_diagnostics(raw, &format);
+
+        let shas: Vec<&str> = result.commits.iter().map(|c| c.sha.as_str()).collect();
+        assert_eq!(shas, vec!["aaa", "ccc"]);
+        assert_eq!(result.diagnostics.len(), 2);
+
+        assert_eq!(result.diagnostics[0].record_index, 1);
+        assert_eq!(result.diagnostics[0].byte_offset, 14);
+        assert_eq!(result.diagnostics[0].reason, "Expected at least 3 fields, found 1");
+        assert_eq!(result.diagnostics[1].record_index, 2);
+        assert_eq!(result.diagnostics[1].byte_offset, 18);
+        assert_eq!(result.diagnostics[1].reason, "Missing commit hash");
+    }
+
+    #[test]
+    fn test_diagnostic_offset_in_transcoded_record() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%s"]).unwrap();
+        // The Latin-1 path before the bad record decodes to two bytes of UTF-8
+        let raw = b"aaa\x00\x00Good\x1e\n1\t0\tcaf\xe9.rs\n\x00\x00No hash\x1e";
+        let result = parse_log_with_diagnostics(raw, &format);
+
+        assert_eq!(result.commits[0].files[0].path, "café.rs");
+        assert_eq!(result.diagnostics[0].reason, "Missing commit hash");
+        assert_eq!(result.diagnostics[0].byte_offset, 23);
+    }
+
+    #[test]
+    fn test_parse_log_resolves_abbreviated_parents() {
+        let format = LogFormat::from_placeholders(&["%H", "%p", "%s"]).unwrap();
+        let tip = "1111111111111111111111111111111111111111";
+        let base = "2222222222222222222222222222222222222222";
+        let raw = format!(
+            "{}\x002222222 3333333\x00Tip\x1e{}\x00\x00Base\x1e",
+            tip, base
+        );
+        let result = parse_log_with_diagnostics(raw.as_bytes(), &format);
+
+        assert_eq!(result.commits[0].parents, vec![base, "3333333"]);
+        assert_eq!(result.commits[1].children, vec![tip]);
+        assert_eq!(result.unresolved_parents.len(), 1);
+        assert_eq!(result.unresolved_parents[0].parent, "3333333");
+    }
+
+    #[test]
+    fn test_parse_log_crlf_and_bom() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%s", "%b"]).unwrap();
+        let raw = b"\xef\xbb\xbfaaa\x00bbb\x00Tip\x00Line one\r\nLine two\r\n\x1e\r\nbad\x1e\r\nbbb\x00\x00Root\x00\x1e\r\n";
+        let result = parse_log_with_diagnostics(raw, &format);
+
+        assert_eq!(result.commits.len(), 2);
+        assert_eq!(result.commits[0].sha, "aaa");
+        assert_eq!(result.commits[0].body, "Line one\nLine two");
+        assert_eq!(result.commits[1].sha, "bbb");
+        assert_eq!(result.commits[1].children, vec!["aaa"]);
+        // Offsets point into the raw input, past the BOM and CRLF
+        assert_eq!(result.diagnostics[0].byte_offset, 38);
+        assert_eq!(&raw[38..41], b"bad");
+    }
+
+    #[test]
+    fn test_parse_log_iso_dates() {
+        let format = LogFormat::from_placeholders(&["%H", "%aI", "%cI", "%s"]).unwrap();
+        let raw = b"aaa\x002023-11-14T23:43:20+01:30\x002023-11-14T14:15:00-08:00\x00Iso\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits[0].author.time, 1700000000);
+        assert_eq!(commits[0].author.tz_offset_min, 90);
+        assert_eq!(commits[0].committer.time, 1700000100);
+        assert_eq!(commits[0].committer.tz_offset_min, -480);
+
+        // ISO values in epoch fields are accepted too
+        let format = LogFormat::from_placeholders(&["%H", "%at", "%s"]).unwrap();
+        let commits = parse_log_with_format(b"bbb\x002023-11-14T22:13:20Z\x00Mislabelled\x1e", &format);
+        assert_eq!(commits[0].author.time, 1700000000);
+    }
+
+    #[test]
+    fn test_parse_log_formatted_dates() {
+        let format = LogFormat::from_placeholders(&["%H", "%ad", "%cd", "%s"]).unwrap();
+        let raw = b"aaa\x001700000000 +0530\x00Tue, 14 Nov 2023 14:13:20 -0800\x00Raw and rfc\x1e\
+bbb\x00Tue Nov 14 23:13:20 2023 +0100\x002023-11-14 22:13:20 +0000\x00Default and iso\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits[0].author.time, 1700000000);
+        assert_eq!(commits[0].author.tz_offset_min, 330);
+        assert_eq!(commits[0].committer.time, 1700000000);
+        assert_eq!(commits[0].committer.tz_offset_min, -480);
+        assert_eq!(commits[1].author.time, 1700000000);
+        assert_eq!(commits[1].author.tz_offset_min, 60);
+        assert_eq!(commits[1].committer.time, 1700000000);
+
+        // `--date=format:%z` pairs an offset with the epoch fields
+        let format = LogFormat::from_placeholders(&["%H", "%at", "%ad", "%ct", "%cd", "%s"]).unwrap();
+        let raw = b"ccc\x001700000000\x00-0330\x001700000100\x00+01:00\x00Offsets\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits[0].author.time, 1700000000);
+        assert_eq!(commits[0].author.tz_offset_min, -210);
+        assert_eq!(commits[0].committer.time, 1700000100);
+        assert_eq!(commits[0].committer.tz_offset_min, 60);
+    }
+
+    #[test]
+    fn test_parse_log_deduplicates_shas() {
+        let format = LogFormat::from_placeholders(&["%H", "%P", "%s", "%d"]).unwrap();
+        let raw = b"aaa\x00bbb\x00Tip\x00 (HEAD -> main)\x1ebbb\x00\x00Root\x00\x1eaaa\x00bbb\x00Tip\x00 (origin/main, main)\x1ebbb\x00\x00Root\x00 (tag: v1.0)\x1e";
+        let result = parse_log_with_diagnostics(raw, &format);
+
+        assert_eq!(result.commits.len(), 2);
+        assert_eq!(result.duplicate_count, 2);
+        assert!(result.diagnostics.is_empty());
+
+        let tip_refs: Vec<&str> = result.commits[0].refs.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(tip_refs, vec!["HEAD", "main", "origin/main"]);
+        assert_eq!(result.commits[1].refs[0].name, "v1.0");
+        assert_eq!(result.commits[1].children, vec!["aaa"]);
+    }
+
+    #[test]
+    fn test_parse_log_keeps_repeated_reflog_entries() {
+        let format = LogFormat::from_placeholders(&["%H", "%gd", "%s"]).unwrap();
+        let raw = b"aaa\x00HEAD@{0}\x00Tip\x1ebbb\x00HEAD@{1}\x00Old\x1eaaa\x00HEAD@{2}\x00Tip\x1e";
+        let result = parse_log_with_diagnostics(raw, &format);
+        assert_eq!(result.commits.len(), 3);
+        assert_eq!(result.duplicate_count, 0);
+    }
+
+    #[test]
+    fn test_parse_log_partial_reports_consumed_bytes() {
+        let first = b"aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Second\x00\x1e";
+        let second = b"bbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00First\x00\x1e";
+        let mut buffer = first.to_vec();
+        buffer.extend_from_slice(&second[..20]);
+
+        let (commits, consumed) = parse_log_partial(&buffer);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(consumed, first.len());
+
+        // Resubmit the tail with the rest of the data
+        let mut next = buffer[consumed..].to_vec();
+        next.extend_from_slice(&second[20..]);
+        let (commits, consumed) = parse_log_partial(&next);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].subject, "First");
+        assert_eq!(consumed, next.len());
+
+        let (commits, consumed) = parse_log_partial(&second[..20]);
+        assert!(commits.is_empty());
+        assert_eq!(consumed, 0);
+    }
+
+    #[test]
+    fn test_parse_describe() {
+        assert_eq!(parse_describe("v2.3.1-14-gabc1234"), Some(("v2.3.1".to_string(), 14)));
+        assert_eq!(parse_describe("v2.3.1"), Some(("v2.3.1".to_string(), 0)));
+        assert_eq!(
+            parse_describe("release-2024-01-3-g0a1b2c3\n"),
+            Some(("release-2024-01".to_string(), 3))
+        );
+        assert_eq!(parse_describe("v1.0-rc-1"), Some(("v1.0-rc-1".to_string(), 0)));
+        assert_eq!(parse_describe(""), None);
+    }
+
+    #[test]
+    fn test_parse_log_describe_field() {
+        let format = LogFormat::from_placeholders(&["%H", "%(describe:tags)", "%s"]).unwrap();
+        let raw = b"aaa\x00v2.3.1-14-gaaa1234\x00Tip\x1ebbb\x00\x00Untagged\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits[0].nearest_tag.as_deref(), Some("v2.3.1"));
+        assert_eq!(commits[0].distance, 14);
+        assert_eq!(commits[1].nearest_tag, None);
+    }
+
+    #[test]
+    fn test_parse_log_source_field() {
+        let format = LogFormat::from_placeholders(&["%H", "%S", "%s"]).unwrap();
+        let raw = b"aaa\x00refs/heads/feature\x00Tip\x1ebbb\x00refs/remotes/origin/main\x00Base\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits[0].source_ref, "refs/heads/feature");
+        assert_eq!(commits[1].source_ref, "refs/remotes/origin/main");
+    }
+
+    #[test]
+    fn test_parse_log_left_right_marks() {
+        // Recorded from `git log --left-right --boundary --format=%m%x00%H%x00%s%x1e`
+        let format = LogFormat::from_placeholders(&["%m", "%H", "%s"]).unwrap();
+        let raw = b">\x00aaa\x00Side\x1e\n<\x00bbb\x00Main\x1e\n-\x00ccc\x00Base\x1e\n";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits[0].side, Some(Side::Right));
+        assert_eq!(commits[1].side, Some(Side::Left));
+        assert_eq!(commits[2].side, Some(Side::Boundary));
+
+        // Marks glued to the hash with `%m%H`
+        let format = LogFormat::from_placeholders(&["%H", "%s"]).unwrap();
+        let commits = parse_log_with_format(b"<aaa\x00Main\x1e>bbb\x00Side\x1eccc\x00Plain\x1e", &format);
+        assert_eq!(commits[0].sha, "aaa");
+        assert_eq!(commits[0].side, Some(Side::Left));
+        assert_eq!(commits[1].sha, "bbb");
+        assert_eq!(commits[1].side, Some(Side::Right));
+        assert_eq!(commits[2].side, None);
+    }
+
+    #[test]
+    fn test_parse_log_with_format_skips_short_records() {
+        let format = LogFormat::from_placeholders(&["%H", "%an", "%s"]).unwrap();
+        let raw = b"abc123\x00Alice\x1edef456\x00Bob\x00Complete\x1e";
+        let commits = parse_log_with_format(raw, &format);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].sha, "def456");
+    }
+
+    #[test]
+    fn test_parse_log_numstat() {
+        let raw = concat!(
+            "aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Two files\x00\x1e\n",
+            "\n",
+            "10\t2\tsrc/graph/layout.rs\n",
+            "-\t-\tresources/icon.png\n",
+            "\n",
+            "bbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00One file\x00\x1e\n",
+            "\n",
+            "3\t0\tREADME.md\n"
+        );
+        let commits = parse_log(raw.as_bytes());
+        assert_eq!(commits.len(), 2);
+
+        assert_eq!(
+            commits[0].files,
+            vec![
+                FileStat {
+                    path: "src/graph/layout.rs".to_string(),
+                    insertions: 10,
+                    deletions: 2,
+                    binary: false,
+                },
+                FileStat {
+                    path: "resources/icon.png".to_string(),
+                    insertions: 0,
+                    deletions: 0,
+                    binary: true,
+                },
+            ]
+        );
+        assert_eq!(commits[1].files.len(), 1);
+        assert_eq!(commits[1].files[0].path, "README.md");
+        assert_eq!(commits[1].files[0].insertions, 3);
+        assert_eq!(commits[1].children, vec!["aaa"]);
+    }
+
+    #[test]
+    fn test_parse_log_name_status() {
+        let raw = concat!(
+            "aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Reorganize\x00\x1e\n",
+            "\n",
+            "A\tsrc/mailmap/mod.rs\n",
+            "M\tsrc/lib.rs\n",
+            "D\tsrc/old.rs\n",
+            "R087\tsrc/graph/parse.rs\tsrc/graph/parser.rs\n",
+            "C100\tLICENSE\tcore/LICENSE\n",
+        );
+        let commits = parse_log(raw.as_bytes());
+        assert_eq!(commits.len(), 1);
+        assert!(commits[0].files.is_empty());
+
+        let changes = &commits[0].changes;
+        assert_eq!(changes.len(), 5);
+        assert_eq!(changes[0].change_type, ChangeType::Added);
+        assert_eq!(changes[1].change_type, ChangeType::Modified);
+        assert_eq!(changes[2].change_type, ChangeType::Deleted);
+        assert_eq!(changes[2].path, "src/old.rs");
+        assert_eq!(
+            changes[3],
+            FileChange {
+                change_type: ChangeType::Renamed,
+                path: "src/graph/parser.rs".to_string(),
+                old_path: Some("src/graph/parse.rs".to_string()),
+                similarity: Some(87),
+            }
+        );
+        assert_eq!(changes[4].change_type, ChangeType::Copied);
+        assert_eq!(changes[4].old_path.as_deref(), Some("LICENSE"));
+        assert_eq!(changes[4].similarity, Some(100));
+    }
+
+    #[test]
+    fn test_split_file_lines_stops_at_record() {
+        let (lines, rest) = split_file_lines("\n\n1\t1\ta.rs\nbbb\x00bb\x00Subject with\ttab\x00\n");
+        assert_eq!(lines, vec!["1\t1\ta.rs"]);
+        assert!(rest.starts_with("bbb\x00"));
     }
 
     #[test]
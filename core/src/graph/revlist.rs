@@ -0,0 +1,138 @@
+use super::encoding::decode_with_label;
+use super::parser::link_children;
+use super::types::CommitNode;
+use crate::input::normalize_input;
+use crate::object::parser::parse_commit_text;
+
+/// Parse `git rev-list --header --parents` output into a Vec<CommitNode>.
+///
+/// Each commit is NUL-terminated and carries its raw object headers, with
+/// the message indented by four spaces:
+/// ```text
+/// <sha> <parent>...\n
+/// tree <sha>\n
+/// parent <sha>\n
+/// author <name> <<email>> <epoch> <tz>\n
+/// committer <name> <<email>> <epoch> <tz>\n
+/// \n
+///     <subject>\n
+///     \n
+///     <body>\n
+/// \0
+/// ```
+/// Since nothing passes through a format string, this input is immune to
+/// separator characters in messages. Parents come from the first line when
+/// `--parents` is given (reflecting history simplification) and from the
+/// `parent` headers otherwise. Ref decorations are not available.
+pub fn parse_rev_list(raw: &[u8]) -> Vec<CommitNode> {
+    let raw = normalize_input(raw);
+    let mut commits: Vec<CommitNode> = raw
+        .split(|&b| b == 0)
+        .filter_map(|record| {
+            let text = decode_with_label(record, &encoding_label(record));
+            parse_rev_list_record(&text)
+        })
+        .collect();
+
+    link_children(&mut commits);
+    commits
+}
+
+/// Value of the `encoding` header, if the record declares one.
+fn encoding_label(record: &[u8]) -> String {
+    record
+        .split(|&b| b == b'\n')
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix(b"encoding "))
+        .map(|label| String::from_utf8_lossy(label).into_owned())
+        .unwrap_or_default()
+}
+
+fn parse_rev_list_record(record: &str) -> Option<CommitNode> {
+    let record = record.trim_start_matches('\n');
+    let (first_line, rest) = record.split_once('\n').unwrap_or((record, ""));
+    let mut ids = first_line.split_whitespace();
+    let sha = ids.next()?;
+    let listed_parents: Vec<String> = ids.map(String::from).collect();
+
+    // Un-indent the message so it reads like a raw commit object
+    let (head, message) = rest.split_once("\n\n").unwrap_or((rest, ""));
+    let message: Vec<&str> = message
+        .lines()
+        .map(|line| line.strip_prefix("    ").unwrap_or(line))
+        .collect();
+    let object = parse_commit_text(sha, &format!("{}\n\n{}", head, message.join("\n")));
+
+    Some(CommitNode {
+        sha: sha.to_string(),
+        short_sha: sha.chars().take(7).collect(),
+        parents: if listed_parents.is_empty() {
+            object.parents
+        } else {
+            listed_parents
+        },
+        author: object.author,
+        committer: object.committer,
+        subject: object.subject,
+        body: object.body,
+        encoding: object.encoding.unwrap_or_default(),
+        lane: -1,
+        row: -1,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Recorded from `git rev-list --header --parents HEAD`
+    const REV_LIST: &[u8] = b"bc0d3e14132108620c0148f79323884e43a5a7d7 2b22692f8c5687776369110bfab41a56a073c41de\n\
+tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+parent 2b22692f8c5687776369110bfab41a56a073c41de\n\
+author B <b@x> 1792149641 +0000\n\
+committer B <b@x> 1792149641 -0130\n\
+\n    Second line\n    \n    Body para\n    \n      indented\n\x00\
+2b22692f8c5687776369110bfab41a56a073c41de\n\
+tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+author A <a@x> 1792149641 +0000\n\
+committer A <a@x> 1792149641 +0000\n\
+\n    First\n\x00";
+
+    #[test]
+    fn test_parse_rev_list_header() {
+        let commits = parse_rev_list(REV_LIST);
+        assert_eq!(commits.len(), 2);
+
+        let tip = &commits[0];
+        assert_eq!(tip.sha, "bc0d3e14132108620c0148f79323884e43a5a7d7");
+        assert_eq!(tip.short_sha, "bc0d3e1");
+        assert_eq!(tip.parents, vec!["2b22692f8c5687776369110bfab41a56a073c41de"]);
+        assert_eq!(tip.author.name, "B");
+        assert_eq!(tip.author.email, "b@x");
+        assert_eq!(tip.committer.time, 1792149641);
+        assert_eq!(tip.committer.tz_offset_min, -90);
+        assert_eq!(tip.subject, "Second line");
+        assert_eq!(tip.body, "Body para\n\n  indented");
+
+        assert!(commits[1].parents.is_empty());
+        assert_eq!(commits[1].subject, "First");
+        assert_eq!(commits[1].children, vec![tip.sha.clone()]);
+    }
+
+    #[test]
+    fn test_parse_rev_list_without_parents_flag() {
+        let raw = b"aaaa\ntree t\nparent bbbb\nparent cccc\nauthor A <a@x> 1 +0000\ncommitter A <a@x> 1 +0000\n\n    Merge\n\x00";
+        let commits = parse_rev_list(raw);
+        assert_eq!(commits[0].parents, vec!["bbbb", "cccc"]);
+    }
+
+    #[test]
+    fn test_parse_rev_list_transcodes_declared_encoding() {
+        let raw = b"aaaa\ntree t\nauthor Ren\xe9 <r@x> 1 +0000\ncommitter Ren\xe9 <r@x> 1 +0000\nencoding ISO-8859-1\n\n    Caf\xe9\n\x00";
+        let commits = parse_rev_list(raw);
+        assert_eq!(commits[0].author.name, "René");
+        assert_eq!(commits[0].subject, "Café");
+        assert_eq!(commits[0].encoding, "ISO-8859-1");
+    }
+}
@@ -0,0 +1,113 @@
+use super::types::{CommitNode, UnresolvedParent};
+
+/// Shortest abbreviation git will produce, and the shortest prefix that is
+/// resolved against the log.
+const MIN_ABBREV: usize = 4;
+
+/// Whether `sha` is a non-empty hex string.
+pub fn is_hex_sha(sha: &str) -> bool {
+    !sha.is_empty() && sha.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Whether `sha` is a full SHA-1 or SHA-256 object name.
+pub fn is_full_sha(sha: &str) -> bool {
+    (sha.len() == 40 || sha.len() == 64) && is_hex_sha(sha)
+}
+
+/// Expand abbreviated parent hashes to the full SHAs of commits in the same
+/// log, so that their edges connect.
+///
+/// Parents that already name a commit in the log, and full-length parents
+/// (which may simply lie beyond the loaded page), are left alone. Returns
+/// the parents that are not valid hex or whose abbreviation matches no
+/// commit or several; these are kept unchanged.
+pub fn normalize_parents(commits: &mut [CommitNode]) -> Vec<UnresolvedParent> {
+    let mut shas: Vec<String> = commits.iter().map(|c| c.sha.clone()).collect();
+    shas.sort();
+    shas.dedup();
+
+    let mut unresolved = Vec::new();
+    for commit in commits.iter_mut() {
+        for parent in &mut commit.parents {
+            if shas.binary_search(parent).is_ok() || is_full_sha(parent) {
+                continue;
+            }
+
+            let reason = if !is_hex_sha(parent) {
+                "Invalid parent hash"
+            } else if parent.len() < MIN_ABBREV {
+                "Abbreviated parent too short"
+            } else {
+                let start = shas.partition_point(|s| s.as_str() < parent.as_str());
+                let mut matches = shas[start..].iter().take_while(|s| s.starts_with(parent.as_str()));
+                match (matches.next(), matches.next()) {
+                    (Some(full), None) => {
+                        *parent = full.clone();
+                        continue;
+                    }
+                    (None, _) => "Abbreviated parent not found in log",
+                    (Some(_), Some(_)) => "Ambiguous abbreviated parent",
+                }
+            };
+            unresolved.push(UnresolvedParent {
+                sha: commit.sha.clone(),
+                parent: parent.clone(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+    unresolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::commit;
+
+    #[test]
+    fn test_sha_validation() {
+        assert!(is_hex_sha("abc1234"));
+        assert!(is_hex_sha("ABC1234"));
+        assert!(!is_hex_sha("abc123g"));
+        assert!(!is_hex_sha(""));
+        assert!(is_full_sha(&"a".repeat(40)));
+        assert!(is_full_sha(&"a".repeat(64)));
+        assert!(!is_full_sha(&"a".repeat(41)));
+    }
+
+    #[test]
+    fn test_normalize_expands_unique_prefixes() {
+        let full = "abc1234def5678abc1234def5678abc1234def56";
+        let mut commits = vec![commit("f00d", &["abc1234"]), commit(full, &[])];
+        let unresolved = normalize_parents(&mut commits);
+        assert!(unresolved.is_empty());
+        assert_eq!(commits[0].parents, vec![full]);
+    }
+
+    #[test]
+    fn test_normalize_flags_unresolvable_parents() {
+        let outside = "9".repeat(40);
+        let mut commits = vec![
+            commit("aaaa1111", &["aaaa", "bbbb", "xyz!", "aa", outside.as_str()]),
+            commit("aaaa2222", &[]),
+        ];
+        let unresolved = normalize_parents(&mut commits);
+
+        let reasons: Vec<(&str, &str)> = unresolved
+            .iter()
+            .map(|u| (u.parent.as_str(), u.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("aaaa", "Ambiguous abbreviated parent"),
+                ("bbbb", "Abbreviated parent not found in log"),
+                ("xyz!", "Invalid parent hash"),
+                ("aa", "Abbreviated parent too short"),
+            ]
+        );
+        assert!(unresolved.iter().all(|u| u.sha == "aaaa1111"));
+        // Unresolved parents are kept as given
+        assert_eq!(commits[0].parents[0], "aaaa");
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use super::encoding::decode_record;
+use super::format::LogFormat;
+use super::parser::{attach_file_lines, parse_record, split_file_lines};
+use super::types::CommitNode;
+use crate::input::{bom_len, normalize_input};
+
+/// Incremental git log parser for output that arrives in chunks.
+///
+/// Chunks may split a record (or a UTF-8 character) anywhere; the trailing
+/// incomplete record is buffered until the next `push` completes it. Children
+/// links are resolved across chunks because a parent always arrives after the
+/// commits that reference it.
+///
+/// File lines (`--numstat`) follow their record's separator, so the most
+/// recent commit is held back until the next record or `finish` shows that
+/// its file list is complete.
+#[derive(Debug, Default)]
+pub struct LogParser {
+    format: LogFormat,
+    pending: Vec<u8>,
+    bytes_consumed: usize,
+    commits_parsed: usize,
+    /// Last parsed commit, waiting for file lines that follow it.
+    held: Option<CommitNode>,
+    /// Children seen for parents that have not been parsed yet.
+    waiting_children: HashMap<String, Vec<String>>,
+}
+
+impl LogParser {
+    pub fn new(format: LogFormat) -> Self {
+        LogParser {
+            format,
+            ..Default::default()
+        }
+    }
+
+    /// Feed the next chunk of output and return the records it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<CommitNode> {
+        self.pending.extend_from_slice(chunk);
+
+        let complete_len = match self.pending.iter().rposition(|&b| b == 0x1e) {
+            Some(pos) => pos + 1,
+            None => return Vec::new(),
+        };
+
+        let complete: Vec<u8> = self.pending.drain(..complete_len).collect();
+        let at_start = self.bytes_consumed == 0;
+        self.bytes_consumed += complete_len;
+        self.parse_records(&complete, at_start)
+    }
+
+    /// Parse whatever is still buffered as a final record, e.g. when git's
+    /// output does not end with a record separator, and release the held
+    /// commit.
+    pub fn finish(&mut self) -> Vec<CommitNode> {
+        let rest = std::mem::take(&mut self.pending);
+        let at_start = self.bytes_consumed == 0;
+        self.bytes_consumed += rest.len();
+        let mut commits = self.parse_records(&rest, at_start);
+        if let Some(last) = self.held.take() {
+            commits.push(last);
+            self.commits_parsed += 1;
+        }
+        commits
+    }
+
+    /// Total bytes turned into records so far. Everything after this offset
+    /// in the concatenated input is still buffered.
+    pub fn bytes_consumed(&self) -> usize {
+        self.bytes_consumed
+    }
+
+    /// Bytes of an incomplete trailing record held for the next chunk.
+    pub fn buffered_bytes(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Total commits emitted so far, not counting the held commit.
+    pub fn commits_parsed(&self) -> usize {
+        self.commits_parsed
+    }
+
+    /// Parse complete records; `at_start` marks the beginning of the
+    /// output, where a byte order mark may appear.
+    fn parse_records(&mut self, bytes: &[u8], at_start: bool) -> Vec<CommitNode> {
+        let mut commits = Vec::new();
+        let bytes = if at_start { &bytes[bom_len(bytes)..] } else { bytes };
+
+        for piece in bytes.split(|&b| b == 0x1e) {
+            let piece = normalize_input(piece);
+            let piece = decode_record(&piece, &self.format);
+            let (file_lines, record) = split_file_lines(&piece);
+            if let Some(held) = self.held.as_mut() {
+                attach_file_lines(held, &file_lines);
+            }
+
+            if record.trim().is_empty() {
+                continue;
+            }
+            // A new record closes the held commit's file list
+            commits.extend(self.held.take());
+
+            let mut node = match parse_record(record, &self.format) {
+                Ok(node) => node,
+                Err(_) => continue,
+            };
+
+            if let Some(children) = self.waiting_children.remove(&node.sha) {
+                node.children = children;
+            }
+            for parent in &node.parents {
+                self.waiting_children
+                    .entry(parent.clone())
+                    .or_default()
+                    .push(node.sha.clone());
+            }
+
+            self.held = Some(node);
+        }
+
+        self.commits_parsed += commits.len();
+        commits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::parser::parse_log;
+
+    const LOG: &[u8] = b"aaa\x00aa\x00bbb\x00Ren\xc3\xa9\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Second\x00\x1e\nbbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00First\x00\x1e\n";
+
+    #[test]
+    fn test_chunked_matches_whole_parse() {
+        let whole = parse_log(LOG);
+
+        // Every split point, including inside the multi-byte "é"
+        for split in 0..LOG.len() {
+            let mut parser = LogParser::new(LogFormat::default());
+            let mut commits = parser.push(&LOG[..split]);
+            commits.extend(parser.push(&LOG[split..]));
+            commits.extend(parser.finish());
+
+            assert_eq!(commits.len(), 2, "split at {}", split);
+            assert_eq!(commits[0].author.name, "René");
+            assert_eq!(commits[1].children, whole[1].children);
+            assert_eq!(parser.bytes_consumed(), LOG.len());
+        }
+    }
+
+    #[test]
+    fn test_partial_record_is_buffered() {
+        let first_end = LOG.iter().position(|&b| b == 0x1e).unwrap() + 1;
+        let mut parser = LogParser::new(LogFormat::default());
+
+        // The first record is complete but held for possible file lines
+        let commits = parser.push(&LOG[..first_end + 10]);
+        assert!(commits.is_empty());
+        assert_eq!(parser.bytes_consumed(), first_end);
+        assert_eq!(parser.buffered_bytes(), 10);
+
+        let commits = parser.push(&LOG[first_end + 10..]);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].sha, "aaa");
+
+        let commits = parser.finish();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].children, vec!["aaa"]);
+        assert_eq!(parser.commits_parsed(), 2);
+    }
+
+    #[test]
+    fn test_numstat_lines_across_chunks() {
+        let log = concat!(
+            "aaa\x00aa\x00bbb\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Second\x00\x1e\n",
+            "\n4\t1\tsrc/lib.rs\n1\t0\tREADME.md\n\n",
+            "bbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00First\x00\x1e\n",
+            "\n2\t0\tsrc/main.rs\n"
+        )
+        .as_bytes();
+        let whole = parse_log(log);
+
+        for split in 0..log.len() {
+            let mut parser = LogParser::new(LogFormat::default());
+            let mut commits = parser.push(&log[..split]);
+            commits.extend(parser.push(&log[split..]));
+            commits.extend(parser.finish());
+
+            assert_eq!(commits.len(), 2, "split at {}", split);
+            assert_eq!(commits[0].files, whole[0].files, "split at {}", split);
+            assert_eq!(commits[1].files, whole[1].files, "split at {}", split);
+        }
+        assert_eq!(whole[0].files.len(), 2);
+    }
+
+    #[test]
+    fn test_crlf_and_bom_across_chunks() {
+        let mut windows = b"\xef\xbb\xbf".to_vec();
+        windows.extend(
+            LOG.split(|&b| b == b'\n')
+                .collect::<Vec<_>>()
+                .join(&b"\r\n"[..]),
+        );
+        let whole = parse_log(LOG);
+
+        for split in 0..windows.len() {
+            let mut parser = LogParser::new(LogFormat::default());
+            let mut commits = parser.push(&windows[..split]);
+            commits.extend(parser.push(&windows[split..]));
+            commits.extend(parser.finish());
+
+            assert_eq!(commits.len(), 2, "split at {}", split);
+            assert_eq!(commits[0].sha, whole[0].sha, "split at {}", split);
+            assert_eq!(commits[1].sha, whole[1].sha, "split at {}", split);
+            assert_eq!(commits[1].children, whole[1].children);
+        }
+    }
+
+    #[test]
+    fn test_finish_flushes_unterminated_record() {
+        let mut parser = LogParser::new(LogFormat::default());
+        let unterminated = &LOG[..LOG.len() - 2];
+        assert!(parser.push(unterminated).is_empty());
+        assert_eq!(parser.finish().len(), 2);
+        assert_eq!(parser.buffered_bytes(), 0);
+    }
+}
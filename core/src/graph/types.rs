@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::signature::Signature;
+
 /// The type of a git reference.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum RefType {
@@ -19,28 +21,178 @@ pub struct RefInfo {
     pub is_head: bool,
 }
 
+/// Per-file line counts from `git log --numstat`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStat {
+    pub path: String,
+    pub insertions: u32,
+    pub deletions: u32,
+    /// git reports `-` for both counts on binary files.
+    pub binary: bool,
+}
+
+/// An issue or merge request key found in a commit message.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueRef {
+    /// The matched text, e.g. `#123` or `PROJ-456`.
+    pub key: String,
+    /// Name of the rule that matched.
+    pub rule: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Kind of change in a `git log --name-status` line.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ChangeType {
+    Added,
+    #[default]
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Unmerged,
+    Unknown,
+}
+
+impl ChangeType {
+    /// Map a status letter (`A`, `M`, `D`, `R`, `C`, `T`, `U`) to a change type.
+    pub fn from_code(code: char) -> ChangeType {
+        match code {
+            'A' => ChangeType::Added,
+            'M' => ChangeType::Modified,
+            'D' => ChangeType::Deleted,
+            'R' => ChangeType::Renamed,
+            'C' => ChangeType::Copied,
+            'T' => ChangeType::TypeChanged,
+            'U' => ChangeType::Unmerged,
+            _ => ChangeType::Unknown,
+        }
+    }
+}
+
+/// A changed file from `git log --name-status`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    pub change_type: ChangeType,
+    /// Path after the change.
+    pub path: String,
+    /// Source path of a rename or copy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// Similarity percentage of a rename or copy (`R100` -> 100).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<u32>,
+}
+
+/// Verification state of a commit's GPG/SSH signature.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Good,
+    Bad,
+    Unknown,
+    Expired,
+    #[default]
+    None,
+}
+
+impl SignatureStatus {
+    /// Map a `%G?` code to a status.
+    ///
+    /// `U` (good, unknown validity) and `E` (key unavailable) are reported as
+    /// `Unknown`; `X`/`Y` (expired signature or key) as `Expired`; a revoked
+    /// key (`R`) is treated as `Bad`.
+    pub fn from_code(code: &str) -> SignatureStatus {
+        match code.trim() {
+            "G" => SignatureStatus::Good,
+            "B" | "R" => SignatureStatus::Bad,
+            "U" | "E" => SignatureStatus::Unknown,
+            "X" | "Y" => SignatureStatus::Expired,
+            _ => SignatureStatus::None,
+        }
+    }
+}
+
 /// A parsed commit node from git log output.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CommitNode {
     pub sha: String,
     pub short_sha: String,
     pub parents: Vec<String>,
     pub children: Vec<String>,
-    pub author_name: String,
-    pub author_email: String,
-    pub author_date: u64,
-    pub committer_name: String,
-    pub committer_email: String,
-    pub commit_date: u64,
+    /// Serialized flat as `author_name`, `author_email`, `author_date` and
+    /// `author_tz_offset_min`.
+    #[serde(flatten, with = "crate::signature::author_fields")]
+    pub author: Signature,
+    /// Serialized flat as `committer_name`, `committer_email`, `commit_date`
+    /// and `committer_tz_offset_min`.
+    #[serde(flatten, with = "crate::signature::committer_fields")]
+    pub committer: Signature,
     pub subject: String,
+    /// Message body after the subject (`%b`); empty when not requested.
+    #[serde(default)]
+    pub body: String,
     pub refs: Vec<RefInfo>,
+    #[serde(default)]
+    pub signature_status: SignatureStatus,
+    /// Signer identity from `%GS`.
+    #[serde(default)]
+    pub signer: String,
+    /// Signing key fingerprint from `%GK`.
+    #[serde(default)]
+    pub signing_key: String,
+    /// Files changed by the commit, when the log was run with `--numstat`.
+    #[serde(default)]
+    pub files: Vec<FileStat>,
+    /// Changed files with change type, when the log was run with
+    /// `--name-status`.
+    #[serde(default)]
+    pub changes: Vec<FileChange>,
+    /// Notes attached via `git notes` (`%N`).
+    #[serde(default)]
+    pub notes: String,
+    /// Reflog selector (`%gd`), e.g. `HEAD@{3}`.
+    #[serde(default)]
+    pub reflog_selector: String,
+    /// Reflog subject (`%gs`), e.g. `commit (amend): Fix typo`.
+    #[serde(default)]
+    pub reflog_subject: String,
+    /// Position in the reflog parsed from the selector (`HEAD@{3}` -> 3).
+    #[serde(default)]
+    pub reflog_index: Option<u32>,
+    /// Encoding declared by the commit (`%e`); text fields are always
+    /// transcoded to UTF-8.
+    #[serde(default)]
+    pub encoding: String,
+    /// Issue keys found in the subject and body; see `issues::IssueExtractor`.
+    #[serde(default)]
+    pub issue_refs: Vec<IssueRef>,
+    /// Nearest tag from `%(describe)`, e.g. `v2.3.1`.
+    #[serde(default)]
+    pub nearest_tag: Option<String>,
+    /// Commits since `nearest_tag`; 0 when the commit is tagged.
+    #[serde(default)]
+    pub distance: u32,
+    /// Ref the commit was listed for, from `%S` with `git log --source`,
+    /// e.g. `refs/heads/feature`.
+    #[serde(default)]
+    pub source_ref: String,
+    /// Side of a `--left-right` comparison, from `%m` or a marker before
+    /// the hash.
+    #[serde(default)]
+    pub side: Option<Side>,
     pub lane: i32,
     pub row: i32,
 }
 
 /// The type of a visual node in the graph layout.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub enum NodeType {
+    #[default]
     Normal,
     Head,
     Stash,
@@ -48,7 +200,7 @@ pub enum NodeType {
 }
 
 /// A node in the rendered graph layout, ready for the UI.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LayoutNode {
     pub sha: String,
@@ -57,11 +209,66 @@ pub struct LayoutNode {
     pub row: i32,
     pub color_index: u32,
     pub subject: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
     pub author_name: String,
+    #[serde(default)]
+    pub author_email: String,
+    #[serde(default)]
+    pub committer_name: String,
+    #[serde(default)]
+    pub committer_email: String,
     pub author_date: u64,
+    /// Author and committer UTC offsets in minutes, for local-time display.
+    #[serde(default)]
+    pub author_tz_offset_min: i32,
+    #[serde(default)]
+    pub committer_tz_offset_min: i32,
     pub refs: Vec<RefInfo>,
     pub parents: Vec<String>,
+    /// Number of parents, saturating at 255; more than one makes a merge.
+    #[serde(default)]
+    pub parent_count: u8,
+    #[serde(default)]
+    pub is_merge: bool,
     pub node_type: NodeType,
+    #[serde(default)]
+    pub signature_status: SignatureStatus,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub signer: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub signing_key: String,
+    /// Changed files for the detail pane, from `--name-status`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FileChange>,
+    /// Per-file line counts, from `--numstat`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<FileStat>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Whether the commit has a note, so the UI can badge it.
+    #[serde(default)]
+    pub has_note: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reflog_selector: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reflog_subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reflog_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issue_refs: Vec<IssueRef>,
+    /// Nearest tag and distance, for rendering versions like `v2.3.1+14`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nearest_tag: Option<String>,
+    #[serde(default)]
+    pub distance: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source_ref: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    /// Shown only as context around the matches of a filter.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_context: bool,
 }
 
 /// The type of an edge connecting two commits.
@@ -69,6 +276,21 @@ pub struct LayoutNode {
 pub enum EdgeType {
     Normal,
     Merge,
+    /// The parent is not part of the loaded commits; `to_row` is -1 and the
+    /// renderer should draw a fading stub in `truncated_direction`.
+    Truncated,
+    /// Stands in for a path through commits hidden by a filter, from a
+    /// match to its nearest matching ancestor; usually drawn dashed.
+    Elided,
+}
+
+/// Which way a truncated edge points, relative to the rows that are loaded.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EdgeDirection {
+    /// The missing endpoint lies below the last loaded row (older history).
+    Down,
+    /// The missing endpoint lies above the first loaded row.
+    Up,
 }
 
 /// An edge connecting two commits in the graph layout.
@@ -83,13 +305,129 @@ pub struct Edge {
     pub to_row: i32,
     pub edge_type: EdgeType,
     pub color_index: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated_direction: Option<EdgeDirection>,
 }
 
 /// The complete result of computing graph layout, returned as JSON to JS.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LayoutResult {
     pub nodes: Vec<LayoutNode>,
     pub edges: Vec<Edge>,
     pub total_count: usize,
 }
+
+/// A log record that was skipped during parsing.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseDiagnostic {
+    /// Zero-based index among the non-blank records in the input.
+    pub record_index: usize,
+    /// Byte offset of the start of the record in the raw input.
+    pub byte_offset: usize,
+    pub reason: String,
+}
+
+/// A parent hash that could not be matched to a full SHA in the log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedParent {
+    /// The commit listing the parent.
+    pub sha: String,
+    pub parent: String,
+    pub reason: String,
+}
+
+/// Parsed commits together with diagnostics for skipped records.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseResult {
+    pub commits: Vec<CommitNode>,
+    pub diagnostics: Vec<ParseDiagnostic>,
+    /// Records dropped because their SHA was already parsed; their ref
+    /// decorations are merged into the first occurrence.
+    pub duplicate_count: usize,
+    /// Abbreviated or malformed parent hashes left unresolved.
+    pub unresolved_parents: Vec<UnresolvedParent>,
+}
+
+/// Which side of a symmetric difference (`git log --left-right A...B`) a
+/// commit was reached from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Side {
+    /// Reachable only from the left revision (`<`).
+    Left,
+    /// Reachable only from the right revision (`>`).
+    Right,
+    /// A boundary commit shown with `--boundary` (`-`).
+    Boundary,
+}
+
+impl Side {
+    /// Map a `%m` mark or graph marker to a side; other marks give `None`.
+    pub fn from_mark(mark: &str) -> Option<Side> {
+        match mark.trim() {
+            "<" => Some(Side::Left),
+            ">" => Some(Side::Right),
+            "-" => Some(Side::Boundary),
+            _ => None,
+        }
+    }
+}
+
+/// Which side of the mainline lane merged branches fan out to.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MergeSide {
+    #[default]
+    Right,
+    Left,
+}
+
+/// Order of the rows of a layout.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CommitOrder {
+    /// The order the commits were given in, normally git's.
+    #[default]
+    Given,
+    /// Newest commit date first, but never a parent above one of its
+    /// children, like `git log --date-order`.
+    Date,
+}
+
+/// How colors are picked for nodes and edges.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ColorStrategy {
+    /// By the branch a lane belongs to, so a branch keeps its color.
+    #[default]
+    Branch,
+    /// By lane, so neighboring lanes always differ.
+    Lane,
+}
+
+/// Knobs controlling how `compute_layout_with_options` assigns lanes and colors.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LayoutOptions {
+    /// When set, commits with a good signature use this color index for
+    /// their node instead of the lane color. Edges keep the lane color.
+    pub verified_color_index: Option<u32>,
+    /// Side merged branches are drawn on. `Left` mirrors all lanes so the
+    /// mainline is the rightmost lane.
+    pub merge_side: MergeSide,
+    /// Treat a merge's second parent as the lane continuation, so the merged
+    /// branch reads as the mainline. Node `parents` keep git's order.
+    pub swap_merge_parents: bool,
+    pub order: CommitOrder,
+    /// Draw only first-parent edges, for `git log --first-parent` output
+    /// where merged branches are not listed and would otherwise hold lanes
+    /// open to the bottom. Node `parents` still list every parent.
+    pub first_parent: bool,
+    pub color_strategy: ColorStrategy,
+    /// Number of colors to pick from, 12 when unset; color indices are in
+    /// `0..palette_size`, apart from `verified_color_index`.
+    pub palette_size: Option<u32>,
+    /// Draw lanes beyond this many on the last one, so a wide history does
+    /// not push the graph column off screen.
+    pub max_lanes: Option<u32>,
+}
@@ -0,0 +1,82 @@
+use super::types::LayoutResult;
+
+/// The rows `offset..offset + limit` of a layout, for drawing one screen of
+/// a large graph without sending the rest.
+///
+/// Keeps the nodes in those rows and every edge that crosses them: edges
+/// with an end in the window and edges passing through it from above to
+/// below. A truncated edge runs to the bottom of the graph. `total_count`
+/// stays that of the whole layout, for sizing the scrollbar.
+pub fn layout_window(layout: &LayoutResult, offset: usize, limit: usize) -> LayoutResult {
+    let start = offset.min(i32::MAX as usize) as i32;
+    let end = offset.saturating_add(limit).min(i32::MAX as usize) as i32;
+
+    let nodes = layout
+        .nodes
+        .iter()
+        .filter(|n| n.row >= start && n.row < end)
+        .cloned()
+        .collect();
+    let edges = layout
+        .edges
+        .iter()
+        .filter(|e| {
+            let to_row = if e.to_row < 0 { i32::MAX } else { e.to_row };
+            e.from_row.min(to_row) < end && e.from_row.max(to_row) >= start
+        })
+        .cloned()
+        .collect();
+
+    LayoutResult {
+        nodes,
+        edges,
+        total_count: layout.total_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::test_support::commit;
+
+    fn shas(layout: &LayoutResult) -> Vec<(&str, &str)> {
+        layout
+            .edges
+            .iter()
+            .map(|e| (e.from_sha.as_str(), e.to_sha.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_layout_window() {
+        // e - d - c - b - a, with a side branch s forking from a
+        let layout = compute_layout(&[
+            commit("e", &["d"]),
+            commit("s", &["a"]),
+            commit("d", &["c"]),
+            commit("c", &["b"]),
+            commit("b", &["a"]),
+            commit("a", &["z"]),
+        ]);
+
+        let window = layout_window(&layout, 2, 2);
+        let rows: Vec<&str> = window.nodes.iter().map(|n| n.sha.as_str()).collect();
+        assert_eq!(rows, vec!["d", "c"]);
+        assert_eq!(window.total_count, 6);
+        // Edges into and out of the window and s -> a passing through it,
+        // but not the truncated a -> z stub below it
+        let mut edges = shas(&window);
+        edges.sort();
+        assert_eq!(edges, vec![("c", "b"), ("d", "c"), ("e", "d"), ("s", "a")]);
+
+        let last = layout_window(&layout, 5, 10);
+        assert_eq!(last.nodes.len(), 1);
+        let mut edges = shas(&last);
+        edges.sort();
+        assert_eq!(edges, vec![("a", "z"), ("b", "a"), ("s", "a")]);
+        assert!(layout_window(&layout, 6, 10).nodes.is_empty());
+        assert!(layout_window(&layout, 0, 0).edges.is_empty());
+        assert_eq!(layout_window(&layout, 0, usize::MAX).edges.len(), layout.edges.len());
+    }
+}
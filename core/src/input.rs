@@ -0,0 +1,86 @@
+use std::borrow::Cow;
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Length of a leading UTF-8 byte order mark, or 0 if there is none.
+pub(crate) fn bom_len(raw: &[u8]) -> usize {
+    if raw.starts_with(UTF8_BOM) {
+        UTF8_BOM.len()
+    } else {
+        0
+    }
+}
+
+/// Undo what Windows shells do to git output piped through them: drop a
+/// leading UTF-8 byte order mark and turn CRLF line endings into LF.
+///
+/// Borrows the input when there is nothing to change. Length-prefixed
+/// formats such as `git cat-file --batch` cannot be repaired this way and
+/// are not normalized.
+pub(crate) fn normalize_input(raw: &[u8]) -> Cow<'_, [u8]> {
+    let raw = &raw[bom_len(raw)..];
+    if !raw.windows(2).any(|w| w == b"\r\n") {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut out = Vec::with_capacity(raw.len());
+    for (i, &b) in raw.iter().enumerate() {
+        if b == b'\r' && raw.get(i + 1) == Some(&b'\n') {
+            continue;
+        }
+        out.push(b);
+    }
+    Cow::Owned(out)
+}
+
+/// `normalize_input` for text that is already decoded, such as `.mailmap`
+/// contents.
+pub(crate) fn normalize_text(text: &str) -> Cow<'_, str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    if text.contains("\r\n") {
+        Cow::Owned(text.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Map an offset in `normalize_input(raw)` (for input without a BOM) back
+/// to the matching offset in `raw`.
+pub(crate) fn raw_offset(raw: &[u8], normalized_offset: usize) -> usize {
+    let mut emitted = 0;
+    for (i, &b) in raw.iter().enumerate() {
+        if emitted == normalized_offset {
+            return i;
+        }
+        if !(b == b'\r' && raw.get(i + 1) == Some(&b'\n')) {
+            emitted += 1;
+        }
+    }
+    raw.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_input_is_borrowed() {
+        assert!(matches!(normalize_input(b"a\nb"), Cow::Borrowed(b"a\nb")));
+        assert!(matches!(normalize_text("a\nb"), Cow::Borrowed("a\nb")));
+    }
+
+    #[test]
+    fn test_strips_bom_and_crlf() {
+        assert_eq!(&*normalize_input(b"\xef\xbb\xbfa\r\nb\rc\r\n"), b"a\nb\rc\n");
+        assert_eq!(normalize_text("\u{feff}a\r\nb"), "a\nb");
+    }
+
+    #[test]
+    fn test_raw_offset() {
+        let raw = b"ab\r\ncd\r\ne";
+        // "ab\ncd\ne": 'c' is at 3, 'e' at 6
+        assert_eq!(raw_offset(raw, 3), 4);
+        assert_eq!(raw_offset(raw, 6), 8);
+        assert_eq!(raw_offset(raw, 0), 0);
+    }
+}
@@ -0,0 +1,165 @@
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::{ErrorCode, ErrorInfo};
+use crate::graph::types::{CommitNode, IssueRef};
+
+/// One issue-key rule, as supplied in the JSON config.
+///
+/// ```json
+/// { "name": "jira", "pattern": "\\b[A-Z][A-Z0-9]+-\\d+\\b",
+///   "url": "https://jira.example.com/browse/$0" }
+/// ```
+/// `url` may reference capture groups with `$1`, `${name}` etc.; `$0` is
+/// the whole match.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IssueRule {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    name: String,
+    regex: Regex,
+    url: Option<String>,
+}
+
+/// Extracts issue references (`#123`, `JIRA-456`, `!78`) from commit
+/// messages using a configurable set of regex rules.
+#[derive(Debug, Clone, Default)]
+pub struct IssueExtractor {
+    rules: Vec<CompiledRule>,
+}
+
+impl IssueExtractor {
+    /// Compile rules, failing on the first invalid pattern.
+    pub fn new(rules: Vec<IssueRule>) -> Result<IssueExtractor, ErrorInfo> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern).map_err(|e| {
+                    let message =
+                        format!("Invalid regex pattern for issue rule {:?}: {}", rule.name, e);
+                    ErrorInfo::with_code(ErrorCode::BadRegex, message)
+                })?;
+                Ok(CompiledRule {
+                    name: rule.name,
+                    regex,
+                    url: rule.url,
+                })
+            })
+            .collect::<Result<Vec<_>, ErrorInfo>>()?;
+        Ok(IssueExtractor { rules })
+    }
+
+    /// Parse a JSON array of rules.
+    pub fn from_json(json: &str) -> Result<IssueExtractor, ErrorInfo> {
+        let rules: Vec<IssueRule> = serde_json::from_str(json).map_err(|e| {
+            ErrorInfo::with_code(ErrorCode::BadArgument, format!("Invalid issue rules: {}", e))
+        })?;
+        IssueExtractor::new(rules)
+    }
+
+    /// Number of rules loaded.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Find issue references in `text`, in order of first appearance per
+    /// rule. The same key is reported once per rule.
+    pub fn extract(&self, text: &str) -> Vec<IssueRef> {
+        let mut refs: Vec<IssueRef> = Vec::new();
+        for rule in &self.rules {
+            for caps in rule.regex.captures_iter(text) {
+                let key = caps[0].to_string();
+                if refs.iter().any(|r| r.rule == rule.name && r.key == key) {
+                    continue;
+                }
+                let url = rule.url.as_ref().map(|template| {
+                    let mut url = String::new();
+                    caps.expand(template, &mut url);
+                    url
+                });
+                refs.push(IssueRef {
+                    key,
+                    rule: rule.name.clone(),
+                    url,
+                });
+            }
+        }
+        refs
+    }
+
+    /// Fill `issue_refs` on each commit from its subject and body.
+    pub fn apply_to_commits(&self, commits: &mut [CommitNode]) {
+        if self.is_empty() {
+            return;
+        }
+        for commit in commits {
+            let mut refs = self.extract(&commit.subject);
+            for r in self.extract(&commit.body) {
+                if !refs.iter().any(|e| e.rule == r.rule && e.key == r.key) {
+                    refs.push(r);
+                }
+            }
+            commit.issue_refs = refs;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r##"[
+        {"name": "github", "pattern": "#(\\d+)", "url": "https://github.com/o/r/issues/$1"},
+        {"name": "jira", "pattern": "\\b[A-Z][A-Z0-9]+-\\d+\\b"},
+        {"name": "gitlab-mr", "pattern": "!(\\d+)", "url": "https://gitlab.com/o/r/-/merge_requests/${1}"}
+    ]"##;
+
+    #[test]
+    fn test_extract_all_rule_kinds() {
+        let extractor = IssueExtractor::from_json(RULES).unwrap();
+        assert_eq!(extractor.len(), 3);
+
+        let refs = extractor.extract("Fix crash (#123, PROJ-456) see !78 and #123");
+        let keys: Vec<&str> = refs.iter().map(|r| r.key.as_str()).collect();
+        assert_eq!(keys, vec!["#123", "PROJ-456", "!78"]);
+        assert_eq!(refs[0].url.as_deref(), Some("https://github.com/o/r/issues/123"));
+        assert_eq!(refs[1].rule, "jira");
+        assert_eq!(refs[1].url, None);
+        assert_eq!(
+            refs[2].url.as_deref(),
+            Some("https://gitlab.com/o/r/-/merge_requests/78")
+        );
+    }
+
+    #[test]
+    fn test_apply_to_commits_merges_subject_and_body() {
+        let extractor = IssueExtractor::from_json(RULES).unwrap();
+        let mut commits = vec![CommitNode {
+            subject: "Fix login (#12)".to_string(),
+            body: "Refs #12 and #13".to_string(),
+            ..Default::default()
+        }];
+        extractor.apply_to_commits(&mut commits);
+        let keys: Vec<&str> = commits[0].issue_refs.iter().map(|r| r.key.as_str()).collect();
+        assert_eq!(keys, vec!["#12", "#13"]);
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let err = IssueExtractor::from_json(r#"[{"name": "bad", "pattern": "("}]"#).unwrap_err();
+        assert!(err.message.contains("\"bad\""));
+        assert_eq!(err.code, ErrorCode::BadRegex);
+        assert_eq!(IssueExtractor::from_json("{}").unwrap_err().code, ErrorCode::BadArgument);
+        assert!(IssueExtractor::from_json("[]").unwrap().is_empty());
+    }
+}
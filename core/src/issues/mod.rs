@@ -0,0 +1,3 @@
+pub mod extractor;
+
+pub use extractor::{IssueExtractor, IssueRule};
@@ -1,14 +1,34 @@
 pub mod graph;
 pub mod blame;
+pub mod analysis;
 pub mod filter;
+pub mod mailmap;
+pub mod refs;
+pub mod object;
+pub mod issues;
+mod input;
+pub mod output;
+mod schema;
+pub mod signature;
+pub mod error;
+pub mod trailers;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::OnceLock;
+use std::sync::{Mutex, MutexGuard};
 
 use wasm_bindgen::prelude::*;
 
+use error::{ErrorCode, ErrorInfo};
 use graph::types::LayoutResult;
+use graph::LogFormat;
+use issues::IssueExtractor;
+use mailmap::Mailmap;
+use output::OutputFormat;
 
 // ---------------------------------------------------------------------------
 // Handle storage for persistent LayoutResult instances across WASM calls.
@@ -21,8 +41,117 @@ fn layout_store() -> &'static Mutex<LayoutStore> {
     STORE.get_or_init(|| Mutex::new(LayoutStore::new()))
 }
 
+/// How pages appended to a stored layout are parsed.
+enum LogInput {
+    /// `git log` output in the given format.
+    Log(LogFormat),
+    /// `git rev-list --header --parents` output.
+    RevList,
+}
+
+impl LogInput {
+    fn parse(&self, raw_log: &[u8]) -> Vec<graph::types::CommitNode> {
+        match self {
+            LogInput::Log(format) => graph::parse_log_with_format(raw_log, format),
+            LogInput::RevList => graph::parse_rev_list(raw_log),
+        }
+    }
+}
+
+/// A layout plus the inputs needed to extend it later.
+struct StoredLayout {
+    layout: LayoutResult,
+    /// Input the layout was parsed from; appends reuse it. `None` for a
+    /// filtered view, which cannot be appended to.
+    input: Option<LogInput>,
+    /// For a filtered view, the layout it was derived from. Freeing a
+    /// layout frees its views with it.
+    parent: Option<u32>,
+    /// Query set with `set_active_filter`, evaluated over each appended
+    /// page.
+    active_filter: Option<filter::Query>,
+    /// When the handle was issued, in milliseconds since the epoch.
+    created_at: f64,
+    /// The commits behind `layout`, in row order, with what its nodes leave
+    /// out (committer dates, children, encodings), so appends rebuild the
+    /// layout from complete data. A view holds its commits from the parent.
+    commits: Vec<graph::types::CommitNode>,
+    /// Parser for log output still arriving via `push_log_chunk`; `None`
+    /// once the layout is complete.
+    session: Option<graph::LogParser>,
+    /// Options the layout was computed with; appends reuse them.
+    options: graph::LayoutOptions,
+}
+
+impl StoredLayout {
+    /// Rough heap footprint: the node and edge structs plus the strings
+    /// they own.
+    fn approx_bytes(&self) -> usize {
+        let nodes: usize = self
+            .layout
+            .nodes
+            .iter()
+            .map(|n| {
+                std::mem::size_of_val(n)
+                    + n.sha.len()
+                    + n.short_sha.len()
+                    + n.subject.len()
+                    + n.body.len()
+                    + n.author_name.len()
+                    + n.author_email.len()
+                    + n.committer_name.len()
+                    + n.committer_email.len()
+                    + n.parents.iter().map(String::len).sum::<usize>()
+                    + n.refs.iter().map(|r| r.name.len()).sum::<usize>()
+                    + n.files.iter().map(|f| f.path.len()).sum::<usize>()
+                    + n.changes.iter().map(|c| c.path.len()).sum::<usize>()
+            })
+            .sum();
+        let edges: usize = self
+            .layout
+            .edges
+            .iter()
+            .map(|e| std::mem::size_of_val(e) + e.from_sha.len() + e.to_sha.len())
+            .sum();
+        let commits: usize = self
+            .commits
+            .iter()
+            .map(|c| {
+                std::mem::size_of_val(c)
+                    + c.sha.len()
+                    + c.short_sha.len()
+                    + c.subject.len()
+                    + c.body.len()
+                    + c.author.name.len()
+                    + c.author.email.len()
+                    + c.committer.name.len()
+                    + c.committer.email.len()
+                    + c.parents.iter().chain(&c.children).map(String::len).sum::<usize>()
+                    + c.refs.iter().map(|r| r.name.len()).sum::<usize>()
+                    + c.files.iter().map(|f| f.path.len()).sum::<usize>()
+                    + c.changes.iter().map(|f| f.path.len()).sum::<usize>()
+            })
+            .sum();
+        nodes + edges + commits
+    }
+}
+
+/// Milliseconds since the Unix epoch, from the JS clock under WASM.
+fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_millis() as f64)
+    }
+}
+
 struct LayoutStore {
-    layouts: HashMap<u32, LayoutResult>,
+    layouts: HashMap<u32, StoredLayout>,
     next_handle: u32,
 }
 
@@ -34,7 +163,7 @@ impl LayoutStore {
         }
     }
 
-    fn insert(&mut self, layout: LayoutResult) -> u32 {
+    fn insert(&mut self, layout: StoredLayout) -> u32 {
         let handle = self.next_handle;
         self.next_handle = self.next_handle.wrapping_add(1);
         if self.next_handle == 0 {
@@ -44,19 +173,147 @@ impl LayoutStore {
         handle
     }
 
-    fn get(&self, handle: u32) -> Option<&LayoutResult> {
+    fn get(&self, handle: u32) -> Option<&StoredLayout> {
         self.layouts.get(&handle)
     }
 
-    fn get_mut(&mut self, handle: u32) -> Option<&mut LayoutResult> {
+    fn get_mut(&mut self, handle: u32) -> Option<&mut StoredLayout> {
         self.layouts.get_mut(&handle)
     }
 
+    /// Remove a layout along with the views derived from it, and theirs.
+    fn remove(&mut self, handle: u32) -> bool {
+        if self.layouts.remove(&handle).is_none() {
+            return false;
+        }
+        let mut freed = vec![handle];
+        while let Some(parent) = freed.pop() {
+            let views: Vec<u32> = self
+                .layouts
+                .iter()
+                .filter(|(_, stored)| stored.parent == Some(parent))
+                .map(|(&view, _)| view)
+                .collect();
+            for view in views {
+                self.layouts.remove(&view);
+                freed.push(view);
+            }
+        }
+        true
+    }
+
+    /// Drop every layout, returning how many there were. Handles keep
+    /// counting up, so stale handles are never reissued.
+    fn clear(&mut self) -> usize {
+        let count = self.layouts.len();
+        self.layouts.clear();
+        count
+    }
+}
+
+/// Global storage for blame results, keyed by opaque u32 handles like
+/// layouts, so the editor can query a viewport without re-sending the
+/// whole file's blame.
+fn blame_store() -> &'static Mutex<BlameStore> {
+    static STORE: OnceLock<Mutex<BlameStore>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(BlameStore::new()))
+}
+
+/// Blame entries parsed so far for one file.
+struct StoredBlame {
+    entries: Vec<blame::BlameEntry>,
+    /// Parser for output still arriving; `None` once the blame is complete.
+    session: Option<blame::BlameParser>,
+}
+
+struct BlameStore {
+    blames: HashMap<u32, StoredBlame>,
+    next_handle: u32,
+}
+
+impl BlameStore {
+    fn new() -> Self {
+        BlameStore {
+            blames: HashMap::new(),
+            next_handle: 1,
+        }
+    }
+
+    fn insert(&mut self, blame: StoredBlame) -> u32 {
+        let handle = self.next_handle;
+        self.next_handle = self.next_handle.wrapping_add(1);
+        if self.next_handle == 0 {
+            self.next_handle = 1; // skip 0 as a sentinel
+        }
+        self.blames.insert(handle, blame);
+        handle
+    }
+
+    fn get(&self, handle: u32) -> Option<&StoredBlame> {
+        self.blames.get(&handle)
+    }
+
+    fn get_mut(&mut self, handle: u32) -> Option<&mut StoredBlame> {
+        self.blames.get_mut(&handle)
+    }
+
     fn remove(&mut self, handle: u32) -> bool {
-        self.layouts.remove(&handle).is_some()
+        self.blames.remove(&handle).is_some()
+    }
+
+    /// Drop every blame and session, as `LayoutStore::clear`.
+    fn clear(&mut self) -> usize {
+        let count = self.blames.len();
+        self.blames.clear();
+        count
     }
 }
 
+/// Parsed blames of open editors, so switching between them does not
+/// re-parse identical output.
+fn blame_cache() -> &'static Mutex<blame::BlameCache> {
+    static CACHE: OnceLock<Mutex<blame::BlameCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(blame::BlameCache::default()))
+}
+
+/// Mailmap applied to every log and blame parse, set via `set_mailmap`.
+fn mailmap_store() -> &'static Mutex<Mailmap> {
+    static MAILMAP: OnceLock<Mutex<Mailmap>> = OnceLock::new();
+    MAILMAP.get_or_init(|| Mutex::new(Mailmap::default()))
+}
+
+/// Issue-key rules applied to every log parse, set via `set_issue_rules`.
+fn issue_rules_store() -> &'static Mutex<IssueExtractor> {
+    static RULES: OnceLock<Mutex<IssueExtractor>> = OnceLock::new();
+    RULES.get_or_init(|| Mutex::new(IssueExtractor::default()))
+}
+
+/// Named queries saved via `save_filter`, shared by every layout.
+fn saved_filters() -> &'static Mutex<filter::SavedFilters> {
+    static FILTERS: OnceLock<Mutex<filter::SavedFilters>> = OnceLock::new();
+    FILTERS.get_or_init(|| Mutex::new(filter::SavedFilters::default()))
+}
+
+/// Lock one of the global stores, recovering it if a call panicked while
+/// holding it.
+///
+/// A plain `lock()` would fail forever after such a panic, taking every
+/// later call down with it. Stores are only written once an export has its
+/// result, so a panic leaves the data behind the lock as it was. This only
+/// helps where panics unwind; see `guarded`.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// Post-parse enrichment shared by every export that parses a log.
+fn enrich_commits(commits: &mut [graph::types::CommitNode]) {
+    lock(mailmap_store()).apply_to_commits(commits);
+    lock(issue_rules_store()).apply_to_commits(commits);
+}
+
 // ---------------------------------------------------------------------------
 // JSON result wrapper for returning handle + data together.
 // ---------------------------------------------------------------------------
@@ -68,16 +325,132 @@ struct HandleResult {
     layout: LayoutResult,
 }
 
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppendResult {
+    handle: u32,
+    #[serde(flatten)]
+    layout: LayoutResult,
+    /// Matches of the active filter among the appended commits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter_matches: Option<LayoutResult>,
+}
+
+#[derive(serde::Serialize)]
+struct BlameChunkResult {
+    handle: u32,
+    entries: Vec<blame::BlameEntry>,
+}
+
+/// Every failed export returns { error: { code, message, details? } }; see
+/// `ErrorCode` for the codes.
 #[derive(serde::Serialize)]
 struct ErrorResult {
-    error: String,
+    error: ErrorInfo,
+}
+
+/// `error_json` for an error of the kind `code` raised here.
+fn json_error(code: ErrorCode, message: impl Into<String>) -> String {
+    error_json(ErrorInfo::with_code(code, message))
+}
+
+fn error_json(error: ErrorInfo) -> String {
+    let fallback = format!(
+        "{{\"error\":{{\"code\":\"INTERNAL\",\"message\":{:?}}}}}",
+        error.message
+    );
+    serde_json::to_string(&ErrorResult { error }).unwrap_or(fallback)
+}
+
+/// The error for a handle that was never issued or has been freed.
+fn invalid_handle(handle: u32) -> String {
+    error_json(invalid_handle_error(handle))
+}
+
+/// `invalid_handle` for helpers that return the error to their export.
+fn invalid_handle_error(handle: u32) -> ErrorInfo {
+    let message = format!("Invalid handle: {}", handle);
+    ErrorInfo {
+        details: Some(serde_json::json!({ "handle": handle })),
+        ..ErrorInfo::with_code(ErrorCode::InvalidHandle, message)
+    }
+}
+
+/// Run an export's body, turning a panic in it into a `PANIC`
+/// error result instead of unwinding into JS.
+///
+/// Every export that does more than hand its arguments to another export
+/// runs in here. With the `console_error_panic_hook` feature the panic
+/// message and stack are also logged to the browser console.
+///
+/// Only builds where panics unwind get the error result: native builds,
+/// tests, and WASM built with `-C panic=unwind` (nightly, `-Z build-std`
+/// and the exception-handling target feature). The stock
+/// wasm32-unknown-unknown build aborts on panic, which traps: the export
+/// throws a `WebAssembly.RuntimeError`, and any store it had locked stays
+/// locked, so later calls that need the store trap as well. The module
+/// instance cannot be recovered then and must be reloaded.
+/// `core_info().features.panicUnwind` tells the two builds apart.
+fn guarded<R>(body: impl FnOnce() -> R, on_panic: impl FnOnce(ErrorInfo) -> R) -> R {
+    init_panic_hook();
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        on_panic(ErrorInfo::with_code(ErrorCode::Panic, format!("Internal panic: {}", reason)))
+    })
+}
+
+/// `guarded` for exports returning JSON.
+fn guarded_json(body: impl FnOnce() -> String) -> String {
+    guarded(body, error_json)
+}
+
+/// Send panic messages to the browser console with their stack, when built
+/// with the `console_error_panic_hook` feature. Called by every guarded
+/// export, so calling it up front is only needed to cover the others.
+#[wasm_bindgen]
+pub fn init_panic_hook() {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+}
+
+/// Schema version of all JSON output, set via `set_output_schema`.
+static OUTPUT_SCHEMA: AtomicU32 = AtomicU32::new(schema::LEGACY);
+
+/// Serialize an export's result in the selected output schema.
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    schema::to_json_string(value, OUTPUT_SCHEMA.load(Ordering::Relaxed))
+        .unwrap_or_else(|e| {
+            json_error(ErrorCode::Serialization, format!("Serialization error: {}", e))
+        })
+}
+
+/// `to_json` for the `_encoded` exports, in the requested format.
+fn encode<T: serde::Serialize>(value: &T, format: OutputFormat) -> Vec<u8> {
+    output::encode(value, format, OUTPUT_SCHEMA.load(Ordering::Relaxed))
+        .unwrap_or_else(|e| {
+            let message = format!("Serialization error: {}", e);
+            encode_error(ErrorInfo::with_code(ErrorCode::Serialization, message), format)
+        })
+}
+
+/// `json_error` for the `_encoded` exports, in the requested format.
+fn encode_error(error: ErrorInfo, format: OutputFormat) -> Vec<u8> {
+    let error = ErrorResult { error };
+    output::encode(&error, format, schema::LEGACY)
+        .unwrap_or_else(|_| error_json(error.error).into_bytes())
 }
 
-fn json_error(msg: &str) -> String {
-    serde_json::to_string(&ErrorResult {
-        error: msg.to_string(),
+/// Parse the `format` argument of an `_encoded` export. An unknown format
+/// has nothing to encode its error in, so the export throws it instead.
+fn parse_output_format(format: &str) -> Result<OutputFormat, String> {
+    OutputFormat::from_name(format).ok_or_else(|| {
+        let message = format!("Unknown output format: '{}'", format);
+        error_json(ErrorInfo::with_code(ErrorCode::BadArgument, message))
     })
-    .unwrap_or_else(|_| format!("{{\"error\":\"{}\"}}", msg))
 }
 
 // ---------------------------------------------------------------------------
@@ -93,246 +466,3943 @@ fn json_error(msg: &str) -> String {
 /// `filter_by_date`, and must be freed with `free_layout` when done.
 #[wasm_bindgen]
 pub fn compute_graph_layout(raw_log: &[u8]) -> String {
-    let commits = graph::parse_log(raw_log);
-    let layout = graph::compute_layout(&commits);
-
-    let mut store = match layout_store().lock() {
-        Ok(s) => s,
-        Err(_) => return json_error("Failed to acquire layout store lock"),
-    };
+    guarded_json(|| compute_and_store(raw_log, LogFormat::default()))
+}
 
-    let handle = store.insert(layout.clone());
+/// Compute the graph layout from git log output in a custom format.
+///
+/// `format_json` is a JSON array of the placeholders used in the
+/// `--format` string, in order, e.g. `["%H", "%P", "%an", "%at", "%s", "%b"]`.
+/// It must include `%H`. The format is remembered for the handle, so
+/// `append_to_layout` must be fed pages in the same format.
+///
+/// Returns: JSON string with { handle, nodes, edges, total_count }.
+#[wasm_bindgen]
+pub fn compute_graph_layout_with_format(raw_log: &[u8], format_json: &str) -> String {
+    guarded_json(|| {
+        match parse_format_json(format_json) {
+            Ok(format) => compute_and_store(raw_log, format),
+            Err(e) => error_json(e),
+        }
+    })
+}
 
-    let result = HandleResult { handle, layout };
+/// Compute the graph layout with layout options.
+///
+/// `options_json` is an object whose keys are all optional:
+/// - `format`: placeholder array as for `compute_graph_layout_with_format`.
+/// - `order`: row order, `"Given"` (as listed, the default) or `"Date"`
+///   (newest committer date first, never a parent above its child).
+/// - `firstParent`: draw only first-parent edges, for
+///   `git log --first-parent` output.
+/// - `colorStrategy`: `"Branch"` (color by branch name, the default) or
+///   `"Lane"` (color by lane index).
+/// - `paletteSize`: number of colors, 12 by default.
+/// - `maxLanes`: lanes beyond this many are drawn on the last one.
+/// - `mergeSide`, `swapMergeParents`, `verifiedColorIndex`: as in
+///   `LayoutOptions`.
+///
+/// An empty string uses the defaults. The options are remembered for the
+/// handle and applied again by `append_to_layout`.
+///
+/// Returns: JSON string with { handle, nodes, edges, total_count }, or
+/// { error } if the options are invalid.
+#[wasm_bindgen]
+pub fn compute_graph_layout_with_options(raw_log: &[u8], options_json: &str) -> String {
+    guarded_json(|| {
+        let options: GraphLayoutOptions = if options_json.trim().is_empty() {
+            GraphLayoutOptions::default()
+        } else {
+            match serde_json::from_str(options_json) {
+                Ok(options) => options,
+                Err(e) => {
+                    return json_error(
+                        ErrorCode::BadArgument,
+                        format!("Invalid layout options: {}", e),
+                    )
+                }
+            }
+        };
+        let format = match options.format {
+            Some(placeholders) => match LogFormat::from_placeholders(&placeholders) {
+                Ok(format) => format,
+                Err(e) => return error_json(e),
+            },
+            None => LogFormat::default(),
+        };
+        let input = LogInput::Log(format);
+        to_json(&layout_and_store(input.parse(raw_log), input, options.layout))
+    })
+}
 
-    serde_json::to_string(&result).unwrap_or_else(|e| json_error(&format!("Serialization error: {}", e)))
+/// Options of `compute_graph_layout_with_options`.
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct GraphLayoutOptions {
+    format: Option<Vec<String>>,
+    #[serde(flatten)]
+    layout: graph::LayoutOptions,
 }
 
-/// Append additional commits to an existing layout.
+/// Compute the graph layout from `git log --graph --format=…` output.
 ///
-/// Parses the new raw log, computes layout for the combined set, and updates
-/// the stored layout in place.
+/// `format_json` lists the placeholders as for
+/// `compute_graph_layout_with_format`. The ASCII graph columns are stripped,
+/// and when the format has no `%P` the parents are read from the drawn
+/// graph instead. Pages passed to `append_to_layout` for the returned handle
+/// must be plain (non-`--graph`) log output.
 ///
-/// Returns: JSON string with the updated { handle, nodes, edges, total_count }.
+/// Returns: JSON string with { handle, nodes, edges, total_count }.
 #[wasm_bindgen]
-pub fn append_to_layout(handle: u32, raw_log: &[u8]) -> String {
-    let new_commits = graph::parse_log(raw_log);
-    if new_commits.is_empty() {
-        // No new commits to add; return the existing layout
-        let store = match layout_store().lock() {
-            Ok(s) => s,
-            Err(_) => return json_error("Failed to acquire layout store lock"),
-        };
-        return match store.get(handle) {
-            Some(layout) => {
-                let result = HandleResult {
-                    handle,
-                    layout: layout.clone(),
-                };
-                serde_json::to_string(&result)
-                    .unwrap_or_else(|e| json_error(&format!("Serialization error: {}", e)))
+pub fn compute_graph_layout_from_graph_log(raw_log: &[u8], format_json: &str) -> String {
+    guarded_json(|| {
+        match parse_format_json(format_json) {
+            Ok(format) => {
+                let commits = graph::parse_graph_log(raw_log, &format);
+                store_commits(commits, LogInput::Log(format))
             }
-            None => json_error(&format!("Invalid handle: {}", handle)),
-        };
-    }
+            Err(e) => error_json(e),
+        }
+    })
+}
 
-    let mut store = match layout_store().lock() {
-        Ok(s) => s,
-        Err(_) => return json_error("Failed to acquire layout store lock"),
-    };
+/// Compute the graph layout from `git rev-list --header --parents` output.
+///
+/// Commits are read from their raw headers, so messages need no escaping.
+/// Pages passed to `append_to_layout` for the returned handle must be
+/// rev-list output too.
+///
+/// Returns: JSON string with { handle, nodes, edges, total_count }.
+#[wasm_bindgen]
+pub fn compute_graph_layout_from_rev_list(raw: &[u8]) -> String {
+    guarded_json(|| store_commits(graph::parse_rev_list(raw), LogInput::RevList))
+}
 
-    let existing_layout = match store.get(handle) {
-        Some(l) => l.clone(),
-        None => return json_error(&format!("Invalid handle: {}", handle)),
-    };
+fn parse_format_json(format_json: &str) -> Result<LogFormat, ErrorInfo> {
+    let placeholders: Vec<String> = serde_json::from_str(format_json)
+        .map_err(|e| {
+            ErrorInfo::with_code(ErrorCode::BadArgument, format!("Invalid log format: {}", e))
+        })?;
+    LogFormat::from_placeholders(&placeholders)
+}
 
-    // Collect existing SHAs to avoid duplicates
-    let existing_shas: std::collections::HashSet<&str> = existing_layout
-        .nodes
-        .iter()
-        .map(|n| n.sha.as_str())
-        .collect();
+/// Parse a log and report records that were skipped, for debugging missing
+/// commits. No layout is computed or stored.
+///
+/// `format_json` is a placeholder array as for
+/// `compute_graph_layout_with_format`, or an empty string for the default.
+/// Returns: JSON string with { commits, diagnostics, duplicateCount }, where
+/// each diagnostic is { recordIndex, byteOffset, reason }.
+#[wasm_bindgen]
+pub fn parse_log_diagnostics(raw_log: &[u8], format_json: &str) -> String {
+    guarded_json(|| {
+        let format = if format_json.trim().is_empty() {
+            LogFormat::default()
+        } else {
+            match parse_format_json(format_json) {
+                Ok(format) => format,
+                Err(e) => return error_json(e),
+            }
+        };
 
-    // Filter out duplicates from new commits
-    let unique_new: Vec<_> = new_commits
-        .into_iter()
-        .filter(|c| !existing_shas.contains(c.sha.as_str()))
-        .collect();
+        let mut result = graph::parse_log_with_diagnostics(raw_log, &format);
+        enrich_commits(&mut result.commits);
+        to_json(&result)
+    })
+}
 
-    if unique_new.is_empty() {
-        let result = HandleResult {
-            handle,
-            layout: existing_layout,
-        };
-        return serde_json::to_string(&result)
-            .unwrap_or_else(|e| json_error(&format!("Serialization error: {}", e)));
-    }
-
-    // Re-parse ALL commits: we need the original raw commit data to rebuild.
-    // Since we only store LayoutResult (not raw CommitNodes), we rebuild
-    // CommitNode entries from the existing layout nodes + new parsed commits.
-    // This is a simplification; for a production system you'd store the raw nodes too.
-    let mut all_commits: Vec<graph::types::CommitNode> = existing_layout
-        .nodes
-        .iter()
-        .map(|ln| graph::types::CommitNode {
-            sha: ln.sha.clone(),
-            short_sha: ln.short_sha.clone(),
-            parents: ln.parents.clone(),
-            children: Vec::new(),
-            author_name: ln.author_name.clone(),
-            author_email: String::new(),
-            author_date: ln.author_date,
-            committer_name: String::new(),
-            committer_email: String::new(),
-            commit_date: 0,
-            subject: ln.subject.clone(),
-            refs: ln.refs.clone(),
-            lane: -1,
-            row: -1,
-        })
-        .collect();
+fn compute_and_store(raw_log: &[u8], format: LogFormat) -> String {
+    let input = LogInput::Log(format);
+    store_commits(input.parse(raw_log), input)
+}
 
-    all_commits.extend(unique_new);
+fn store_commits(commits: Vec<graph::types::CommitNode>, input: LogInput) -> String {
+    to_json(&layout_and_store(commits, input, graph::LayoutOptions::default()))
+}
 
-    // Recompute layout on the combined set
-    let new_layout = graph::compute_layout(&all_commits);
+fn layout_and_store(
+    mut commits: Vec<graph::types::CommitNode>,
+    input: LogInput,
+    options: graph::LayoutOptions,
+) -> HandleResult {
+    enrich_commits(&mut commits);
+    let layout = graph::compute_layout_with_options(&commits, &options);
 
-    // Update the store
-    if let Some(stored) = store.get_mut(handle) {
-        *stored = new_layout.clone();
-    }
+    let mut store = lock(layout_store());
 
-    let result = HandleResult {
-        handle,
-        layout: new_layout,
-    };
+    let handle = store.insert(StoredLayout {
+        layout: layout.clone(),
+        input: Some(input),
+        parent: None,
+        active_filter: None,
+        created_at: now_ms(),
+        commits,
+        session: None,
+        options,
+    });
 
-    serde_json::to_string(&result)
-        .unwrap_or_else(|e| json_error(&format!("Serialization error: {}", e)))
+    HandleResult { handle, layout }
 }
 
-/// Free a previously allocated layout handle and its associated data.
+/// `compute_graph_layout`, returning the result in `format`: "json" or
+/// "msgpack" (MessagePack with the same keys and shape as the JSON, which
+/// avoids the cost of building and parsing a JSON string for large logs).
 ///
-/// After calling this, the handle is invalid and must not be used.
+/// Returns: the encoded { handle, nodes, edges, total_count }, or an
+/// encoded { error }. Throws a JSON { error } string, as `GraphLayout`'s
+/// constructor does, if the format is unknown.
 #[wasm_bindgen]
-pub fn free_layout(handle: u32) {
-    if let Ok(mut store) = layout_store().lock() {
-        store.remove(handle);
-    }
+pub fn compute_graph_layout_encoded(raw_log: &[u8], format: &str) -> Result<Vec<u8>, String> {
+    let format = parse_output_format(format)?;
+    Ok(guarded(
+        || {
+            let input = LogInput::Log(LogFormat::default());
+            let options = graph::LayoutOptions::default();
+            encode(&layout_and_store(input.parse(raw_log), input, options), format)
+        },
+        |e| encode_error(e, format),
+    ))
 }
 
-/// Parse raw `git blame --incremental` output into JSON.
+/// A buffer in WASM memory for the host to write input into directly, so
+/// a multi-megabyte log or blame crosses the boundary with one copy into
+/// the module instead of another for every call that takes `&[u8]`.
 ///
-/// Returns: JSON array of BlameEntry objects.
+/// Write through `new Uint8Array(memory.buffer, buf.ptr(), buf.len())`,
+/// then pass the buffer to the `_from_buffer` exports. JS arrays live
+/// outside WASM memory, so input has to be copied in once either way;
+/// this lets it be written in place and reused page after page. Views
+/// are invalidated when the memory grows or the buffer is resized. Call
+/// `free()` when done.
 #[wasm_bindgen]
-pub fn parse_blame(raw_blame: &[u8]) -> String {
-    let entries = blame::parse_blame_output(raw_blame);
-    serde_json::to_string(&entries)
-        .unwrap_or_else(|e| json_error(&format!("Serialization error: {}", e)))
+pub struct InputBuffer {
+    bytes: Vec<u8>,
 }
 
-/// Filter commits in a stored layout by a regex pattern on a field.
-///
-/// Supported fields: "message", "author", "committer", "sha".
-/// Returns: JSON LayoutResult with only matching commits and edges.
 #[wasm_bindgen]
-pub fn filter_commits(handle: u32, field: &str, pattern: &str) -> String {
-    let store = match layout_store().lock() {
-        Ok(s) => s,
-        Err(_) => return json_error("Failed to acquire layout store lock"),
-    };
+impl InputBuffer {
+    /// Byte offset of the buffer in WASM memory.
+    pub fn ptr(&mut self) -> *mut u8 {
+        self.bytes.as_mut_ptr()
+    }
 
-    let layout = match store.get(handle) {
-        Some(l) => l,
-        None => return json_error(&format!("Invalid handle: {}", handle)),
-    };
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
 
-    match filter::filter_commits_by_field(layout, field, pattern) {
-        Ok(filtered) => serde_json::to_string(&filtered)
-            .unwrap_or_else(|e| json_error(&format!("Serialization error: {}", e))),
-        Err(e) => json_error(&e),
+    /// Grow or shrink the buffer to `len` bytes, e.g. for the next page of
+    /// a log. Any new bytes are zero, and `ptr()` may change.
+    pub fn resize(&mut self, len: usize) {
+        self.bytes.resize(len, 0);
     }
 }
 
-/// Filter commits in a stored layout by date range.
+impl InputBuffer {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Allocate a zeroed `InputBuffer` of `len` bytes for the host to write
+/// input into.
+#[wasm_bindgen]
+pub fn alloc_input_buffer(len: usize) -> InputBuffer {
+    InputBuffer {
+        bytes: vec![0; len],
+    }
+}
+
+/// `compute_graph_layout_with_format` over input written into an
+/// `InputBuffer`. An empty `format_json` selects the default format.
 ///
-/// `after` and `before` are unix epoch timestamps. Use 0 for no constraint.
-/// Returns: JSON LayoutResult with only matching commits and edges.
+/// Returns: JSON string with { handle, nodes, edges, totalCount }, or
+/// { error }.
 #[wasm_bindgen]
-pub fn filter_by_date(handle: u32, after: u64, before: u64) -> String {
-    let store = match layout_store().lock() {
-        Ok(s) => s,
-        Err(_) => return json_error("Failed to acquire layout store lock"),
-    };
+pub fn compute_graph_layout_from_buffer(input: &InputBuffer, format_json: &str) -> String {
+    if format_json.trim().is_empty() {
+        compute_graph_layout(input.bytes())
+    } else {
+        compute_graph_layout_with_format(input.bytes(), format_json)
+    }
+}
 
-    let layout = match store.get(handle) {
-        Some(l) => l,
-        None => return json_error(&format!("Invalid handle: {}", handle)),
-    };
+/// Append additional commits to an existing layout.
+///
+/// Parses the new raw log, computes layout for the combined set, and updates
+/// the stored layout in place. When the layout has an active filter (see
+/// `set_active_filter`), it is evaluated over the new commits only.
+///
+/// Returns: JSON string with the updated { handle, nodes, edges, total_count },
+/// plus { filterMatches } with an active filter: the new matching commits
+/// and the edges connecting them to each other and to earlier matches.
+#[wasm_bindgen]
+pub fn append_to_layout(handle: u32, raw_log: &[u8]) -> String {
+    guarded_json(|| {
+        let mut store = lock(layout_store());
 
-    let filtered = filter::filter_commits_by_date(layout, after, before);
-    serde_json::to_string(&filtered)
-        .unwrap_or_else(|e| json_error(&format!("Serialization error: {}", e)))
-}
+        let (existing_layout, mut new_commits, active_filter) = match store.get(handle) {
+            Some(StoredLayout { input: None, .. }) => {
+                return json_error(
+                    ErrorCode::InvalidState,
+                    format!("Cannot append to a filtered view: {}", handle),
+                )
+            }
+            Some(StoredLayout {
+                session: Some(_), ..
+            }) => {
+                return json_error(
+                    ErrorCode::InvalidState,
+                    format!("Cannot append to a streaming layout: {}", handle),
+                )
+            }
+            Some(StoredLayout {
+                layout,
+                input: Some(input),
+                active_filter,
+                ..
+            }) => (layout.clone(), input.parse(raw_log), active_filter.clone()),
+            None => return invalid_handle(handle),
+        };
+        enrich_commits(&mut new_commits);
 
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
+        if new_commits.is_empty() {
+            // No new commits to add; return the existing layout
+            let result = AppendResult {
+                handle,
+                layout: existing_layout,
+                filter_matches: active_filter.map(|_| LayoutResult::default()),
+            };
+            return to_json(&result);
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // Collect existing SHAs to avoid duplicates
+        let existing_shas: std::collections::HashSet<&str> = existing_layout
+            .nodes
+            .iter()
+            .map(|n| n.sha.as_str())
+            .collect();
 
-    #[test]
-    fn test_compute_graph_layout_and_free() {
-        let raw = b"aaa111aaa111aaa111aaa111aaa111aaa111aaa1\x00aaa111a\x00bbb222bbb222bbb222bbb222bbb222bbb222bbb2\x00Alice\x00alice@example.com\x001700000000\x00Alice\x00alice@example.com\x001700000000\x00Second commit\x00 (HEAD -> main)\x1ebbb222bbb222bbb222bbb222bbb222bbb222bbb2\x00bbb222b\x00\x00Bob\x00bob@example.com\x001699999000\x00Bob\x00bob@example.com\x001699999000\x00Initial commit\x00\x1e";
-        let result_json = compute_graph_layout(raw);
-        let parsed: serde_json::Value = serde_json::from_str(&result_json).unwrap();
+        // Filter out duplicates from new commits
+        let unique_new: Vec<_> = new_commits
+            .into_iter()
+            .filter(|c| !existing_shas.contains(c.sha.as_str()))
+            .collect();
 
-        assert!(parsed.get("handle").is_some());
-        assert!(parsed.get("nodes").is_some());
-        assert!(parsed.get("edges").is_some());
-        assert_eq!(parsed["totalCount"], 2);
+        if unique_new.is_empty() {
+            let result = AppendResult {
+                handle,
+                layout: existing_layout,
+                filter_matches: active_filter.map(|_| LayoutResult::default()),
+            };
+            return to_json(&result);
+        }
 
-        let handle = parsed["handle"].as_u64().unwrap() as u32;
+        let (mut all_commits, options) = match store.get(handle) {
+            Some(stored) => (stored.commits.clone(), stored.options.clone()),
+            None => return invalid_handle(handle),
+        };
 
-        // Free the layout
-        free_layout(handle);
+        let new_shas: Vec<String> = unique_new.iter().map(|c| c.sha.clone()).collect();
+        all_commits.extend(unique_new);
+        // Abbreviated parents on earlier pages may name commits in this one
+        graph::normalize_parents(&mut all_commits);
+        graph::parser::link_children(&mut all_commits);
 
-        // Filtering on a freed handle should return an error
-        let err_json = filter_commits(handle, "author", "Alice");
-        let err_parsed: serde_json::Value = serde_json::from_str(&err_json).unwrap();
-        assert!(err_parsed.get("error").is_some());
-    }
+        // Recompute layout on the combined set
+        let new_layout = graph::compute_layout_with_options(&all_commits, &options);
 
-    #[test]
-    fn test_parse_blame_wasm() {
-        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 3\nauthor Alice\nauthor-mail <alice@example.com>\nauthor-time 1700000000\nauthor-tz +0000\ncommitter Bob\ncommitter-mail <bob@example.com>\ncommitter-time 1700000100\ncommitter-tz +0000\nsummary Initial commit\nfilename src/main.rs\n";
-        let result_json = parse_blame(raw);
-        let parsed: serde_json::Value = serde_json::from_str(&result_json).unwrap();
-        assert!(parsed.is_array());
-        assert_eq!(parsed.as_array().unwrap().len(), 1);
-        assert_eq!(parsed[0]["author_name"], "Alice");
-    }
+        let filter_matches = active_filter.map(|query| {
+            let new_shas = new_shas.iter().map(String::as_str).collect();
+            filter::filter_new_commits(&new_layout, &query, &new_shas)
+        });
 
-    #[test]
-    fn test_filter_commits_wasm() {
-        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1ebbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00Add feature\x00\x1e";
-        let result_json = compute_graph_layout(raw);
-        let parsed: serde_json::Value = serde_json::from_str(&result_json).unwrap();
-        let handle = parsed["handle"].as_u64().unwrap() as u32;
+        // Update the store
+        if let Some(stored) = store.get_mut(handle) {
+            stored.layout = new_layout.clone();
+            stored.commits = all_commits;
+        }
 
-        let filtered_json = filter_commits(handle, "author", "Alice");
-        let filtered: serde_json::Value = serde_json::from_str(&filtered_json).unwrap();
-        assert_eq!(filtered["totalCount"], 1);
+        let result = AppendResult {
+            handle,
+            layout: new_layout,
+            filter_matches,
+        };
 
-        free_layout(handle);
-    }
+        to_json(&result)
+    })
+}
 
-    #[test]
-    fn test_filter_by_date_wasm() {
-        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Recent\x00\x1ebbb\x00bb\x00\x00Bob\x00b@e.com\x001600000000\x00Bob\x00b@e.com\x001600000000\x00Old\x00\x1e";
+/// `append_to_layout` over a page written into an `InputBuffer`.
+#[wasm_bindgen]
+pub fn append_to_layout_from_buffer(handle: u32, input: &InputBuffer) -> String {
+    append_to_layout(handle, input.bytes())
+}
+
+/// Start a layout from `git log` output streamed in chunks as git produces
+/// it, instead of buffering the whole log first. `format_json` is a
+/// placeholder array as for `compute_graph_layout_with_format`, or empty
+/// for the default format.
+///
+/// Feed the output with `push_log_chunk`, then call `finish_layout` to
+/// compute the layout. The handle can be appended to and filtered once
+/// finished, and must be freed with `free_layout`.
+/// Returns: JSON string with { handle }, or { error }.
+#[wasm_bindgen]
+pub fn begin_layout(format_json: &str) -> String {
+    guarded_json(|| {
+        let format = if format_json.trim().is_empty() {
+            LogFormat::default()
+        } else {
+            match parse_format_json(format_json) {
+                Ok(format) => format,
+                Err(e) => return error_json(e),
+            }
+        };
+
+        let mut store = lock(layout_store());
+        let handle = store.insert(StoredLayout {
+            layout: LayoutResult::default(),
+            input: Some(LogInput::Log(format.clone())),
+            parent: None,
+            active_filter: None,
+            created_at: now_ms(),
+            commits: Vec::new(),
+            session: Some(graph::LogParser::new(format)),
+            options: graph::LayoutOptions::default(),
+        });
+        serde_json::json!({ "handle": handle }).to_string()
+    })
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LayoutProgress {
+    handle: u32,
+    /// Commits parsed so far.
+    commits_parsed: usize,
+    /// Bytes of output turned into commits so far.
+    bytes_consumed: usize,
+    /// Bytes of an incomplete record waiting for the next chunk.
+    buffered_bytes: usize,
+    /// Whether `finish_layout` has been called.
+    finished: bool,
+}
+
+fn layout_progress_of(handle: u32, stored: &StoredLayout) -> LayoutProgress {
+    let session = stored.session.as_ref();
+    LayoutProgress {
+        handle,
+        commits_parsed: stored.commits.len(),
+        bytes_consumed: session.map_or(0, |s| s.bytes_consumed()),
+        buffered_bytes: session.map_or(0, |s| s.buffered_bytes()),
+        finished: session.is_none(),
+    }
+}
+
+/// Feed the next chunk of streamed log output to a layout from
+/// `begin_layout`. Chunks may split records anywhere.
+///
+/// Returns: JSON string with the progress so far, as `layout_progress`.
+#[wasm_bindgen]
+pub fn push_log_chunk(handle: u32, chunk: &[u8]) -> String {
+    guarded_json(|| {
+        let mut store = lock(layout_store());
+        let stored = match store.get_mut(handle) {
+            Some(stored) => stored,
+            None => return invalid_handle(handle),
+        };
+        let mut commits = match stored.session.as_mut() {
+            Some(session) => session.push(chunk),
+            None => {
+                let msg = format!("Cannot push to a layout that is not streaming: {}", handle);
+                return json_error(ErrorCode::InvalidState, msg);
+            }
+        };
+        enrich_commits(&mut commits);
+        stored.commits.extend(commits);
+        to_json(&layout_progress_of(handle, stored))
+    })
+}
+
+/// Progress of a streamed layout, e.g. for a "loaded N commits" status
+/// while git is still running.
+///
+/// Returns: JSON string with { handle, commitsParsed, bytesConsumed,
+/// bufferedBytes, finished }, or { error }. The byte counts are 0 once
+/// finished, and for layouts that were not streamed.
+#[wasm_bindgen]
+pub fn layout_progress(handle: u32) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+        match store.get(handle) {
+            Some(stored) => to_json(&layout_progress_of(handle, stored)),
+            None => invalid_handle(handle),
+        }
+    })
+}
+
+/// End a streamed layout once git has exited: parse any trailing record
+/// and compute the layout over every commit pushed.
+///
+/// Returns: JSON string with { handle, nodes, edges, totalCount }, as
+/// `compute_graph_layout`, or { error }.
+#[wasm_bindgen]
+pub fn finish_layout(handle: u32) -> String {
+    guarded_json(|| {
+        let mut store = lock(layout_store());
+        let stored = match store.get_mut(handle) {
+            Some(stored) => stored,
+            None => return invalid_handle(handle),
+        };
+        let mut rest = match stored.session.take() {
+            Some(mut session) => session.finish(),
+            None => {
+                let msg = format!("Cannot finish a layout that is not streaming: {}", handle);
+                return json_error(ErrorCode::InvalidState, msg);
+            }
+        };
+        enrich_commits(&mut rest);
+
+        let mut commits = std::mem::take(&mut stored.commits);
+        commits.extend(rest);
+        // Concatenated logs may repeat commits; keep the first of each
+        let mut seen = std::collections::HashSet::new();
+        commits.retain(|c| seen.insert(c.sha.clone()));
+        graph::normalize_parents(&mut commits);
+
+        stored.layout = graph::compute_layout_with_options(&commits, &stored.options);
+        stored.commits = commits;
+        to_json(&HandleResult {
+            handle,
+            layout: stored.layout.clone(),
+        })
+    })
+}
+
+/// Free a previously allocated layout handle and its associated data,
+/// including the filtered views derived from it.
+///
+/// After calling this, the handle and its views' handles are invalid and
+/// must not be used.
+#[wasm_bindgen]
+pub fn free_layout(handle: u32) {
+    guarded(
+        || {
+            lock(layout_store()).remove(handle);
+        },
+        |_| (),
+    )
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LayoutInfo {
+    handle: u32,
+    /// "Log", "RevList" or "View".
+    kind: &'static str,
+    /// For a view, the handle it was derived from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<u32>,
+    node_count: usize,
+    edge_count: usize,
+    approx_bytes: usize,
+    created_at: f64,
+    has_active_filter: bool,
+}
+
+/// List the live layout handles, to spot handles that were never freed.
+///
+/// Returns: JSON array of { handle, kind ("Log", "RevList" or "View"),
+/// parent? (for views), nodeCount, edgeCount, approxBytes, createdAt
+/// (epoch milliseconds), hasActiveFilter }, by handle, or { error }.
+#[wasm_bindgen]
+pub fn list_layouts() -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+
+        let mut layouts: Vec<LayoutInfo> = store
+            .layouts
+            .iter()
+            .map(|(&handle, stored)| {
+                let kind = match stored.input {
+                    Some(LogInput::Log(_)) => "Log",
+                    Some(LogInput::RevList) => "RevList",
+                    None => "View",
+                };
+                LayoutInfo {
+                    handle,
+                    kind,
+                    parent: stored.parent,
+                    node_count: stored.layout.nodes.len(),
+                    edge_count: stored.layout.edges.len(),
+                    approx_bytes: stored.approx_bytes(),
+                    created_at: stored.created_at,
+                    has_active_filter: stored.active_filter.is_some(),
+                }
+            })
+            .collect();
+        layouts.sort_by_key(|info| info.handle);
+        to_json(&layouts)
+    })
+}
+
+/// Fetch rows `offset..offset + limit` of a stored layout, so the UI can
+/// draw what is on screen without keeping the whole layout in JS.
+///
+/// Includes every edge drawn across those rows: edges between them, edges
+/// leaving them for rows outside, and edges passing through them.
+/// Returns: JSON string with { nodes, edges, totalCount }, totalCount being
+/// that of the whole layout, or { error }.
+#[wasm_bindgen]
+pub fn get_layout_rows(handle: u32, offset: u32, limit: u32) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+        match store.get(handle) {
+            Some(stored) => to_json(&graph::layout_window(
+                &stored.layout,
+                offset as usize,
+                limit as usize,
+            )),
+            None => invalid_handle(handle),
+        }
+    })
+}
+
+/// Look up one node of a stored layout by SHA or unambiguous SHA prefix,
+/// for detail panes and hovers.
+///
+/// Returns: JSON string with { node, edges }, the node's LayoutNode and the
+/// edges to its parents and children, or { error } (NOT_FOUND when no node
+/// matches).
+#[wasm_bindgen]
+pub fn get_node(handle: u32, sha: &str) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+        match store.get(handle) {
+            Some(stored) => match graph::node_detail(&stored.layout, sha) {
+                Ok(detail) => to_json(&detail),
+                Err(e) => error_json(e),
+            },
+            None => invalid_handle(handle),
+        }
+    })
+}
+
+/// A layout's node data as parallel typed columns in WASM memory, from
+/// `export_layout_columns`.
+///
+/// Each `*_ptr` is a byte offset into the WASM memory, so JS can view a
+/// column without copying, e.g.
+/// `new Uint32Array(memory.buffer, columns.rows_ptr(), columns.len())`.
+/// Rows, lanes and colors are u32 and author dates f64 (epoch seconds).
+/// SHAs and subjects are string tables: a UTF-8 byte buffer plus `len() + 1`
+/// u32 offsets, string `i` spanning bytes `offsets[i]..offsets[i + 1]`.
+/// Views are invalidated when the memory grows, so take them right before
+/// drawing, and call `free()` when done.
+#[wasm_bindgen]
+pub struct LayoutColumns {
+    columns: graph::NodeColumns,
+}
+
+#[wasm_bindgen]
+impl LayoutColumns {
+    /// Number of nodes, the length of each numeric column.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    pub fn rows_ptr(&self) -> *const u32 {
+        self.columns.rows.as_ptr()
+    }
+
+    pub fn lanes_ptr(&self) -> *const u32 {
+        self.columns.lanes.as_ptr()
+    }
+
+    pub fn colors_ptr(&self) -> *const u32 {
+        self.columns.colors.as_ptr()
+    }
+
+    pub fn author_dates_ptr(&self) -> *const f64 {
+        self.columns.author_dates.as_ptr()
+    }
+
+    pub fn sha_bytes_ptr(&self) -> *const u8 {
+        self.columns.shas.bytes.as_ptr()
+    }
+
+    pub fn sha_bytes_len(&self) -> usize {
+        self.columns.shas.bytes.len()
+    }
+
+    pub fn sha_offsets_ptr(&self) -> *const u32 {
+        self.columns.shas.offsets.as_ptr()
+    }
+
+    pub fn subject_bytes_ptr(&self) -> *const u8 {
+        self.columns.subjects.bytes.as_ptr()
+    }
+
+    pub fn subject_bytes_len(&self) -> usize {
+        self.columns.subjects.bytes.len()
+    }
+
+    pub fn subject_offsets_ptr(&self) -> *const u32 {
+        self.columns.subjects.offsets.as_ptr()
+    }
+}
+
+/// Export a stored layout's node data as typed columns for rendering
+/// straight from WASM memory (see `LayoutColumns`).
+///
+/// Returns: the columns. Throws the `{ error }` JSON for an invalid handle.
+#[wasm_bindgen]
+pub fn export_layout_columns(handle: u32) -> Result<LayoutColumns, String> {
+    guarded(
+        || {
+            let store = lock(layout_store());
+            let stored = store.get(handle).ok_or_else(|| invalid_handle(handle))?;
+            Ok(LayoutColumns {
+                columns: graph::NodeColumns::from_layout(&stored.layout),
+            })
+        },
+        |e| Err(error_json(e)),
+    )
+}
+
+/// A stored layout owned by a JS object, as an alternative to passing raw
+/// handles to the free functions.
+///
+/// The layout is freed with the object: by calling `free()`, or by the
+/// garbage collector when the module is built with `--weak-refs`. Methods
+/// that cannot return an `{ error }` result throw its JSON instead.
+/// `handle` gives the underlying handle for the functions not available
+/// as methods; it must not be passed to `free_layout`.
+#[wasm_bindgen]
+pub struct GraphLayout {
+    handle: u32,
+}
+
+#[wasm_bindgen]
+impl GraphLayout {
+    /// Lay out `git log` output in the format of the placeholder array
+    /// `format_json`, as for `compute_graph_layout_with_format`, or in the
+    /// default format when it is empty.
+    #[wasm_bindgen(constructor)]
+    pub fn new(raw_log: &[u8], format_json: &str) -> Result<GraphLayout, String> {
+        let format = if format_json.trim().is_empty() {
+            LogFormat::default()
+        } else {
+            parse_format_json(format_json).map_err(error_json)?
+        };
+        GraphLayout::store(raw_log, LogInput::Log(format))
+    }
+
+    /// Lay out `git rev-list --header --parents` output.
+    pub fn from_rev_list(raw: &[u8]) -> Result<GraphLayout, String> {
+        GraphLayout::store(raw, LogInput::RevList)
+    }
+
+    fn store(raw: &[u8], input: LogInput) -> Result<GraphLayout, String> {
+        guarded(
+            || {
+                let options = graph::LayoutOptions::default();
+                let result = layout_and_store(input.parse(raw), input, options);
+                Ok(GraphLayout {
+                    handle: result.handle,
+                })
+            },
+            |e| Err(error_json(e)),
+        )
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Returns: JSON string with { nodes, edges, totalCount }.
+    pub fn layout(&self) -> String {
+        guarded_json(|| match lock(layout_store()).get(self.handle) {
+            Some(stored) => to_json(&stored.layout),
+            None => invalid_handle(self.handle),
+        })
+    }
+
+    /// Append a page of commits, as `append_to_layout`.
+    pub fn append(&self, raw_log: &[u8]) -> String {
+        append_to_layout(self.handle, raw_log)
+    }
+
+    /// Filter the layout with a query, as `filter_query_into`; `options_json`
+    /// is as for `filter_query_with_options`.
+    ///
+    /// Returns: the matches as a new `GraphLayout`. Dropping it frees only
+    /// the view; dropping this one frees the view too.
+    pub fn filter(&self, query: &str, options_json: &str) -> Result<GraphLayout, String> {
+        guarded(
+            || {
+                let filtered = query_with_options(self.handle, query, options_json)?;
+                let mut store = lock(layout_store());
+                Ok(GraphLayout {
+                    handle: insert_view(&mut store, self.handle, filtered)?,
+                })
+            },
+            Err,
+        )
+        .map_err(error_json)
+    }
+
+    /// The rows `offset..offset + limit` with the edges crossing them, as
+    /// `get_layout_rows`.
+    pub fn window(&self, offset: u32, limit: u32) -> String {
+        get_layout_rows(self.handle, offset, limit)
+    }
+
+    /// One node with its edges, as `get_node`.
+    pub fn node(&self, sha: &str) -> String {
+        get_node(self.handle, sha)
+    }
+}
+
+impl Drop for GraphLayout {
+    fn drop(&mut self) {
+        free_layout(self.handle);
+    }
+}
+
+/// Free every stored layout, view, blame and blame session, and empty the
+/// blame cache, e.g. when the webview reloads or the repository changes
+/// and the extension may have lost track of its handles.
+///
+/// Settings (mailmap, issue rules, saved filters, output schema) are kept.
+/// Handles are not reused afterwards, so stale ones stay invalid.
+/// Returns: JSON string with { layouts, blames, cachedBlames }, the number
+/// of each dropped.
+#[wasm_bindgen]
+pub fn free_all_layouts() -> String {
+    guarded_json(|| {
+        let layouts = lock(layout_store()).clear();
+        let blames = lock(blame_store()).clear();
+        let cached_blames = lock(blame_cache()).invalidate("", "");
+        serde_json::json!({
+            "layouts": layouts,
+            "blames": blames,
+            "cachedBlames": cached_blames,
+        })
+        .to_string()
+    })
+}
+
+/// Parse raw `git blame --incremental`, `--porcelain` or `--line-porcelain`
+/// output into JSON. Porcelain input also fills each entry's `lines`.
+/// With `-M`/`-C`, each entry's `provenance` tells whether its lines were
+/// moved within the file or came from another file (`source_path`).
+///
+/// Returns: JSON array of BlameEntry objects.
+#[wasm_bindgen]
+pub fn parse_blame(raw_blame: &[u8]) -> String {
+    guarded_json(|| to_json(&parse_blame_entries(raw_blame)))
+}
+
+/// Parse blame output with post-processing.
+///
+/// `options_json` is
+/// `{ mergeAdjacent?, ignoreRevs?, groupByCommit?, sortBy?, reverse?, path? }`:
+/// - `path`: the blamed file's path, against which entries get their
+///   `provenance` (`"Original"`, `"Moved"` or `"Copied"` with
+///   `source_path`); inferred from the entries when absent.
+/// - `reverse`: the output is from `git blame --reverse`, so each entry's
+///   commit is the last one in which its lines existed; entries get
+///   `mode: "Reverse"` instead of `"Forward"`.
+/// - `ignoreRevs`: SHAs, or prefixes of at least 7 characters (or
+///   `.git-blame-ignore-revs` lines), whose entries are reattributed to the
+///   previous commit that touched the lines, via the `previous` headers,
+///   with `orig_line` 0. Entries that cannot be reattributed are flagged
+///   `is_unblamable`.
+/// - `mergeAdjacent`: coalesce consecutive entries from the same commit
+///   into one range, after any reattribution.
+/// - `groupByCommit`: return one group per commit with all its line
+///   ranges, sorted by `sortBy`: `"Date"` (newest first, the default) or
+///   `"Lines"` (most lines first).
+///
+/// Returns: JSON array of BlameEntry objects, or with `groupByCommit` of
+/// { sha, shortSha, author, committer, summary, lineCount, ranges } groups;
+/// or { error } if the options are invalid.
+#[wasm_bindgen]
+pub fn parse_blame_with_options(raw_blame: &[u8], options_json: &str) -> String {
+    guarded_json(|| {
+        match blame_with_options(raw_blame, options_json) {
+            Ok(BlameOutput::Entries(entries)) => to_json(&entries),
+            Ok(BlameOutput::Groups(groups)) => to_json(&groups),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// `parse_blame_with_options`, returning the result in `format` as for
+/// `compute_graph_layout_encoded`.
+///
+/// Returns: the encoded entries or groups, or an encoded { error }; throws
+/// if the format is unknown.
+#[wasm_bindgen]
+pub fn parse_blame_encoded(
+    raw_blame: &[u8],
+    options_json: &str,
+    format: &str,
+) -> Result<Vec<u8>, String> {
+    let format = parse_output_format(format)?;
+    Ok(guarded(
+        || match blame_with_options(raw_blame, options_json) {
+            Ok(BlameOutput::Entries(entries)) => encode(&entries, format),
+            Ok(BlameOutput::Groups(groups)) => encode(&groups, format),
+            Err(e) => encode_error(e, format),
+        },
+        |e| encode_error(e, format),
+    ))
+}
+
+/// `parse_blame_with_options` over output written into an `InputBuffer`.
+/// An empty `options_json` parses without options, as `parse_blame`.
+#[wasm_bindgen]
+pub fn parse_blame_from_buffer(input: &InputBuffer, options_json: &str) -> String {
+    if options_json.trim().is_empty() {
+        parse_blame(input.bytes())
+    } else {
+        parse_blame_with_options(input.bytes(), options_json)
+    }
+}
+
+enum BlameOutput {
+    Entries(Vec<blame::BlameEntry>),
+    Groups(Vec<blame::CommitGroup>),
+}
+
+fn blame_with_options(raw_blame: &[u8], options_json: &str) -> Result<BlameOutput, ErrorInfo> {
+    let options: blame::BlameOptions = serde_json::from_str(options_json).map_err(|e| {
+        ErrorInfo::with_code(ErrorCode::BadArgument, format!("Invalid blame options: {}", e))
+    })?;
+
+    let mut entries = parse_blame_entries(raw_blame);
+    if options.path.is_some() {
+        blame::classify_provenance(&mut entries, options.path.as_deref());
+    }
+    if options.reverse {
+        for entry in &mut entries {
+            entry.mode = blame::BlameMode::Reverse;
+        }
+    }
+    blame::apply_ignore_revs(&mut entries, &blame::parse_ignore_revs(&options.ignore_revs));
+    if options.merge_adjacent {
+        entries = blame::merge_adjacent(&entries);
+    }
+    if options.group_by_commit {
+        return Ok(BlameOutput::Groups(blame::group_by_commit(&entries, options.sort_by)));
+    }
+    Ok(BlameOutput::Entries(entries))
+}
+
+/// Parse blame output, canonicalize identities through the mailmap and
+/// classify line provenance against the inferred file path.
+fn parse_blame_entries(raw_blame: &[u8]) -> Vec<blame::BlameEntry> {
+    let mut entries = blame::parse_blame_output(raw_blame);
+    lock(mailmap_store()).apply_to_blame(&mut entries);
+    blame::classify_provenance(&mut entries, None);
+    entries
+}
+
+fn blame_chunk_result(handle: u32, entries: Vec<blame::BlameEntry>) -> String {
+    to_json(&BlameChunkResult { handle, entries })
+}
+
+/// Parse complete blame output and store it for `blame_for_lines`.
+///
+/// Returns: JSON string with { handle, entryCount, lineCount }, where
+/// `lineCount` is the last blamed line number.
+#[wasm_bindgen]
+pub fn store_blame(raw_blame: &[u8]) -> String {
+    guarded_json(|| {
+        let entries = parse_blame_entries(raw_blame);
+        let entry_count = entries.len();
+        let line_count = entries
+            .iter()
+            .map(|e| e.final_line.saturating_add(e.num_lines.saturating_sub(1)))
+            .max()
+            .unwrap_or(0);
+
+        let mut store = lock(blame_store());
+        let handle = store.insert(StoredBlame {
+            entries,
+            session: None,
+        });
+        serde_json::json!({
+            "handle": handle,
+            "entryCount": entry_count,
+            "lineCount": line_count,
+        })
+        .to_string()
+    })
+}
+
+/// Parse blame output for `path` at `revision` and cache the result,
+/// replacing any earlier blame for the same key. The least recently used
+/// blames are evicted once the cached raw output exceeds the size budget.
+///
+/// Returns: JSON string with { cached, entryCount, evicted }; `cached` is
+/// false if the output alone exceeds the budget.
+#[wasm_bindgen]
+pub fn blame_cache_put(path: &str, revision: &str, raw_blame: &[u8]) -> String {
+    guarded_json(|| {
+        let entries = parse_blame_entries(raw_blame);
+        let entry_count = entries.len();
+        let mut cache = lock(blame_cache());
+        let (cached, evicted) = cache.put(path, revision, entries, raw_blame.len());
+        serde_json::json!({
+            "cached": cached,
+            "entryCount": entry_count,
+            "evicted": evicted,
+        })
+        .to_string()
+    })
+}
+
+/// Cached blame for `path` at `revision`.
+///
+/// Returns: JSON string with { hit, entries }, where `entries` is the
+/// BlameEntry array on a hit and empty on a miss.
+#[wasm_bindgen]
+pub fn blame_cache_get(path: &str, revision: &str) -> String {
+    guarded_json(|| {
+        let mut cache = lock(blame_cache());
+        let (hit, entries) = match cache.get(path, revision) {
+            Some(entries) => (true, entries),
+            None => (false, &[][..]),
+        };
+        to_json(&serde_json::json!({ "hit": hit, "entries": entries }))
+    })
+}
+
+/// Drop cached blames, e.g. after the file is saved or HEAD moves: one
+/// revision of `path`, every revision of `path` when `revision` is empty,
+/// or everything when both are empty.
+///
+/// Returns: the number of blames removed.
+#[wasm_bindgen]
+pub fn blame_cache_invalidate(path: &str, revision: &str) -> u32 {
+    guarded(
+        || lock(blame_cache()).invalidate(path, revision) as u32,
+        |_| 0,
+    )
+}
+
+/// Set the blame cache's size budget in bytes of raw blame output
+/// (default 32 MiB), evicting as needed.
+///
+/// Returns: the number of blames evicted.
+#[wasm_bindgen]
+pub fn blame_cache_set_budget(bytes: u32) -> u32 {
+    guarded(
+        || lock(blame_cache()).set_budget(bytes as usize) as u32,
+        |_| 0,
+    )
+}
+
+/// Start parsing blame output that arrives in chunks, e.g. from a running
+/// `git blame --incremental`. Entries are stored under the handle as they
+/// complete, so `blame_for_lines` works while git is still running.
+///
+/// Returns: JSON string with { handle } for `blame_append`.
+#[wasm_bindgen]
+pub fn create_blame_session() -> String {
+    guarded_json(|| {
+        let mut store = lock(blame_store());
+        let handle = store.insert(StoredBlame {
+            entries: Vec::new(),
+            session: Some(blame::BlameParser::new()),
+        });
+        serde_json::json!({ "handle": handle }).to_string()
+    })
+}
+
+/// Feed chunks through a stored session, or finish it when `chunk` is None.
+fn advance_blame_session(handle: u32, chunk: Option<&[u8]>) -> String {
+    let mut store = lock(blame_store());
+    let stored = match store.get_mut(handle) {
+        Some(stored) => stored,
+        None => return invalid_handle(handle),
+    };
+    let mut entries = match (&mut stored.session, chunk) {
+        (Some(session), Some(chunk)) => session.push(chunk),
+        (Some(session), None) => session.finish(),
+        (None, _) => return json_error(ErrorCode::InvalidState, "Blame session already finished"),
+    };
+    if chunk.is_none() {
+        stored.session = None;
+    }
+
+    lock(mailmap_store()).apply_to_blame(&mut entries);
+    stored.entries.extend(entries.iter().cloned());
+    blame_chunk_result(handle, entries)
+}
+
+/// Feed the next chunk of blame output to a session. Chunks may split
+/// lines anywhere.
+///
+/// Returns: JSON string with { handle, entries }, where `entries` holds
+/// only the BlameEntry objects this chunk completed.
+#[wasm_bindgen]
+pub fn blame_append(handle: u32, chunk: &[u8]) -> String {
+    guarded_json(|| advance_blame_session(handle, Some(chunk)))
+}
+
+/// End a blame session once git has exited. The handle stays valid for
+/// queries until `free_blame`.
+///
+/// Returns: JSON string with { handle, entries }, where `entries` holds
+/// the entries still pending at the end of the output.
+#[wasm_bindgen]
+pub fn blame_finish(handle: u32) -> String {
+    guarded_json(|| advance_blame_session(handle, None))
+}
+
+/// Stored blame entries for the lines `start..=end` (1-based), ordered by
+/// line, for rendering the visible part of a file. Entries straddling the
+/// range are split so only the requested lines are returned.
+///
+/// Returns: JSON string with { handle, entries }.
+#[wasm_bindgen]
+pub fn blame_for_lines(handle: u32, start: u32, end: u32) -> String {
+    guarded_json(|| {
+        let store = lock(blame_store());
+        let stored = match store.get(handle) {
+            Some(stored) => stored,
+            None => return invalid_handle(handle),
+        };
+
+        blame_chunk_result(handle, blame::clip_to_lines(&stored.entries, start, end))
+    })
+}
+
+/// Annotate a stored blame with the row, lane and color of each entry's
+/// commit in a stored layout (`layout_row`, `layout_lane`, `color_index`),
+/// so blamed lines can scroll the graph and share its lane colors.
+/// Entries for commits outside the layout are left unannotated.
+///
+/// Returns: JSON string with { handle, linkedCount, entryCount }.
+#[wasm_bindgen]
+pub fn link_blame_to_layout(blame_handle: u32, layout_handle: u32) -> String {
+    guarded_json(|| {
+        let layouts = lock(layout_store());
+        let layout = match layouts.get(layout_handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(layout_handle),
+        };
+
+        let mut blames = lock(blame_store());
+        let stored = match blames.get_mut(blame_handle) {
+            Some(stored) => stored,
+            None => return invalid_handle(blame_handle),
+        };
+
+        let linked_count = blame::link_to_layout(&mut stored.entries, layout);
+        serde_json::json!({
+            "handle": blame_handle,
+            "linkedCount": linked_count,
+            "entryCount": stored.entries.len(),
+        })
+        .to_string()
+    })
+}
+
+/// Free a stored blame or blame session.
+/// After calling this, the handle is invalid and must not be used.
+#[wasm_bindgen]
+pub fn free_blame(handle: u32) {
+    guarded(
+        || {
+            lock(blame_store()).remove(handle);
+        },
+        |_| (),
+    )
+}
+
+/// Group blamed lines into age buckets for "code age" gutter coloring.
+///
+/// `bucket_spec` is JSON `{ now?, boundaries? }`, where `now` is the
+/// reference epoch (default: the newest line's author time) and
+/// `boundaries` are increasing age limits in seconds (default: one week,
+/// 30 days, 365 days). Pass an empty string for all defaults.
+/// Returns: JSON string with { lineBuckets, boundaries, now }, where
+/// `lineBuckets[i]` is the bucket of line i + 1, from 0 (newest) to
+/// `boundaries.length`, or -1 if the line is not blamed.
+#[wasm_bindgen]
+pub fn blame_heatmap(raw_blame: &[u8], bucket_spec: &str) -> String {
+    guarded_json(|| {
+        let spec = if bucket_spec.trim().is_empty() {
+            blame::BucketSpec::default()
+        } else {
+            match serde_json::from_str(bucket_spec) {
+                Ok(spec) => spec,
+                Err(e) => {
+                    return json_error(ErrorCode::BadArgument, format!("Invalid bucket spec: {}", e))
+                }
+            }
+        };
+
+        let entries = blame::parse_blame_output(raw_blame);
+        match blame::compute_heatmap(&entries, &spec) {
+            Ok(heatmap) => to_json(&heatmap),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Parse blame output and keep only the lines `start..=end` (1-based),
+/// splitting entries that straddle the range.
+///
+/// Returns: JSON array of BlameEntry objects, ordered by line.
+#[wasm_bindgen]
+pub fn parse_blame_range(raw_blame: &[u8], start: u32, end: u32) -> String {
+    guarded_json(|| {
+        let entries = blame::clip_to_lines(&parse_blame_entries(raw_blame), start, end);
+        to_json(&entries)
+    })
+}
+
+/// Compare two blames of the same file, e.g. before and after a PR, and
+/// report the lines whose owning commit changed.
+///
+/// Returns: JSON string with { changed, removedLines, unchangedCount },
+/// where each change is { line, oldLine?, oldSha?, newSha }; `oldLine` and
+/// `oldSha` are absent for inserted lines.
+#[wasm_bindgen]
+pub fn diff_blame(raw_blame_old: &[u8], raw_blame_new: &[u8]) -> String {
+    guarded_json(|| {
+        let old = blame::parse_blame_output(raw_blame_old);
+        let new = blame::parse_blame_output(raw_blame_new);
+        to_json(&blame::diff_blame(&old, &new))
+    })
+}
+
+/// Render the Markdown hover for a blamed line, so every editor surface
+/// shows the same text.
+///
+/// `entry_json` is a BlameEntry as returned by the blame exports, in either
+/// output schema. `options_json` is
+/// `{ template?, now?, avatarUrl?, uncommittedTemplate? }`:
+/// `template` is Markdown with `{sha}`, `{shortSha}`, `{author}`,
+/// `{authorEmail}`, `{committer}`, `{date}`, `{relativeDate}`, `{summary}`,
+/// `{filename}` and `{avatar}` placeholders; `now` (epoch seconds) enables
+/// relative dates; `avatarUrl` may use `{email}` and `{name}`;
+/// `uncommittedTemplate` replaces `template` for uncommitted lines. Pass an
+/// empty string for the defaults.
+/// Returns: JSON string with { markdown }, or { error }.
+#[wasm_bindgen]
+pub fn blame_hover_markdown(entry_json: &str, options_json: &str) -> String {
+    guarded_json(|| {
+        let mut value: serde_json::Value = match serde_json::from_str(entry_json) {
+            Ok(value) => value,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid blame entry: {}", e))
+            }
+        };
+        schema::snake_case_keys(&mut value);
+        let entry: blame::BlameEntry = match serde_json::from_value(value) {
+            Ok(entry) => entry,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid blame entry: {}", e))
+            }
+        };
+
+        let options = if options_json.trim().is_empty() {
+            blame::HoverOptions::default()
+        } else {
+            match serde_json::from_str(options_json) {
+                Ok(options) => options,
+                Err(e) => {
+                    return json_error(
+                        ErrorCode::BadArgument,
+                        format!("Invalid hover options: {}", e),
+                    )
+                }
+            }
+        };
+        serde_json::json!({ "markdown": blame::render_hover(&entry, &options) }).to_string()
+    })
+}
+
+/// Headline numbers for the header above the blame gutter.
+///
+/// Returns: JSON string with { oldestDate, newestDate, lineCount,
+/// uncommittedLines, commitCount, authorCount, largestSpan? }, where
+/// `largestSpan` is the longest run of lines from one commit as
+/// { sha, finalLine, numLines }. Only `lineCount` includes uncommitted
+/// lines.
+#[wasm_bindgen]
+pub fn blame_summary(raw_blame: &[u8]) -> String {
+    guarded_json(|| {
+        let summary = blame::summarize(&parse_blame_entries(raw_blame));
+        to_json(&summary)
+    })
+}
+
+/// Paths a blamed file has had, for the file-history panel. Run blame with
+/// `-M`/`-C` or across renames so the `previous` headers name old paths.
+///
+/// Returns: JSON string with { pathAtCommit, paths, renames }, where
+/// `pathAtCommit` maps SHAs to the file's path in that commit, `paths` lists
+/// every path most recent first, and each rename is { sha, from, to }.
+#[wasm_bindgen]
+pub fn blame_rename_history(raw_blame: &[u8]) -> String {
+    guarded_json(|| {
+        let history = blame::rename_history(&blame::parse_blame_output(raw_blame));
+        to_json(&history)
+    })
+}
+
+/// Per-author line counts for a blamed file, for the "file owners" panel.
+///
+/// Authors are grouped by email after applying the mailmap.
+/// Returns: JSON string with { totalLines, uncommittedLines, owners }, where
+/// each owner is { name, email, lines, percent }, sorted by most lines
+/// first. Uncommitted lines are not in `totalLines`.
+#[wasm_bindgen]
+pub fn blame_ownership(raw_blame: &[u8]) -> String {
+    guarded_json(|| {
+        let ownership = blame::compute_ownership(&parse_blame_entries(raw_blame));
+        to_json(&ownership)
+    })
+}
+
+/// Trace the lines of a file through blames taken at several points in its
+/// history, for a "code decay" chart.
+///
+/// `snapshots_json` is `[{ label, entries }]`, oldest first, with each
+/// BlameEntry array as returned by the blame exports, in either output
+/// schema. Lines are identified by the commit and line they come from.
+/// Returns: JSON string with { snapshots, lineIntroduced }. Each snapshot is
+/// { label, lineCount, introduced, surviving, survivalRate, cohorts }, where
+/// `surviving` counts its lines still in the latest snapshot and
+/// `cohorts[j]` its lines first seen in snapshot j. `lineIntroduced[i]` is
+/// the snapshot that introduced line i + 1 of the latest snapshot, or -1.
+/// Uncommitted lines are not counted. Returns { error } for invalid input.
+#[wasm_bindgen]
+pub fn blame_survival(snapshots_json: &str) -> String {
+    guarded_json(|| {
+        let mut value: serde_json::Value = match serde_json::from_str(snapshots_json) {
+            Ok(value) => value,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid blame snapshots: {}", e))
+            }
+        };
+        schema::snake_case_keys(&mut value);
+        let snapshots: Vec<analysis::BlameSnapshot> = match serde_json::from_value(value) {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid blame snapshots: {}", e))
+            }
+        };
+
+        to_json(&analysis::line_survival(&snapshots))
+    })
+}
+
+/// Per-author line counts for every directory in a batch of blamed files,
+/// for a "who owns this folder" treemap.
+///
+/// `files_json` is `[{ path, entries }]`, with each file's path relative to
+/// the repository root and its BlameEntry array as returned by the blame
+/// exports, in either output schema.
+/// Returns: JSON string with { directories }, where each directory is
+/// { path, fileCount, totalLines, uncommittedLines, owners } and counts
+/// every file beneath it. The root has an empty path and parents come
+/// before their subdirectories. Returns { error } for invalid input.
+#[wasm_bindgen]
+pub fn aggregate_blame(files_json: &str) -> String {
+    guarded_json(|| {
+        let mut value: serde_json::Value = match serde_json::from_str(files_json) {
+            Ok(value) => value,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid blame files: {}", e))
+            }
+        };
+        schema::snake_case_keys(&mut value);
+        let files: Vec<blame::FileBlame> = match serde_json::from_value(value) {
+            Ok(files) => files,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid blame files: {}", e))
+            }
+        };
+
+        let directories = blame::aggregate_ownership(&files);
+        to_json(&serde_json::json!({ "directories": directories }))
+    })
+}
+
+/// Normalized age of each blamed line for gutter color ramps.
+///
+/// `range_json` is `{ oldest?, newest? }` in epoch seconds; missing ends
+/// default to the file's oldest and newest author times. Pass an empty
+/// string for both defaults.
+/// Returns: JSON string with { ages, oldest, newest }, where `ages[i]` is
+/// line i + 1's age from 0.0 (newest) to 1.0 (oldest), or -1 if the line
+/// is not blamed.
+#[wasm_bindgen]
+pub fn blame_line_ages(raw_blame: &[u8], range_json: &str) -> String {
+    guarded_json(|| {
+        let range = if range_json.trim().is_empty() {
+            blame::AgeRange::default()
+        } else {
+            match serde_json::from_str(range_json) {
+                Ok(range) => range,
+                Err(e) => {
+                    return json_error(ErrorCode::BadDate, format!("Invalid age range: {}", e))
+                }
+            }
+        };
+
+        let ages = blame::compute_line_ages(&blame::parse_blame_output(raw_blame), &range);
+        to_json(&ages)
+    })
+}
+
+/// Select the key casing of all subsequent JSON output.
+///
+/// Version 1 (the default) keeps the original keys, where commits in
+/// diagnostics and blame entries use snake_case (`short_sha`). Version 2
+/// uses camelCase for every key (`shortSha`), matching the layout types.
+/// Input JSON (formats, options, rules) is unaffected.
+/// Returns: JSON string with { schema }, or { error } for an unknown
+/// version, in which case the schema is unchanged.
+#[wasm_bindgen]
+pub fn set_output_schema(version: u32) -> String {
+    guarded_json(|| {
+        if version != schema::LEGACY && version != schema::CAMEL_CASE {
+            return json_error(
+                ErrorCode::BadArgument,
+                format!("Unsupported output schema: {}", version),
+            );
+        }
+        OUTPUT_SCHEMA.store(version, Ordering::Relaxed);
+        serde_json::json!({ "schema": version }).to_string()
+    })
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CoreInfo {
+    /// Crate version, e.g. "0.1.0".
+    version: &'static str,
+    /// Output schema selected with `set_output_schema`.
+    schema: u32,
+    supported_schemas: [u32; 2],
+    features: CoreFeatures,
+    /// Formats the `_encoded` exports accept.
+    output_formats: [&'static str; 2],
+    /// Fields of `filter_query` terms.
+    query_fields: &'static [&'static str],
+    /// Fields of `filter_commits` and the other single-field filters.
+    filter_fields: &'static [&'static str],
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CoreFeatures {
+    /// Parsing and layout run on worker threads. There is no threaded build
+    /// yet, so this is always false.
+    threads: bool,
+    /// MessagePack output from the `_encoded` exports.
+    binary_output: bool,
+    /// Built with the `console_error_panic_hook` feature.
+    panic_hook: bool,
+    /// Panics unwind, so exports report them as `PANIC` errors instead of
+    /// trapping (see `guarded`).
+    panic_unwind: bool,
+}
+
+/// Describe this build of the module, so the extension can adapt to an
+/// older or newer WASM core than it was written against.
+///
+/// Returns: JSON string with { version, schema, supportedSchemas, features:
+/// { threads, binaryOutput, panicHook, panicUnwind }, outputFormats,
+/// queryFields, filterFields }.
+#[wasm_bindgen]
+pub fn core_info() -> String {
+    guarded_json(|| {
+        let info = CoreInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            schema: OUTPUT_SCHEMA.load(Ordering::Relaxed),
+            supported_schemas: [schema::LEGACY, schema::CAMEL_CASE],
+            features: CoreFeatures {
+                threads: false,
+                binary_output: true,
+                panic_hook: cfg!(feature = "console_error_panic_hook"),
+                panic_unwind: cfg!(panic = "unwind"),
+            },
+            output_formats: ["json", "msgpack"],
+            query_fields: filter::QUERY_FIELDS,
+            filter_fields: filter::FILTER_FIELDS,
+        };
+        to_json(&info)
+    })
+}
+
+/// Load issue-key extraction rules applied to all subsequent log parses.
+///
+/// `rules_json` is a JSON array of `{ name, pattern, url? }`, where `url`
+/// may reference capture groups (`$1`). Pass `[]` to disable extraction.
+/// Existing layouts are not rewritten.
+/// Returns: JSON string with { ruleCount }, or { error } if a rule is
+/// invalid, in which case the previous rules stay active.
+#[wasm_bindgen]
+pub fn set_issue_rules(rules_json: &str) -> String {
+    guarded_json(|| {
+        let extractor = match IssueExtractor::from_json(rules_json) {
+            Ok(e) => e,
+            Err(e) => return error_json(e),
+        };
+        let rule_count = extractor.len();
+        *lock(issue_rules_store()) = extractor;
+        serde_json::json!({ "ruleCount": rule_count }).to_string()
+    })
+}
+
+/// Parse raw `git for-each-ref` output into JSON.
+///
+/// See `refs::parse_for_each_ref` for the expected `--format`.
+/// Returns: JSON array of RefEntry objects.
+#[wasm_bindgen]
+pub fn parse_ref_list(raw_refs: &[u8]) -> String {
+    guarded_json(|| {
+        let entries = refs::parse_for_each_ref(raw_refs);
+        to_json(&entries)
+    })
+}
+
+/// Parse the contents of `.git/HEAD`.
+///
+/// Returns: JSON `{ kind: "symbolic", target }` or `{ kind: "detached", sha }`,
+/// or an error for unrecognized contents.
+#[wasm_bindgen]
+pub fn parse_head(contents: &str) -> String {
+    guarded_json(|| {
+        match refs::parse_head(contents) {
+            Some(head) => to_json(&head),
+            None => json_error(ErrorCode::ParseFailed, "Unrecognized HEAD contents"),
+        }
+    })
+}
+
+/// Mark the HEAD commit of a stored layout from the contents of
+/// `.git/HEAD`, replacing HEAD markers taken from decorations.
+///
+/// Returns: JSON string with { handle, nodes, edges, total_count, headFound }.
+#[wasm_bindgen]
+pub fn annotate_layout_head(handle: u32, head_contents: &str) -> String {
+    guarded_json(|| {
+        let head = match refs::parse_head(head_contents) {
+            Some(head) => head,
+            None => return json_error(ErrorCode::ParseFailed, "Unrecognized HEAD contents"),
+        };
+
+        let mut store = lock(layout_store());
+        let stored = match store.get_mut(handle) {
+            Some(stored) => stored,
+            None => return invalid_handle(handle),
+        };
+
+        let head_found = refs::annotate_head(&mut stored.layout, &head);
+        // Keep the HEAD marker when appends rebuild the layout from the commits;
+        // rows need not follow commit order (see `LayoutOptions::order`)
+        let refs: HashMap<&str, &Vec<graph::types::RefInfo>> = stored
+            .layout
+            .nodes
+            .iter()
+            .map(|n| (n.sha.as_str(), &n.refs))
+            .collect();
+        for commit in &mut stored.commits {
+            if let Some(node_refs) = refs.get(commit.sha.as_str()) {
+                commit.refs.clone_from(node_refs);
+            }
+        }
+        let result = HandleResult {
+            handle,
+            layout: stored.layout.clone(),
+        };
+        match serde_json::to_value(&result) {
+            Ok(mut value) => {
+                value["headFound"] = serde_json::Value::Bool(head_found);
+                to_json(&value)
+            }
+            Err(e) => json_error(ErrorCode::Serialization, format!("Serialization error: {}", e)),
+        }
+    })
+}
+
+/// Parse raw `git cat-file --batch` output into JSON.
+///
+/// Used to hydrate full messages and tag annotations for commits that are
+/// already in a layout. Blob contents are not returned.
+/// Returns: JSON array of objects tagged by `type`
+/// (`commit`, `tag`, `tree`, `blob` or `missing`).
+#[wasm_bindgen]
+pub fn parse_cat_file(raw_batch: &[u8]) -> String {
+    guarded_json(|| {
+        let objects = object::parse_cat_file_batch(raw_batch);
+        to_json(&objects)
+    })
+}
+
+/// Load `.mailmap` contents used to canonicalize author and committer
+/// names and emails in all subsequent log and blame parses.
+///
+/// Pass an empty string to clear. Existing layouts are not rewritten.
+/// Returns: number of mailmap rules loaded.
+#[wasm_bindgen]
+pub fn set_mailmap(contents: &str) -> u32 {
+    guarded(
+        || {
+            let mailmap = Mailmap::parse(contents);
+            let count = mailmap.len() as u32;
+            *lock(mailmap_store()) = mailmap;
+            count
+        },
+        |_| 0,
+    )
+}
+
+/// Filter commits in a stored layout by a regex pattern on a field.
+///
+/// Supported fields: "message", "subject", "body", "author",
+/// "author_email", "committer", "committer_email", "email" (either email),
+/// "author_domain", "committer_domain", "domain" (either email's domain),
+/// "sha", "source", "ref" (any branch or tag decorating the commit) and
+/// "any" (subject, author, email or sha, like a search box).
+/// Returns: JSON LayoutResult with only matching commits and edges.
+#[wasm_bindgen]
+pub fn filter_commits(handle: u32, field: &str, pattern: &str) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        let flags = filter::MatchFlags::default();
+        match filter::filter_commits_by_field(layout, field, pattern, &flags) {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// `filter_commits` with matching options.
+///
+/// `flags_json` is `{ case?, negate?, syntax? }`, where `case` is
+/// "Sensitive" (the default), "Insensitive" or "Smart" (insensitive unless
+/// the pattern contains an uppercase letter), `negate` keeps the commits
+/// that do not match, e.g. everything not authored by a bot, and `syntax`
+/// is "Regex" (the default) or "Glob", matching the whole field against a
+/// glob like `*fix*`. Pass an empty string for the defaults.
+/// Returns: JSON LayoutResult with only matching commits and edges.
+#[wasm_bindgen]
+pub fn filter_commits_with_flags(
+    handle: u32,
+    field: &str,
+    pattern: &str,
+    flags_json: &str,
+) -> String {
+    guarded_json(|| {
+        let flags = if flags_json.trim().is_empty() {
+            filter::MatchFlags::default()
+        } else {
+            match serde_json::from_str(flags_json) {
+                Ok(flags) => flags,
+                Err(e) => {
+                    return json_error(ErrorCode::BadArgument, format!("Invalid match flags: {}", e))
+                }
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_commits_by_field(layout, field, pattern, &flags) {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Find the commits `filter_commits_with_flags` would keep without
+/// building a filtered layout, so the UI can dim the other rows in place.
+///
+/// `flags_json` is as for `filter_commits_with_flags`; pass an empty string
+/// for the defaults.
+/// Returns: JSON array of { sha, row, field, ranges } in row order, where
+/// `field` is the field that matched (subject, author, email or sha for
+/// "any") and `ranges` are `[start, end)` UTF-16 offsets of the matches in
+/// its main text (the subject for "message"), or { error }.
+#[wasm_bindgen]
+pub fn highlight_commits(handle: u32, field: &str, pattern: &str, flags_json: &str) -> String {
+    guarded_json(|| {
+        let flags = if flags_json.trim().is_empty() {
+            filter::MatchFlags::default()
+        } else {
+            match serde_json::from_str(flags_json) {
+                Ok(flags) => flags,
+                Err(e) => {
+                    return json_error(ErrorCode::BadArgument, format!("Invalid match flags: {}", e))
+                }
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::highlight_commits(layout, field, pattern, &flags) {
+            Ok(matches) => to_json(&matches),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Fuzzy (fzf-style subsequence) search over commits in a stored layout,
+/// for quick-open-style commit search.
+///
+/// Supported fields: "subject" (or "message"), "author", "committer",
+/// "sha", "source". `flags_json` is `{ case? }` as for
+/// `filter_commits_with_flags`; pass an empty string for smart case.
+/// Returns: JSON array of { node, score, positions }, best match first,
+/// where `positions` are UTF-16 offsets of the matched characters in the
+/// field, or { error }.
+#[wasm_bindgen]
+pub fn fuzzy_filter_commits(
+    handle: u32,
+    field: &str,
+    pattern: &str,
+    flags_json: &str,
+) -> String {
+    fuzzy_filter_commits_sorted(handle, field, pattern, flags_json, "")
+}
+
+/// `fuzzy_filter_commits` with the matches in another order for the flat
+/// search results list.
+///
+/// `sort` is "Relevance" (best score first, the default for an empty
+/// string), "Row", "DateDesc" or "DateAsc"; ties keep row order.
+/// Returns: JSON array of { node, score, positions }, or { error }.
+#[wasm_bindgen]
+pub fn fuzzy_filter_commits_sorted(
+    handle: u32,
+    field: &str,
+    pattern: &str,
+    flags_json: &str,
+    sort: &str,
+) -> String {
+    guarded_json(|| {
+        let order = if sort.trim().is_empty() {
+            filter::SortOrder::Relevance
+        } else {
+            match serde_json::from_value(serde_json::Value::String(sort.trim().to_string())) {
+                Ok(order) => order,
+                Err(e) => {
+                    return json_error(ErrorCode::BadArgument, format!("Invalid sort order: {}", e))
+                }
+            }
+        };
+
+        let flags = if flags_json.trim().is_empty() {
+            filter::MatchFlags {
+                case: filter::CaseMode::Smart,
+                ..Default::default()
+            }
+        } else {
+            match serde_json::from_str(flags_json) {
+                Ok(flags) => flags,
+                Err(e) => {
+                    return json_error(ErrorCode::BadArgument, format!("Invalid match flags: {}", e))
+                }
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::fuzzy_filter_commits(layout, field, pattern, &flags) {
+            Ok(mut matches) => {
+                filter::sort_fuzzy_matches(&mut matches, order);
+                to_json(&matches)
+            }
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Store `layout` as a filtered view derived from `parent` and return it
+/// with its new handle.
+fn store_view(store: &mut LayoutStore, parent: u32, layout: LayoutResult) -> String {
+    match insert_view(store, parent, layout.clone()) {
+        Ok(handle) => to_json(&HandleResult { handle, layout }),
+        Err(e) => error_json(e),
+    }
+}
+
+/// Store `layout` as a view derived from `parent`, which must still be
+/// stored: views are freed with the layout they were derived from.
+fn insert_view(
+    store: &mut LayoutStore,
+    parent: u32,
+    layout: LayoutResult,
+) -> Result<u32, ErrorInfo> {
+    let commits = match store.get(parent) {
+        Some(stored) => {
+            let shas: std::collections::HashSet<&str> =
+                layout.nodes.iter().map(|n| n.sha.as_str()).collect();
+            stored
+                .commits
+                .iter()
+                .filter(|c| shas.contains(c.sha.as_str()))
+                .cloned()
+                .collect()
+        }
+        None => return Err(invalid_handle_error(parent)),
+    };
+    Ok(store.insert(StoredLayout {
+        layout,
+        input: None,
+        parent: Some(parent),
+        active_filter: None,
+        created_at: now_ms(),
+        commits,
+        session: None,
+        options: graph::LayoutOptions::default(),
+    }))
+}
+
+/// `filter_commits`, storing the result as a new layout so that further
+/// filters, windowing and stats can run on the filtered view. Views can be
+/// filtered again, forming a stack the UI pops with `pop_layout_view`; they
+/// cannot be appended to.
+///
+/// Returns: JSON string with the view's { handle, nodes, edges,
+/// totalCount }, or { error }.
+#[wasm_bindgen]
+pub fn filter_commits_into(handle: u32, field: &str, pattern: &str) -> String {
+    guarded_json(|| {
+        let mut store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        let flags = filter::MatchFlags::default();
+        match filter::filter_commits_by_field(layout, field, pattern, &flags) {
+            Ok(filtered) => store_view(&mut store, handle, filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// `filter_query_with_options`, storing the result as a new layout like
+/// `filter_commits_into`.
+///
+/// Returns: JSON string with the view's { handle, nodes, edges,
+/// totalCount }, or { error }.
+#[wasm_bindgen]
+pub fn filter_query_into(handle: u32, query: &str, options_json: &str) -> String {
+    guarded_json(|| {
+        let options: filter::FilterOptions = if options_json.trim().is_empty() {
+            filter::FilterOptions::default()
+        } else {
+            match serde_json::from_str(options_json) {
+                Ok(options) => options,
+                Err(e) => {
+                    return json_error(
+                        ErrorCode::BadArgument,
+                        format!("Invalid filter options: {}", e),
+                    )
+                }
+            }
+        };
+
+        let mut store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_commits_by_query(layout, query) {
+            Ok(mut filtered) => {
+                options.apply(layout, &mut filtered);
+                store_view(&mut store, handle, filtered)
+            }
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Set the filter query evaluated over commits appended to a layout, so
+/// the UI can extend a filtered graph page by page without re-running the
+/// filter over the whole layout. An empty query clears it.
+///
+/// Returns: JSON LayoutResult with the current matches of the query, or
+/// { error } if it does not parse or the handle is a filtered view.
+#[wasm_bindgen]
+pub fn set_active_filter(handle: u32, query: &str) -> String {
+    guarded_json(|| {
+        let query = if query.trim().is_empty() {
+            None
+        } else {
+            match filter::parse_query(query) {
+                Ok(query) => Some(query),
+                Err(e) => return error_json(e),
+            }
+        };
+
+        let mut store = lock(layout_store());
+
+        let stored = match store.get_mut(handle) {
+            Some(StoredLayout { input: None, .. }) => {
+                let message = format!("Cannot filter appends to a filtered view: {}", handle);
+                return json_error(ErrorCode::InvalidState, message);
+            }
+            Some(stored) => stored,
+            None => return invalid_handle(handle),
+        };
+
+        let matches = match &query {
+            Some(query) => filter::select_nodes(&stored.layout, |n| query.matches(n)),
+            None => stored.layout.clone(),
+        };
+        stored.active_filter = query;
+        to_json(&matches)
+    })
+}
+
+/// Free a filtered view, and any views derived from it, and return the
+/// handle it was derived from.
+///
+/// Returns: JSON string with { handle } of the parent, or { error } if the
+/// handle is not a filtered view.
+#[wasm_bindgen]
+pub fn pop_layout_view(handle: u32) -> String {
+    guarded_json(|| {
+        let mut store = lock(layout_store());
+
+        let parent = match store.get(handle) {
+            Some(StoredLayout {
+                parent: Some(parent),
+                ..
+            }) => *parent,
+            Some(_) => {
+                return json_error(
+                    ErrorCode::InvalidState,
+                    format!("Not a filtered view: {}", handle),
+                )
+            }
+            None => return invalid_handle(handle),
+        };
+        store.remove(handle);
+        serde_json::json!({ "handle": parent }).to_string()
+    })
+}
+
+/// Filter commits in a stored layout by a compound query such as
+/// `author:alice AND (message:/panic/i OR sha:abc123) AND after:2024-01-01`.
+///
+/// Fields are message (the default), subject, body, author, committer,
+/// email, domain, sha, source, ref, trailer (`trailer:Signed-off-by`,
+/// `trailer:co-authored-by=alice`), is (`is:merge`, `is:nonmerge`),
+/// signature (`signature:unsigned`, `signature:verified`), path,
+/// insertions, deletions, lines and files (counts such as `lines:>500`),
+/// after and before; values are words, "quoted strings" or /regex/
+/// with i, m, s and x flags. Terms combine with AND, OR, NOT (or a `!`
+/// prefix, as in `!author:dependabot`) and parentheses, and adjacent terms
+/// are ANDed.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error } if the query does not parse.
+#[wasm_bindgen]
+pub fn filter_query(handle: u32, query: &str) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_commits_by_query(layout, query) {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// `filter_query` with options for building the filtered view.
+///
+/// `options_json` is `{ elideHidden?, context?, compact?, sort? }`:
+/// `elideHidden` adds `Elided` edges from each match to its nearest
+/// matching ancestors through filtered-out commits, so the view stays
+/// connected, and `context` also keeps the commits up to that many parent
+/// or child links from a match, flagged `isContext`. `compact` re-runs row
+/// and lane assignment over the kept commits for a gap-free view; by
+/// default they keep their rows and lanes in the full graph, so the view
+/// lines up with it when the other rows are dimmed. `sort` orders the
+/// nodes for a flat results list: "Row" (the default), "DateDesc" or
+/// "DateAsc". Pass an empty string for the defaults.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_query_with_options(handle: u32, query: &str, options_json: &str) -> String {
+    guarded_json(|| {
+        match query_with_options(handle, query, options_json) {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// `filter_query_with_options`, returning the result in `format` as for
+/// `compute_graph_layout_encoded`.
+///
+/// This is the one binary path for filters: the single-field exports
+/// (`filter_commits`, `filter_by_path`, `filter_by_date`, ...) return JSON
+/// only, and each has a query equivalent (`author:`, `path:`, `after:`,
+/// ...) to use here when the result is large.
+///
+/// Returns: the encoded LayoutResult, or an encoded { error }; throws if
+/// the format is unknown.
+#[wasm_bindgen]
+pub fn filter_query_encoded(
+    handle: u32,
+    query: &str,
+    options_json: &str,
+    format: &str,
+) -> Result<Vec<u8>, String> {
+    let format = parse_output_format(format)?;
+    Ok(guarded(
+        || match query_with_options(handle, query, options_json) {
+            Ok(filtered) => encode(&filtered, format),
+            Err(e) => encode_error(e, format),
+        },
+        |e| encode_error(e, format),
+    ))
+}
+
+fn query_with_options(
+    handle: u32,
+    query: &str,
+    options_json: &str,
+) -> Result<LayoutResult, ErrorInfo> {
+    let options: filter::FilterOptions = if options_json.trim().is_empty() {
+        filter::FilterOptions::default()
+    } else {
+        serde_json::from_str(options_json)
+            .map_err(|e| {
+                let message = format!("Invalid filter options: {}", e);
+                ErrorInfo::with_code(ErrorCode::BadArgument, message)
+            })?
+    };
+
+    let store = lock(layout_store());
+
+    let layout = match store.get(handle) {
+        Some(stored) => &stored.layout,
+        None => return Err(invalid_handle_error(handle)),
+    };
+
+    let mut filtered = filter::filter_commits_by_query(layout, query)?;
+    options.apply(layout, &mut filtered);
+    Ok(filtered)
+}
+
+/// `filter_query_with_options`, also counting the matches per author, year,
+/// month and branch so the search sidebar can offer narrowing chips.
+///
+/// Returns: JSON string with { nodes, edges, totalCount, facets: { authors,
+/// years, months, refs } }, each facet a list of { value, count }, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_query_with_facets(handle: u32, query: &str, options_json: &str) -> String {
+    guarded_json(|| {
+        let options: filter::FilterOptions = if options_json.trim().is_empty() {
+            filter::FilterOptions::default()
+        } else {
+            match serde_json::from_str(options_json) {
+                Ok(options) => options,
+                Err(e) => {
+                    return json_error(
+                        ErrorCode::BadArgument,
+                        format!("Invalid filter options: {}", e),
+                    )
+                }
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_commits_by_query(layout, query) {
+            Ok(mut filtered) => {
+                options.apply(layout, &mut filtered);
+                let facets = filter::compute_facets(layout, &filtered);
+                to_json(&filter::FacetView {
+                    layout: filtered,
+                    facets,
+                })
+            }
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Filter commits in a stored layout to those touching a path, for "show
+/// history of src/graph/" within the loaded graph.
+///
+/// `pattern` is a path, matching it and everything below it, or a glob
+/// with `*`, `?`, `**` and `[...]`. Needs changed files from
+/// `--name-status` or `--numstat`; `--name-status` renames match on either
+/// path.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_by_path(handle: u32, pattern: &str) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_commits_by_path(layout, pattern) {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Filter commits in a stored layout to a caller-provided set of SHAs, e.g.
+/// the commits of a pull request fetched from the forge API.
+///
+/// `shas_json` is a JSON array of full or abbreviated SHAs; unknown SHAs
+/// are ignored.
+/// Returns: JSON LayoutResult with only the listed commits and the edges
+/// between them, or { error }.
+#[wasm_bindgen]
+pub fn filter_by_shas(handle: u32, shas_json: &str) -> String {
+    guarded_json(|| {
+        let shas: Vec<String> = match serde_json::from_str(shas_json) {
+            Ok(s) => s,
+            Err(e) => return json_error(ErrorCode::BadArgument, format!("Invalid SHA list: {}", e)),
+        };
+
+        let store = lock(layout_store());
+
+        match store.get(handle) {
+            Some(stored) => to_json(&filter::filter_commits_by_shas(&stored.layout, &shas)),
+            None => invalid_handle(handle),
+        }
+    })
+}
+
+/// Save a filter query under a name so it can be applied to any layout
+/// with `apply_saved_filter`. Saving an existing name replaces it.
+///
+/// The query is parsed once here (see `filter_query` for the syntax).
+/// Saved filters last for the lifetime of the module; persist the output
+/// of `list_filters` and save them again to keep them across sessions.
+/// Returns: JSON string with { name, query }, or { error } if the name is
+/// blank or the query does not parse.
+#[wasm_bindgen]
+pub fn save_filter(name: &str, query: &str) -> String {
+    guarded_json(|| {
+        let mut filters = lock(saved_filters());
+        match filters.save(name, query) {
+            Ok(saved) => to_json(saved),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Filter commits in a stored layout by a filter saved with `save_filter`.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn apply_saved_filter(handle: u32, name: &str) -> String {
+    guarded_json(|| {
+        let query = match lock(saved_filters()).get(name) {
+            Some(saved) => saved.parsed.clone(),
+            None => return json_error(ErrorCode::NotFound, format!("Unknown filter: {}", name)),
+        };
+
+        let store = lock(layout_store());
+
+        match store.get(handle) {
+            Some(stored) => to_json(&filter::select_nodes(&stored.layout, |n| query.matches(n))),
+            None => invalid_handle(handle),
+        }
+    })
+}
+
+/// List the saved filters in name order.
+/// Returns: JSON array of { name, query }.
+#[wasm_bindgen]
+pub fn list_filters() -> String {
+    guarded_json(|| to_json(&lock(saved_filters()).list()))
+}
+
+/// Delete a saved filter.
+/// Returns: true if a filter of that name existed.
+#[wasm_bindgen]
+pub fn delete_filter(name: &str) -> bool {
+    guarded(
+        || lock(saved_filters()).remove(name),
+        |_| false,
+    )
+}
+
+/// Filter commits in a stored layout by how much they change, to locate
+/// risky commits such as those touching more than 500 lines.
+///
+/// `filter_json` is a SizeFilter with optional inclusive `minInsertions`,
+/// `maxInsertions`, `minDeletions`, `maxDeletions`, `minLines`, `maxLines`,
+/// `minFiles` and `maxFiles`. Line counts need a log run with `--numstat`.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_by_size(handle: u32, filter_json: &str) -> String {
+    guarded_json(|| {
+        let filter: filter::SizeFilter = match serde_json::from_str(filter_json) {
+            Ok(f) => f,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid size filter: {}", e))
+            }
+        };
+
+        let store = lock(layout_store());
+
+        match store.get(handle) {
+            Some(stored) => to_json(&filter::filter_commits_by_size(&stored.layout, &filter)),
+            None => invalid_handle(handle),
+        }
+    })
+}
+
+/// Filter commits in a stored layout by date range.
+///
+/// `after` and `before` are unix epoch timestamps. Use 0 for no constraint.
+/// Returns: JSON LayoutResult with only matching commits and edges.
+#[wasm_bindgen]
+pub fn filter_by_date(handle: u32, after: u64, before: u64) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        let filtered = filter::filter_commits_by_date(layout, after, before);
+        to_json(&filtered)
+    })
+}
+
+/// `filter_commits_with_flags` and `filter_by_date` in one call, so the
+/// frontend need not intersect two filtered layouts.
+///
+/// `after` and `before` are unix epoch timestamps; use 0 for no
+/// constraint. `flags_json` is as for `filter_commits_with_flags`, and its
+/// `negate` inverts only the pattern, not the date range. The same
+/// combination is available as a query, e.g.
+/// `author:alice after:2024-01-01` with `filter_query`.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_commits_in_date_range(
+    handle: u32,
+    field: &str,
+    pattern: &str,
+    flags_json: &str,
+    after: u64,
+    before: u64,
+) -> String {
+    guarded_json(|| {
+        let flags = if flags_json.trim().is_empty() {
+            filter::MatchFlags::default()
+        } else {
+            match serde_json::from_str(flags_json) {
+                Ok(flags) => flags,
+                Err(e) => {
+                    return json_error(ErrorCode::BadArgument, format!("Invalid match flags: {}", e))
+                }
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        let filtered =
+            filter::filter_commits_by_field_in_range(layout, field, pattern, &flags, after, before);
+        match filtered {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Filter commits in a stored layout by date bounds written the way people
+/// write them: `"2 weeks ago"`, `"yesterday"`, `"2024-03-01"`.
+///
+/// `after` and `before` are date expressions (see
+/// `filter::parse_date_expr`); pass an empty string for no constraint.
+/// Relative expressions count back from `now` (epoch seconds), and days
+/// start at midnight in `tz_offset_min`, the user's UTC offset.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error } if a bound does not parse.
+#[wasm_bindgen]
+pub fn filter_by_date_expr(
+    handle: u32,
+    after: &str,
+    before: &str,
+    now: u64,
+    tz_offset_min: i32,
+) -> String {
+    guarded_json(|| {
+        let parse = |text: &str| match text.trim() {
+            "" => Ok(0),
+            text => filter::parse_date_expr(text, now, tz_offset_min),
+        };
+        let (after, before) = match (parse(after), parse(before)) {
+            (Ok(after), Ok(before)) => (after, before),
+            (Err(e), _) | (_, Err(e)) => return error_json(e),
+        };
+
+        filter_by_date(handle, after, before)
+    })
+}
+
+/// Filter commits in a stored layout by email domain, e.g. to keep only
+/// `@mycompany.com` or, with `exclude`, to audit external contributions.
+///
+/// `field` is "author", "committer" or "email" (either); `domains_json` is
+/// a JSON array of domains, each also covering its subdomains.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_by_email_domain(
+    handle: u32,
+    field: &str,
+    domains_json: &str,
+    exclude: bool,
+) -> String {
+    guarded_json(|| {
+        let domains: Vec<String> = match serde_json::from_str(domains_json) {
+            Ok(d) => d,
+            Err(e) => {
+                return json_error(ErrorCode::BadArgument, format!("Invalid domain list: {}", e))
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_commits_by_domain(layout, field, &domains, exclude) {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Filter commits in a stored layout by a trailer of their message, e.g.
+/// those with `Co-authored-by` naming someone or, with `negate`, those
+/// missing `Signed-off-by`.
+///
+/// `key` is compared without regard to case; `pattern` is matched against
+/// the trailer values, or pass an empty string to match any value.
+/// `flags_json` is as for `filter_commits_with_flags`; pass an empty string
+/// for the defaults.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_by_trailer(handle: u32, key: &str, pattern: &str, flags_json: &str) -> String {
+    guarded_json(|| {
+        let flags = if flags_json.trim().is_empty() {
+            filter::MatchFlags::default()
+        } else {
+            match serde_json::from_str(flags_json) {
+                Ok(flags) => flags,
+                Err(e) => {
+                    return json_error(ErrorCode::BadArgument, format!("Invalid match flags: {}", e))
+                }
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_commits_by_trailer(layout, key, pattern, &flags) {
+            Ok(filtered) => to_json(&filtered),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Filter commits in a stored layout by signature verification status, so
+/// audits can isolate unsigned or badly signed commits in the graph.
+///
+/// `status` is "verified" (a good signature), "unverified" (anything else,
+/// unsigned included), "bad", "signed" or "unsigned", in any case. The
+/// layout must come from a log format including `%G?`.
+/// Returns: JSON LayoutResult with only matching commits and edges, or
+/// { error }.
+#[wasm_bindgen]
+pub fn filter_by_signature(handle: u32, status: &str) -> String {
+    guarded_json(|| {
+        let filter = match filter::SignatureFilter::from_name(status) {
+            Some(filter) => filter,
+            None => {
+                return json_error(
+                    ErrorCode::BadArgument,
+                    format!("Invalid signature filter: '{}'", status),
+                )
+            }
+        };
+
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        to_json(&filter::filter_commits_by_signature(layout, filter))
+    })
+}
+
+/// Keep only the release commits of a stored layout, for a "releases only"
+/// toggle.
+///
+/// `pattern` is a regex matched against tag names; pass an empty string
+/// for version-like tags such as `v1.2.3` or `2.0.0-rc.1`.
+/// Returns: JSON string with { nodes, edges, totalCount, releases }, where
+/// consecutive releases are joined by `Elided` edges and each release is
+/// { sha, row, tags, commitCount } with the number of loaded commits since
+/// the previous release, or { error }.
+#[wasm_bindgen]
+pub fn filter_releases(handle: u32, pattern: &str) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        match filter::filter_releases(layout, pattern) {
+            Ok(view) => to_json(&view),
+            Err(e) => error_json(e),
+        }
+    })
+}
+
+/// Filter commits in a stored layout by whether they are merges.
+///
+/// `merges_only` true keeps only merges (like `--merges`); false drops them
+/// (like `--no-merges`).
+/// Returns: JSON LayoutResult with only matching commits and edges.
+#[wasm_bindgen]
+pub fn filter_merges(handle: u32, merges_only: bool) -> String {
+    guarded_json(|| {
+        let store = lock(layout_store());
+
+        let layout = match store.get(handle) {
+            Some(stored) => &stored.layout,
+            None => return invalid_handle(handle),
+        };
+
+        let filtered = filter::filter_commits_by_merge(layout, merges_only);
+        to_json(&filtered)
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_graph_layout_and_free() {
+        let raw = b"aaa111aaa111aaa111aaa111aaa111aaa111aaa1\x00aaa111a\x00bbb222bbb222bbb222bbb222bbb222bbb222bbb2\x00Alice\x00alice@example.com\x001700000000\x00Alice\x00alice@example.com\x001700000000\x00Second commit\x00 (HEAD -> main)\x1ebbb222bbb222bbb222bbb222bbb222bbb222bbb2\x00bbb222b\x00\x00Bob\x00bob@example.com\x001699999000\x00Bob\x00bob@example.com\x001699999000\x00Initial commit\x00\x1e";
+        let result_json = compute_graph_layout(raw);
+        let parsed: serde_json::Value = serde_json::from_str(&result_json).unwrap();
+
+        assert!(parsed.get("handle").is_some());
+        assert!(parsed.get("nodes").is_some());
+        assert!(parsed.get("edges").is_some());
+        assert_eq!(parsed["totalCount"], 2);
+
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        // Free the layout
+        free_layout(handle);
+
+        // Filtering on a freed handle should return an error
+        let err_json = filter_commits(handle, "author", "Alice");
+        let err_parsed: serde_json::Value = serde_json::from_str(&err_json).unwrap();
+        assert!(err_parsed.get("error").is_some());
+    }
+
+    #[test]
+    fn test_compute_graph_layout_with_format_and_append() {
+        let format = r#"["%H", "%P", "%an", "%at", "%s"]"#;
+        let raw = b"aaa\x00bbb\x00Alice\x001700000000\x00Second\x1e";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_format(raw, format)).unwrap();
+        assert_eq!(parsed["totalCount"], 1);
+        assert_eq!(parsed["nodes"][0]["subject"], "Second");
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        // Appended pages are parsed with the handle's format
+        let page = b"bbb\x00\x00Bob\x001699999000\x00First\x1e";
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, page)).unwrap();
+        assert_eq!(appended["totalCount"], 2);
+        assert_eq!(appended["nodes"][1]["authorName"], "Bob");
+        assert_eq!(appended["edges"][0]["toRow"], 1);
+
+        free_layout(handle);
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_format(raw, r#"["%s"]"#)).unwrap();
+        assert!(bad.get("error").is_some());
+    }
+
+    #[test]
+    fn test_compute_graph_layout_with_options() {
+        let options = r#"{
+            "format": ["%H", "%P", "%an", "%ct", "%s"],
+            "order": "Date",
+            "paletteSize": 1,
+            "maxLanes": 1
+        }"#;
+        let raw = b"bbb\x00aaa\x00Bob\x001700000100\x00Old\x1eccc\x00aaa\x00Ann\x001700000200\x00New\x1e";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_options(raw, options)).unwrap();
+        assert_eq!(parsed["nodes"][0]["subject"], "New");
+        assert_eq!(parsed["nodes"][1]["lane"], 0);
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        // Appends keep the handle's format and options
+        let page = b"aaa\x00\x00Bob\x001700000000\x00Root\x1e";
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, page)).unwrap();
+        assert_eq!(appended["totalCount"], 3);
+        let nodes = appended["nodes"].as_array().unwrap();
+        assert_eq!(nodes[0]["subject"], "New");
+        assert!(nodes.iter().all(|n| n["lane"] == 0 && n["colorIndex"] == 0));
+        free_layout(handle);
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_options(b"", "")).unwrap();
+        assert_eq!(parsed["totalCount"], 0);
+        free_layout(parsed["handle"].as_u64().unwrap() as u32);
+        let bad: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_options(raw, r#"{"order": 1}"#))
+                .unwrap();
+        assert_eq!(bad["error"]["code"], "BAD_ARGUMENT");
+    }
+
+    #[test]
+    fn test_active_filter_on_append() {
+        let format = r#"["%H", "%P", "%an", "%at", "%s"]"#;
+        let raw = b"ccc\x00bbb\x00Alice\x001700000200\x00Fix tip\x1ebbb\x00aaa\x00Bob\x001700000100\x00Docs\x1e";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_format(raw, format)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&set_active_filter(handle, "(fix")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+        let current: serde_json::Value =
+            serde_json::from_str(&set_active_filter(handle, "fix")).unwrap();
+        assert_eq!(current["totalCount"], 1);
+
+        let page = b"aaa\x00zzz\x00Bob\x001700000000\x00Fix base\x1ezzz\x00\x00Bob\x001699999000\x00Root\x1e";
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, page)).unwrap();
+        assert_eq!(appended["totalCount"], 4);
+        assert_eq!(appended["filterMatches"]["totalCount"], 1);
+        assert_eq!(appended["filterMatches"]["nodes"][0]["subject"], "Fix base");
+        assert_eq!(appended["filterMatches"]["nodes"][0]["row"], 2);
+
+        // Nothing new still reports (empty) matches; clearing drops them
+        let again: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, page)).unwrap();
+        assert_eq!(again["filterMatches"]["totalCount"], 0);
+        set_active_filter(handle, "");
+        let again: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, page)).unwrap();
+        assert!(again.get("filterMatches").is_none());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_compute_graph_layout_from_graph_log() {
+        let format = r#"["%H", "%s"]"#;
+        let raw = b"*   mmm\x00Merge\x1e\n|\\  \n| * fff\x00Feature\x1e\n|/  \n* bbb\x00Base\x1e\n";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_from_graph_log(raw, format)).unwrap();
+        assert_eq!(parsed["totalCount"], 3);
+        assert_eq!(parsed["nodes"][0]["parents"], serde_json::json!(["bbb", "fff"]));
+        assert_eq!(parsed["nodes"][1]["subject"], "Feature");
+        free_layout(parsed["handle"].as_u64().unwrap() as u32);
+    }
+
+    #[test]
+    fn test_compute_graph_layout_from_rev_list_and_append() {
+        let raw = b"aaaa bbbb\ntree t\nauthor A <a@x> 1700000000 +0000\ncommitter A <a@x> 1700000000 +0000\n\n    Tip\n\x00";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_from_rev_list(raw)).unwrap();
+        assert_eq!(parsed["totalCount"], 1);
+        assert_eq!(parsed["nodes"][0]["subject"], "Tip");
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let page = b"bbbb\ntree t\nauthor B <b@x> 1699999000 +0000\ncommitter B <b@x> 1699999000 +0000\n\n    Base\n\x00";
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, page)).unwrap();
+        assert_eq!(appended["totalCount"], 2);
+        assert_eq!(appended["nodes"][1]["authorName"], "B");
+        assert_eq!(appended["edges"][0]["toRow"], 1);
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_parse_log_diagnostics_wasm() {
+        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Ok\x00\x1etruncated\x00record\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&parse_log_diagnostics(raw, "")).unwrap();
+        assert_eq!(parsed["commits"].as_array().unwrap().len(), 1);
+        assert_eq!(parsed["diagnostics"][0]["recordIndex"], 1);
+        assert_eq!(parsed["diagnostics"][0]["byteOffset"], 62);
+        assert!(parsed["diagnostics"][0]["reason"].as_str().unwrap().contains("found 2"));
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&parse_log_diagnostics(raw, r#"["%s"]"#)).unwrap();
+        assert!(bad.get("error").is_some());
+    }
+
+    #[test]
+    fn test_blame_heatmap_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nauthor-time 1700000000\nfilename a.rs\n\
+1111111111111111111111111111111111111111 1 3 1\nauthor-time 1690000000\nfilename a.rs\n";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&blame_heatmap(raw, r#"{"boundaries": [86400]}"#)).unwrap();
+        assert_eq!(parsed["lineBuckets"], serde_json::json!([0, 0, 1]));
+        assert_eq!(parsed["now"], 1700000000);
+
+        let defaults: serde_json::Value = serde_json::from_str(&blame_heatmap(raw, "")).unwrap();
+        assert_eq!(defaults["boundaries"].as_array().unwrap().len(), 3);
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&blame_heatmap(raw, r#"{"boundaries": [2, 1]}"#)).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        let malformed = b"abcdef0123456789abcdef0123456789abcdef01 1 4294967295 2\nfilename a.rs\n";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&blame_heatmap(malformed, "")).unwrap();
+        assert_eq!(parsed["lineBuckets"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_blame_line_ages_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\nauthor-time 1000\nfilename a.rs\n\
+1111111111111111111111111111111111111111 1 2 1\nauthor-time 3000\nfilename a.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&blame_line_ages(raw, "")).unwrap();
+        assert_eq!(parsed["ages"], serde_json::json!([1.0, 0.0]));
+
+        let ranged: serde_json::Value =
+            serde_json::from_str(&blame_line_ages(raw, r#"{"oldest": 0}"#)).unwrap();
+        assert_eq!(parsed["newest"], 3000);
+        assert!((ranged["ages"][0].as_f64().unwrap() - 2.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_blame_summary_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 3\nauthor-mail <s1@summary.test>\n\
+author-time 1000\nfilename a.rs\n\
+1111111111111111111111111111111111111111 1 4 1\nauthor-mail <s2@summary.test>\n\
+author-time 2000\nfilename a.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&blame_summary(raw)).unwrap();
+        assert_eq!(parsed["oldestDate"], 1000);
+        assert_eq!(parsed["newestDate"], 2000);
+        assert_eq!(parsed["commitCount"], 2);
+        assert_eq!(parsed["authorCount"], 2);
+        assert_eq!(parsed["largestSpan"]["numLines"], 3);
+    }
+
+    #[test]
+    fn test_blame_ownership_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 3\nauthor Ann\nauthor-mail <ann@owners.test>\nfilename a.rs\n\
+1111111111111111111111111111111111111111 1 4 1\nauthor Ben\nauthor-mail <ben@owners.test>\nfilename a.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&blame_ownership(raw)).unwrap();
+        assert_eq!(parsed["totalLines"], 4);
+        assert_eq!(parsed["owners"][0]["name"], "Ann");
+        assert_eq!(parsed["owners"][0]["percent"], 75.0);
+        assert_eq!(parsed["owners"][1]["lines"], 1);
+    }
+
+    #[test]
+    fn test_aggregate_blame_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nauthor Ann\n\
+author-mail <ann@aggregate.test>\nfilename src/a.rs\n";
+        let entries: serde_json::Value = serde_json::from_str(&parse_blame(raw)).unwrap();
+        let mut camel = entries.clone();
+        schema::camel_case_keys(&mut camel);
+        let files = serde_json::json!([
+            { "path": "src/a.rs", "entries": entries },
+            { "path": "src/b.rs", "entries": camel },
+        ]);
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&aggregate_blame(&files.to_string())).unwrap();
+        let directories = parsed["directories"].as_array().unwrap();
+        assert_eq!(directories.len(), 2);
+        assert_eq!(directories[1]["path"], "src");
+        assert_eq!(directories[1]["fileCount"], 2);
+        assert_eq!(directories[1]["totalLines"], 4);
+        assert_eq!(directories[1]["owners"][0]["name"], "Ann");
+
+        let bad: serde_json::Value = serde_json::from_str(&aggregate_blame("{}")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+    }
+
+    #[test]
+    fn test_blame_survival_wasm() {
+        let old = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nauthor Ann\nfilename a.rs\n";
+        let new = b"abcdef0123456789abcdef0123456789abcdef01 2 1 1\nauthor Ann\nfilename a.rs\n\
+1111111111111111111111111111111111111111 1 2 1\nauthor Ben\nfilename a.rs\n";
+        let snapshots = serde_json::json!([
+            {
+                "label": "v1",
+                "entries": serde_json::from_str::<serde_json::Value>(&parse_blame(old)).unwrap(),
+            },
+            {
+                "label": "v2",
+                "entries": serde_json::from_str::<serde_json::Value>(&parse_blame(new)).unwrap(),
+            },
+        ]);
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&blame_survival(&snapshots.to_string())).unwrap();
+        assert_eq!(parsed["snapshots"][0]["surviving"], 1);
+        assert_eq!(parsed["snapshots"][0]["survivalRate"], 0.5);
+        assert_eq!(parsed["snapshots"][1]["cohorts"], serde_json::json!([1, 1]));
+        assert_eq!(parsed["lineIntroduced"], serde_json::json!([0, 1]));
+
+        let bad: serde_json::Value = serde_json::from_str(&blame_survival("[{}]")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+    }
+
+    #[test]
+    fn test_blame_session_wasm() {
+        let created: serde_json::Value = serde_json::from_str(&create_blame_session()).unwrap();
+        let handle = created["handle"].as_u64().unwrap() as u32;
+
+        let first: serde_json::Value = serde_json::from_str(&blame_append(
+            handle,
+            b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\nauthor Ann\nfile",
+        ))
+        .unwrap();
+        assert_eq!(first["entries"].as_array().unwrap().len(), 0);
+
+        let second: serde_json::Value = serde_json::from_str(&blame_append(
+            handle,
+            b"name a.rs\nabcdef0123456789abcdef0123456789abcdef01 2 2 1\nfilename a.rs\n",
+        ))
+        .unwrap();
+        assert_eq!(second["entries"][0]["final_line"], 1);
+        assert_eq!(second["entries"][1]["author_name"], "Ann");
+
+        let done: serde_json::Value = serde_json::from_str(&blame_finish(handle)).unwrap();
+        assert_eq!(done["entries"].as_array().unwrap().len(), 0);
+
+        let finished: serde_json::Value = serde_json::from_str(&blame_append(handle, b"")).unwrap();
+        assert!(finished["error"]["message"].is_string());
+
+        let stored: serde_json::Value =
+            serde_json::from_str(&blame_for_lines(handle, 1, 10)).unwrap();
+        assert_eq!(stored["entries"].as_array().unwrap().len(), 2);
+        free_blame(handle);
+    }
+
+    #[test]
+    fn test_store_blame_and_query_lines() {
+        let raw = b"1111111111111111111111111111111111111111 1 5 3\nfilename a.rs\n\
+abcdef0123456789abcdef0123456789abcdef01 1 1 4\nfilename a.rs\n\
+2222222222222222222222222222222222222222 1 8 2\nfilename a.rs\n";
+        let stored: serde_json::Value = serde_json::from_str(&store_blame(raw)).unwrap();
+        assert_eq!(stored["entryCount"], 3);
+        assert_eq!(stored["lineCount"], 9);
+        let handle = stored["handle"].as_u64().unwrap() as u32;
+
+        let visible: serde_json::Value =
+            serde_json::from_str(&blame_for_lines(handle, 4, 6)).unwrap();
+        let ranges: Vec<(u64, u64)> = visible["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["final_line"].as_u64().unwrap(), e["num_lines"].as_u64().unwrap()))
+            .collect();
+        // Straddling entries are split at the range boundaries
+        assert_eq!(ranges, vec![(4, 1), (5, 2)]);
+        assert_eq!(visible["entries"][1]["orig_line"], 1);
+
+        free_blame(handle);
+        let freed: serde_json::Value =
+            serde_json::from_str(&blame_for_lines(handle, 1, 1)).unwrap();
+        assert!(freed["error"]["message"].is_string());
+    }
+
+    #[test]
+    fn test_link_blame_to_layout_wasm() {
+        let sha = "b1a3e0123456789abcdef0123456789abcdef012";
+        let log = format!("{}\x00\x00\x00A\x00a@x\x001\x00A\x00a@x\x001\x00Linked\x00\x1e", sha);
+        let layout: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout(log.as_bytes())).unwrap();
+        let layout_handle = layout["handle"].as_u64().unwrap() as u32;
+
+        let raw = format!(
+            "{} 1 1 2\nfilename a.rs\n1111111111111111111111111111111111111111 1 3 1\nfilename a.rs\n",
+            sha
+        );
+        let stored: serde_json::Value =
+            serde_json::from_str(&store_blame(raw.as_bytes())).unwrap();
+        let blame_handle = stored["handle"].as_u64().unwrap() as u32;
+
+        let linked: serde_json::Value =
+            serde_json::from_str(&link_blame_to_layout(blame_handle, layout_handle)).unwrap();
+        assert_eq!(linked["linkedCount"], 1);
+        assert_eq!(linked["entryCount"], 2);
+
+        let lines: serde_json::Value =
+            serde_json::from_str(&blame_for_lines(blame_handle, 1, 3)).unwrap();
+        assert_eq!(lines["entries"][0]["layout_row"], 0);
+        assert_eq!(lines["entries"][0]["color_index"], layout["nodes"][0]["colorIndex"]);
+        assert!(lines["entries"][1].get("layout_row").is_none());
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&link_blame_to_layout(blame_handle, 0)).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_blame(blame_handle);
+        free_layout(layout_handle);
+    }
+
+    #[test]
+    fn test_parse_blame_range_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 10 1 3\nfilename a.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&parse_blame_range(raw, 2, 9)).unwrap();
+        assert_eq!(parsed[0]["orig_line"], 11);
+        assert_eq!(parsed[0]["final_line"], 2);
+        assert_eq!(parsed[0]["num_lines"], 2);
+    }
+
+    #[test]
+    fn test_diff_blame_wasm() {
+        let old = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nfilename a.rs\n";
+        let new = b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\nfilename a.rs\n\
+1111111111111111111111111111111111111111 2 2 1\nfilename a.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&diff_blame(old, new)).unwrap();
+        assert_eq!(parsed["unchangedCount"], 1);
+        assert_eq!(parsed["changed"][0]["line"], 2);
+        assert_eq!(parsed["changed"][0]["oldSha"], "abcdef0123456789abcdef0123456789abcdef01");
+        assert_eq!(parsed["changed"][0]["newSha"], "1111111111111111111111111111111111111111");
+        assert_eq!(parsed["removedLines"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_parse_blame_with_options_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nfilename a.rs\n\
+abcdef0123456789abcdef0123456789abcdef01 5 3 1\nfilename a.rs\n";
+        let merged: serde_json::Value =
+            serde_json::from_str(&parse_blame_with_options(raw, r#"{"mergeAdjacent": true}"#))
+                .unwrap();
+        assert_eq!(merged.as_array().unwrap().len(), 1);
+        assert_eq!(merged[0]["num_lines"], 3);
+
+        let plain: serde_json::Value =
+            serde_json::from_str(&parse_blame_with_options(raw, "{}")).unwrap();
+        assert_eq!(plain.as_array().unwrap().len(), 2);
+
+        let ignored: serde_json::Value = serde_json::from_str(&parse_blame_with_options(
+            raw,
+            r##"{"ignoreRevs": ["# reformat", "abcdef01"]}"##,
+        ))
+        .unwrap();
+        assert_eq!(ignored[0]["is_unblamable"], true);
+        assert_eq!(ignored[0]["ignored_sha"], "abcdef0123456789abcdef0123456789abcdef01");
+
+        let grouped: serde_json::Value = serde_json::from_str(&parse_blame_with_options(
+            raw,
+            r#"{"groupByCommit": true, "sortBy": "Lines"}"#,
+        ))
+        .unwrap();
+        assert_eq!(grouped[0]["lineCount"], 3);
+        assert_eq!(grouped[0]["ranges"].as_array().unwrap().len(), 2);
+        assert_eq!(grouped[0]["ranges"][1]["finalLine"], 3);
+
+        assert_eq!(plain[0]["mode"], "Forward");
+        let reverse: serde_json::Value =
+            serde_json::from_str(&parse_blame_with_options(raw, r#"{"reverse": true}"#)).unwrap();
+        assert_eq!(reverse[1]["mode"], "Reverse");
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&parse_blame_with_options(raw, "[")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+    }
+
+    #[test]
+    fn test_blame_rename_history_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\n\
+previous 1111111111111111111111111111111111111111 old.rs\nfilename new.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&blame_rename_history(raw)).unwrap();
+        assert_eq!(parsed["paths"], serde_json::json!(["new.rs", "old.rs"]));
+        assert_eq!(parsed["pathAtCommit"]["1111111111111111111111111111111111111111"], "old.rs");
+        assert_eq!(parsed["renames"][0]["to"], "new.rs");
+    }
+
+    #[test]
+    fn test_blame_cache_wasm() {
+        // Keys no other test uses, since the cache is global
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\nfilename cached.rs\n";
+        let put: serde_json::Value =
+            serde_json::from_str(&blame_cache_put("cache-test/a.rs", "HEAD", raw)).unwrap();
+        assert_eq!(put["cached"], true);
+        assert_eq!(put["entryCount"], 1);
+
+        let hit: serde_json::Value =
+            serde_json::from_str(&blame_cache_get("cache-test/a.rs", "HEAD")).unwrap();
+        assert_eq!(hit["hit"], true);
+        assert_eq!(hit["entries"][0]["filename"], "cached.rs");
+
+        assert_eq!(blame_cache_invalidate("cache-test/a.rs", ""), 1);
+        let miss: serde_json::Value =
+            serde_json::from_str(&blame_cache_get("cache-test/a.rs", "HEAD")).unwrap();
+        assert_eq!(miss["hit"], false);
+        assert_eq!(miss["entries"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_parse_blame_provenance_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nfilename main.rs\n\
+1111111111111111111111111111111111111111 30 3 1\nfilename utils.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&parse_blame(raw)).unwrap();
+        assert_eq!(parsed[0]["provenance"], "Original");
+        assert_eq!(parsed[1]["provenance"], "Copied");
+        assert_eq!(parsed[1]["source_path"], "utils.rs");
+
+        // An explicit path overrides the inferred one
+        let explicit: serde_json::Value = serde_json::from_str(&parse_blame_with_options(
+            raw,
+            r#"{"path": "utils.rs"}"#,
+        ))
+        .unwrap();
+        assert_eq!(explicit[0]["provenance"], "Copied");
+        assert_eq!(explicit[1]["provenance"], "Original");
+    }
+
+    #[test]
+    fn test_export_layout_columns_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let columns = export_layout_columns(handle).unwrap();
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns.sha_bytes_len(), 6);
+        assert_eq!(columns.subject_bytes_len(), "FeatureFix bug".len());
+        // The pointers address the columns' own buffers
+        assert_eq!(columns.rows_ptr(), columns.columns.rows.as_ptr());
+        assert_eq!(columns.columns.author_dates, vec![1700000100.0, 1700000000.0]);
+        assert_eq!(columns.columns.subjects.get(1), Some("Fix bug"));
+
+        free_layout(handle);
+        let error = export_layout_columns(handle).err().unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&error).unwrap();
+        assert_eq!(parsed["error"]["code"], "INVALID_HANDLE");
+        assert_eq!(parsed["error"]["details"]["handle"], handle);
+    }
+
+    #[test]
+    fn test_store_clear_keeps_handles_unique() {
+        // On private stores: `free_all_layouts` would pull the global ones
+        // from under concurrently running tests
+        let mut layouts = LayoutStore::new();
+        let stored = || StoredLayout {
+            layout: LayoutResult::default(),
+            input: Some(LogInput::RevList),
+            parent: None,
+            active_filter: None,
+            created_at: 0.0,
+            commits: Vec::new(),
+            session: None,
+            options: graph::LayoutOptions::default(),
+        };
+        let first = layouts.insert(stored());
+        layouts.insert(stored());
+        assert_eq!(layouts.clear(), 2);
+        assert!(layouts.get(first).is_none());
+        assert!(layouts.insert(stored()) > first + 1);
+        assert_eq!(layouts.clear(), 1);
+
+        let mut blames = BlameStore::new();
+        blames.insert(StoredBlame {
+            entries: Vec::new(),
+            session: None,
+        });
+        assert_eq!(blames.clear(), 1);
+        assert_eq!(blames.clear(), 0);
+    }
+
+    #[test]
+    fn test_get_layout_rows_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let rows: serde_json::Value =
+            serde_json::from_str(&get_layout_rows(handle, 1, 50)).unwrap();
+        assert_eq!(rows["nodes"].as_array().unwrap().len(), 1);
+        assert_eq!(rows["nodes"][0]["sha"], "aaa");
+        assert_eq!(rows["edges"][0]["fromSha"], "bbb");
+        assert_eq!(rows["totalCount"], 2);
+        let rows: serde_json::Value =
+            serde_json::from_str(&get_layout_rows(handle, 2, 50)).unwrap();
+        assert!(rows["nodes"].as_array().unwrap().is_empty());
+        assert!(rows["edges"].as_array().unwrap().is_empty());
+
+        free_layout(handle);
+        let parsed: serde_json::Value =
+            serde_json::from_str(&get_layout_rows(handle, 0, 50)).unwrap();
+        assert_eq!(parsed["error"]["code"], "INVALID_HANDLE");
+    }
+
+    #[test]
+    fn test_get_node_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let detail: serde_json::Value = serde_json::from_str(&get_node(handle, "aaa")).unwrap();
+        assert_eq!(detail["node"]["subject"], "Fix bug");
+        assert_eq!(detail["edges"].as_array().unwrap().len(), 1);
+        assert_eq!(detail["edges"][0]["fromSha"], "bbb");
+        let missing: serde_json::Value = serde_json::from_str(&get_node(handle, "ccc")).unwrap();
+        assert_eq!(missing["error"]["code"], "NOT_FOUND");
+
+        free_layout(handle);
+        let parsed: serde_json::Value = serde_json::from_str(&get_node(handle, "aaa")).unwrap();
+        assert_eq!(parsed["error"]["code"], "INVALID_HANDLE");
+    }
+
+    #[test]
+    fn test_append_keeps_commit_data_wasm() {
+        let page1 = b"ccc\x00cc\x00bbb\x00Carol\x00c@e.com\x001700000200\x00Carol\x00c@e.com\x001700000250\x00Fix typo\x00 (HEAD -> main, main)\x1e";
+        let page2 = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000150\x00Feature\x00\x1e";
+        let page3 = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000050\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(page1)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+        annotate_layout_head(handle, "0123456789012345678901234567890123456789");
+        append_to_layout(handle, page2);
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, page3)).unwrap();
+        assert_eq!(appended["totalCount"], 3);
+
+        {
+            let store = lock(layout_store());
+            let commits = &store.get(handle).unwrap().commits;
+            let shas: Vec<&str> = commits.iter().map(|c| c.sha.as_str()).collect();
+            assert_eq!(shas, vec!["ccc", "bbb", "aaa"]);
+            // Committer dates are not in the layout nodes, and children span
+            // pages
+            assert_eq!(commits[0].committer.time, 1700000250);
+            assert_eq!(commits[1].children, vec!["ccc"]);
+            assert_eq!(commits[2].children, vec!["bbb"]);
+            // The detached HEAD set before the appends is still not on ccc
+            assert!(!commits[0].refs.iter().any(|r| r.ref_type == graph::RefType::Head));
+        }
+        assert_eq!(appended["nodes"][0]["nodeType"], "Normal");
+
+        let view: serde_json::Value =
+            serde_json::from_str(&filter_query_into(handle, "fix", "")).unwrap();
+        let view_handle = view["handle"].as_u64().unwrap() as u32;
+        {
+            let store = lock(layout_store());
+            let commits = &store.get(view_handle).unwrap().commits;
+            let shas: Vec<&str> = commits.iter().map(|c| c.sha.as_str()).collect();
+            assert_eq!(shas, vec!["ccc", "aaa"]);
+        }
+        free_layout(view_handle);
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_input_buffer_wasm() {
+        // Stands in for the host writing through a view of WASM memory
+        fn write(buffer: &mut InputBuffer, bytes: &[u8]) {
+            buffer.resize(bytes.len());
+            let ptr = buffer.ptr();
+            unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len()) };
+        }
+
+        let mut buffer = alloc_input_buffer(16);
+        assert_eq!(buffer.len(), 16);
+        write(&mut buffer, b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1e");
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_from_buffer(&buffer, "")).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        write(&mut buffer, b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e");
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout_from_buffer(handle, &buffer)).unwrap();
+        assert_eq!(appended["totalCount"], 2);
+        assert_eq!(appended["edges"][0]["toSha"], "aaa");
+        free_layout(handle);
+
+        write(&mut buffer, b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nauthor-time 1700000000\nfilename a.rs\n");
+        let entries: serde_json::Value =
+            serde_json::from_str(&parse_blame_from_buffer(&buffer, "")).unwrap();
+        assert_eq!(entries[0]["num_lines"], 2);
+        let grouped: serde_json::Value = serde_json::from_str(&parse_blame_from_buffer(
+            &buffer,
+            r#"{"groupByCommit": true}"#,
+        ))
+        .unwrap();
+        assert_eq!(grouped.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_streamed_layout_wasm() {
+        let raw = b"ccc\x00cc\x00bbb\x00Carol\x00c@e.com\x001700000200\x00Carol\x00c@e.com\x001700000200\x00Fix typo\x00\x1ebbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let begun: serde_json::Value = serde_json::from_str(&begin_layout("")).unwrap();
+        let handle = begun["handle"].as_u64().unwrap() as u32;
+
+        // Split mid-record: the first chunk completes only ccc, which waits
+        // for file lines that may follow it
+        let progress: serde_json::Value =
+            serde_json::from_str(&push_log_chunk(handle, &raw[..100])).unwrap();
+        assert_eq!(progress["commitsParsed"], 0);
+        assert!(progress["bytesConsumed"].as_u64().unwrap() > 0);
+        assert_eq!(progress["finished"], false);
+        assert!(progress["bufferedBytes"].as_u64().unwrap() > 0);
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout(handle, raw)).unwrap();
+        assert_eq!(appended["error"]["code"], "INVALID_STATE");
+
+        push_log_chunk(handle, &raw[100..]);
+        let progress: serde_json::Value = serde_json::from_str(&layout_progress(handle)).unwrap();
+        assert_eq!(progress["commitsParsed"], 2);
+        assert_eq!(progress["bytesConsumed"], raw.len());
+
+        let finished: serde_json::Value = serde_json::from_str(&finish_layout(handle)).unwrap();
+        assert_eq!(finished["handle"], handle);
+        assert_eq!(finished["totalCount"], 3);
+        assert_eq!(finished["edges"].as_array().unwrap().len(), 2);
+        let progress: serde_json::Value = serde_json::from_str(&layout_progress(handle)).unwrap();
+        assert_eq!(progress["commitsParsed"], 3);
+        assert_eq!(progress["finished"], true);
+
+        let again: serde_json::Value = serde_json::from_str(&finish_layout(handle)).unwrap();
+        assert_eq!(again["error"]["code"], "INVALID_STATE");
+        let pushed: serde_json::Value =
+            serde_json::from_str(&push_log_chunk(handle, raw)).unwrap();
+        assert_eq!(pushed["error"]["code"], "INVALID_STATE");
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_core_info_wasm() {
+        let info: serde_json::Value = serde_json::from_str(&core_info()).unwrap();
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(info["supportedSchemas"], serde_json::json!([1, 2]));
+        assert_eq!(info["features"]["binaryOutput"], true);
+        assert_eq!(info["features"]["threads"], false);
+        assert_eq!(info["features"]["panicUnwind"], true);
+        assert!(info["queryFields"].as_array().unwrap().contains(&"trailer".into()));
+        assert!(info["filterFields"].as_array().unwrap().contains(&"author_email".into()));
+    }
+
+    #[test]
+    fn test_graph_layout_class_wasm() {
+        let page1 = b"ccc\x00cc\x00bbb\x00Carol\x00c@e.com\x001700000200\x00Carol\x00c@e.com\x001700000200\x00Fix typo\x00\x1ebbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1e";
+        let page2 = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let graph = GraphLayout::new(page1, "").unwrap();
+        let appended: serde_json::Value = serde_json::from_str(&graph.append(page2)).unwrap();
+        assert_eq!(appended["handle"], graph.handle());
+        assert_eq!(appended["totalCount"], 3);
+
+        let fixes = graph.filter("fix", "").unwrap();
+        assert_ne!(fixes.handle(), graph.handle());
+        let parsed: serde_json::Value = serde_json::from_str(&fixes.layout()).unwrap();
+        let shas: Vec<&str> = parsed["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n["sha"].as_str().unwrap())
+            .collect();
+        assert_eq!(shas, vec!["ccc", "aaa"]);
+
+        let window: serde_json::Value = serde_json::from_str(&graph.window(1, 1)).unwrap();
+        assert_eq!(window["nodes"].as_array().unwrap().len(), 1);
+        assert_eq!(window["nodes"][0]["sha"], "bbb");
+        assert_eq!(window["edges"].as_array().unwrap().len(), 2);
+        assert_eq!(window["totalCount"], 3);
+
+        let error: serde_json::Value =
+            serde_json::from_str(&graph.filter("(fix", "").err().unwrap()).unwrap();
+        assert_eq!(error["error"]["code"], "BAD_QUERY");
+        let error = GraphLayout::new(page1, "[\"%s\"]").err().unwrap();
+        assert!(error.contains("%H"));
+
+        // Dropping the object frees its layout and the views derived from it
+        let (handle, view) = (graph.handle(), fixes.handle());
+        drop(graph);
+        let parsed: serde_json::Value = serde_json::from_str(&filter_query(handle, "")).unwrap();
+        assert_eq!(parsed["error"]["code"], "INVALID_HANDLE");
+        assert!(export_layout_columns(view).is_err());
+        drop(fixes);
+    }
+
+    #[test]
+    fn test_panic_recovery() {
+        // A panic while holding a store leaves it usable
+        let store = Mutex::new(LayoutStore::new());
+        let result = guarded_json(|| {
+            let _store = lock(&store);
+            panic!("layout invariant broken");
+        });
+        assert!(store.is_poisoned());
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed["error"]["code"], "PANIC");
+        assert_eq!(parsed["error"]["message"], "Internal panic: layout invariant broken");
+
+        assert!(lock(&store).get(1).is_none());
+        assert!(!store.is_poisoned());
+
+        let packed = guarded(
+            || panic!("row {} out of range", 7),
+            |e| encode_error(e, OutputFormat::MessagePack),
+        );
+        let parsed: serde_json::Value = rmp_serde::from_slice(&packed).unwrap();
+        assert_eq!(parsed["error"]["message"], "Internal panic: row 7 out of range");
+    }
+
+    #[test]
+    fn test_list_layouts_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+        let view: serde_json::Value =
+            serde_json::from_str(&filter_query_into(handle, "fix", "")).unwrap();
+        let view_handle = view["handle"].as_u64().unwrap() as u32;
+
+        // Other tests run concurrently, so look only at these handles
+        let listed: serde_json::Value = serde_json::from_str(&list_layouts()).unwrap();
+        let info = |h: u32| {
+            listed
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|info| info["handle"] == h)
+                .cloned()
+                .unwrap()
+        };
+        let full = info(handle);
+        assert_eq!(full["kind"], "Log");
+        assert_eq!(full["nodeCount"], 2);
+        assert_eq!(full["edgeCount"], 1);
+        assert!(full["createdAt"].as_f64().unwrap() > 0.0);
+        assert!(full.get("parent").is_none());
+        let filtered = info(view_handle);
+        assert_eq!(filtered["kind"], "View");
+        assert_eq!(filtered["parent"], handle);
+        assert!(filtered["approxBytes"].as_u64() < full["approxBytes"].as_u64());
+
+        free_layout(view_handle);
+        free_layout(handle);
+        let listed: serde_json::Value = serde_json::from_str(&list_layouts()).unwrap();
+        assert!(!listed.as_array().unwrap().iter().any(|info| info["handle"] == handle));
+    }
+
+    #[test]
+    fn test_encoded_exports_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let packed = compute_graph_layout_encoded(raw, "msgpack").unwrap();
+        let parsed: serde_json::Value = rmp_serde::from_slice(&packed).unwrap();
+        assert_eq!(parsed["totalCount"], 2);
+        assert_eq!(parsed["nodes"][1]["authorName"], "Alice");
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&filter_query_encoded(handle, "fix", "", "json").unwrap())
+                .unwrap();
+        let packed: serde_json::Value =
+            rmp_serde::from_slice(&filter_query_encoded(handle, "fix", "", "msgpack").unwrap())
+                .unwrap();
+        assert_eq!(packed, json);
+        assert_eq!(packed["nodes"][0]["sha"], "aaa");
+
+        let bad: serde_json::Value =
+            rmp_serde::from_slice(&filter_query_encoded(handle, "(", "", "msgpack").unwrap())
+                .unwrap();
+        assert!(bad["error"]["message"].is_string());
+        let unknown = filter_query_encoded(handle, "fix", "", "bincode").err().unwrap();
+        let unknown: serde_json::Value = serde_json::from_str(&unknown).unwrap();
+        assert_eq!(unknown["error"]["code"], "BAD_ARGUMENT");
+        assert!(unknown["error"]["message"].as_str().unwrap().contains("Unknown output format"));
+        assert!(parse_blame_encoded(b"", "{}", "bincode").is_err());
+
+        let blame = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nfilename a.rs\n";
+        let entries: serde_json::Value =
+            rmp_serde::from_slice(&parse_blame_encoded(blame, "{}", "msgpack").unwrap()).unwrap();
+        assert_eq!(entries[0]["num_lines"], 2);
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_blame_hover_markdown_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\nauthor Ann\n\
+author-time 1700000000\nsummary Fix it\nfilename a.rs\n";
+        let entries: serde_json::Value = serde_json::from_str(&parse_blame(raw)).unwrap();
+        let entry = entries[0].to_string();
+
+        let hover: serde_json::Value = serde_json::from_str(&blame_hover_markdown(
+            &entry,
+            r#"{"template": "{author} {relativeDate}: {summary}", "now": 1700007200}"#,
+        ))
+        .unwrap();
+        assert_eq!(hover["markdown"], "Ann 2 hours ago: Fix it");
+
+        // Entries serialized with camelCase keys are accepted too
+        let mut camel = entries[0].clone();
+        schema::camel_case_keys(&mut camel);
+        let hover: serde_json::Value =
+            serde_json::from_str(&blame_hover_markdown(&camel.to_string(), "")).unwrap();
+        assert!(hover["markdown"].as_str().unwrap().starts_with("**Ann**, 2023-11-14"));
+
+        let bad: serde_json::Value = serde_json::from_str(&blame_hover_markdown("{}", "")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+    }
+
+    #[test]
+    fn test_parse_blame_wasm() {
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 3\nauthor Alice\nauthor-mail <alice@example.com>\nauthor-time 1700000000\nauthor-tz +0000\ncommitter Bob\ncommitter-mail <bob@example.com>\ncommitter-time 1700000100\ncommitter-tz +0000\nsummary Initial commit\nfilename src/main.rs\n";
+        let result_json = parse_blame(raw);
+        let parsed: serde_json::Value = serde_json::from_str(&result_json).unwrap();
+        assert!(parsed.is_array());
+        assert_eq!(parsed.as_array().unwrap().len(), 1);
+        assert_eq!(parsed[0]["author_name"], "Alice");
+    }
+
+    #[test]
+    fn test_parse_ref_list_wasm() {
+        let raw = b"refs/heads/main\x00aaa\x00commit\x00\x00origin/main\x001700000000\x00*\n";
+        let parsed: serde_json::Value = serde_json::from_str(&parse_ref_list(raw)).unwrap();
+        assert_eq!(parsed[0]["shortName"], "main");
+        assert_eq!(parsed[0]["refType"], "Branch");
+        assert_eq!(parsed[0]["targetSha"], "aaa");
+        assert_eq!(parsed[0]["isHead"], true);
+    }
+
+    #[test]
+    fn test_parse_cat_file_wasm() {
+        let raw = b"aaa commit 64\ntree bbb\nauthor Alice <a@e.com> 1700000000 +0000\n\nSubject\n\nBody\n\nccc missing\n";
+        let parsed: serde_json::Value = serde_json::from_str(&parse_cat_file(raw)).unwrap();
+        assert_eq!(parsed[0]["type"], "commit");
+        assert_eq!(parsed[0]["author"]["tzOffsetMin"], 0);
+        assert_eq!(parsed[0]["body"], "Body");
+        assert_eq!(parsed[1]["type"], "missing");
+    }
+
+    #[test]
+    fn test_filter_commits_wasm() {
+        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1ebbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00Add feature\x00\x1e";
+        let result_json = compute_graph_layout(raw);
+        let parsed: serde_json::Value = serde_json::from_str(&result_json).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let filtered_json = filter_commits(handle, "author", "Alice");
+        let filtered: serde_json::Value = serde_json::from_str(&filtered_json).unwrap();
+        assert_eq!(filtered["totalCount"], 1);
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_commits_with_flags_wasm() {
+        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let filtered: serde_json::Value = serde_json::from_str(&filter_commits_with_flags(
+            handle,
+            "message",
+            "fix",
+            r#"{"case": "Smart"}"#,
+        ))
+        .unwrap();
+        assert_eq!(filtered["totalCount"], 1);
+
+        let filtered: serde_json::Value =
+            serde_json::from_str(&filter_commits_with_flags(handle, "message", "fix", ""))
+                .unwrap();
+        assert_eq!(filtered["totalCount"], 0);
+
+        let filtered: serde_json::Value = serde_json::from_str(&filter_commits_with_flags(
+            handle,
+            "author",
+            "Alice",
+            r#"{"negate": true}"#,
+        ))
+        .unwrap();
+        assert_eq!(filtered["totalCount"], 0);
+
+        let filtered: serde_json::Value = serde_json::from_str(&filter_commits_with_flags(
+            handle,
+            "message",
+            "*fix*",
+            r#"{"case": "Insensitive", "syntax": "Glob"}"#,
+        ))
+        .unwrap();
+        assert_eq!(filtered["totalCount"], 1);
+
+        let bad: serde_json::Value = serde_json::from_str(&filter_commits_with_flags(
+            handle,
+            "message",
+            "fix",
+            r#"{"case": "Loud"}"#,
+        ))
+        .unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_fuzzy_filter_commits_wasm() {
+        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1ebbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00Add feature\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let matches: serde_json::Value =
+            serde_json::from_str(&fuzzy_filter_commits(handle, "subject", "fb", "")).unwrap();
+        assert_eq!(matches.as_array().unwrap().len(), 1);
+        assert_eq!(matches[0]["node"]["sha"], "aaa");
+        assert_eq!(matches[0]["positions"], serde_json::json!([0, 4]));
+
+        let oldest_first: serde_json::Value = serde_json::from_str(
+            &fuzzy_filter_commits_sorted(handle, "subject", "u", "", "DateAsc"),
+        )
+        .unwrap();
+        assert_eq!(oldest_first.as_array().unwrap().len(), 2);
+        assert_eq!(oldest_first[0]["node"]["sha"], "bbb");
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&fuzzy_filter_commits(handle, "nope", "fb", "")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+        let bad: serde_json::Value = serde_json::from_str(&fuzzy_filter_commits_sorted(
+            handle, "subject", "u", "", "Random",
+        ))
+        .unwrap();
+        assert!(bad["error"]["message"].as_str().unwrap().starts_with("Invalid sort order"));
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_highlight_commits_wasm() {
+        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1ebbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00Add feature\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let matches: serde_json::Value =
+            serde_json::from_str(&highlight_commits(handle, "subject", "bug", "")).unwrap();
+        assert_eq!(matches.as_array().unwrap().len(), 1);
+        assert_eq!(matches[0]["sha"], "aaa");
+        assert_eq!(matches[0]["row"], 0);
+        assert_eq!(matches[0]["field"], "subject");
+        assert_eq!(matches[0]["ranges"], serde_json::json!([[4, 7]]));
+
+        let any: serde_json::Value =
+            serde_json::from_str(&highlight_commits(handle, "any", "bob", r#"{"case": "Smart"}"#))
+                .unwrap();
+        assert_eq!(any[0]["sha"], "bbb");
+        assert_eq!(any[0]["field"], "author");
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&highlight_commits(handle, "subject", "[", "")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_query_wasm() {
+        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1ebbb\x00bb\x00\x00Bob\x00b@e.com\x001699999000\x00Bob\x00b@e.com\x001699999000\x00Fix feature\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let filtered: serde_json::Value =
+            serde_json::from_str(&filter_query(handle, "fix AND NOT author:alice")).unwrap();
+        assert_eq!(filtered["totalCount"], 1);
+        assert_eq!(filtered["nodes"][0]["sha"], "bbb");
+
+        let bad: serde_json::Value = serde_json::from_str(&filter_query(handle, "(fix")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_query_with_options_wasm() {
+        let raw = b"ccc\x00cc\x00bbb\x00Alice\x00a@e.com\x001700000200\x00Alice\x00a@e.com\x001700000200\x00Fix again\x00\x1ebbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let plain: serde_json::Value =
+            serde_json::from_str(&filter_query_with_options(handle, "fix", "")).unwrap();
+        assert_eq!(plain["totalCount"], 2);
+        assert_eq!(plain["edges"].as_array().unwrap().len(), 0);
+
+        let connected: serde_json::Value = serde_json::from_str(&filter_query_with_options(
+            handle,
+            "fix",
+            r#"{"elideHidden": true}"#,
+        ))
+        .unwrap();
+        assert_eq!(connected["edges"][0]["fromSha"], "ccc");
+        assert_eq!(connected["edges"][0]["toSha"], "aaa");
+        assert_eq!(connected["edges"][0]["edgeType"], "Elided");
+
+        let context: serde_json::Value = serde_json::from_str(&filter_query_with_options(
+            handle,
+            "again",
+            r#"{"context": 1}"#,
+        ))
+        .unwrap();
+        assert_eq!(context["totalCount"], 2);
+        assert!(context["nodes"][0].get("isContext").is_none());
+        assert_eq!(context["nodes"][1]["isContext"], true);
+
+        let oldest_first: serde_json::Value = serde_json::from_str(&filter_query_with_options(
+            handle,
+            "fix",
+            r#"{"sort": "DateAsc"}"#,
+        ))
+        .unwrap();
+        assert_eq!(oldest_first["nodes"][0]["sha"], "aaa");
+        assert_eq!(oldest_first["nodes"][1]["sha"], "ccc");
+
+        assert_eq!(connected["nodes"][1]["row"], 2);
+        let compact: serde_json::Value = serde_json::from_str(&filter_query_with_options(
+            handle,
+            "fix",
+            r#"{"elideHidden": true, "compact": true}"#,
+        ))
+        .unwrap();
+        assert_eq!(compact["nodes"][1]["row"], 1);
+        assert_eq!(compact["edges"][0]["toRow"], 1);
+        assert_eq!(compact["edges"][0]["edgeType"], "Elided");
+
+        let bad: serde_json::Value = serde_json::from_str(&filter_query_with_options(
+            handle,
+            "fix",
+            r#"{"elideHidden": "yes"}"#,
+        ))
+        .unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+        let freed: serde_json::Value =
+            serde_json::from_str(&filter_query_with_options(handle, "fix", "")).unwrap();
+        assert_eq!(freed["error"]["code"], "INVALID_HANDLE");
+        assert_eq!(freed["error"]["details"]["handle"], handle);
+    }
+
+    #[test]
+    fn test_filter_by_path_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Docs\x00\x1e\n\nM\tREADME.md\n\
+aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Layout\x00\x1e\n\nA\tsrc/graph/layout.rs\n";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let filtered: serde_json::Value =
+            serde_json::from_str(&filter_by_path(handle, "src/graph/")).unwrap();
+        assert_eq!(filtered["totalCount"], 1);
+        assert_eq!(filtered["nodes"][0]["subject"], "Layout");
+
+        let bad: serde_json::Value = serde_json::from_str(&filter_by_path(handle, "")).unwrap();
+        assert_eq!(bad["error"]["code"], "BAD_ARGUMENT");
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_by_path(handle, "src/[z-a]")).unwrap();
+        assert_eq!(bad["error"]["code"], "BAD_REGEX");
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_by_shas_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Second\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00First\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let filtered: serde_json::Value =
+            serde_json::from_str(&filter_by_shas(handle, r#"["aaa", "bbb", "zzz"]"#)).unwrap();
+        assert_eq!(filtered["totalCount"], 2);
+        assert_eq!(filtered["edges"].as_array().unwrap().len(), 1);
+
+        let one: serde_json::Value =
+            serde_json::from_str(&filter_by_shas(handle, r#"["bb"]"#)).unwrap();
+        assert_eq!(one["nodes"][0]["subject"], "Second");
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_by_shas(handle, "aaa")).unwrap();
+        assert!(bad["error"]["message"].as_str().unwrap().starts_with("Invalid SHA list"));
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_by_size_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Rewrite\x00\x1e\n\n400\t200\tsrc/graph/layout.rs\n-\t-\tlogo.png\n\n\
+aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Typo\x00\x1e\n\n1\t1\tREADME.md\n";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+        assert_eq!(parsed["nodes"][0]["files"][0]["insertions"], 400);
+
+        let risky: serde_json::Value =
+            serde_json::from_str(&filter_by_size(handle, r#"{"minLines": 501}"#)).unwrap();
+        assert_eq!(risky["totalCount"], 1);
+        assert_eq!(risky["nodes"][0]["subject"], "Rewrite");
+
+        let one_file: serde_json::Value =
+            serde_json::from_str(&filter_by_size(handle, r#"{"maxFiles": 1}"#)).unwrap();
+        assert_eq!(one_file["nodes"][0]["subject"], "Typo");
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_by_size(handle, r#"{"minLines": -1}"#)).unwrap();
+        assert!(bad["error"]["message"].as_str().unwrap().starts_with("Invalid size filter"));
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_saved_filters_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Fix feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&save_filter("wasm-bob", "fix author:bob")).unwrap();
+        assert_eq!(saved["name"], "wasm-bob");
+        assert_eq!(saved["query"], "fix author:bob");
+
+        let filtered: serde_json::Value =
+            serde_json::from_str(&apply_saved_filter(handle, "wasm-bob")).unwrap();
+        assert_eq!(filtered["totalCount"], 1);
+        assert_eq!(filtered["nodes"][0]["subject"], "Fix feature");
+
+        let listed: serde_json::Value = serde_json::from_str(&list_filters()).unwrap();
+        assert!(listed.as_array().unwrap().iter().any(|f| f["name"] == "wasm-bob"));
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&save_filter("wasm-bad", "(fix")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        assert!(delete_filter("wasm-bob"));
+        let missing: serde_json::Value =
+            serde_json::from_str(&apply_saved_filter(handle, "wasm-bob")).unwrap();
+        assert_eq!(missing["error"]["message"], "Unknown filter: wasm-bob");
+        assert_eq!(missing["error"]["code"], "NOT_FOUND");
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_by_date_expr_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000000\x00Bob\x00b@e.com\x001700000000\x00Recent\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001690000000\x00Alice\x00a@e.com\x001690000000\x00Old\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+        let now = 1700086400;
+
+        let recent: serde_json::Value =
+            serde_json::from_str(&filter_by_date_expr(handle, "2 weeks ago", "", now, 0)).unwrap();
+        assert_eq!(recent["totalCount"], 1);
+        assert_eq!(recent["nodes"][0]["subject"], "Recent");
+
+        let old: serde_json::Value =
+            serde_json::from_str(&filter_by_date_expr(handle, "", "2023-08-01", now, 60)).unwrap();
+        assert_eq!(old["nodes"][0]["subject"], "Old");
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_by_date_expr(handle, "soon", "", now, 0)).unwrap();
+        assert_eq!(bad["error"]["message"], "Invalid date: 'soon'");
+        assert_eq!(bad["error"]["code"], "BAD_DATE");
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_releases_wasm() {
+        let raw = b"ccc\x00cc\x00bbb\x00Alice\x00a@e.com\x001700000200\x00Alice\x00a@e.com\x001700000200\x00Release 2\x00tag: v2.0\x1ebbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Work\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Release 1\x00tag: v1.0\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let view: serde_json::Value = serde_json::from_str(&filter_releases(handle, "")).unwrap();
+        assert_eq!(view["totalCount"], 2);
+        assert_eq!(view["edges"][0]["edgeType"], "Elided");
+        assert_eq!(view["releases"][0]["tags"], serde_json::json!(["v2.0"]));
+        assert_eq!(view["releases"][0]["commitCount"], 2);
+        assert_eq!(view["releases"][1]["commitCount"], 1);
+
+        let bad: serde_json::Value = serde_json::from_str(&filter_releases(handle, "(")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_query_with_facets_wasm() {
+        let raw = b"ccc\x00cc\x00bbb\x00Alice\x00a@e.com\x001700000200\x00Alice\x00a@e.com\x001700000200\x00Fix again\x00HEAD -> main\x1ebbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Fix feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Initial\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let view: serde_json::Value =
+            serde_json::from_str(&filter_query_with_facets(handle, "fix", "")).unwrap();
+        assert_eq!(view["totalCount"], 2);
+        assert_eq!(view["facets"]["authors"][0], serde_json::json!({"value": "Alice", "count": 1}));
+        assert_eq!(view["facets"]["months"][0], serde_json::json!({"value": "2023-11", "count": 2}));
+        assert_eq!(view["facets"]["refs"][0], serde_json::json!({"value": "main", "count": 2}));
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_query_with_facets(handle, "fix", "{")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_by_trailer_wasm() {
+        let format = r#"["%H", "%P", "%s", "%b"]"#;
+        let raw = b"bbb\x00aaa\x00Pair\x00Co-authored-by: Bob <b@e.com>\n\x1eaaa\x00\x00Root\x00Signed-off-by: Alice <a@e.com>\n\x1e";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_format(raw, format)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let unsigned: serde_json::Value = serde_json::from_str(&filter_by_trailer(
+            handle,
+            "signed-off-by",
+            "",
+            r#"{"negate": true}"#,
+        ))
+        .unwrap();
+        assert_eq!(unsigned["totalCount"], 1);
+        assert_eq!(unsigned["nodes"][0]["subject"], "Pair");
+
+        let paired: serde_json::Value =
+            serde_json::from_str(&filter_by_trailer(handle, "Co-authored-by", "Bob", "")).unwrap();
+        assert_eq!(paired["nodes"][0]["sha"], "bbb");
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_by_trailer(handle, "", "", "")).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_commits_in_date_range_wasm() {
+        let raw = b"ccc\x00cc\x00bbb\x00Alice\x00a@e.com\x001700000200\x00Alice\x00a@e.com\x001700000200\x00Fix again\x00\x1ebbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let recent: serde_json::Value = serde_json::from_str(&filter_commits_in_date_range(
+            handle, "author", "Alice", "", 1700000100, 0,
+        ))
+        .unwrap();
+        assert_eq!(recent["totalCount"], 1);
+        assert_eq!(recent["nodes"][0]["sha"], "ccc");
+
+        let old: serde_json::Value = serde_json::from_str(&filter_commits_in_date_range(
+            handle,
+            "author",
+            "Alice",
+            r#"{"negate": true}"#,
+            0,
+            1700000150,
+        ))
+        .unwrap();
+        assert_eq!(old["totalCount"], 1);
+        assert_eq!(old["nodes"][0]["sha"], "bbb");
+
+        let bad: serde_json::Value = serde_json::from_str(&filter_commits_in_date_range(
+            handle, "author", "(", "", 0, 0,
+        ))
+        .unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_by_signature_wasm() {
+        let format = r#"["%H", "%P", "%s", "%G?"]"#;
+        let raw = b"ccc\x00bbb\x00Signed\x00G\x1ebbb\x00aaa\x00Tampered\x00B\x1eaaa\x00\x00Plain\x00N\x1e";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_format(raw, format)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let unverified: serde_json::Value =
+            serde_json::from_str(&filter_by_signature(handle, "unverified")).unwrap();
+        assert_eq!(unverified["totalCount"], 2);
+        assert_eq!(unverified["edges"].as_array().unwrap().len(), 1);
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_by_signature(handle, "Bad")).unwrap();
+        assert_eq!(bad["nodes"][0]["subject"], "Tampered");
+
+        let unsigned: serde_json::Value =
+            serde_json::from_str(&filter_query(handle, "signature:unsigned")).unwrap();
+        assert_eq!(unsigned["nodes"][0]["subject"], "Plain");
+
+        let invalid: serde_json::Value =
+            serde_json::from_str(&filter_by_signature(handle, "trusted")).unwrap();
+        assert!(invalid["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_by_email_domain_wasm() {
+        let raw = b"bbb\x00bb\x00aaa\x00Bob\x00bob@gmail.com\x001700000100\x00Alice\x00alice@corp.example\x001700000100\x00Patch\x00\x1eaaa\x00aa\x00\x00Alice\x00alice@corp.example\x001700000000\x00Alice\x00alice@corp.example\x001700000000\x00Init\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+        let domains = r#"["corp.example"]"#;
+
+        let external: serde_json::Value =
+            serde_json::from_str(&filter_by_email_domain(handle, "author", domains, true))
+                .unwrap();
+        assert_eq!(external["totalCount"], 1);
+        assert_eq!(external["nodes"][0]["subject"], "Patch");
+
+        let either: serde_json::Value =
+            serde_json::from_str(&filter_by_email_domain(handle, "email", domains, false))
+                .unwrap();
+        assert_eq!(either["totalCount"], 2);
+
+        let bad: serde_json::Value =
+            serde_json::from_str(&filter_by_email_domain(handle, "sha", domains, false)).unwrap();
+        assert!(bad["error"]["message"].is_string());
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filtered_views_wasm() {
+        let raw = b"ccc\x00cc\x00bbb\x00Alice\x00a@e.com\x001700000200\x00Alice\x00a@e.com\x001700000200\x00Fix again\x00\x1ebbb\x00bb\x00aaa\x00Bob\x00b@e.com\x001700000100\x00Bob\x00b@e.com\x001700000100\x00Fix feature\x00\x1eaaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix bug\x00\x1e";
+        let parsed: serde_json::Value = serde_json::from_str(&compute_graph_layout(raw)).unwrap();
+        let root = parsed["handle"].as_u64().unwrap() as u32;
+
+        let fixes: serde_json::Value =
+            serde_json::from_str(&filter_commits_into(root, "subject", "^Fix")).unwrap();
+        let fixes_handle = fixes["handle"].as_u64().unwrap() as u32;
+        assert_ne!(fixes_handle, root);
+        assert_eq!(fixes["totalCount"], 3);
+
+        // Views stack: filter the view again
+        let alice: serde_json::Value =
+            serde_json::from_str(&filter_query_into(fixes_handle, "author:alice", "")).unwrap();
+        let alice_handle = alice["handle"].as_u64().unwrap() as u32;
+        assert_eq!(alice["totalCount"], 2);
+        let again: serde_json::Value =
+            serde_json::from_str(&filter_query(alice_handle, "again")).unwrap();
+        assert_eq!(again["totalCount"], 1);
+
+        let appended: serde_json::Value =
+            serde_json::from_str(&append_to_layout(alice_handle, raw)).unwrap();
+        assert!(appended["error"]["message"].is_string());
+
+        let popped: serde_json::Value =
+            serde_json::from_str(&pop_layout_view(alice_handle)).unwrap();
+        assert_eq!(popped["handle"], fixes_handle);
+        let gone: serde_json::Value =
+            serde_json::from_str(&filter_query(alice_handle, "")).unwrap();
+        assert!(gone["error"]["message"].is_string());
+
+        let not_view: serde_json::Value = serde_json::from_str(&pop_layout_view(root)).unwrap();
+        assert!(not_view["error"]["message"].is_string());
+
+        // Freeing the root frees the views stacked on it
+        let alice: serde_json::Value =
+            serde_json::from_str(&filter_query_into(fixes_handle, "author:alice", "")).unwrap();
+        let alice_handle = alice["handle"].as_u64().unwrap() as u32;
+        free_layout(root);
+        assert!(export_layout_columns(fixes_handle).is_err());
+        assert!(export_layout_columns(alice_handle).is_err());
+        let orphan: serde_json::Value =
+            serde_json::from_str(&filter_commits_into(fixes_handle, "subject", "^Fix")).unwrap();
+        assert_eq!(orphan["error"]["code"], "INVALID_HANDLE");
+    }
+
+    #[test]
+    fn test_filter_by_date_wasm() {
+        let raw = b"aaa\x00aa\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Recent\x00\x1ebbb\x00bb\x00\x00Bob\x00b@e.com\x001600000000\x00Bob\x00b@e.com\x001600000000\x00Old\x00\x1e";
         let result_json = compute_graph_layout(raw);
         let parsed: serde_json::Value = serde_json::from_str(&result_json).unwrap();
         let handle = parsed["handle"].as_u64().unwrap() as u32;
@@ -344,4 +4414,55 @@ mod tests {
 
         free_layout(handle);
     }
+
+    #[test]
+    fn test_parse_head_and_annotate_wasm() {
+        let head: serde_json::Value =
+            serde_json::from_str(&parse_head("ref: refs/heads/main\n")).unwrap();
+        assert_eq!(head["kind"], "symbolic");
+        assert_eq!(head["target"], "refs/heads/main");
+        let bad: serde_json::Value = serde_json::from_str(&parse_head("garbage")).unwrap();
+        assert!(bad.get("error").is_some());
+
+        let format = r#"["%H", "%P", "%s", "%d"]"#;
+        let raw = b"aaa\x00bbb\x00Tip\x00 (HEAD -> main)\x1ebbb\x00\x00Base\x00 (dev)\x1e";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_format(raw, format)).unwrap();
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let annotated: serde_json::Value =
+            serde_json::from_str(&annotate_layout_head(handle, "ref: refs/heads/dev")).unwrap();
+        assert_eq!(annotated["headFound"], true);
+        assert_eq!(annotated["nodes"][0]["nodeType"], "Normal");
+        assert_eq!(annotated["nodes"][1]["nodeType"], "Head");
+
+        free_layout(handle);
+    }
+
+    #[test]
+    fn test_filter_merges_wasm() {
+        let format = r#"["%H", "%P", "%s"]"#;
+        let raw = b"mmm\x00aaa bbb\x00Merge\x1ebbb\x00aaa\x00Topic\x1eaaa\x00\x00Root\x1e";
+        let parsed: serde_json::Value =
+            serde_json::from_str(&compute_graph_layout_with_format(raw, format)).unwrap();
+        assert_eq!(parsed["nodes"][0]["isMerge"], true);
+        assert_eq!(parsed["nodes"][0]["parentCount"], 2);
+        let handle = parsed["handle"].as_u64().unwrap() as u32;
+
+        let filtered: serde_json::Value =
+            serde_json::from_str(&filter_merges(handle, false)).unwrap();
+        assert_eq!(filtered["totalCount"], 2);
+        assert_eq!(filtered["nodes"][0]["subject"], "Topic");
+
+        // Combined with other conditions through the query language
+        let combined: serde_json::Value =
+            serde_json::from_str(&filter_query(handle, "is:nonmerge subject:/^R/")).unwrap();
+        assert_eq!(combined["totalCount"], 1);
+        assert_eq!(combined["nodes"][0]["subject"], "Root");
+
+        free_layout(handle);
+        let missing: serde_json::Value =
+            serde_json::from_str(&filter_merges(handle, true)).unwrap();
+        assert!(missing.get("error").is_some());
+    }
 }
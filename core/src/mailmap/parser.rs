@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use crate::blame::types::BlameEntry;
+use crate::graph::types::CommitNode;
+use crate::input::normalize_text;
+use crate::signature::Signature;
+
+/// One `.mailmap` rule, keyed in `Mailmap` by the lowercased commit email.
+#[derive(Debug, Clone)]
+struct MailmapEntry {
+    /// Only match commits with this name (case-insensitive) when set.
+    commit_name: Option<String>,
+    proper_name: Option<String>,
+    proper_email: Option<String>,
+}
+
+/// Parsed `.mailmap` contents used to canonicalize author and committer
+/// identities.
+///
+/// Supports the four forms documented in `gitmailmap(5)`:
+/// ```text
+/// Proper Name <commit@email>
+/// <proper@email> <commit@email>
+/// Proper Name <proper@email> <commit@email>
+/// Proper Name <proper@email> Commit Name <commit@email>
+/// ```
+/// Emails and names are matched case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct Mailmap {
+    entries: HashMap<String, Vec<MailmapEntry>>,
+}
+
+impl Mailmap {
+    /// Parse the contents of a `.mailmap` file. Malformed lines are skipped.
+    pub fn parse(contents: &str) -> Mailmap {
+        let mut mailmap = Mailmap::default();
+
+        for line in normalize_text(contents).lines() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (first_name, first_email, rest) = match split_identity(line) {
+                Some(parts) => parts,
+                None => continue,
+            };
+
+            let (key, entry) = match split_identity(rest) {
+                Some((commit_name, commit_email, _)) => (
+                    commit_email,
+                    MailmapEntry {
+                        commit_name: commit_name.map(str::to_string),
+                        proper_name: first_name.map(str::to_string),
+                        proper_email: Some(first_email.to_string()),
+                    },
+                ),
+                None => (
+                    first_email,
+                    MailmapEntry {
+                        commit_name: None,
+                        proper_name: first_name.map(str::to_string),
+                        proper_email: None,
+                    },
+                ),
+            };
+
+            mailmap
+                .entries
+                .entry(key.to_lowercase())
+                .or_default()
+                .push(entry);
+        }
+
+        mailmap
+    }
+
+    /// Number of rules loaded.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Resolve a commit identity to its canonical `(name, email)`.
+    ///
+    /// A rule that also names the commit author wins over an email-only rule.
+    pub fn resolve(&self, name: &str, email: &str) -> (String, String) {
+        let candidates = match self.entries.get(&email.to_lowercase()) {
+            Some(c) => c,
+            None => return (name.to_string(), email.to_string()),
+        };
+
+        let by_name = candidates.iter().rev().find(|e| {
+            e.commit_name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        });
+        let entry = by_name.or_else(|| candidates.iter().rev().find(|e| e.commit_name.is_none()));
+
+        match entry {
+            Some(e) => (
+                e.proper_name.clone().unwrap_or_else(|| name.to_string()),
+                e.proper_email.clone().unwrap_or_else(|| email.to_string()),
+            ),
+            None => (name.to_string(), email.to_string()),
+        }
+    }
+
+    /// Canonicalize the name and email of a signature in place.
+    pub fn resolve_signature(&self, signature: &mut Signature) {
+        (signature.name, signature.email) = self.resolve(&signature.name, &signature.email);
+    }
+
+    /// Canonicalize author and committer identities of parsed commits.
+    pub fn apply_to_commits(&self, commits: &mut [CommitNode]) {
+        if self.is_empty() {
+            return;
+        }
+        for commit in commits {
+            self.resolve_signature(&mut commit.author);
+            self.resolve_signature(&mut commit.committer);
+        }
+    }
+
+    /// Canonicalize author and committer identities of blame entries.
+    pub fn apply_to_blame(&self, entries: &mut [BlameEntry]) {
+        if self.is_empty() {
+            return;
+        }
+        for entry in entries {
+            self.resolve_signature(&mut entry.author);
+            self.resolve_signature(&mut entry.committer);
+        }
+    }
+}
+
+/// Drop a trailing `# comment`, ignoring `#` inside `<email>`.
+fn strip_comment(line: &str) -> &str {
+    let mut in_email = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '<' => in_email = true,
+            '>' => in_email = false,
+            '#' if !in_email => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Split `Name <email> rest` into (optional name, email, rest).
+fn split_identity(s: &str) -> Option<(Option<&str>, &str, &str)> {
+    let open = s.find('<')?;
+    let close = open + s[open..].find('>')?;
+    let name = s[..open].trim();
+    let email = s[open + 1..close].trim();
+    if email.is_empty() {
+        return None;
+    }
+    let name = if name.is_empty() { None } else { Some(name) };
+    Some((name, email, &s[close + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAILMAP: &str = "\
+# Canonical identities
+Alice Smith <alice@example.com>
+<bob@example.com> <bob@old-laptop.local>
+Charlie Lee <charlie@example.com> <chuck@example.com>
+Diana Wu <diana@example.com> diana <shared@example.com>
+Eve Ops <eve@example.com> <shared@example.com> # catch-all
+";
+
+    #[test]
+    fn test_parse_counts_entries() {
+        let mailmap = Mailmap::parse(MAILMAP);
+        assert_eq!(mailmap.len(), 5);
+        assert!(Mailmap::parse("# only a comment\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_name_only() {
+        let mailmap = Mailmap::parse(MAILMAP);
+        assert_eq!(
+            mailmap.resolve("alice", "Alice@Example.com"),
+            ("Alice Smith".to_string(), "Alice@Example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_email_only() {
+        let mailmap = Mailmap::parse(MAILMAP);
+        assert_eq!(
+            mailmap.resolve("Bob", "bob@old-laptop.local"),
+            ("Bob".to_string(), "bob@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_name_and_email() {
+        let mailmap = Mailmap::parse(MAILMAP);
+        assert_eq!(
+            mailmap.resolve("Chuck", "chuck@example.com"),
+            ("Charlie Lee".to_string(), "charlie@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_prefers_commit_name_match() {
+        let mailmap = Mailmap::parse(MAILMAP);
+        assert_eq!(
+            mailmap.resolve("Diana", "shared@example.com"),
+            ("Diana Wu".to_string(), "diana@example.com".to_string())
+        );
+        assert_eq!(
+            mailmap.resolve("someone", "shared@example.com"),
+            ("Eve Ops".to_string(), "eve@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_unknown_identity_unchanged() {
+        let mailmap = Mailmap::parse(MAILMAP);
+        assert_eq!(
+            mailmap.resolve("Frank", "frank@example.com"),
+            ("Frank".to_string(), "frank@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_to_commits_and_blame() {
+        let mailmap = Mailmap::parse(MAILMAP);
+
+        let mut commits = vec![CommitNode {
+            author: Signature {
+                name: "Chuck".to_string(),
+                email: "chuck@example.com".to_string(),
+                ..Default::default()
+            },
+            committer: Signature {
+                name: "bob".to_string(),
+                email: "bob@old-laptop.local".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }];
+        mailmap.apply_to_commits(&mut commits);
+        assert_eq!(commits[0].author.name, "Charlie Lee");
+        assert_eq!(commits[0].committer.email, "bob@example.com");
+
+        let raw = b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\nauthor Chuck\nauthor-mail <chuck@example.com>\ncommitter Chuck\ncommitter-mail <chuck@example.com>\nfilename a.rs\n";
+        let mut entries = crate::blame::parse_blame_output(raw);
+        mailmap.apply_to_blame(&mut entries);
+        assert_eq!(entries[0].author.name, "Charlie Lee");
+        assert_eq!(entries[0].committer.email, "charlie@example.com");
+    }
+}
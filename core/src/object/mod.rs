@@ -0,0 +1,5 @@
+pub mod types;
+pub mod parser;
+
+pub use types::*;
+pub use parser::parse_cat_file_batch;
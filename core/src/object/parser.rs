@@ -0,0 +1,333 @@
+use super::types::{CommitObject, GitObject, TagObject, TreeEntry, TreeObject};
+use crate::signature::{parse_tz_offset, Signature};
+
+/// Parse `git cat-file --batch` output into a Vec<GitObject>.
+///
+/// Each object is a header line followed by its raw contents and a newline:
+/// ```text
+/// <sha> <type> <size>\n
+/// <size bytes of contents>\n
+/// ```
+/// Names that do not resolve produce `<name> missing\n` (or `ambiguous`)
+/// with no contents. Parsing stops at the first truncated object, so a
+/// partially read batch yields every object that arrived in full.
+pub fn parse_cat_file_batch(raw: &[u8]) -> Vec<GitObject> {
+    let mut objects = Vec::new();
+    let mut pos = 0;
+
+    while pos < raw.len() {
+        let header_end = match raw[pos..].iter().position(|&b| b == b'\n') {
+            Some(i) => pos + i,
+            None => break,
+        };
+        let header = String::from_utf8_lossy(&raw[pos..header_end]);
+        pos = header_end + 1;
+
+        let parts: Vec<&str> = header.split_whitespace().collect();
+        match parts.as_slice() {
+            [] => continue,
+            [sha, "missing" | "ambiguous"] => {
+                objects.push(GitObject::Missing {
+                    sha: sha.to_string(),
+                });
+            }
+            [sha, kind, size] => {
+                let size: usize = match size.parse() {
+                    Ok(s) => s,
+                    Err(_) => break,
+                };
+                let end = match pos.checked_add(size).filter(|&end| end <= raw.len()) {
+                    Some(end) => end,
+                    None => break,
+                };
+                let contents = &raw[pos..end];
+                // Contents are followed by a single newline
+                pos = end + 1;
+
+                objects.push(match *kind {
+                    "commit" => GitObject::Commit(parse_commit(sha, contents)),
+                    "tag" => GitObject::Tag(parse_tag(sha, contents)),
+                    "tree" => GitObject::Tree(parse_tree(sha, contents)),
+                    _ => GitObject::Blob {
+                        sha: sha.to_string(),
+                        size,
+                    },
+                });
+            }
+            _ => break,
+        }
+    }
+
+    objects
+}
+
+/// Split raw object text into header lines and the message after the first
+/// blank line. Continuation lines (leading space) are folded into the
+/// preceding header's value, newline-separated.
+fn split_headers(text: &str) -> (Vec<(&str, String)>, &str) {
+    let (head, message) = match text.find("\n\n") {
+        Some(i) => (&text[..i], &text[i + 2..]),
+        None => (text.trim_end_matches('\n'), ""),
+    };
+
+    let mut headers: Vec<(&str, String)> = Vec::new();
+    for line in head.lines() {
+        if let Some(continuation) = line.strip_prefix(' ') {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push('\n');
+                value.push_str(continuation);
+            }
+            continue;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        headers.push((key, value.to_string()));
+    }
+
+    (headers, message)
+}
+
+fn parse_commit(sha: &str, contents: &[u8]) -> CommitObject {
+    parse_commit_text(sha, &String::from_utf8_lossy(contents))
+}
+
+/// Parse the text of a raw commit object: headers, a blank line, then the
+/// message.
+pub(crate) fn parse_commit_text(sha: &str, text: &str) -> CommitObject {
+    let (headers, message) = split_headers(text);
+
+    let mut commit = CommitObject {
+        sha: sha.to_string(),
+        ..Default::default()
+    };
+
+    for (key, value) in headers {
+        match key {
+            "tree" => commit.tree = value,
+            "parent" => commit.parents.push(value),
+            "author" => commit.author = parse_identity(&value),
+            "committer" => commit.committer = parse_identity(&value),
+            "encoding" => commit.encoding = Some(value),
+            "gpgsig" | "gpgsig-sha256" => commit.signature = Some(value),
+            _ => {}
+        }
+    }
+
+    let message = message.trim_end();
+    let (subject, body) = message.split_once("\n\n").unwrap_or((message, ""));
+    commit.subject = subject.lines().collect::<Vec<_>>().join(" ");
+    commit.body = body.to_string();
+    commit
+}
+
+fn parse_tag(sha: &str, contents: &[u8]) -> TagObject {
+    let text = String::from_utf8_lossy(contents);
+    let (headers, message) = split_headers(&text);
+
+    let mut tag = TagObject {
+        sha: sha.to_string(),
+        message: message.trim_end().to_string(),
+        ..Default::default()
+    };
+
+    for (key, value) in headers {
+        match key {
+            "object" => tag.object = value,
+            "type" => tag.target_type = value,
+            "tag" => tag.name = value,
+            "tagger" => tag.tagger = Some(parse_identity(&value)),
+            _ => {}
+        }
+    }
+
+    tag
+}
+
+/// Parse binary tree entries: `<mode> <name>\0<raw hash>`.
+///
+/// The hash width (20 bytes for SHA-1, 32 for SHA-256) follows from the
+/// length of the tree's own hex SHA.
+fn parse_tree(sha: &str, contents: &[u8]) -> TreeObject {
+    let hash_len = if sha.len() == 64 { 32 } else { 20 };
+    let mut entries = Vec::new();
+    let mut pos = 0;
+
+    while pos < contents.len() {
+        let nul = match contents[pos..].iter().position(|&b| b == 0) {
+            Some(i) => pos + i,
+            None => break,
+        };
+        if nul + 1 + hash_len > contents.len() {
+            break;
+        }
+        let entry = String::from_utf8_lossy(&contents[pos..nul]);
+        let (mode, name) = entry.split_once(' ').unwrap_or(("", &entry));
+        let hash = &contents[nul + 1..nul + 1 + hash_len];
+
+        entries.push(TreeEntry {
+            mode: mode.to_string(),
+            name: name.to_string(),
+            sha: hash.iter().map(|b| format!("{:02x}", b)).collect(),
+        });
+        pos = nul + 1 + hash_len;
+    }
+
+    TreeObject {
+        sha: sha.to_string(),
+        entries,
+    }
+}
+
+/// Parse `Name <email> <epoch> <+hhmm>`.
+fn parse_identity(value: &str) -> Signature {
+    let (name, rest) = match value.find('<') {
+        Some(i) => (value[..i].trim(), &value[i + 1..]),
+        None => {
+            return Signature {
+                name: value.trim().to_string(),
+                ..Default::default()
+            }
+        }
+    };
+    let (email, rest) = rest.split_once('>').unwrap_or((rest, ""));
+    let mut parts = rest.split_whitespace();
+    let time = parts.next().and_then(|t| t.parse().ok()).unwrap_or(0);
+    let tz_offset_min = parts.next().map(parse_tz_offset).unwrap_or(0);
+
+    Signature {
+        name: name.to_string(),
+        email: email.to_string(),
+        time,
+        tz_offset_min,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch_object(sha: &str, kind: &str, contents: &[u8]) -> Vec<u8> {
+        let mut out = format!("{} {} {}\n", sha, kind, contents.len()).into_bytes();
+        out.extend_from_slice(contents);
+        out.push(b'\n');
+        out
+    }
+
+    const COMMIT: &str = "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+parent 1111111111111111111111111111111111111111\n\
+parent 2222222222222222222222222222222222222222\n\
+author Alice Smith <alice@example.com> 1700000000 +0130\n\
+committer Bob Jones <bob@example.com> 1700000100 -0800\n\
+gpgsig -----BEGIN PGP SIGNATURE-----\n \n iQEzBAAB\n -----END PGP SIGNATURE-----\n\
+\n\
+Merge branch 'feature'\n\
+\n\
+Brings in the blame rewrite.\n\
+\n\
+Signed-off-by: Alice Smith <alice@example.com>\n";
+
+    #[test]
+    fn test_parse_commit_object() {
+        let raw = batch_object("aaa", "commit", COMMIT.as_bytes());
+        let objects = parse_cat_file_batch(&raw);
+        assert_eq!(objects.len(), 1);
+
+        let commit = match &objects[0] {
+            GitObject::Commit(c) => c,
+            other => panic!("expected commit, got {:?}", other),
+        };
+        assert_eq!(commit.tree, "4b825dc642cb6eb9a060e54bf8d69288fbee4904");
+        assert_eq!(commit.parents.len(), 2);
+        assert_eq!(commit.author.name, "Alice Smith");
+        assert_eq!(commit.author.email, "alice@example.com");
+        assert_eq!(commit.author.time, 1700000000);
+        assert_eq!(commit.author.tz_offset_min, 90);
+        assert_eq!(commit.committer.tz_offset_min, -480);
+        assert_eq!(
+            commit.signature.as_deref(),
+            Some("-----BEGIN PGP SIGNATURE-----\n\niQEzBAAB\n-----END PGP SIGNATURE-----")
+        );
+        assert_eq!(commit.subject, "Merge branch 'feature'");
+        assert_eq!(
+            commit.body,
+            "Brings in the blame rewrite.\n\nSigned-off-by: Alice Smith <alice@example.com>"
+        );
+    }
+
+    #[test]
+    fn test_parse_tag_and_missing() {
+        let tag = "object aaa\ntype commit\ntag v1.0\ntagger Alice <alice@example.com> 1700000500 +0000\n\nRelease 1.0\n\nFirst stable release.\n";
+        let mut raw = batch_object("ttt", "tag", tag.as_bytes());
+        raw.extend_from_slice(b"nosuchref missing\n");
+
+        let objects = parse_cat_file_batch(&raw);
+        assert_eq!(objects.len(), 2);
+        match &objects[0] {
+            GitObject::Tag(t) => {
+                assert_eq!(t.object, "aaa");
+                assert_eq!(t.target_type, "commit");
+                assert_eq!(t.name, "v1.0");
+                assert_eq!(t.tagger.as_ref().unwrap().time, 1700000500);
+                assert_eq!(t.message, "Release 1.0\n\nFirst stable release.");
+            }
+            other => panic!("expected tag, got {:?}", other),
+        }
+        assert_eq!(
+            objects[1],
+            GitObject::Missing {
+                sha: "nosuchref".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_tree_and_blob() {
+        let mut tree = b"100644 README.md\x00".to_vec();
+        tree.extend_from_slice(&[0xab; 20]);
+        tree.extend_from_slice(b"40000 src\x00");
+        tree.extend_from_slice(&[0x01; 20]);
+
+        let mut raw = batch_object("4b825dc642cb6eb9a060e54bf8d69288fbee4904", "tree", &tree);
+        raw.extend(batch_object("bbb", "blob", b"binary\x00\ncontents"));
+
+        let objects = parse_cat_file_batch(&raw);
+        assert_eq!(objects.len(), 2);
+        match &objects[0] {
+            GitObject::Tree(t) => {
+                assert_eq!(t.entries.len(), 2);
+                assert_eq!(t.entries[0].mode, "100644");
+                assert_eq!(t.entries[0].name, "README.md");
+                assert_eq!(t.entries[0].sha, "ab".repeat(20));
+                assert_eq!(t.entries[1].name, "src");
+            }
+            other => panic!("expected tree, got {:?}", other),
+        }
+        assert_eq!(
+            objects[1],
+            GitObject::Blob {
+                sha: "bbb".to_string(),
+                size: 16
+            }
+        );
+    }
+
+    #[test]
+    fn test_truncated_object_is_dropped() {
+        let mut raw = batch_object("aaa", "commit", COMMIT.as_bytes());
+        let second = batch_object("bbb", "commit", COMMIT.as_bytes());
+        raw.extend_from_slice(&second[..second.len() / 2]);
+
+        let objects = parse_cat_file_batch(&raw);
+        assert_eq!(objects.len(), 1);
+    }
+
+    #[test]
+    fn test_huge_size_header_is_truncation() {
+        for size in [usize::MAX, usize::MAX - 8] {
+            let mut raw = batch_object("aaa", "commit", COMMIT.as_bytes());
+            raw.extend_from_slice(format!("bbb blob {}\nxyz\n", size).as_bytes());
+
+            let objects = parse_cat_file_batch(&raw);
+            assert_eq!(objects.len(), 1);
+        }
+    }
+}
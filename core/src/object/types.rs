@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::signature::Signature;
+
+/// A raw commit object.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitObject {
+    pub sha: String,
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: Signature,
+    pub committer: Signature,
+    /// Value of the `encoding` header, if the commit is not UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Armored `gpgsig` block, without the header continuation spaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub subject: String,
+    pub body: String,
+}
+
+/// A raw annotated tag object.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TagObject {
+    pub sha: String,
+    /// SHA of the tagged object.
+    pub object: String,
+    /// Type of the tagged object, usually `commit`.
+    pub target_type: String,
+    pub name: String,
+    /// Absent on some very old tags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tagger: Option<Signature>,
+    pub message: String,
+}
+
+/// One entry of a tree object.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeEntry {
+    /// Octal mode as written by git, e.g. `100644` or `40000`.
+    pub mode: String,
+    pub name: String,
+    pub sha: String,
+}
+
+/// A raw tree object.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeObject {
+    pub sha: String,
+    pub entries: Vec<TreeEntry>,
+}
+
+/// An object from `git cat-file --batch`, tagged by `type` in JSON.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GitObject {
+    Commit(CommitObject),
+    Tag(TagObject),
+    Tree(TreeObject),
+    /// Blob contents are not kept; only the size is reported.
+    Blob { sha: String, size: usize },
+    /// The requested name did not resolve to an object.
+    Missing { sha: String },
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use crate::schema;
+
+/// Encodings the `_encoded` exports can return their result in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// UTF-8 JSON, byte for byte what the string exports return.
+    #[default]
+    Json,
+    /// MessagePack with the same keys and shape as the JSON, so a decoder
+    /// such as `@msgpack/msgpack` yields the same object `JSON.parse` would,
+    /// without building and scanning a string.
+    MessagePack,
+}
+
+impl OutputFormat {
+    /// Parse a format name, without regard to case: "json" or "msgpack"
+    /// (also "messagepack"). An empty name is JSON.
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "" | "json" => Some(OutputFormat::Json),
+            "msgpack" | "messagepack" => Some(OutputFormat::MessagePack),
+            _ => None,
+        }
+    }
+}
+
+/// Encode `value` in `format`, with keys in the given output schema
+/// version.
+pub(crate) fn encode<T: Serialize>(
+    value: &T,
+    format: OutputFormat,
+    schema_version: u32,
+) -> Result<Vec<u8>, String> {
+    match format {
+        OutputFormat::Json => schema::to_json_string(value, schema_version)
+            .map(String::into_bytes)
+            .map_err(|e| e.to_string()),
+        OutputFormat::MessagePack if schema_version == schema::LEGACY => {
+            rmp_serde::to_vec_named(value).map_err(|e| e.to_string())
+        }
+        OutputFormat::MessagePack => {
+            let mut value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+            schema::camel_case_keys(&mut value);
+            rmp_serde::to_vec_named(&value).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::types::{LayoutNode, LayoutResult, NodeType};
+
+    #[test]
+    fn test_output_format_names() {
+        assert_eq!(OutputFormat::from_name(""), Some(OutputFormat::Json));
+        assert_eq!(OutputFormat::from_name("MsgPack"), Some(OutputFormat::MessagePack));
+        assert_eq!(OutputFormat::from_name("bincode"), None);
+    }
+
+    #[test]
+    fn test_msgpack_matches_json() {
+        let layout = LayoutResult {
+            nodes: vec![LayoutNode {
+                sha: "aaa".to_string(),
+                node_type: NodeType::Head,
+                author_date: 1_700_000_000,
+                ..Default::default()
+            }],
+            edges: vec![],
+            total_count: 1,
+        };
+        for schema_version in [schema::LEGACY, schema::CAMEL_CASE] {
+            let json = encode(&layout, OutputFormat::Json, schema_version).unwrap();
+            let packed = encode(&layout, OutputFormat::MessagePack, schema_version).unwrap();
+            assert!(packed.len() < json.len());
+
+            let from_json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+            let from_packed: serde_json::Value = rmp_serde::from_slice(&packed).unwrap();
+            assert_eq!(from_packed, from_json);
+        }
+    }
+}
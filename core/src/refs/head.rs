@@ -0,0 +1,174 @@
+use super::types::HeadState;
+use crate::graph::sha::is_full_sha;
+use crate::input::normalize_text;
+use crate::graph::types::{LayoutResult, NodeType, RefInfo, RefType};
+
+/// Parse the contents of `.git/HEAD`.
+///
+/// A checked-out branch is stored as `ref: refs/heads/<name>`; a detached
+/// HEAD holds the commit SHA. Returns `None` for anything else.
+pub fn parse_head(contents: &str) -> Option<HeadState> {
+    let contents = normalize_text(contents);
+    let contents = contents.trim();
+    if let Some(target) = contents.strip_prefix("ref:") {
+        let target = target.trim();
+        if target.is_empty() {
+            return None;
+        }
+        return Some(HeadState::Symbolic {
+            target: target.to_string(),
+        });
+    }
+    if is_full_sha(contents) {
+        return Some(HeadState::Detached {
+            sha: contents.to_ascii_lowercase(),
+        });
+    }
+    None
+}
+
+/// Mark the commit HEAD points at in a layout, replacing any HEAD markers
+/// that came from `%d` decorations.
+///
+/// For a symbolic HEAD the node carrying the branch gets a `HEAD` ref and
+/// its branch ref is flagged `is_head`; for a detached HEAD the node with
+/// that SHA is marked. Returns false if the commit is not in the layout,
+/// e.g. an unborn branch or a commit outside the loaded pages.
+pub fn annotate_head(layout: &mut LayoutResult, head: &HeadState) -> bool {
+    for node in &mut layout.nodes {
+        node.refs.retain(|r| r.ref_type != RefType::Head);
+        for r in &mut node.refs {
+            r.is_head = false;
+        }
+        if node.node_type == NodeType::Head {
+            node.node_type = if node.refs.iter().any(|r| r.ref_type == RefType::Stash) {
+                NodeType::Stash
+            } else {
+                NodeType::Normal
+            };
+        }
+    }
+
+    let target = match head {
+        HeadState::Symbolic { target } => {
+            let branch = match target.strip_prefix("refs/heads/") {
+                Some(branch) => branch,
+                None => return false,
+            };
+            layout.nodes.iter_mut().find_map(|node| {
+                let r = node
+                    .refs
+                    .iter_mut()
+                    .find(|r| r.ref_type == RefType::Branch && r.name == branch)?;
+                r.is_head = true;
+                Some(node)
+            })
+        }
+        HeadState::Detached { sha } => layout.nodes.iter_mut().find(|node| node.sha == *sha),
+    };
+
+    match target {
+        Some(node) => {
+            node.refs.insert(
+                0,
+                RefInfo {
+                    name: "HEAD".to_string(),
+                    ref_type: RefType::Head,
+                    is_head: true,
+                },
+            );
+            node.node_type = NodeType::Head;
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::compute_layout;
+    use crate::graph::types::CommitNode;
+
+    const TIP: &str = "1111111111111111111111111111111111111111";
+    const BASE: &str = "2222222222222222222222222222222222222222";
+
+    fn make_test_layout() -> LayoutResult {
+        compute_layout(&[
+            CommitNode {
+                sha: TIP.to_string(),
+                parents: vec![BASE.to_string()],
+                refs: vec![
+                    RefInfo {
+                        name: "HEAD".to_string(),
+                        ref_type: RefType::Head,
+                        is_head: true,
+                    },
+                    RefInfo {
+                        name: "main".to_string(),
+                        ref_type: RefType::Branch,
+                        is_head: true,
+                    },
+                ],
+                ..Default::default()
+            },
+            CommitNode {
+                sha: BASE.to_string(),
+                refs: vec![RefInfo {
+                    name: "release".to_string(),
+                    ref_type: RefType::Branch,
+                    is_head: false,
+                }],
+                ..Default::default()
+            },
+        ])
+    }
+
+    #[test]
+    fn test_parse_head() {
+        assert_eq!(
+            parse_head("ref: refs/heads/main\n"),
+            Some(HeadState::Symbolic {
+                target: "refs/heads/main".to_string()
+            })
+        );
+        assert_eq!(
+            parse_head(&format!("{}\n", TIP)),
+            Some(HeadState::Detached {
+                sha: TIP.to_string()
+            })
+        );
+        assert_eq!(parse_head("ref:"), None);
+        assert_eq!(parse_head("abc123"), None);
+        assert_eq!(parse_head(""), None);
+    }
+
+    #[test]
+    fn test_annotate_symbolic_head_moves_marker() {
+        let mut layout = make_test_layout();
+        assert_eq!(layout.nodes[0].node_type, NodeType::Head);
+
+        let head = parse_head("ref: refs/heads/release").unwrap();
+        assert!(annotate_head(&mut layout, &head));
+
+        assert_eq!(layout.nodes[0].node_type, NodeType::Normal);
+        assert!(layout.nodes[0].refs.iter().all(|r| !r.is_head));
+        assert_eq!(layout.nodes[1].node_type, NodeType::Head);
+        assert_eq!(layout.nodes[1].refs[0].name, "HEAD");
+        assert!(layout.nodes[1].refs[1].is_head);
+    }
+
+    #[test]
+    fn test_annotate_detached_head() {
+        let mut layout = make_test_layout();
+        let head = parse_head(BASE).unwrap();
+        assert!(annotate_head(&mut layout, &head));
+        assert_eq!(layout.nodes[1].node_type, NodeType::Head);
+        assert!(!layout.nodes[1].refs[1].is_head);
+
+        // A HEAD outside the loaded commits clears the stale markers
+        let head = parse_head(&"3".repeat(40)).unwrap();
+        assert!(!annotate_head(&mut layout, &head));
+        assert!(layout.nodes.iter().all(|n| n.node_type == NodeType::Normal));
+    }
+}
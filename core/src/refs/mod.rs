@@ -0,0 +1,7 @@
+pub mod types;
+pub mod parser;
+pub mod head;
+
+pub use types::*;
+pub use parser::parse_for_each_ref;
+pub use head::{annotate_head, parse_head};
@@ -0,0 +1,159 @@
+use crate::graph::types::RefType;
+
+use super::types::RefEntry;
+use crate::input::normalize_input;
+
+/// Parse `git for-each-ref` output into a Vec<RefEntry>.
+///
+/// Expects one ref per line, with NUL-separated fields produced by:
+/// ```text
+/// %(refname)%00%(objectname)%00%(objecttype)%00%(*objectname)%00%(upstream:short)%00%(creatordate:unix)%00%(HEAD)
+/// ```
+/// Only the first three fields are required. Refs outside `refs/heads`,
+/// `refs/remotes`, `refs/tags` and `refs/stash` are skipped, as are
+/// symbolic refs such as `refs/remotes/origin/HEAD`.
+pub fn parse_for_each_ref(raw: &[u8]) -> Vec<RefEntry> {
+    let raw = normalize_input(raw);
+    let input = String::from_utf8_lossy(&raw);
+
+    input
+        .lines()
+        .filter_map(parse_ref_line)
+        .collect()
+}
+
+fn parse_ref_line(line: &str) -> Option<RefEntry> {
+    let fields: Vec<&str> = line.split('\x00').collect();
+    if fields.len() < 3 {
+        return None;
+    }
+    let field = |i: usize| fields.get(i).map(|f| f.trim()).unwrap_or("");
+
+    let name = field(0);
+    let object_sha = field(1);
+    if name.is_empty() || object_sha.is_empty() {
+        return None;
+    }
+
+    let (ref_type, short_name) = classify_ref(name)?;
+    if ref_type == RefType::RemoteBranch && short_name.ends_with("/HEAD") {
+        return None;
+    }
+
+    let peeled = field(3);
+    let upstream = field(4);
+
+    Some(RefEntry {
+        name: name.to_string(),
+        short_name: short_name.to_string(),
+        ref_type,
+        target_sha: if peeled.is_empty() { object_sha } else { peeled }.to_string(),
+        object_sha: object_sha.to_string(),
+        object_type: field(2).to_string(),
+        upstream: if upstream.is_empty() {
+            None
+        } else {
+            Some(upstream.to_string())
+        },
+        created: field(5).parse().unwrap_or(0),
+        is_head: field(6) == "*",
+    })
+}
+
+/// Map a full ref name to its type and short name.
+fn classify_ref(name: &str) -> Option<(RefType, &str)> {
+    if let Some(short) = name.strip_prefix("refs/heads/") {
+        Some((RefType::Branch, short))
+    } else if let Some(short) = name.strip_prefix("refs/remotes/") {
+        Some((RefType::RemoteBranch, short))
+    } else if let Some(short) = name.strip_prefix("refs/tags/") {
+        Some((RefType::Tag, short))
+    } else if name == "refs/stash" {
+        Some((RefType::Stash, "stash"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA_A: &str = "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2";
+    const SHA_B: &str = "b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3";
+    const SHA_T: &str = "c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4";
+
+    fn line(fields: &[&str]) -> String {
+        fields.join("\x00") + "\n"
+    }
+
+    #[test]
+    fn test_parse_branches_and_remotes() {
+        let raw = [
+            line(&["refs/heads/main", SHA_A, "commit", "", "origin/main", "1700000000", "*"]),
+            line(&["refs/heads/feature/x", SHA_B, "commit", "", "", "1700000100", " "]),
+            line(&["refs/remotes/origin/HEAD", SHA_A, "commit", "", "", "1700000000", " "]),
+            line(&["refs/remotes/origin/main", SHA_A, "commit", "", "", "1700000000", " "]),
+        ]
+        .concat();
+        let refs = parse_for_each_ref(raw.as_bytes());
+        assert_eq!(refs.len(), 3);
+
+        assert_eq!(refs[0].short_name, "main");
+        assert_eq!(refs[0].ref_type, RefType::Branch);
+        assert_eq!(refs[0].upstream.as_deref(), Some("origin/main"));
+        assert_eq!(refs[0].created, 1700000000);
+        assert!(refs[0].is_head);
+
+        assert_eq!(refs[1].short_name, "feature/x");
+        assert_eq!(refs[1].upstream, None);
+        assert!(!refs[1].is_head);
+
+        assert_eq!(refs[2].ref_type, RefType::RemoteBranch);
+        assert_eq!(refs[2].short_name, "origin/main");
+    }
+
+    #[test]
+    fn test_annotated_tag_targets_peeled_commit() {
+        let raw = [
+            line(&["refs/tags/v1.0", SHA_T, "tag", SHA_A, "", "1700000500"]),
+            line(&["refs/tags/v0.9", SHA_B, "commit", "", "", "1690000000"]),
+        ]
+        .concat();
+        let refs = parse_for_each_ref(raw.as_bytes());
+
+        assert_eq!(refs[0].ref_type, RefType::Tag);
+        assert_eq!(refs[0].object_sha, SHA_T);
+        assert_eq!(refs[0].object_type, "tag");
+        assert_eq!(refs[0].target_sha, SHA_A);
+        assert_eq!(refs[1].target_sha, SHA_B);
+    }
+
+    #[test]
+    fn test_minimal_fields_and_skipped_refs() {
+        let raw = [
+            line(&["refs/stash", SHA_A, "commit"]),
+            line(&["refs/notes/commits", SHA_B, "commit"]),
+            line(&["refs/heads/broken"]),
+            "\n".to_string(),
+        ]
+        .concat();
+        let refs = parse_for_each_ref(raw.as_bytes());
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].ref_type, RefType::Stash);
+        assert_eq!(refs[0].short_name, "stash");
+        assert_eq!(refs[0].created, 0);
+    }
+
+    #[test]
+    fn test_crlf_and_bom() {
+        let raw = format!(
+            "\u{feff}refs/heads/main\x00{}\x00commit\r\nrefs/tags/v1\x00{}\x00commit\r\n",
+            SHA_A, SHA_B
+        );
+        let refs = parse_for_each_ref(raw.as_bytes());
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].name, "refs/heads/main");
+        assert_eq!(refs[1].object_type, "commit");
+    }
+}
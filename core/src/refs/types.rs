@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::graph::types::RefType;
+
+/// A reference listed by `git for-each-ref`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RefEntry {
+    /// Full ref name, e.g. `refs/remotes/origin/main`.
+    pub name: String,
+    /// Name as shown in decorations, e.g. `origin/main`.
+    pub short_name: String,
+    pub ref_type: RefType,
+    /// Commit the ref resolves to; for annotated tags this is the peeled
+    /// commit rather than the tag object.
+    pub target_sha: String,
+    /// SHA of the object the ref points at directly.
+    pub object_sha: String,
+    /// `commit`, `tag`, `tree` or `blob`.
+    pub object_type: String,
+    /// Upstream of a local branch, e.g. `origin/main`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// Creation date (unix epoch): tagger date for annotated tags,
+    /// committer date otherwise.
+    pub created: u64,
+    /// Whether this is the checked-out branch.
+    pub is_head: bool,
+}
+
+/// What `.git/HEAD` points at.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum HeadState {
+    /// A checked-out branch, e.g. `refs/heads/main`; the branch may be
+    /// unborn.
+    Symbolic { target: String },
+    /// A detached HEAD at this commit.
+    Detached { sha: String },
+}
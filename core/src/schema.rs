@@ -0,0 +1,142 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Output schema versions selectable with `set_output_schema`.
+///
+/// Version 1 is the original output, where `CommitNode` and `BlameEntry`
+/// serialize their fields in snake_case and everything else in camelCase.
+pub const LEGACY: u32 = 1;
+/// Version 2 serializes every key in camelCase.
+pub const CAMEL_CASE: u32 = 2;
+
+/// Serialize `value` as JSON in the given schema version.
+pub(crate) fn to_json_string<T: Serialize>(value: &T, schema: u32) -> serde_json::Result<String> {
+    if schema == LEGACY {
+        return serde_json::to_string(value);
+    }
+    let mut value = serde_json::to_value(value)?;
+    camel_case_keys(&mut value);
+    serde_json::to_string(&value)
+}
+
+/// Rename snake_case object keys to camelCase, recursively.
+///
+/// Only keys made of lowercase letters, digits and underscores are
+/// renamed, so data used as keys (SHAs, paths) is left alone.
+pub(crate) fn camel_case_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let renamed: Vec<(String, Value)> = std::mem::take(map)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    camel_case_keys(&mut value);
+                    (to_camel_case(&key).unwrap_or(key), value)
+                })
+                .collect();
+            map.extend(renamed);
+        }
+        Value::Array(items) => items.iter_mut().for_each(camel_case_keys),
+        _ => {}
+    }
+}
+
+/// Rename camelCase object keys to snake_case, recursively, so JSON
+/// produced under `CAMEL_CASE` can be read back into the legacy types.
+pub(crate) fn snake_case_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let renamed: Vec<(String, Value)> = std::mem::take(map)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    snake_case_keys(&mut value);
+                    (to_snake_case(&key).unwrap_or(key), value)
+                })
+                .collect();
+            map.extend(renamed);
+        }
+        Value::Array(items) => items.iter_mut().for_each(snake_case_keys),
+        _ => {}
+    }
+}
+
+/// `authorTzOffsetMin` -> `author_tz_offset_min`; `None` if `key` is not a
+/// camelCase identifier with at least one uppercase letter.
+fn to_snake_case(key: &str) -> Option<String> {
+    let is_camel = key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.bytes().any(|b| b.is_ascii_uppercase())
+        && key.bytes().all(|b| b.is_ascii_alphanumeric());
+    if !is_camel {
+        return None;
+    }
+
+    let mut out = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+/// `author_tz_offset_min` -> `authorTzOffsetMin`; `None` if `key` is not a
+/// snake_case identifier.
+fn to_camel_case(key: &str) -> Option<String> {
+    let is_snake = key.contains('_')
+        && key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+    if !is_snake {
+        return None;
+    }
+
+    let mut out = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("author_tz_offset_min").as_deref(), Some("authorTzOffsetMin"));
+        assert_eq!(to_camel_case("shortSha"), None);
+        assert_eq!(to_camel_case("sha"), None);
+        assert_eq!(to_camel_case("src/my_file.rs"), None);
+
+        assert_eq!(to_snake_case("authorTzOffsetMin").as_deref(), Some("author_tz_offset_min"));
+        assert_eq!(to_snake_case("short_sha"), None);
+        assert_eq!(to_snake_case("sha"), None);
+    }
+
+    #[test]
+    fn test_schema_versions() {
+        let value = serde_json::json!({
+            "short_sha": "abc",
+            "nested": [{ "final_line": 1 }],
+            "pathAtCommit": { "abc123": "my_file.rs" },
+        });
+        let legacy: Value = serde_json::from_str(&to_json_string(&value, LEGACY).unwrap()).unwrap();
+        assert_eq!(legacy["short_sha"], "abc");
+
+        let camel: Value =
+            serde_json::from_str(&to_json_string(&value, CAMEL_CASE).unwrap()).unwrap();
+        assert_eq!(camel["shortSha"], "abc");
+        assert_eq!(camel["nested"][0]["finalLine"], 1);
+        assert_eq!(camel["pathAtCommit"]["abc123"], "my_file.rs");
+        assert!(camel.get("short_sha").is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+/// A person and timestamp attached to a commit, blame chunk or tag: who
+/// authored, committed or tagged it and when.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    /// Unix epoch seconds.
+    pub time: u64,
+    /// Offset from UTC in minutes, e.g. `+0130` -> 90.
+    pub tz_offset_min: i32,
+}
+
+/// Convert a `+hhmm`/`-hhmm` offset to signed minutes; malformed offsets
+/// are treated as UTC.
+pub(crate) fn parse_tz_offset(tz: &str) -> i32 {
+    let tz = tz.trim();
+    let (sign, digits) = match tz.as_bytes().first() {
+        Some(b'-') => (-1, &tz[1..]),
+        Some(b'+') => (1, &tz[1..]),
+        _ => (1, tz),
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return 0;
+    }
+    let hours: i32 = digits[..2].parse().unwrap_or(0);
+    let minutes: i32 = digits[2..].parse().unwrap_or(0);
+    sign * (hours * 60 + minutes)
+}
+
+/// Define a serde `with` module that flattens a `Signature` into the
+/// prefixed keys used by the flat JSON of `CommitNode` and `BlameEntry`.
+macro_rules! flat_signature {
+    ($(#[$doc:meta])* $module:ident { $name:tt, $email:tt, $time:tt, $tz:tt }) => {
+        $(#[$doc])*
+        pub mod $module {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            use super::Signature;
+
+            #[derive(Serialize)]
+            struct Fields<'a> {
+                #[serde(rename = $name)]
+                name: &'a str,
+                #[serde(rename = $email)]
+                email: &'a str,
+                #[serde(rename = $time)]
+                time: u64,
+                #[serde(rename = $tz)]
+                tz_offset_min: i32,
+            }
+
+            #[derive(Deserialize)]
+            struct OwnedFields {
+                #[serde(rename = $name)]
+                name: String,
+                #[serde(rename = $email)]
+                email: String,
+                #[serde(rename = $time)]
+                time: u64,
+                #[serde(rename = $tz, default)]
+                tz_offset_min: i32,
+            }
+
+            pub fn serialize<S: Serializer>(
+                signature: &Signature,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                Fields {
+                    name: &signature.name,
+                    email: &signature.email,
+                    time: signature.time,
+                    tz_offset_min: signature.tz_offset_min,
+                }
+                .serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Signature, D::Error> {
+                let fields = OwnedFields::deserialize(deserializer)?;
+                Ok(Signature {
+                    name: fields.name,
+                    email: fields.email,
+                    time: fields.time,
+                    tz_offset_min: fields.tz_offset_min,
+                })
+            }
+        }
+    };
+}
+
+flat_signature!(
+    /// `author_name`, `author_email`, `author_date`, `author_tz_offset_min`.
+    author_fields { "author_name", "author_email", "author_date", "author_tz_offset_min" }
+);
+
+flat_signature!(
+    /// `committer_name`, `committer_email`, `commit_date`,
+    /// `committer_tz_offset_min`, as on `CommitNode`.
+    committer_fields {
+        "committer_name", "committer_email", "commit_date", "committer_tz_offset_min"
+    }
+);
+
+flat_signature!(
+    /// `committer_name`, `committer_email`, `committer_date`,
+    /// `committer_tz_offset_min`, as on `BlameEntry`.
+    blame_committer_fields {
+        "committer_name", "committer_email", "committer_date", "committer_tz_offset_min"
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Flat {
+        sha: String,
+        #[serde(flatten, with = "author_fields")]
+        author: Signature,
+        #[serde(flatten, with = "committer_fields")]
+        committer: Signature,
+    }
+
+    #[test]
+    fn test_flattened_keys_round_trip() {
+        let flat = Flat {
+            sha: "aaa".to_string(),
+            author: Signature {
+                name: "Alice".to_string(),
+                email: "alice@example.com".to_string(),
+                time: 1700000000,
+                tz_offset_min: 90,
+            },
+            committer: Signature {
+                name: "Bob".to_string(),
+                ..Default::default()
+            },
+        };
+        let json = serde_json::to_value(&flat).unwrap();
+        assert_eq!(json["author_name"], "Alice");
+        assert_eq!(json["author_date"], 1700000000);
+        assert_eq!(json["author_tz_offset_min"], 90);
+        assert_eq!(json["committer_name"], "Bob");
+        assert_eq!(json["commit_date"], 0);
+
+        let back: Flat = serde_json::from_value(json).unwrap();
+        assert_eq!(back, flat);
+    }
+
+    #[test]
+    fn test_parse_tz_offset() {
+        assert_eq!(parse_tz_offset("+0130"), 90);
+        assert_eq!(parse_tz_offset("-0800"), -480);
+        assert_eq!(parse_tz_offset("bogus"), 0);
+    }
+}
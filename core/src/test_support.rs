@@ -0,0 +1,25 @@
+//! Builders shared by the unit tests.
+
+use crate::blame::BlameEntry;
+use crate::graph::types::CommitNode;
+
+/// A commit with only its SHA and parents set.
+pub(crate) fn commit(sha: &str, parents: &[&str]) -> CommitNode {
+    CommitNode {
+        sha: sha.to_string(),
+        parents: parents.iter().map(|p| p.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+/// An entry blaming `num_lines` lines of `f.rs` on `sha`.
+pub(crate) fn entry(sha: &str, orig_line: u32, final_line: u32, num_lines: u32) -> BlameEntry {
+    BlameEntry {
+        sha: sha.to_string(),
+        orig_line,
+        final_line,
+        num_lines,
+        filename: "f.rs".to_string(),
+        ..Default::default()
+    }
+}
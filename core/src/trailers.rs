@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+/// A `Key: value` line from the trailer block at the end of a commit
+/// message, such as `Signed-off-by: Alice <a@example.com>`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+impl Trailer {
+    /// Whether this trailer has `key`, compared without regard to case as
+    /// git does.
+    pub fn has_key(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key.trim())
+    }
+}
+
+/// Parse the trailers of a commit message body, like
+/// `git interpret-trailers --parse`.
+///
+/// Trailers are the last paragraph of the body when every line in it is a
+/// `Key: value` trailer, an indented continuation of the previous value or
+/// a `(cherry picked from commit ...)` note. Keys are letters, digits and
+/// `-`. A body whose last paragraph is prose has no trailers.
+pub fn parse_trailers(body: &str) -> Vec<Trailer> {
+    let body = body.trim_end();
+    let block = match body.rfind("\n\n") {
+        Some(pos) => &body[pos + 2..],
+        None => body,
+    };
+
+    let mut trailers: Vec<Trailer> = Vec::new();
+    for line in block.lines() {
+        if line.starts_with([' ', '\t']) {
+            match trailers.last_mut() {
+                Some(last) => {
+                    last.value.push(' ');
+                    last.value.push_str(line.trim());
+                    continue;
+                }
+                None => return Vec::new(),
+            }
+        }
+        if line.starts_with("(cherry picked from commit ") {
+            continue;
+        }
+        match parse_trailer_line(line) {
+            Some(trailer) => trailers.push(trailer),
+            None => return Vec::new(),
+        }
+    }
+    trailers
+}
+
+fn parse_trailer_line(line: &str) -> Option<Trailer> {
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim_end();
+    let valid_key =
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid_key.then(|| Trailer {
+        key: key.to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trailer(key: &str, value: &str) -> Trailer {
+        Trailer {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_trailers() {
+        let body = "Explain the change: it was broken.\n\n\
+                    Signed-off-by: Alice <a@e.com>\n\
+                    Co-authored-by: Bob\n  <b@e.com>\n\
+                    (cherry picked from commit abc123)\n";
+        assert_eq!(
+            parse_trailers(body),
+            vec![
+                trailer("Signed-off-by", "Alice <a@e.com>"),
+                trailer("Co-authored-by", "Bob <b@e.com>"),
+            ]
+        );
+        assert_eq!(parse_trailers("Fixes: #12"), vec![trailer("Fixes", "#12")]);
+        assert!(trailer("Signed-off-by", "").has_key("signed-off-by"));
+    }
+
+    #[test]
+    fn test_parse_trailers_needs_a_trailer_block() {
+        assert!(parse_trailers("").is_empty());
+        assert!(parse_trailers("Signed-off-by: Alice\n\nJust prose.").is_empty());
+        assert_eq!(parse_trailers("Signed-off-by: Alice\nNote: fine").len(), 2);
+        assert!(parse_trailers("Signed-off-by: Alice\nnot a trailer").is_empty());
+        assert!(parse_trailers("Bad key: value").is_empty());
+        assert!(parse_trailers("  indented: first").is_empty());
+    }
+}
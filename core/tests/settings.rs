@@ -0,0 +1,94 @@
+//! Tests for the exports that change module-wide settings: the mailmap,
+//! the issue rules and the output schema.
+//!
+//! Every export call sees these settings, so the tests live in their own
+//! binary, away from the replay tests that expect the defaults, and run
+//! their bodies through `with_settings`.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, PoisonError};
+
+use gitex_core::*;
+use serde_json::Value;
+use wasm_bindgen_test::*;
+
+fn parse_json(json: &str) -> Value {
+    serde_json::from_str(json).expect("exported function returned invalid JSON")
+}
+
+/// Run `test` while no other test changes settings, then put the defaults
+/// back, even if it failed.
+fn with_settings(test: impl FnOnce()) {
+    static SETTINGS: Mutex<()> = Mutex::new(());
+    let _settings = SETTINGS.lock().unwrap_or_else(PoisonError::into_inner);
+    let result = panic::catch_unwind(AssertUnwindSafe(test));
+    set_mailmap("");
+    set_issue_rules("[]");
+    set_output_schema(1);
+    if let Err(payload) = result {
+        panic::resume_unwind(payload);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn mailmap_canonicalizes_log_and_blame() {
+    with_settings(|| {
+        let mailmap = "Mallory Canonical <mallory@mailmap.test> <mal@old.mailmap.test>\n";
+        assert_eq!(set_mailmap(mailmap), 1);
+
+        let raw = b"mmm\x00mm\x00\x00mal\x00mal@old.mailmap.test\x001700000000\x00mal\x00mal@old.mailmap.test\x001700000000\x00Mapped\x00\x1e";
+        let parsed = parse_json(&compute_graph_layout(raw));
+        assert_eq!(parsed["nodes"][0]["authorName"], "Mallory Canonical");
+        free_layout(parsed["handle"].as_u64().unwrap() as u32);
+
+        let blame = b"abcdef0123456789abcdef0123456789abcdef01 1 1 1\nauthor mal\nauthor-mail <mal@old.mailmap.test>\nfilename a.rs\n";
+        let entries = parse_json(&parse_blame(blame));
+        assert_eq!(entries[0]["author_name"], "Mallory Canonical");
+        assert_eq!(entries[0]["author_email"], "mallory@mailmap.test");
+
+        assert_eq!(set_mailmap(""), 0);
+    });
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn issue_rules_apply_to_later_parses() {
+    with_settings(|| {
+        let rules = r#"[{"name": "ticket", "pattern": "TKT-\\d+", "url": "https://t.example/$0"}]"#;
+        assert_eq!(parse_json(&set_issue_rules(rules))["ruleCount"], 1);
+
+        let raw = b"iii\x00ii\x00\x00Alice\x00a@e.com\x001700000000\x00Alice\x00a@e.com\x001700000000\x00Fix TKT-42\x00\x1e";
+        let parsed = parse_json(&compute_graph_layout(raw));
+        assert_eq!(parsed["nodes"][0]["issueRefs"][0]["key"], "TKT-42");
+        assert_eq!(parsed["nodes"][0]["issueRefs"][0]["url"], "https://t.example/TKT-42");
+        free_layout(parsed["handle"].as_u64().unwrap() as u32);
+
+        let bad = parse_json(&set_issue_rules(r#"[{"name": "x", "pattern": "("}]"#));
+        assert_eq!(bad["error"]["code"], "BAD_REGEX");
+
+        assert_eq!(parse_json(&set_issue_rules("[]"))["ruleCount"], 0);
+    });
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn output_schema_selects_key_casing() {
+    with_settings(|| {
+        let blame = b"abcdef0123456789abcdef0123456789abcdef01 1 1 2\nauthor Alice\nfilename a.rs\n";
+        let legacy = parse_json(&parse_blame(blame));
+        assert_eq!(legacy[0]["short_sha"], "abcdef0");
+        assert_eq!(legacy[0]["author_name"], "Alice");
+
+        assert_eq!(parse_json(&set_output_schema(2))["schema"], 2);
+        let camel = parse_json(&parse_blame(blame));
+        assert_eq!(camel[0]["shortSha"], "abcdef0");
+        assert_eq!(camel[0]["authorName"], "Alice");
+        assert_eq!(camel[0]["numLines"], 2);
+        assert!(camel[0].get("short_sha").is_none());
+        let packed = parse_blame_encoded(blame, "{}", "msgpack").unwrap();
+        let packed: Value = rmp_serde::from_slice(&packed).unwrap();
+        assert_eq!(packed, camel);
+
+        let bad = parse_json(&set_output_schema(9));
+        assert_eq!(bad["error"]["code"], "BAD_ARGUMENT");
+        assert_eq!(parse_json(&core_info())["schema"], 2);
+    });
+}
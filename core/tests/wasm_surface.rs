@@ -0,0 +1,257 @@
+//! End-to-end replay tests for the exported WASM surface.
+//!
+//! These tests feed the recorded fixtures in `tests/fixtures/` through the
+//! same `#[wasm_bindgen]` functions the extension calls, and assert on the
+//! JSON envelope and handle semantics rather than on internal types.
+//!
+//! Run natively with `cargo test`, or under node with
+//! `wasm-pack test --node`.
+
+use gitex_core::*;
+use serde_json::Value;
+use wasm_bindgen_test::*;
+
+const SAMPLE_LOG: &[u8] = include_bytes!("fixtures/sample_log.txt");
+const SAMPLE_BLAME: &[u8] = include_bytes!("fixtures/sample_blame.txt");
+
+fn parse_json(json: &str) -> Value {
+    serde_json::from_str(json).expect("exported function returned invalid JSON")
+}
+
+fn handle_of(result: &Value) -> u32 {
+    result["handle"].as_u64().expect("missing handle") as u32
+}
+
+/// Split the fixture log into (first `n` records, remaining records), keeping
+/// the record separators so each half is a valid log on its own.
+fn split_log(n: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut seen = 0;
+    for (i, &b) in SAMPLE_LOG.iter().enumerate() {
+        if b == 0x1e {
+            seen += 1;
+            if seen == n {
+                return (SAMPLE_LOG[..=i].to_vec(), SAMPLE_LOG[i + 1..].to_vec());
+            }
+        }
+    }
+    (SAMPLE_LOG.to_vec(), Vec::new())
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn compute_envelope_has_handle_nodes_edges_and_count() {
+    let result = parse_json(&compute_graph_layout(SAMPLE_LOG));
+    let handle = handle_of(&result);
+    assert_ne!(handle, 0);
+
+    let nodes = result["nodes"].as_array().unwrap();
+    assert_eq!(result["totalCount"], 8);
+    assert_eq!(nodes.len(), 8);
+    assert!(result["edges"].is_array());
+
+    // camelCase field names are part of the contract with the TS layer
+    let head = &nodes[0];
+    assert_eq!(head["sha"], "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2");
+    assert_eq!(head["shortSha"], "a1b2c3d");
+    assert_eq!(head["authorName"], "Alice Smith");
+    assert_eq!(head["authorEmail"], "alice@example.com");
+    assert_eq!(head["committerName"], "Alice Smith");
+    assert_eq!(head["committerEmail"], "alice@example.com");
+    assert_eq!(head["nodeType"], "Head");
+    assert_eq!(head["row"], 0);
+    assert!(head.get("colorIndex").is_some());
+    assert_eq!(head["refs"][0]["refType"], "Head");
+
+    // Rows are assigned in input order
+    for (i, node) in nodes.iter().enumerate() {
+        assert_eq!(node["row"], i as u64);
+    }
+
+    free_layout(handle);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn compute_edges_resolve_rows_within_fixture() {
+    let result = parse_json(&compute_graph_layout(SAMPLE_LOG));
+    let edges = result["edges"].as_array().unwrap();
+
+    // The merge commit at row 0 fans out to both of its parents
+    let from_head: Vec<_> = edges.iter().filter(|e| e["fromRow"] == 0).collect();
+    assert_eq!(from_head.len(), 2);
+    assert!(from_head.iter().any(|e| e["edgeType"] == "Merge"));
+
+    for edge in edges {
+        assert!(edge["toRow"].as_i64().unwrap() > edge["fromRow"].as_i64().unwrap());
+    }
+
+    free_layout(handle_of(&result));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn layout_rows_replay_fixture_window() {
+    let graph = GraphLayout::new(SAMPLE_LOG, "").unwrap();
+
+    // Row 2 is the feature-branch commit; the edge from row 1 to row 3 runs
+    // past it on the main line
+    let window = parse_json(&graph.window(2, 1));
+    assert_eq!(window["totalCount"], 8);
+    let nodes = window["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0]["shortSha"], "d4e5f6a");
+    assert_eq!(nodes[0]["row"], 2);
+    let mut rows: Vec<(i64, i64)> = window["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["fromRow"].as_i64().unwrap(), e["toRow"].as_i64().unwrap()))
+        .collect();
+    rows.sort();
+    assert_eq!(rows, vec![(0, 2), (1, 3), (2, 3)]);
+
+    assert_eq!(window, parse_json(&get_layout_rows(graph.handle(), 2, 1)));
+
+    // A window running past the end is cut short
+    let tail = parse_json(&get_layout_rows(graph.handle(), 7, 5));
+    assert_eq!(tail["nodes"].as_array().unwrap().len(), 1);
+    assert_eq!(tail["nodes"][0]["shortSha"], "c9d0e1f");
+    assert_eq!(tail["totalCount"], 8);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn handles_are_distinct_per_layout() {
+    let a = handle_of(&parse_json(&compute_graph_layout(SAMPLE_LOG)));
+    let b = handle_of(&parse_json(&compute_graph_layout(SAMPLE_LOG)));
+    assert_ne!(a, b);
+    free_layout(a);
+    free_layout(b);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn append_extends_layout_under_same_handle() {
+    let (first, rest) = split_log(5);
+    let initial = parse_json(&compute_graph_layout(&first));
+    let handle = handle_of(&initial);
+    assert_eq!(initial["totalCount"], 5);
+
+    let appended = parse_json(&append_to_layout(handle, &rest));
+    assert_eq!(handle_of(&appended), handle);
+    assert_eq!(appended["totalCount"], 8);
+    // Commits from the first page keep their emails
+    assert_eq!(appended["nodes"][0]["authorEmail"], "alice@example.com");
+
+    // Filters observe the appended commits through the handle
+    let filtered = parse_json(&filter_commits(handle, "author", "Diana"));
+    assert_eq!(filtered["totalCount"], 1);
+
+    free_layout(handle);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn append_ignores_already_loaded_commits() {
+    let result = parse_json(&compute_graph_layout(SAMPLE_LOG));
+    let handle = handle_of(&result);
+
+    let again = parse_json(&append_to_layout(handle, SAMPLE_LOG));
+    assert_eq!(again["totalCount"], 8);
+
+    let empty = parse_json(&append_to_layout(handle, b""));
+    assert_eq!(empty["totalCount"], 8);
+
+    free_layout(handle);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn filter_by_field_returns_layout_envelope() {
+    let handle = handle_of(&parse_json(&compute_graph_layout(SAMPLE_LOG)));
+
+    let by_author = parse_json(&filter_commits(handle, "author", "^Bob"));
+    assert_eq!(by_author["totalCount"], 2);
+    assert!(by_author.get("handle").is_none());
+
+    let by_message = parse_json(&filter_commits(handle, "message", "(?i)blame"));
+    assert_eq!(by_message["totalCount"], 2);
+
+    let bad = parse_json(&filter_commits(handle, "author", "[unclosed"));
+    assert!(bad["error"]["message"].as_str().unwrap().contains("Invalid regex"));
+    assert_eq!(bad["error"]["code"], "BAD_REGEX");
+
+    free_layout(handle);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn filter_by_date_returns_layout_envelope() {
+    let handle = handle_of(&parse_json(&compute_graph_layout(SAMPLE_LOG)));
+
+    let recent = parse_json(&filter_by_date(handle, 1700008000, 0));
+    assert_eq!(recent["totalCount"], 3);
+
+    let all = parse_json(&filter_by_date(handle, 0, 0));
+    assert_eq!(all["totalCount"], 8);
+
+    free_layout(handle);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn freed_handles_are_rejected_everywhere() {
+    let handle = handle_of(&parse_json(&compute_graph_layout(SAMPLE_LOG)));
+    free_layout(handle);
+    // Double free is a no-op
+    free_layout(handle);
+
+    for json in [
+        append_to_layout(handle, SAMPLE_LOG),
+        filter_commits(handle, "author", "Alice"),
+        filter_by_date(handle, 0, 0),
+    ] {
+        let value = parse_json(&json);
+        assert!(value["error"]["message"].as_str().unwrap().contains("Invalid handle"));
+        assert_eq!(value["error"]["code"], "INVALID_HANDLE");
+        assert_eq!(value["error"]["details"]["handle"], handle);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn parse_blame_replays_fixture() {
+    let entries = parse_json(&parse_blame(SAMPLE_BLAME));
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 4);
+
+    assert_eq!(entries[0]["sha"], "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2");
+    assert_eq!(entries[0]["short_sha"], "a1b2c3d");
+    assert_eq!(entries[0]["num_lines"], 5);
+    assert_eq!(entries[1]["author_name"], "Bob Jones");
+    assert_eq!(entries[1]["final_line"], 6);
+    assert_eq!(entries[1]["previous_filename"], "src/main.rs");
+    assert_eq!(entries[3]["filename"], "src/main.rs");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn streamed_blame_matches_stored_blame() {
+    let stored = parse_json(&store_blame(SAMPLE_BLAME));
+    let stored_handle = handle_of(&stored);
+
+    let session = handle_of(&parse_json(&create_blame_session()));
+    let mut streamed = 0;
+    for chunk in SAMPLE_BLAME.chunks(64) {
+        streamed += parse_json(&blame_append(session, chunk))["entries"]
+            .as_array()
+            .unwrap()
+            .len();
+    }
+    streamed += parse_json(&blame_finish(session))["entries"]
+        .as_array()
+        .unwrap()
+        .len();
+    assert_eq!(stored["entryCount"], streamed);
+
+    assert_eq!(
+        parse_json(&blame_for_lines(stored_handle, 1, 100))["entries"],
+        parse_json(&blame_for_lines(session, 1, 100))["entries"]
+    );
+
+    for handle in [stored_handle, session] {
+        free_blame(handle);
+        let value = parse_json(&blame_for_lines(handle, 1, 1));
+        assert!(value["error"]["message"].as_str().unwrap().contains("Invalid handle"));
+        assert_eq!(value["error"]["code"], "INVALID_HANDLE");
+    }
+}